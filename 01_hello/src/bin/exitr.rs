@@ -0,0 +1,28 @@
+use clap::{value_parser, Arg, Command};
+use clir_common::i18n::tr_str;
+use clir_common::CommandExt;
+
+fn main() {
+    let matches = Command::new("exitr")
+        .version("0.1.0")
+        .author("dtorannpu")
+        .about("Exit with the given status")
+        .arg(
+            Arg::new("code")
+                .value_name("CODE")
+                .help(tr_str("Exit status (0-255)", "終了ステータス (0-255)"))
+                .default_value("0")
+                .value_parser(value_parser!(u8)),
+        )
+        .arg(Arg::new("message").value_name("MESSAGE").help(tr_str(
+            "Message to print to stderr",
+            "標準エラー出力に表示するメッセージ",
+        )))
+        .get_tool_matches();
+
+    if let Some(message) = matches.get_one::<String>("message") {
+        eprintln!("{}", message);
+    }
+
+    std::process::exit(*matches.get_one::<u8>("code").unwrap() as i32);
+}
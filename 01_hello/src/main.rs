@@ -1,3 +1,37 @@
+use clap::{Arg, Command};
+use clir_common::i18n::tr_str;
+use clir_common::CommandExt;
+
 fn main() {
-    println!("Hello, world!!!");
+    let matches = Command::new("hello")
+        .version("0.1.0")
+        .author("dtorannpu")
+        .about("Rust hello")
+        .arg(
+            Arg::new("name")
+                .value_name("NAME")
+                .help(tr_str("Name to greet", "あいさつする相手の名前")),
+        )
+        .arg(
+            Arg::new("greeting")
+                .value_name("TEMPLATE")
+                .short('g')
+                .long("greeting")
+                .help(tr_str(
+                    "Greeting template, \"{name}\" is replaced with NAME",
+                    "あいさつのテンプレート(\"{name}\" はNAMEに置き換わる)",
+                )),
+        )
+        .get_tool_matches();
+
+    let name = matches
+        .get_one::<String>("name")
+        .map(|v| v.as_str())
+        .unwrap_or(tr_str("world", "世界"));
+    let template = matches
+        .get_one::<String>("greeting")
+        .map(|v| v.as_str())
+        .unwrap_or(tr_str("Hello, {name}!!!", "こんにちは、{name}!!!"));
+
+    println!("{}", template.replace("{name}", name));
 }
@@ -5,9 +5,8 @@ fn works() {
     let mut cmd = Command::new("hello");
     let res = cmd.output();
 
-    match res {
-        Ok(ref output) => println!("{}", String::from_utf8_lossy(&output.stdout)),
-        _ => {}
+    if let Ok(ref output) = res {
+        println!("{}", String::from_utf8_lossy(&output.stdout))
     }
     assert!(res.is_ok());
 }
@@ -15,7 +14,12 @@ fn works() {
 #[test]
 fn runs() {
     let mut cmd = Command::cargo_bin("hello").unwrap();
-    cmd.assert().success().stdout("Hello, world!!!\n");
+    cmd.env("LANG", "C")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .assert()
+        .success()
+        .stdout("Hello, world!!!\n");
 }
 
 #[test]
@@ -29,3 +33,85 @@ fn false_not_ok() {
     let mut cmd = Command::cargo_bin("false").unwrap();
     cmd.assert().failure();
 }
+
+#[test]
+fn exitr_default_ok() {
+    let mut cmd = Command::cargo_bin("exitr").unwrap();
+    cmd.assert().success().stderr("");
+}
+
+#[test]
+fn exitr_code() {
+    let mut cmd = Command::cargo_bin("exitr").unwrap();
+    cmd.arg("3").assert().code(3).stderr("");
+}
+
+#[test]
+fn exitr_message() {
+    let mut cmd = Command::cargo_bin("exitr").unwrap();
+    cmd.args(["42", "something went wrong"])
+        .assert()
+        .code(42)
+        .stdout("")
+        .stderr("something went wrong\n");
+}
+
+#[test]
+fn exitr_bad_code() {
+    let mut cmd = Command::cargo_bin("exitr").unwrap();
+    cmd.arg("256")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("invalid value '256'"));
+}
+
+#[test]
+fn hello_name() {
+    let mut cmd = Command::cargo_bin("hello").unwrap();
+    cmd.env("LANG", "C")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .arg("Ferris")
+        .assert()
+        .success()
+        .stdout("Hello, Ferris!!!\n");
+}
+
+#[test]
+fn hello_greeting_template() {
+    let mut cmd = Command::cargo_bin("hello").unwrap();
+    cmd.args(["--greeting", "Good morning, {name}.", "Ferris"])
+        .assert()
+        .success()
+        .stdout("Good morning, Ferris.\n");
+}
+
+#[test]
+fn hello_japanese_locale() {
+    let mut cmd = Command::cargo_bin("hello").unwrap();
+    cmd.env("LANG", "ja_JP.UTF-8")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .assert()
+        .success()
+        .stdout("こんにちは、世界!!!\n");
+}
+
+#[test]
+fn hello_completions() {
+    let mut cmd = Command::cargo_bin("hello").unwrap();
+    cmd.args(["--completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("_hello()"))
+        .stdout(predicates::str::contains("--greeting"));
+}
+
+#[test]
+fn exitr_man() {
+    let mut cmd = Command::cargo_bin("exitr").unwrap();
+    cmd.arg("--man")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(".TH exitr 1"));
+}
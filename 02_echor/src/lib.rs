@@ -0,0 +1,144 @@
+use clap::{value_parser, Arg, ArgAction, Command};
+use clir_common::escape::{unescape, Escapes};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{parse_duration, CommandExt, ToolError};
+use std::io;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug)]
+pub struct Config {
+    text: Vec<String>,
+    omit_newline: bool,
+    escapes: bool,
+    repeat: u64,
+    delay: Duration,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("echor")
+        .version("0.1.0")
+        .author("dtorannpu")
+        .about("Rust echo")
+        .arg(
+            Arg::new("text")
+                .value_name("TEXT")
+                .help(tr_str("Input text", "入力テキスト"))
+                .required(true)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("omit_newline")
+                .short('n')
+                .help(tr_str("Do not print newline", "改行を出力しない"))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("escapes")
+                .short('e')
+                .help(tr_str(
+                    "Interpret backslash escapes",
+                    "バックスラッシュのエスケープを解釈する",
+                ))
+                .action(ArgAction::SetTrue)
+                .overrides_with("no_escapes"),
+        )
+        .arg(
+            Arg::new("no_escapes")
+                .short('E')
+                .help(tr_str(
+                    "Do not interpret backslash escapes (default)",
+                    "バックスラッシュのエスケープを解釈しない(既定)",
+                ))
+                .action(ArgAction::SetTrue)
+                .overrides_with("escapes"),
+        )
+        .arg(
+            Arg::new("repeat")
+                .long("repeat")
+                .value_name("N")
+                .help(tr_str("Print the text N times", "テキストをN回表示する"))
+                .default_value("1")
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("delay")
+                .long("delay")
+                .value_name("DURATION")
+                .help(tr_str(
+                    "Wait between repetitions, e.g. 0.5s or 1m",
+                    "繰り返しのあいだに待つ時間(例: 0.5s、1m)",
+                ))
+                .value_parser(parse_duration),
+        )
+        .get_tool_matches();
+
+    Ok(Config {
+        text: matches
+            .get_many::<String>("text")
+            .unwrap()
+            .map(|v| v.to_string())
+            .collect(),
+        omit_newline: matches.get_flag("omit_newline"),
+        escapes: matches.get_flag("escapes"),
+        repeat: *matches.get_one("repeat").unwrap(),
+        delay: matches.get_one("delay").copied().unwrap_or(Duration::ZERO),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let text = config.text.join(" ");
+    let (text, stop) = if config.escapes {
+        let mut out = vec![];
+        // echo の方言は失敗しない
+        let stop = unescape(text.as_bytes(), Escapes::Echo, &mut out).unwrap_or(false);
+        (out, stop)
+    } else {
+        (text.into_bytes(), false)
+    };
+    for i in 0..config.repeat {
+        if i > 0 && !config.delay.is_zero() {
+            // 待っている間にそれまでの出力が見えるようにする
+            out.flush()?;
+            thread::sleep(config.delay);
+        }
+        out.write_all(&text)?;
+        if !config.omit_newline && !stop {
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{run, Config};
+
+    #[test]
+    fn test_run_writers() {
+        let config = Config {
+            text: vec!["Hello".to_string(), "there".to_string()],
+            omit_newline: true,
+            escapes: false,
+            repeat: 1,
+            delay: Duration::ZERO,
+        };
+        let mut out = vec![];
+        let mut err = vec![];
+        assert!(run(config, &mut out, &mut err).is_ok());
+        assert_eq!(out, b"Hello there");
+        assert!(err.is_empty());
+    }
+}
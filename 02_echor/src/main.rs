@@ -1,32 +1,9 @@
-use clap::{Arg, ArgAction, Command};
+use std::io;
 
 fn main() {
-    let matches = Command::new("echor")
-        .version("0.1.0")
-        .author("dtorannpu")
-        .about("Rust echo")
-        .arg(
-            Arg::new("text")
-                .value_name("TEXT")
-                .help("Input text")
-                .required(true)
-                .action(ArgAction::Append),
-        )
-        .arg(
-            Arg::new("omit_newline")
-                .short('n')
-                .help("Do not print newline")
-                .action(ArgAction::SetTrue),
-        )
-        .get_matches();
-
-    let text = matches
-        .get_many::<String>("text")
-        .unwrap()
-        .map(|v| v.as_str())
-        .collect::<Vec<_>>();
-
-    let omit_newline = matches.get_flag("omit_newline");
-
-    print!("{}{}", text.join(" "), if omit_newline { "" } else { "\n" });
+    let result = echor::get_args()
+        .and_then(|config| echor::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("echor", &e));
+    }
 }
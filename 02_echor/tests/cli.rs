@@ -49,3 +49,84 @@ fn hello1_no_newline() -> TestResult {
 fn hello2_no_newline() -> TestResult {
     run(&["-n", "Hello", "there"], "tests/expected/hello2.n.txt")
 }
+
+#[test]
+fn escapes() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["-e", r"a\tb\nc", r"\x41\0102\\"])
+        .assert()
+        .success()
+        .stdout("a\tb\nc AB\\\n");
+    Ok(())
+}
+
+#[test]
+fn escapes_stop() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["-e", r"one\c", "two"])
+        .assert()
+        .success()
+        .stdout("one");
+    Ok(())
+}
+
+#[test]
+fn no_escapes() -> TestResult {
+    // 後に指定したほうが優先される
+    for args in [&[r"a\tb"][..], &["-E", r"a\tb"], &["-e", "-E", r"a\tb"]] {
+        Command::cargo_bin("echor")?
+            .args(args)
+            .assert()
+            .success()
+            .stdout("a\\tb\n");
+    }
+    Command::cargo_bin("echor")?
+        .args(["-E", "-e", r"a\tb"])
+        .assert()
+        .success()
+        .stdout("a\tb\n");
+    Ok(())
+}
+
+#[test]
+fn repeat() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--repeat", "3", "ping", "pong"])
+        .assert()
+        .success()
+        .stdout("ping pong\nping pong\nping pong\n");
+    Command::cargo_bin("echor")?
+        .args(["--repeat", "2", "-n", "-e", r"a\tb"])
+        .assert()
+        .success()
+        .stdout("a\tba\tb");
+    Command::cargo_bin("echor")?
+        .args(["--repeat", "0", "ping"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn repeat_delay() -> TestResult {
+    let start = std::time::Instant::now();
+    Command::cargo_bin("echor")?
+        .args(["--repeat", "3", "--delay", "100ms", "ping"])
+        .assert()
+        .success()
+        .stdout("ping\nping\nping\n");
+    // 待つのは繰り返しの間の2回だけ
+    assert!(start.elapsed() >= std::time::Duration::from_millis(200));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_delay() -> TestResult {
+    Command::cargo_bin("echor")?
+        .args(["--delay", "soon", "ping"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid time interval \"soon\""));
+    Ok(())
+}
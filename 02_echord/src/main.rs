@@ -1,4 +1,6 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use clir_common::i18n::tr_str;
+use clir_common::CommandExt;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Rust echo")]
@@ -6,15 +8,16 @@ struct Args {
     #[arg(
     required = true,
     action = clap::ArgAction::Append,
-    help = "Input text"
+    help = tr_str("Input text", "入力テキスト")
     )]
     text: Vec<String>,
-    #[arg(short = 'n', help = "Do not print newline")]
+    #[arg(short = 'n', help = tr_str("Do not print newline", "改行を出力しない"))]
     omit_newline: bool,
 }
 
 fn main() {
-    let args = Args::parse();
+    let matches = Args::command().get_tool_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     print!(
         "{}{}",
         args.text.join(" "),
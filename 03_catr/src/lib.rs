@@ -1,25 +1,82 @@
-use clap::{Arg, ArgAction, Command};
-use std::error::Error;
-use std::fs::File;
+use clap::error::ErrorKind;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{warn_to, CommandExt, FileArg, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use std::ffi::OsString;
+use std::fs;
+use std::fs::OpenOptions;
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufWriter, Write};
+use std::os::unix::fs::MetadataExt;
+use thiserror::Error;
 
 #[derive(Debug)]
 pub struct Config {
-    files: Vec<String>,
+    files: Vec<FileArg>,
     number_lines: bool,
     number_nonblank_lines: bool,
+    squeeze_blank: bool,
+    show: Show,
+    decompress: bool,
+    output: Option<String>,
+    append: bool,
 }
 
-pub fn get_args() -> MyResult<Config> {
-    let matches = Command::new("catr")
+/// Which non-printing characters are made visible, as with GNU cat's
+/// `-v`, `-E` and `-T`.
+#[derive(Debug, Default, Clone, Copy)]
+struct Show {
+    nonprinting: bool,
+    ends: bool,
+    tabs: bool,
+}
+
+impl Show {
+    fn any(self) -> bool {
+        self.nonprinting || self.ends || self.tabs
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{path}: {}", tr_io(.source))]
+    Create { path: String, source: io::Error },
+    #[error("{}", tr_text(
+        "{0}: input file is output file",
+        "{0}: 入力ファイルが出力ファイルと同じです",
+        &[.0],
+    ))]
+    InputIsOutput(String),
+    #[error("{0}")]
+    Usage(clap::Error),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    Ok(config_from(&cli().get_tool_matches()))
+}
+
+fn cli() -> Command {
+    Command::new("catr")
         .version("0.1.0")
         .author("dtorannpu")
         .about("Rust cat")
         .arg(
             Arg::new("files")
                 .value_name("FILE")
-                .help("Input file(s)")
+                .help(tr_str("Input file(s)", "入力ファイル"))
                 .action(ArgAction::Append)
                 .default_value("-"),
         )
@@ -28,64 +85,367 @@ pub fn get_args() -> MyResult<Config> {
                 .short('n')
                 .long("number")
                 .action(ArgAction::SetTrue)
-                .help("Number lines")
-                .conflicts_with("number_nonblank"),
+                .help(tr_str("Number lines", "行番号を付ける")),
         )
         .arg(
             Arg::new("number_nonblank")
                 .short('b')
                 .long("number-nonblank")
                 .action(ArgAction::SetTrue)
-                .help("Number non-blank lines"),
+                .help(tr_str("Number non-blank lines", "空行以外に行番号を付ける")),
+        )
+        .arg(
+            Arg::new("squeeze_blank")
+                .short('s')
+                .long("squeeze-blank")
+                .action(ArgAction::SetTrue)
+                .help(tr_str(
+                    "Suppress repeated empty output lines",
+                    "連続する空行を1行にまとめる",
+                )),
+        )
+        .arg(
+            Arg::new("show_all")
+                .short('A')
+                .long("show-all")
+                .action(ArgAction::SetTrue)
+                .help(tr_str("Equivalent to -vET", "-vET と同じ")),
+        )
+        .arg(
+            Arg::new("e")
+                .short('e')
+                .action(ArgAction::SetTrue)
+                .help(tr_str("Equivalent to -vE", "-vE と同じ")),
+        )
+        .arg(
+            Arg::new("show_ends")
+                .short('E')
+                .long("show-ends")
+                .action(ArgAction::SetTrue)
+                .help(tr_str(
+                    "Display $ at end of each line",
+                    "各行の末尾に$を表示する",
+                )),
+        )
+        .arg(
+            Arg::new("t")
+                .short('t')
+                .action(ArgAction::SetTrue)
+                .help(tr_str("Equivalent to -vT", "-vT と同じ")),
+        )
+        .arg(
+            Arg::new("show_tabs")
+                .short('T')
+                .long("show-tabs")
+                .action(ArgAction::SetTrue)
+                .help(tr_str(
+                    "Display TAB characters as ^I",
+                    "TAB文字を^Iと表示する",
+                )),
+        )
+        .arg(
+            Arg::new("show_nonprinting")
+                .short('v')
+                .long("show-nonprinting")
+                .action(ArgAction::SetTrue)
+                .help(tr_str(
+                    "Use ^ and M- notation, except for LFD and TAB",
+                    "LFDとTAB以外に ^ と M- 記法を使う",
+                )),
+        )
+        .arg(
+            Arg::new("decompress")
+                .long("decompress")
+                .help(tr_str(
+                    "Decompress gzip, bzip2, xz and zstd input",
+                    "gzip、bzip2、xz、zstdの入力を展開する",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .help(tr_str(
+                    "Write to FILE instead of standard output",
+                    "標準出力ではなくFILEに書く",
+                )),
+        )
+        .arg(
+            Arg::new("append")
+                .long("append")
+                .action(ArgAction::SetTrue)
+                .requires("output")
+                .help(tr_str(
+                    "Append to the --output file instead of truncating it",
+                    "--output のファイルを切り詰めずに追記する",
+                )),
         )
-        .get_matches();
+}
 
+fn config_from(matches: &ArgMatches) -> Config {
     let files = matches
         .get_many::<String>("files")
         .expect("files required")
-        .map(|v| v.to_string())
+        .map(FileArg::from)
         .collect::<Vec<_>>();
 
-    Ok(Config {
+    let all = matches.get_flag("show_all");
+    let show = Show {
+        nonprinting: all
+            || matches.get_flag("e")
+            || matches.get_flag("t")
+            || matches.get_flag("show_nonprinting"),
+        ends: all || matches.get_flag("e") || matches.get_flag("show_ends"),
+        tabs: all || matches.get_flag("t") || matches.get_flag("show_tabs"),
+    };
+
+    Config {
         files,
-        number_lines: matches.get_flag("number"),
+        // GNU cat と同じく、両方あれば -b を優先する
+        number_lines: matches.get_flag("number") && !matches.get_flag("number_nonblank"),
         number_nonblank_lines: matches.get_flag("number_nonblank"),
-    })
+        squeeze_blank: matches.get_flag("squeeze_blank"),
+        show,
+        decompress: matches.get_flag("decompress"),
+        output: matches.get_one::<String>("output").cloned(),
+        append: matches.get_flag("append"),
+    }
 }
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+/// Runs catr on `args`, program name first, as if they were given on the
+/// command line, for callers that want the output without spawning the
+/// binary. Unlike the binary it ignores the config file and
+/// `CATR_OPTIONS`, and never exits the process: `--help` and
+/// `--version` are written to `out`.
+pub fn run_with<I, T>(args: I, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    match cli().try_get_matches_from(args) {
+        Ok(matches) => run(config_from(&matches), out, err),
+        Err(e) if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) => {
+            write!(out, "{}", e)?;
+            Ok(0)
+        }
+        Err(e) => Err(Error::Usage(e)),
+    }
+}
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    // GNU cat と同じく、ファイルをまたいで続く空行もまとめる
+    let mut prev_blank = false;
+    // 改行で終わらないファイルの続きは同じ行なので、行番号を付けない
+    let mut at_line_start = true;
+    // 行ごとの処理が要らなければ、行に分けずにそのまま流す
+    let plain = !config.number_lines
+        && !config.number_nonblank_lines
+        && !config.squeeze_blank
+        && !config.show.any();
+    let mut out: Box<dyn Write + '_> = match &config.output {
+        Some(path) => Box::new(BufWriter::new(create_output(
+            path,
+            &config.files,
+            config.append,
+        )?)),
+        None => Box::new(out),
+    };
+    let open = if config.decompress {
+        FileArg::open_decompressed
+    } else {
+        FileArg::open
+    };
     for filename in config.files {
         match open(&filename) {
-            Err(err) => eprintln!("Failed to open {}: {}", filename, err),
-            Ok(file) => {
+            Err(source) => {
+                warn_to(
+                    err,
+                    "catr",
+                    &Error::Open {
+                        path: filename.to_string(),
+                        source,
+                    },
+                );
+                status = EXIT_FAILURE;
+            }
+            Ok(mut file) if plain => {
+                io::copy(&mut file, &mut out)?;
+            }
+            Ok(mut file) => {
                 let mut last_num = 0;
-                for (line_num, line_result) in file.lines().enumerate() {
-                    let line = line_result?;
-
-                    if config.number_lines {
-                        println!("{:>6}\t{}", line_num + 1, line);
-                    } else if config.number_nonblank_lines {
-                        if !line.is_empty() {
-                            last_num += 1;
-                            println!("{:>6}\t{}", last_num, line);
-                        } else {
-                            println!();
-                        }
+                let mut buf = vec![];
+                let mut rendered = vec![];
+                loop {
+                    buf.clear();
+                    if file.read_until(b'\n', &mut buf)? == 0 {
+                        break;
+                    }
+                    let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+                    let blank = at_line_start && buf == b"\n";
+                    if config.squeeze_blank && blank && prev_blank {
+                        continue;
+                    }
+                    prev_blank = blank;
+                    let number =
+                        config.number_lines || (config.number_nonblank_lines && !line.is_empty());
+                    if number && at_line_start {
+                        last_num += 1;
+                        write!(out, "{:>6}\t", last_num)?;
+                    }
+                    if config.show.any() {
+                        rendered.clear();
+                        render(&buf, config.show, &mut rendered);
+                        out.write_all(&rendered)?;
                     } else {
-                        println!("{}", line);
+                        out.write_all(&buf)?;
                     }
+                    at_line_start = buf.ends_with(b"\n");
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(status)
+}
+
+/// Opens the `--output` file, refusing one that is also an input since
+/// it would be truncated before it is read, or grow while being read.
+fn create_output(path: &str, files: &[FileArg], append: bool) -> Result<fs::File, Error> {
+    if let Ok(meta) = fs::metadata(path) {
+        let same = |file: &&FileArg| {
+            file.path()
+                .and_then(|input| fs::metadata(input).ok())
+                .is_some_and(|input| input.dev() == meta.dev() && input.ino() == meta.ino())
+        };
+        if let Some(file) = files.iter().find(same) {
+            return Err(Error::InputIsOutput(file.to_string()));
+        }
+    }
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .map_err(|source| Error::Create {
+            path: path.to_string(),
+            source,
+        })
+}
+
+/// Appends `line` to `out` with the characters `show` asks for made
+/// visible the way GNU cat does it.
+fn render(line: &[u8], show: Show, out: &mut Vec<u8>) {
+    for (i, &byte) in line.iter().enumerate() {
+        match byte {
+            b'\n' if show.ends => out.extend_from_slice(b"$\n"),
+            b'\n' => out.push(byte),
+            b'\t' if !show.tabs => out.push(byte),
+            // -E だけでもCRLFの CR は見えるようにする（GNU cat と同じ）
+            b'\r' if show.ends && line.get(i + 1) == Some(&b'\n') => out.extend_from_slice(b"^M"),
+            b'\t' => out.extend_from_slice(b"^I"),
+            _ if !show.nonprinting => out.push(byte),
+            _ => {
+                let mut byte = byte;
+                if byte >= 128 {
+                    out.extend_from_slice(b"M-");
+                    byte -= 128;
+                }
+                match byte {
+                    0..=31 => out.extend_from_slice(&[b'^', byte + 64]),
+                    127 => out.extend_from_slice(b"^?"),
+                    _ => out.push(byte),
                 }
             }
         }
     }
-    Ok(())
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+#[cfg(test)]
+mod tests {
+    use clir_common::{FileArg, ToolError};
+
+    use super::{render, run, run_with, Config, Show};
+
+    #[test]
+    fn test_run_writers() {
+        let config = Config {
+            files: vec![
+                FileArg::from("tests/inputs/fox.txt"),
+                FileArg::from("tests/inputs/missing.txt"),
+            ],
+            number_lines: true,
+            number_nonblank_lines: false,
+            squeeze_blank: false,
+            show: Show::default(),
+            decompress: false,
+            output: None,
+            append: false,
+        };
+        let mut out = vec![];
+        let mut err = vec![];
+        assert_eq!(run(config, &mut out, &mut err).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "     1\tThe quick brown fox jumps over the lazy dog.\n"
+        );
+        assert!(String::from_utf8(err)
+            .unwrap()
+            .starts_with("catr: tests/inputs/missing.txt: "));
+    }
+
+    #[test]
+    fn test_render() {
+        let render_with = |line: &[u8], nonprinting, ends, tabs| {
+            let mut out = vec![];
+            let show = Show {
+                nonprinting,
+                ends,
+                tabs,
+            };
+            render(line, show, &mut out);
+            String::from_utf8(out).unwrap()
+        };
+        let line = b"a\tb\x01\x7f\x80\x89\xff\r\n";
+        assert_eq!(
+            render_with(line, true, true, true),
+            "a^Ib^A^?M-^@M-^IM-^?^M$\n"
+        );
+        assert_eq!(
+            render_with(line, true, false, false),
+            "a\tb^A^?M-^@M-^IM-^?^M\n"
+        );
+        assert_eq!(render_with(b"a\tb\r\n", false, true, false), "a\tb^M$\n");
+        assert_eq!(render_with(b"a\tb\r", false, true, true), "a^Ib\r");
+    }
+
+    #[test]
+    fn test_run_with() {
+        let mut out = vec![];
+        let mut err = vec![];
+        let status = run_with(
+            ["catr", "-b", "tests/inputs/spiders.txt"],
+            &mut out,
+            &mut err,
+        );
+        assert_eq!(status.unwrap(), 0);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "     1\tDon't worry, spiders,\n     2\tI keep house\n     3\tcasually.\n"
+        );
+        assert!(err.is_empty());
+
+        let mut out = vec![];
+        assert_eq!(
+            run_with(["catr", "--version"], &mut out, &mut err).unwrap(),
+            0
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), "catr 0.1.0\n");
+
+        let err = run_with(["catr", "--bogus"], &mut vec![], &mut vec![]).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
     }
 }
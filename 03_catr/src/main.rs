@@ -1,6 +1,18 @@
+use std::io;
+use std::io::{BufWriter, IsTerminal, Write};
+
 fn main() {
-    if let Err(e) = catr::get_args().and_then(catr::run) {
-        eprint!("{}", e);
-        std::process::exit(1);
+    let stdout = io::stdout();
+    // 端末にはすぐ見えるよう行ごとに、それ以外にはまとめて書き出す
+    let mut out: Box<dyn Write> = if stdout.is_terminal() {
+        Box::new(stdout.lock())
+    } else {
+        Box::new(BufWriter::new(stdout.lock()))
+    };
+    let result =
+        catr::get_args().and_then(|config| catr::run(config, &mut out, &mut io::stderr().lock()));
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("catr", &e)),
     }
 }
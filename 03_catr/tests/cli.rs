@@ -1,10 +1,11 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, gen_text, Fixture, GnuCompare, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-use std::error::Error;
 use std::fs;
-
-type TestResult = Result<(), Box<dyn Error>>;
+use std::io::{Read, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::process::Stdio;
+use std::thread;
 
 const PRG: &str = "catr";
 const EMPTY: &str = "tests/inputs/empty.txt";
@@ -25,18 +26,55 @@ fn usage() -> TestResult {
 }
 
 // --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
+#[test]
+fn usage_ja() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--help")
+        .env("LC_ALL", "ja_JP.UTF-8")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("使い方: catr"))
+        .stdout(predicate::str::contains("ヘルプを表示する"))
+        .stdout(predicate::str::contains("行番号を付ける"));
+    Ok(())
+}
 
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
+// --------------------------------------------------
+#[test]
+fn completions() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("_catr()"))
+        .stdout(predicate::str::contains("--number-nonblank"))
+        .stdout(predicate::str::contains("--completions").not());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_completions() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--completions", "tcsh"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid value 'tcsh'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn man() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--man")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(".ie"))
+        .stdout(predicate::str::contains(".TH catr 1"))
+        .stdout(predicate::str::contains("Rust cat"))
+        .stdout(predicate::str::contains("\\-\\-number\\-nonblank"));
+    Ok(())
 }
 
 // --------------------------------------------------
@@ -47,11 +85,52 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
+        .code(1)
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file_ja() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!(
+        "{}: そのようなファイルやディレクトリはありません [(]os error 2[)]",
+        bad
+    );
+    Command::cargo_bin(PRG)?
+        .arg(&bad)
+        .env("LC_ALL", "ja_JP.UTF-8")
+        .assert()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn skips_directory() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", FOX])
+        .assert()
+        .code(1)
+        .stderr("catr: tests/inputs: Is a directory (os error 21)\n")
+        .stdout("The quick brown fox jumps over the lazy dog.\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dev_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "/dev/stdin"])
+        .write_stdin("hello\n")
+        .assert()
+        .success()
+        .stdout("     1\thello\n");
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     let expected = fs::read_to_string(expected_file)?;
@@ -63,6 +142,36 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn quiet_on_closed_pipe() -> TestResult {
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // パイプの容量を超える量を流して、catrが書き込みで詰まるようにする
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || {
+        let line = "The quick brown fox jumps over the lazy dog.\n".repeat(1024);
+        for _ in 0..64 {
+            if stdin.write_all(line.as_bytes()).is_err() {
+                break;
+            }
+        }
+    });
+    let mut buf = [0; 10];
+    child.stdout.as_mut().unwrap().read_exact(&mut buf)?;
+    assert_eq!(&buf, b"The quick ");
+
+    drop(child.stdout.take());
+    let output = child.wait_with_output()?;
+    writer.join().unwrap();
+    assert_eq!(output.status.signal(), Some(13));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run_stdin(input_file: &str, args: &[&str], expected_file: &str) -> TestResult {
     let input = fs::read_to_string(input_file)?;
@@ -194,3 +303,257 @@ fn all_n() -> TestResult {
 fn all_b() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn decompress() -> TestResult {
+    let expected = [
+        fs::read_to_string("tests/expected/the-bustle.txt.out")?,
+        fs::read_to_string("tests/expected/spiders.txt.out")?,
+        fs::read_to_string("tests/expected/fox.txt.out")?,
+        fs::read_to_string("tests/expected/fox.txt.out")?,
+    ]
+    .concat();
+    Command::cargo_bin(PRG)?
+        .args([
+            "--decompress",
+            "tests/inputs/the-bustle.txt.gz",
+            "tests/inputs/spiders.txt.xz",
+            "tests/inputs/fox.txt.zst",
+            "tests/inputs/fox.txt.bz2",
+        ])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_stdin() -> TestResult {
+    let input = fs::read("tests/inputs/the-bustle.txt.gz")?;
+    let expected = fs::read_to_string("tests/expected/the-bustle.txt.n.stdin.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--decompress", "-n", "-"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn same_as_gnu() -> TestResult {
+    for flag in ["-n", "-b"] {
+        GnuCompare::new(PRG, "cat")
+            .args([flag])
+            .stdin(gen_text(200, 1))
+            .assert()?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn show_all() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-A", "-n"])
+        .write_stdin("a\tb\x01\r\n\nend")
+        .assert()
+        .success()
+        .stdout("     1\ta^Ib^A^M$\n     2\t$\n     3\tend");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn show_same_as_gnu() -> TestResult {
+    let mut input = gen_text(100, 2).into_bytes();
+    input.extend_from_slice(b"\x00\x1b[0m\x7f\x80\xfe\r\n\r\nno newline\r");
+    for flag in ["-A", "-v", "-E", "-T", "-e", "-t", "-vn"] {
+        GnuCompare::new(PRG, "cat")
+            .args([flag])
+            .stdin(input.clone())
+            .assert()?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn squeeze_blank() -> TestResult {
+    let input = "a\n\n\n\nb\n\r\n\r\n\n\n";
+    Command::cargo_bin(PRG)?
+        .arg("-s")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("a\n\nb\n\r\n\r\n\n");
+    Command::cargo_bin(PRG)?
+        .args(["-s", "-n"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("     1\ta\n     2\t\n     3\tb\n     4\t\r\n     5\t\r\n     6\t\n");
+    for flag in ["-s", "-sn", "-sb", "-sE"] {
+        GnuCompare::new(PRG, "cat")
+            .args([flag])
+            .stdin(input)
+            .assert()?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn squeeze_blank_across_files() -> TestResult {
+    let fixture = Fixture::new()?;
+    let a = fixture.file("a.txt", "a\n\n")?;
+    let b = fixture.file("b.txt", "\nb\n")?;
+    Command::cargo_bin(PRG)?
+        .args(["-s", &a, &b])
+        .assert()
+        .success()
+        .stdout("a\n\nb\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_safe() -> TestResult {
+    let mut input: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+    input.extend_from_slice(b"\r\nno newline\r");
+    let fixture = Fixture::new()?;
+    let file = fixture.file("data.bin", &input)?;
+    let output = Command::cargo_bin(PRG)?.arg(&file).output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, input);
+
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input.clone())
+        .output()?;
+    assert_eq!(output.stdout, input);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_trailing_newline() -> TestResult {
+    let fixture = Fixture::new()?;
+    let a = fixture.file("a.txt", "a")?;
+    let b = fixture.file("b.txt", "\nb")?;
+    Command::cargo_bin(PRG)?
+        .args([&a, &b])
+        .assert()
+        .success()
+        .stdout("a\nb");
+    // 前のファイルの最後の行の続きには行番号を付けない
+    Command::cargo_bin(PRG)?
+        .args(["-n", &a, &b])
+        .assert()
+        .success()
+        .stdout("     1\ta\n     1\tb");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_file() -> TestResult {
+    let fixture = Fixture::new()?;
+    let out = fixture.file("out.txt", "old\n")?;
+    Command::cargo_bin(PRG)?
+        .args(["-n", FOX, "-o", &out])
+        .assert()
+        .success()
+        .stdout("");
+    let expected = fs::read_to_string("tests/expected/fox.txt.n.out")?;
+    assert_eq!(fs::read_to_string(&out)?, expected);
+
+    Command::cargo_bin(PRG)?
+        .args(["--output", &out, "--append", FOX])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(&out)?,
+        expected + &fs::read_to_string(FOX)?
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_input_is_output() -> TestResult {
+    let fixture = Fixture::new()?;
+    let file = fixture.file("a.txt", "a\n")?;
+    Command::cargo_bin(PRG)?
+        .args([FOX, &file, "--output", &file, "--append"])
+        .assert()
+        .code(1)
+        .stderr(format!("catr: {}: input file is output file\n", file));
+    // 出力先は切り詰められずに残る
+    assert_eq!(fs::read_to_string(&file)?, "a\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_append_without_output() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--append", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--output"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn number_nonblank_overrides_number() -> TestResult {
+    for args in [["-n", "-b"], ["-b", "-n"]] {
+        run(
+            &[args[0], args[1], BUSTLE],
+            "tests/expected/the-bustle.txt.b.out",
+        )?;
+        GnuCompare::new(PRG, "cat")
+            .args(args)
+            .stdin(gen_text(50, 3))
+            .assert()?;
+    }
+    run(&["-nb", FOX, SPIDERS, BUSTLE], "tests/expected/all.b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_between_files() -> TestResult {
+    let fox = fs::read_to_string(FOX)?;
+    Command::cargo_bin(PRG)?
+        .args([FOX, "-", FOX])
+        .write_stdin("middle\n")
+        .assert()
+        .success()
+        .stdout(format!("{}middle\n{}", fox, fox));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_twice() -> TestResult {
+    // 2回目の「-」は読み切った標準入力なので何も出さない
+    Command::cargo_bin(PRG)?
+        .args(["-", "-"])
+        .write_stdin("a\n\nb\n")
+        .assert()
+        .success()
+        .stdout("a\n\nb\n");
+    Command::cargo_bin(PRG)?
+        .args(["-n", "-", "/dev/stdin"])
+        .write_stdin("a\n\nb\n")
+        .assert()
+        .success()
+        .stdout("     1\ta\n     2\t\n     3\tb\n");
+    GnuCompare::new(PRG, "cat")
+        .args(["-n", "-", "-"])
+        .stdin("a\nb\n")
+        .assert()
+}
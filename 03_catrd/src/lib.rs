@@ -1,16 +1,16 @@
-use std::error::Error;
-use std::fs::File;
-use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, Write};
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{open_decompressed, CommandExt, ToolError};
+use thiserror::Error;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Rust cat")]
 pub struct Args {
     #[arg(
     value_name = "FILE",
-    help = "Input file(s)",
+    help = tr_str("Input file(s)", "入力ファイル"),
     action = clap::ArgAction::Append,
     default_value = "-",
     )]
@@ -18,7 +18,7 @@ pub struct Args {
     #[arg(
         short = 'n',
         long = "number",
-        help = "Number lines",
+        help = tr_str("Number lines", "行番号を付ける"),
         conflicts_with = "number_nonblank"
     )]
     number_lines: bool,
@@ -26,36 +26,53 @@ pub struct Args {
         id = "number_nonblank",
         short = 'b',
         long = "number-nonblank",
-        help = "Number non-blank lines"
+        help = tr_str("Number non-blank lines", "空行以外に行番号を付ける")
     )]
     number_nonblank_lines: bool,
 }
-pub fn get_args() -> MyResult<Args> {
-    Ok(Args::parse())
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
 }
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Args, Error> {
+    let matches = Args::command().get_tool_matches();
+    Ok(Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
+}
 
-pub fn run(args: Args) -> MyResult<()> {
+pub fn run(args: Args, out: &mut impl Write, err: &mut impl Write) -> Result<(), Error> {
     for filename in args.files {
-        match open(&filename) {
-            Err(err) => eprintln!("Failed to open {}: {}", filename, err),
+        // zcat のように、圧縮されたファイルはそのまま展開して表示する
+        match open_decompressed(&filename) {
+            Err(e) => writeln!(
+                err,
+                "{}",
+                tr_text(
+                    "Failed to open {0}: {1}",
+                    "{0} を開けません: {1}",
+                    &[&filename, &tr_io(&e)]
+                )
+            )?,
             Ok(file) => {
                 let mut last_num = 0;
                 for (line_num, line_result) in file.lines().enumerate() {
                     let line = line_result?;
 
                     if args.number_lines {
-                        println!("{:>6}\t{}", line_num + 1, line);
+                        writeln!(out, "{:>6}\t{}", line_num + 1, line)?;
                     } else if args.number_nonblank_lines {
                         if !line.is_empty() {
                             last_num += 1;
-                            println!("{:>6}\t{}", last_num, line);
+                            writeln!(out, "{:>6}\t{}", last_num, line)?;
                         } else {
-                            println!();
+                            writeln!(out)?;
                         }
                     } else {
-                        println!("{}", line);
+                        writeln!(out, "{}", line)?;
                     }
                 }
             }
@@ -63,10 +80,3 @@ pub fn run(args: Args) -> MyResult<()> {
     }
     Ok(())
 }
-
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
-}
@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = catrd::get_args().and_then(catrd::run) {
-        eprint!("{}", e);
-        std::process::exit(1);
+    let result = catrd::get_args()
+        .and_then(|args| catrd::run(args, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("catrd", &e));
     }
 }
@@ -1,11 +1,8 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-use std::error::Error;
 use std::fs;
 
-type TestResult = Result<(), Box<dyn Error>>;
-
 const PRG: &str = "catrd";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
@@ -25,18 +22,27 @@ fn usage() -> TestResult {
 }
 
 // --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
+#[test]
+fn completions() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("_catrd()"))
+        .stdout(predicate::str::contains("--number-nonblank"));
+    Ok(())
+}
 
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
+// --------------------------------------------------
+#[test]
+fn man() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--man")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".TH catrd 1"))
+        .stdout(predicate::str::contains("Rust cat"));
+    Ok(())
 }
 
 // --------------------------------------------------
@@ -194,3 +200,38 @@ fn all_n() -> TestResult {
 fn all_b() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn decompress() -> TestResult {
+    let expected = [
+        fs::read_to_string("tests/expected/the-bustle.txt.n.out")?,
+        fs::read_to_string("tests/expected/spiders.txt.n.out")?,
+        fs::read_to_string("tests/expected/fox.txt.n.out")?,
+    ]
+    .concat();
+    Command::cargo_bin(PRG)?
+        .args([
+            "-n",
+            "tests/inputs/the-bustle.txt.gz",
+            "tests/inputs/spiders.txt.xz",
+            "tests/inputs/fox.txt.zst",
+        ])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_stdin() -> TestResult {
+    let input = fs::read("tests/inputs/the-bustle.txt.gz")?;
+    let expected = fs::read_to_string("tests/expected/the-bustle.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
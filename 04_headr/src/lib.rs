@@ -1,104 +1,399 @@
-use clap::{value_parser, Arg, ArgAction, Command};
-use std::error::Error;
-use std::fs::File;
+use clap::{Arg, ArgAction, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{
+    parse_size, read_files0, warn_to, CommandExt, FileArg, ParseError, Terminator, ToolError,
+    EXIT_FAILURE,
+};
+use std::collections::VecDeque;
 use std::io;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{BufRead, Read, Write};
+use thiserror::Error;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+use crate::Count::{AllButLast, First};
+
+/// How many lines or bytes to print: the first N (`-n 5`) or all but the
+/// last N (`-n -5`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Count {
+    First(u64),
+    AllButLast(u64),
+}
 
 #[derive(Debug)]
 pub struct Config {
-    files: Vec<String>,
-    lines: u64,
-    bytes: Option<u64>,
+    files: Vec<FileArg>,
+    lines: Count,
+    bytes: Option<Count>,
+    quiet: bool,
+    verbose: bool,
+    terminator: Terminator,
+    decompress: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
 }
 
-pub fn get_args() -> MyResult<Config> {
-    let matches = Command::new("headr")
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = cli().get_tool_matches();
+
+    let files = match matches.get_one::<String>("files0_from") {
+        Some(list) => read_files0(&FileArg::from(list))
+            .and_then(|names| names.collect())
+            .map_err(|source| Error::Open {
+                path: list.to_string(),
+                source,
+            })?,
+        None => matches
+            .get_many::<String>("files")
+            .expect("files required")
+            .map(FileArg::from)
+            .collect::<Vec<_>>(),
+    };
+
+    let lines: Count = *matches.get_one("lines").expect("illegal state");
+
+    let bytes: Option<Count> = matches.get_one("bytes").copied();
+
+    Ok(Config {
+        files,
+        lines,
+        bytes,
+        quiet: matches.get_flag("quiet"),
+        verbose: matches.get_flag("verbose"),
+        terminator: if matches.get_flag("zero_terminated") {
+            Terminator::Nul
+        } else {
+            Terminator::Newline
+        },
+        decompress: matches.get_flag("decompress"),
+    })
+}
+
+fn cli() -> Command {
+    Command::new("headr")
         .version("0.1.0")
         .about("Rust head")
         .arg(
             Arg::new("files")
                 .value_name("FILE")
-                .help("Input file(s)")
+                .help(tr_str("Input file(s)", "入力ファイル"))
                 .action(ArgAction::Append)
                 .default_value("-"),
         )
+        .arg(
+            Arg::new("files0_from")
+                .long("files0-from")
+                .value_name("F")
+                .help(tr_str(
+                    "Read NUL-separated file names from F (- for stdin)",
+                    "NUL区切りのファイル名をFから読む(- なら標準入力)",
+                ))
+                .conflicts_with("files"),
+        )
         .arg(
             Arg::new("lines")
                 .short('n')
                 .long("lines")
-                .help("Number of lines")
+                .help(tr_str(
+                    "Number of lines, or all but the last N with -N (suffixes like K, M allowed)",
+                    "行数(-N なら末尾のN行以外すべて、K や M などの接尾辞が使える)",
+                ))
                 .default_value("10")
-                .value_parser(value_parser!(u64).range(1..)),
+                .allow_hyphen_values(true)
+                .value_parser(parse_count),
         )
         .arg(
             Arg::new("bytes")
                 .short('c')
                 .long("bytes")
                 .conflicts_with("lines")
-                .help("Number of bytes")
-                .value_parser(value_parser!(u64).range(1..)),
+                .help(tr_str(
+                    "Number of bytes, or all but the last N with -N (suffixes like K, M allowed)",
+                    "バイト数(-N なら末尾のNバイト以外すべて、K や M などの接尾辞が使える)",
+                ))
+                .allow_hyphen_values(true)
+                .value_parser(parse_count),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .alias("silent")
+                .help(tr_str("Never print headers", "ヘッダを表示しない"))
+                .overrides_with("verbose")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help(tr_str("Always print headers", "常にヘッダを表示する"))
+                .overrides_with("quiet")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("zero_terminated")
+                .short('z')
+                .long("zero-terminated")
+                .help(tr_str(
+                    "Line delimiter is NUL, not newline",
+                    "行の区切りを改行ではなくNULにする",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("decompress")
+                .long("decompress")
+                .help(tr_str(
+                    "Decompress gzip, bzip2, xz and zstd input",
+                    "gzip、bzip2、xz、zstdの入力を展開する",
+                ))
+                .action(ArgAction::SetTrue),
         )
-        .get_matches();
-
-    let files = matches
-        .get_many::<String>("files")
-        .expect("files required")
-        .map(|v| v.to_string())
-        .collect::<Vec<_>>();
-
-    let lines: u64 = *matches.get_one("lines").expect("illegal state");
-
-    let bytes: Option<u64> = matches.get_one("bytes").copied();
-
-    Ok(Config {
-        files,
-        lines,
-        bytes,
-    })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    let open = if config.decompress {
+        FileArg::open_decompressed
+    } else {
+        FileArg::open
+    };
     let num_files = config.files.len();
+    // 開けなかったファイルは見出しを出さないので、区切りの空行は実際に出した見出しで決める
+    let mut first_header = true;
 
-    for (file_num, filename) in config.files.iter().enumerate() {
+    for filename in &config.files {
         match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(mut file) => {
-                if num_files > 1 {
-                    println!(
+            Err(source) => {
+                warn_to(
+                    err,
+                    "headr",
+                    &Error::Open {
+                        path: filename.to_string(),
+                        source,
+                    },
+                );
+                status = EXIT_FAILURE;
+            }
+            Ok(file) => {
+                if config.verbose || (!config.quiet && num_files > 1) {
+                    writeln!(
+                        out,
                         "{}==> {} <==",
-                        if file_num > 0 { "\n" } else { "" },
+                        if first_header { "" } else { "\n" },
                         &filename
-                    );
+                    )?;
+                    first_header = false;
                 }
 
-                if let Some(num_bytes) = config.bytes {
-                    let mut handle = file.take(num_bytes);
-                    let mut buffer = vec![0; num_bytes as usize];
-                    let bytes_read = handle.read(&mut buffer)?;
-                    print!("{}", String::from_utf8_lossy(&buffer[..bytes_read]));
-                } else {
-                    let mut line = String::new();
-                    for _ in 0..config.lines {
-                        let bytes = file.read_line(&mut line)?;
-                        if bytes == 0 {
-                            break;
-                        }
-                        print!("{}", line);
-                        line.clear();
-                    }
+                match config.bytes {
+                    Some(num_bytes) => head_bytes(file, num_bytes, out)?,
+                    None => head_lines(file, config.lines, config.terminator, out)?,
                 }
             }
         }
     }
+    Ok(status)
+}
+
+/// Parses a `-n`/`-c` value. A leading `-` means "all but the last N".
+pub fn parse_count(val: &str) -> Result<Count, ParseError> {
+    match val.strip_prefix('-') {
+        Some(size) => parse_size(size).map(AllButLast),
+        None => parse_size(val.strip_prefix('+').unwrap_or(val)).map(First),
+    }
+}
+
+/// Writes the lines of `reader` selected by `count` to `out`. Lines end
+/// with `terminator`, which is written along with them.
+pub fn head_lines(
+    reader: impl BufRead,
+    count: Count,
+    terminator: Terminator,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    match count {
+        First(num_lines) => print_first_lines(reader, num_lines, terminator, out),
+        AllButLast(num_lines) => print_all_but_last_lines(reader, num_lines, terminator, out),
+    }
+}
+
+/// Writes the bytes of `reader` selected by `count` to `out`.
+pub fn head_bytes(reader: impl Read, count: Count, out: &mut impl Write) -> io::Result<()> {
+    match count {
+        First(num_bytes) => print_first_bytes(reader, num_bytes, out),
+        AllButLast(num_bytes) => print_all_but_last_bytes(reader, num_bytes, out),
+    }
+}
+
+fn print_first_bytes(file: impl Read, num_bytes: u64, out: &mut impl Write) -> io::Result<()> {
+    // -cの値に関わらず、io::copyの固定長バッファで少しずつ書き出す
+    io::copy(&mut file.take(num_bytes), out).map(|_| ())
+}
+
+fn print_all_but_last_bytes(
+    mut file: impl Read,
+    num_bytes: u64,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut pending = VecDeque::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        pending.extend(&buffer[..bytes_read]);
+        let excess = (pending.len() as u64).saturating_sub(num_bytes) as usize;
+        let (front, back) = pending.as_slices();
+        let split = excess.min(front.len());
+        out.write_all(&front[..split])?;
+        out.write_all(&back[..excess - split])?;
+        pending.drain(..excess);
+    }
+    Ok(())
+}
+
+fn print_first_lines(
+    mut file: impl BufRead,
+    num_lines: u64,
+    terminator: Terminator,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    for _ in 0..num_lines {
+        let bytes = file.read_until(terminator.byte(), &mut line)?;
+        if bytes == 0 {
+            break;
+        }
+        out.write_all(&line)?;
+        line.clear();
+    }
+    Ok(())
+}
+
+fn print_all_but_last_lines(
+    mut file: impl BufRead,
+    num_lines: u64,
+    terminator: Terminator,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    // 末尾のN行になるかもしれない行だけを持ち、溢れた行から順に出力する
+    let mut pending = VecDeque::new();
+    loop {
+        let mut line = Vec::new();
+        if file.read_until(terminator.byte(), &mut line)? == 0 {
+            break;
+        }
+        pending.push_back(line);
+        if pending.len() as u64 > num_lines {
+            if let Some(line) = pending.pop_front() {
+                out.write_all(&line)?;
+            }
+        }
+    }
     Ok(())
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+#[cfg(test)]
+mod tests {
+    use clir_common::Terminator;
+    use proptest::prelude::*;
+
+    use super::{cli, head_bytes, head_lines, parse_count, Count};
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("3").unwrap(), Count::First(3));
+        assert_eq!(parse_count("+3").unwrap(), Count::First(3));
+        assert_eq!(parse_count("-3").unwrap(), Count::AllButLast(3));
+        assert_eq!(parse_count("-0").unwrap(), Count::AllButLast(0));
+        assert_eq!(parse_count("4K").unwrap(), Count::First(4096));
+        assert_eq!(parse_count("-2MB").unwrap(), Count::AllButLast(2_000_000));
+        assert!(parse_count("--3").is_err());
+        assert!(parse_count("-").is_err());
+        assert!(parse_count("4X").is_err());
+    }
+
+    fn lines(input: &[u8], count: Count, terminator: Terminator) -> Vec<u8> {
+        let mut out = Vec::new();
+        head_lines(input, count, terminator, &mut out).unwrap();
+        out
+    }
+
+    fn bytes(input: &[u8], count: Count) -> Vec<u8> {
+        let mut out = Vec::new();
+        head_bytes(input, count, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_head_lines() {
+        let newline = Terminator::Newline;
+        assert_eq!(lines(b"a\nb\nc", Count::First(2), newline), b"a\nb\n");
+        assert_eq!(lines(b"a\nb\nc", Count::First(5), newline), b"a\nb\nc");
+        assert_eq!(lines(b"a\nb\nc", Count::First(0), newline), b"");
+        assert_eq!(lines(b"a\nb\nc", Count::AllButLast(1), newline), b"a\nb\n");
+        assert_eq!(lines(b"a\nb\n", Count::AllButLast(5), newline), b"");
+        assert_eq!(
+            lines(b"a\nb\0c\0", Count::First(1), Terminator::Nul),
+            b"a\nb\0"
+        );
+        assert_eq!(
+            lines(b"a\0b\0", Count::AllButLast(1), Terminator::Nul),
+            b"a\0"
+        );
+    }
+
+    #[test]
+    fn test_head_bytes() {
+        let input: Vec<u8> = (0..20000).map(|n| n as u8).collect();
+        assert_eq!(bytes(&input, Count::First(100)), &input[..100]);
+        assert_eq!(bytes(&input, Count::First(30000)), input);
+        assert_eq!(bytes(&input, Count::AllButLast(100)), &input[..19900]);
+        assert_eq!(bytes(&input, Count::AllButLast(30000)), b"");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_count_accepts_positive(num in 1..=u64::MAX) {
+            let val = num.to_string();
+            let matches = cli().try_get_matches_from(["headr", "-n", &val]).unwrap();
+            prop_assert_eq!(matches.get_one::<Count>("lines"), Some(&Count::First(num)));
+            let matches = cli().try_get_matches_from(["headr", "-c", &val]).unwrap();
+            prop_assert_eq!(matches.get_one::<Count>("bytes"), Some(&Count::First(num)));
+        }
+
+        #[test]
+        fn prop_count_accepts_negative(num in 1..=u64::MAX) {
+            let val = format!("--lines=-{}", num);
+            let matches = cli().try_get_matches_from(["headr", &val]).unwrap();
+            prop_assert_eq!(matches.get_one::<Count>("lines"), Some(&Count::AllButLast(num)));
+        }
+
+        #[test]
+        fn prop_count_matches_u64_parse(val in "[0-9]{1,19}") {
+            // 単位のない数値はu64としてそのまま読む
+            let expected = val.parse::<u64>().map(Count::First).ok();
+            let arg = format!("--bytes={}", val);
+            let res = cli().try_get_matches_from(["headr", &arg]);
+            prop_assert_eq!(res.ok().and_then(|m| m.get_one::<Count>("bytes").copied()), expected);
+        }
+
+        #[test]
+        fn prop_count_matches_parse_count(val in "\\PC{0,24}") {
+            let expected = parse_count(&val).ok();
+            let arg = format!("--bytes={}", val);
+            let res = cli().try_get_matches_from(["headr", &arg]);
+            prop_assert_eq!(res.ok().and_then(|m| m.get_one::<Count>("bytes").copied()), expected);
+        }
     }
 }
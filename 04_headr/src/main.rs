@@ -1,6 +1,10 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = headr::get_args().and_then(headr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = headr::get_args()
+        .and_then(|config| headr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("headr", &e)),
     }
 }
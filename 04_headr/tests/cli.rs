@@ -1,13 +1,10 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, gen_text, random_string, Fixture, GnuCompare, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-use std::io::prelude::*;
-use std::{
-    error::Error,
-    fs::{self, File},
-};
-
-type TestResult = Result<(), Box<dyn Error>>;
+use std::fs;
+use std::io::Read;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Stdio;
 
 const PRG: &str = "headr";
 const EMPTY: &str = "./tests/inputs/empty.txt";
@@ -16,35 +13,16 @@ const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TEN: &str = "./tests/inputs/ten.txt";
 
-// --------------------------------------------------
-fn random_string() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(7)
-        .map(char::from)
-        .collect()
-}
-
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename = random_string();
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!(
-        "error: invalid value '{}' for '--bytes <bytes>': invalid digit found in string",
-        &bad
+        "error: invalid value '{}' for '--bytes <bytes>': invalid size \"{}\"",
+        &bad, &bad
     );
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -57,11 +35,11 @@ fn dies_bad_bytes() -> TestResult {
 fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!(
-        "error: invalid value '{}' for '--lines <lines>': invalid digit found in string",
-        &bad
+        "error: invalid value '{}' for '--lines <lines>': invalid size \"{}\"",
+        &bad, &bad
     );
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -75,7 +53,7 @@ fn dies_bytes_and_lines() -> TestResult {
     let msg = "error: the argument '--lines <lines>' cannot be used with '--bytes <bytes>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -91,42 +69,79 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .args([EMPTY, &bad, ONE])
         .assert()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
 
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn skips_bad_file_keeps_going() -> TestResult {
+    // 開けなかったファイルがあっても残りは出力し、終了コードは1にする
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["-n", "1", &bad, TEN, "tests", ONE])
+        .assert()
+        .code(1)
+        .stdout(format!(
+            "==> {} <==\none\n\n==> {} <==\n{}",
+            TEN,
+            ONE,
+            fs::read_to_string(ONE)?
+        ))
+        .stderr(predicate::str::contains("tests: Is a directory"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn quiet_on_closed_pipe() -> TestResult {
+    let fixture = Fixture::new()?;
+    let big = fixture.file("big.txt", gen_text(100_000, 1))?;
+    for count in [["-n", "1000000"], ["-c", "100M"]] {
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .args(count)
+            .arg(&big)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // パイプの容量を超える量を出すので、読み手が閉じると書き込みで止まる
+        let mut buf = [0; 10];
+        child.stdout.as_mut().unwrap().read_exact(&mut buf)?;
+
+        drop(child.stdout.take());
+        let output = child.wait_with_output()?;
+        assert_eq!(output.status.signal(), Some(13));
+        assert!(output.stderr.is_empty());
+    }
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
+    // -cは文字の途中で切れることがあるので、バイト列のまま比べる
+    let expected = fs::read(expected_file)?;
 
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(expected);
 
     Ok(())
 }
 
 // --------------------------------------------------
 fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
-    // Extra work here due to lossy UTF
-    let mut file = File::open(expected_file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let expected = String::from_utf8_lossy(&buffer);
-    let input = fs::read_to_string(input_file)?;
+    let expected = fs::read(expected_file)?;
+    let input = fs::read(input_file)?;
 
     Command::cargo_bin(PRG)?
         .write_stdin(input)
         .args(args)
         .assert()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(expected);
 
     Ok(())
 }
@@ -420,3 +435,157 @@ fn multiple_files_c4() -> TestResult {
         "tests/expected/all.c4.out",
     )
 }
+
+#[test]
+fn ten_n_minus3() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "-3", TEN])
+        .assert()
+        .success()
+        .stdout("one\ntwo\nthree\nfour\nfive\nsix\nseven\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_n_minus8_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--lines=-8")
+        .write_stdin(fs::read_to_string(TEN)?)
+        .assert()
+        .success()
+        .stdout("one\ntwo\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_quiet() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-q", "-n", "1", ONE, TEN])
+        .assert()
+        .success()
+        .stdout(format!("{}one\n", fs::read_to_string(ONE)?));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn one_verbose() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-v", "-n", "2", TEN])
+        .assert()
+        .success()
+        .stdout(format!("==> {} <==\none\ntwo\n", TEN));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-n", "2"])
+        .write_stdin("a\nb\0c\0d\0")
+        .assert()
+        .success()
+        .stdout("a\nb\0c\0");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from=-", "-n", "1"])
+        .write_stdin(format!("{}\0{}\0", TEN, ONE))
+        .assert()
+        .success()
+        .stdout(format!(
+            "==> {} <==\none\n\n==> {} <==\n{}",
+            TEN,
+            ONE,
+            fs::read_to_string(ONE)?
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_with_files() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from=-", ONE])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_empty_name() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=-")
+        .write_stdin(format!("{}\0\0", ONE))
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr("headr: -: invalid zero-length file name\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_c_minus4() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "-4", TEN])
+        .assert()
+        .success()
+        .stdout("one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_c_suffix() -> TestResult {
+    run(&["-c", "1K", TEN], "tests/expected/ten.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ten_c_huge() -> TestResult {
+    // 指定したバイト数のバッファを確保しないこと
+    run(&["-c", "10000000000", TEN], "tests/expected/ten.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress() -> TestResult {
+    run(
+        &["--decompress", "-n", "2", "tests/inputs/ten.txt.gz"],
+        "tests/expected/ten.txt.n2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn same_as_gnu() -> TestResult {
+    let fixture = Fixture::new()?;
+    let a = fixture.file("a.txt", gen_text(30, 1))?;
+    let b = fixture.file("b.txt", gen_text(3, 2))?;
+    for args in [
+        vec!["-n", "5"],
+        vec!["-c", "7"],
+        vec!["-n", "-5"],
+        vec!["-c", "-9"],
+        vec!["-n", "1K"],
+        vec!["-q"],
+        vec!["-v", "-q"],
+        vec![],
+    ] {
+        GnuCompare::new(PRG, "head")
+            .args(&args)
+            .args([&a, &b])
+            .assert()?;
+    }
+    Ok(())
+}
@@ -1,18 +1,16 @@
-use std::error::Error;
-use std::fs::File;
-use std::io;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead, Read, Write};
 
-use clap::{value_parser, Parser};
-
-type MyResult<T> = Result<T, Box<dyn Error>>;
+use clap::{value_parser, CommandFactory, FromArgMatches, Parser};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{open, CommandExt, ToolError};
+use thiserror::Error;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Rust head")]
 pub struct Args {
     #[arg(
     value_name = "FILE",
-    help = "Input file(s)",
+    help = tr_str("Input file(s)", "入力ファイル"),
     action = clap::ArgAction::Append,
     default_value = "-",
     )]
@@ -21,7 +19,7 @@ pub struct Args {
     id = "lines",
     short = 'n',
     long = "lines",
-    help = "Number of lines",
+    help = tr_str("Number of lines", "行数"),
     default_value = "10",
     value_parser = value_parser!(u64).range(1..)
     )]
@@ -30,37 +28,47 @@ pub struct Args {
     id = "bytes",
     short = 'c',
     long = "bytes",
-    help = "Number of bytes",
+    help = tr_str("Number of bytes", "バイト数"),
     value_parser = value_parser!(u64).range(1..),
     conflicts_with = "lines"
     )]
     bytes: Option<u64>,
 }
 
-pub fn get_args() -> MyResult<Args> {
-    Ok(Args::parse())
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Args, Error> {
+    let matches = Args::command().get_tool_matches();
+    Ok(Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
 }
 
-pub fn run(args: Args) -> MyResult<()> {
+pub fn run(args: Args, out: &mut impl Write, err: &mut impl Write) -> Result<(), Error> {
     let num_files = args.files.len();
 
     for (file_num, filename) in args.files.iter().enumerate() {
         match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
+            Err(e) => writeln!(err, "{}: {}", filename, tr_io(&e))?,
             Ok(mut file) => {
                 if num_files > 1 {
-                    println!(
+                    writeln!(
+                        out,
                         "{}==> {} <==",
                         if file_num > 0 { "\n" } else { "" },
                         &filename
-                    );
+                    )?;
                 }
 
                 if let Some(num_bytes) = args.bytes {
                     let mut handle = file.take(num_bytes);
                     let mut buffer = vec![0; num_bytes as usize];
                     let bytes_read = handle.read(&mut buffer)?;
-                    print!("{}", String::from_utf8_lossy(&buffer[..bytes_read]));
+                    write!(out, "{}", String::from_utf8_lossy(&buffer[..bytes_read]))?;
                 } else {
                     let mut line = String::new();
                     for _ in 0..args.lines {
@@ -68,7 +76,7 @@ pub fn run(args: Args) -> MyResult<()> {
                         if bytes == 0 {
                             break;
                         }
-                        print!("{}", line);
+                        write!(out, "{}", line)?;
                         line.clear();
                     }
                 }
@@ -77,10 +85,3 @@ pub fn run(args: Args) -> MyResult<()> {
     }
     Ok(())
 }
-
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
-}
@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = headrd::get_args().and_then(headrd::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = headrd::get_args()
+        .and_then(|args| headrd::run(args, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("headrd", &e));
     }
 }
@@ -1,13 +1,8 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, random_string, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
+use std::fs::{self, File};
 use std::io::prelude::*;
-use std::{
-    error::Error,
-    fs::{self, File},
-};
-
-type TestResult = Result<(), Box<dyn Error>>;
 
 const PRG: &str = "headrd";
 const EMPTY: &str = "./tests/inputs/empty.txt";
@@ -16,25 +11,6 @@ const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TEN: &str = "./tests/inputs/ten.txt";
 
-// --------------------------------------------------
-fn random_string() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(7)
-        .map(char::from)
-        .collect()
-}
-
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename = random_string();
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_bad_bytes() -> TestResult {
@@ -44,7 +20,7 @@ fn dies_bad_bytes() -> TestResult {
         &bad
     );
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -61,7 +37,7 @@ fn dies_bad_lines() -> TestResult {
         &bad
     );
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -75,7 +51,7 @@ fn dies_bytes_and_lines() -> TestResult {
     let msg = "error: the argument '--lines <lines>' cannot be used with '--bytes <bytes>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -108,7 +84,7 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
         .args(args)
         .assert()
         .success()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(expected.as_bytes() as &[u8]));
 
     Ok(())
 }
@@ -126,7 +102,7 @@ fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult
         .write_stdin(input)
         .args(args)
         .assert()
-        .stdout(predicate::eq(&expected.as_bytes() as &[u8]));
+        .stdout(predicate::eq(expected.as_bytes() as &[u8]));
 
     Ok(())
 }
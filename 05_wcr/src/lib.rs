@@ -1,176 +1,596 @@
-use std::error::Error;
-use std::fs::File;
+use std::ffi::OsString;
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, Write};
 
-use clap::{Arg, ArgAction, Command};
-
-type MyResult<T> = Result<T, Box<dyn Error>>;
+use clap::builder::EnumValueParser;
+use clap::error::ErrorKind;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command, ValueEnum};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::progress::{self, ByteBars};
+use clir_common::{
+    decompress, read_files0, warn_to, CommandExt, FileArg, ToolError, EXIT_FAILURE, EXIT_USAGE,
+};
+use memchr::memchr_iter;
+use rayon::prelude::*;
+use rayon::{ThreadPoolBuildError, ThreadPoolBuilder};
+use regex::Regex;
+use serde::Serialize;
+use thiserror::Error;
+use unicode_width::UnicodeWidthChar;
 
 #[derive(Debug)]
 pub struct Config {
-    files: Vec<String>,
+    files: Vec<FileArg>,
+    files0_from: Option<FileArg>,
     lines: bool,
     words: bool,
     bytes: bool,
     chars: bool,
+    paragraphs: bool,
+    sentences: bool,
+    word_regex: Option<Regex>,
+    format: Format,
+    decompress: bool,
+    jobs: usize,
+    progress: bool,
+}
+
+/// Files at least this large get a bar with `--progress`.
+const PROGRESS_MIN_BYTES: u64 = 64 * 1024 * 1024;
+
+/// How the counts are written: aligned columns like wc, or one JSON
+/// object or TSV row per file for other programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    Tsv,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{path}: {}", tr_io(.source))]
+    Read { path: String, source: io::Error },
+    #[error("{0}")]
+    Usage(clap::Error),
+    #[error(transparent)]
+    ThreadPool(#[from] ThreadPoolBuildError),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
 }
 
-#[derive(Debug, PartialEq)]
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
 pub struct FileInfo {
     num_lines: usize,
     num_words: usize,
     num_bytes: usize,
     num_chars: usize,
+    num_paragraphs: usize,
+    num_sentences: usize,
+    max_line: usize,
+}
+
+impl FileInfo {
+    fn add(&mut self, other: &FileInfo) {
+        self.num_lines += other.num_lines;
+        self.num_words += other.num_words;
+        self.num_bytes += other.num_bytes;
+        self.num_chars += other.num_chars;
+        self.num_paragraphs += other.num_paragraphs;
+        self.num_sentences += other.num_sentences;
+        self.max_line = self.max_line.max(other.max_line);
+    }
+}
+
+/// A line of `--format json` output. The total has no file, and
+/// paragraphs and sentences are only there when asked for.
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    file: Option<&'a str>,
+    lines: usize,
+    words: usize,
+    bytes: usize,
+    chars: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    paragraphs: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sentences: Option<usize>,
+    max_line: usize,
 }
 
-pub fn get_args() -> MyResult<Config> {
-    let matches = Command::new("wcr")
+pub fn get_args() -> Result<Config, Error> {
+    Ok(config_from(&cli().get_tool_matches()))
+}
+
+fn cli() -> Command {
+    Command::new("wcr")
         .version("0.1.0")
         .about("Rust wc")
         .arg(
             Arg::new("files")
                 .value_name("FILE")
-                .help("Input file(s)")
+                .help(tr_str("Input file(s)", "入力ファイル"))
                 .action(ArgAction::Append)
                 .default_value("-"),
         )
+        .arg(
+            Arg::new("files0_from")
+                .long("files0-from")
+                .value_name("F")
+                .help(tr_str(
+                    "Read NUL-separated file names from F (- for stdin)",
+                    "NUL区切りのファイル名をFから読む(- なら標準入力)",
+                ))
+                .conflicts_with("files"),
+        )
         .arg(
             Arg::new("lines")
                 .short('l')
                 .long("lines")
-                .help("Show line count")
+                .help(tr_str("Show line count", "行数を表示する"))
                 .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("words")
                 .short('w')
                 .long("words")
-                .help("Show word count")
+                .help(tr_str("Show word count", "単語数を表示する"))
                 .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("bytes")
                 .short('c')
                 .long("bytes")
-                .help("Show byte count")
+                .help(tr_str("Show byte count", "バイト数を表示する"))
                 .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("chars")
                 .short('m')
                 .long("chars")
-                .help("Show character count")
+                .help(tr_str("Show character count", "文字数を表示する"))
                 .conflicts_with("bytes")
                 .action(ArgAction::SetTrue)
                 .conflicts_with("bytes"),
         )
-        .get_matches();
+        .arg(
+            Arg::new("paragraphs")
+                .long("paragraphs")
+                .help(tr_str(
+                    "Show paragraph count (blank-line separated)",
+                    "段落数を表示する(空行区切り)",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sentences")
+                .long("sentences")
+                .help(tr_str(
+                    "Show sentence count (ending in . ! or ?)",
+                    "文の数を表示する(. ! ? で終わるもの)",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("word_regex")
+                .long("word-regex")
+                .value_name("PATTERN")
+                .help(tr_str(
+                    "Count matches of PATTERN as words instead of whitespace-separated runs",
+                    "空白区切りではなくPATTERNに一致する部分を単語として数える",
+                ))
+                .value_parser(|val: &str| Regex::new(val)),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help(tr_str("Output format", "出力形式"))
+                .value_parser(EnumValueParser::<Format>::new())
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("decompress")
+                .long("decompress")
+                .help(tr_str(
+                    "Decompress gzip, bzip2, xz and zstd input",
+                    "gzip、bzip2、xz、zstdの入力を展開する",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .help(tr_str(
+                    "Count up to N files at once [default: number of CPUs]",
+                    "一度にN個までのファイルを数える [既定: CPU数]",
+                ))
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(progress::arg())
+}
 
+fn config_from(matches: &ArgMatches) -> Config {
     let files = matches
         .get_many::<String>("files")
         .expect("files required")
-        .map(|v| v.to_string())
+        .map(FileArg::from)
         .collect::<Vec<_>>();
 
     let mut lines = matches.get_flag("lines");
     let mut words = matches.get_flag("words");
     let mut bytes = matches.get_flag("bytes");
     let chars = matches.get_flag("chars");
+    let paragraphs = matches.get_flag("paragraphs");
+    let sentences = matches.get_flag("sentences");
 
-    if [lines, words, bytes, chars].iter().all(|v| v == &false) {
+    if [lines, words, bytes, chars, paragraphs, sentences]
+        .iter()
+        .all(|v| v == &false)
+    {
         lines = true;
         words = true;
         bytes = true;
     }
 
-    Ok(Config {
+    Config {
         files,
+        files0_from: matches.get_one::<String>("files0_from").map(FileArg::from),
         lines,
         words,
         bytes,
         chars,
-    })
+        paragraphs,
+        sentences,
+        word_regex: matches.get_one::<Regex>("word_regex").cloned(),
+        format: *matches.get_one("format").expect("has a default"),
+        decompress: matches.get_flag("decompress"),
+        // 0はrayonの既定値で、CPUの数だけスレッドを使う
+        jobs: matches.get_one::<u64>("jobs").map_or(0, |&n| n as usize),
+        progress: matches.get_flag("progress"),
+    }
+}
+
+/// Runs wcr on `args`, program name first, as if they were given on the
+/// command line. Unlike the binary it ignores the config file and
+/// `WCR_OPTIONS`, and never exits the process: `--help` and `--version`
+/// are written to `out`.
+pub fn run_with<I, T>(args: I, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    match cli().try_get_matches_from(args) {
+        Ok(matches) => run(config_from(&matches), out, err),
+        Err(e) if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) => {
+            write!(out, "{}", e)?;
+            Ok(0)
+        }
+        Err(e) => Err(Error::Usage(e)),
+    }
 }
 
-pub fn run(config: Config) -> MyResult<()> {
-    let mut total_lines = 0;
-    let mut total_words = 0;
-    let mut total_bytes = 0;
-    let mut total_chars = 0;
-
-    for filename in &config.files {
-        match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(file) => {
-                if let Ok(info) = count(file) {
-                    println!(
-                        "{}{}{}{}{}",
-                        format_field(info.num_lines, config.lines),
-                        format_field(info.num_words, config.words),
-                        format_field(info.num_bytes, config.bytes),
-                        format_field(info.num_chars, config.chars),
-                        if filename == "-" {
-                            "".to_string()
-                        } else {
-                            format!(" {}", filename)
-                        }
-                    );
-
-                    total_lines += info.num_lines;
-                    total_words += info.num_words;
-                    total_bytes += info.num_bytes;
-                    total_chars += info.num_chars;
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    let pool = ThreadPoolBuilder::new().num_threads(config.jobs).build()?;
+    // 数え終わった順ではなく引数の順に出力するため、少しずつまとめて数える
+    let batch_size = pool.current_num_threads() * 4;
+    let bars = ByteBars::new(config.progress, "wcr");
+    let mut total = FileInfo::default();
+    let mut num_files = 0;
+
+    // 一覧はファイル名を読むたびに数えて出力するので、全体を溜めない
+    let files: Box<dyn Iterator<Item = io::Result<FileArg>>> = match &config.files0_from {
+        Some(list) => Box::new(read_files0(list).map_err(|source| Error::Open {
+            path: list.to_string(),
+            source,
+        })?),
+        None => Box::new(config.files.iter().cloned().map(Ok)),
+    };
+
+    if config.format == Format::Tsv {
+        write!(out, "file\tlines\twords\tbytes\tchars")?;
+        if config.paragraphs {
+            write!(out, "\tparagraphs")?;
+        }
+        if config.sentences {
+            write!(out, "\tsentences")?;
+        }
+        writeln!(out, "\tmax_line")?;
+    }
+    let mut files = files.peekable();
+    'batches: while files.peek().is_some() {
+        let batch = files.by_ref().take(batch_size).collect::<Vec<_>>();
+        let results = pool.install(|| {
+            batch
+                .into_par_iter()
+                .map(|filename| {
+                    filename.map(|filename| {
+                        let info = count_file(&filename, &config, &bars);
+                        (filename, info)
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for result in results {
+            let (filename, info) = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    let list = config.files0_from.as_ref().expect("read from a list");
+                    warn_to(err, "wcr", &format_args!("{}: {}", list, tr_io(&e)));
+                    status = EXIT_FAILURE;
+                    // 不正な名前は飛ばして続けるが、一覧自体が読めなければ止める
+                    if e.kind() == io::ErrorKind::InvalidData {
+                        continue;
+                    }
+                    break 'batches;
+                }
+            };
+            num_files += 1;
+            match info {
+                Err(e) => {
+                    warn_to(err, "wcr", &e);
+                    status = EXIT_FAILURE;
+                }
+                Ok(info) => {
+                    print_counts(out, &info, Some(&filename), &config)?;
+                    total.add(&info);
                 }
             }
         }
     }
 
-    if config.files.len() > 1 {
-        println!(
-            "{}{}{}{} total",
-            format_field(total_lines, config.lines),
-            format_field(total_words, config.words),
-            format_field(total_bytes, config.bytes),
-            format_field(total_chars, config.chars),
-        )
+    if num_files > 1 {
+        print_counts(out, &total, None, &config)?;
+    }
+    Ok(status)
+}
+
+/// Writes the counts of `file`, or the total when `file` is `None`.
+fn print_counts(
+    out: &mut impl Write,
+    info: &FileInfo,
+    file: Option<&FileArg>,
+    config: &Config,
+) -> io::Result<()> {
+    match config.format {
+        Format::Text => writeln!(
+            out,
+            "{}{}{}{}{}{}{}",
+            format_field(info.num_lines, config.lines),
+            format_field(info.num_words, config.words),
+            format_field(info.num_bytes, config.bytes),
+            format_field(info.num_chars, config.chars),
+            format_field(info.num_paragraphs, config.paragraphs),
+            format_field(info.num_sentences, config.sentences),
+            match file {
+                Some(file) if file.name() == "-" => "".to_string(),
+                Some(file) => format!(" {}", file),
+                None => " total".to_string(),
+            }
+        ),
+        Format::Json => {
+            let record = Record {
+                file: file.map(FileArg::name),
+                lines: info.num_lines,
+                words: info.num_words,
+                bytes: info.num_bytes,
+                chars: info.num_chars,
+                paragraphs: config.paragraphs.then_some(info.num_paragraphs),
+                sentences: config.sentences.then_some(info.num_sentences),
+                max_line: info.max_line,
+            };
+            writeln!(out, "{}", serde_json::to_string(&record)?)
+        }
+        Format::Tsv => {
+            write!(
+                out,
+                "{}\t{}\t{}\t{}\t{}",
+                file.map_or("", FileArg::name),
+                info.num_lines,
+                info.num_words,
+                info.num_bytes,
+                info.num_chars,
+            )?;
+            if config.paragraphs {
+                write!(out, "\t{}", info.num_paragraphs)?;
+            }
+            if config.sentences {
+                write!(out, "\t{}", info.num_sentences)?;
+            }
+            writeln!(out, "\t{}", info.max_line)
+        }
     }
-    Ok(())
 }
 
-pub fn count(mut file: impl BufRead) -> MyResult<FileInfo> {
+fn count_file(filename: &FileArg, config: &Config, bars: &ByteBars) -> Result<FileInfo, Error> {
+    let open_error = |source| Error::Open {
+        path: filename.to_string(),
+        source,
+    };
+    let mut file = filename.open().map_err(open_error)?;
+    // 伸長する前に包み、ファイルの大きさに対して読んだ量を示す
+    if config.progress {
+        let len = filename
+            .path()
+            .and_then(|path| path.metadata().ok())
+            .map_or(0, |meta| meta.len());
+        if len >= PROGRESS_MIN_BYTES {
+            file = bars.wrap(filename.name(), len, file);
+        }
+    }
+    if config.decompress {
+        file = decompress(file, filename.name()).map_err(open_error)?;
+    }
+    // 構造化した出力ではすべての数を出すので、速い経路は使えない
+    let info = if config.words
+        || config.chars
+        || config.paragraphs
+        || config.sentences
+        || config.format != Format::Text
+    {
+        count_impl(file, config.word_regex.as_ref())
+    } else {
+        count_lines_bytes(file)
+    };
+    info.map_err(|e| match e {
+        Error::Io(source) => Error::Read {
+            path: filename.to_string(),
+            source,
+        },
+        e => e,
+    })
+}
+
+/// Counts lines, words, bytes, characters, paragraphs and sentences.
+/// Invalid UTF-8 is decoded lossily: each bad sequence counts as one
+/// character inside a word.
+pub fn count(file: impl BufRead) -> Result<FileInfo, Error> {
+    count_impl(file, None)
+}
+
+/// Like [`count`], but a word is a non-empty match of `word_regex`.
+/// Matches do not span lines.
+pub fn count_with_words(file: impl BufRead, word_regex: &Regex) -> Result<FileInfo, Error> {
+    count_impl(file, Some(word_regex))
+}
+
+fn count_impl(mut file: impl BufRead, word_regex: Option<&Regex>) -> Result<FileInfo, Error> {
     let mut num_lines = 0;
     let mut num_words = 0;
     let mut num_bytes = 0;
     let mut num_chars = 0;
-    let mut line = String::new();
+    let mut num_paragraphs = 0;
+    let mut num_sentences = 0;
+    let mut max_line = 0;
+    let mut line = Vec::new();
+    let mut in_paragraph = false;
+    let mut in_sentence = false;
 
     loop {
-        let line_bytes = file.read_line(&mut line)?;
+        let line_bytes = file.read_until(b'\n', &mut line)?;
         if line_bytes == 0 {
             break;
         }
 
+        // 不正なUTF-8は置換文字として読み、ファイルごと飛ばさない
+        let text = String::from_utf8_lossy(&line);
         num_bytes += line_bytes;
         num_lines += 1;
-        num_words += line.split_whitespace().count();
-        num_chars += line.chars().count();
+        num_words += match word_regex {
+            // 空の一致は語に数えない
+            Some(re) => re.find_iter(&text).filter(|m| !m.is_empty()).count(),
+            None => text.split_whitespace().count(),
+        };
+        num_chars += text.chars().count();
+        // 空白だけの行も段落の区切りとみなす
+        let blank = text.trim().is_empty();
+        if !blank && !in_paragraph {
+            num_paragraphs += 1;
+        }
+        // 句点がなくても段落の終わりで文も終わる
+        if blank && in_sentence {
+            num_sentences += 1;
+            in_sentence = false;
+        }
+        in_paragraph = !blank;
+        num_sentences += count_sentence_ends(&text, &mut in_sentence);
+        max_line = max_line.max(display_width(&text));
         line.clear();
     }
+    // 句点のない最後の文も1文と数える
+    if in_sentence {
+        num_sentences += 1;
+    }
 
     Ok(FileInfo {
         num_lines,
         num_words,
         num_bytes,
         num_chars,
+        num_paragraphs,
+        num_sentences,
+        max_line,
     })
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+/// Counts the sentences that end in `line`. A sentence ends at `.`, `!`
+/// or `?` followed by whitespace or the end of the line, or at a CJK
+/// full stop, once it has some letters or digits. `in_sentence` carries
+/// over from the previous line, since sentences may span lines.
+fn count_sentence_ends(line: &str, in_sentence: &mut bool) -> usize {
+    let mut ends = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let end = match c {
+            '。' | '！' | '？' => true,
+            // "3.14" や "e.g" のように語の途中にあれば文を終えない
+            '.' | '!' | '?' => chars.peek().is_none_or(|c| c.is_whitespace()),
+            c => {
+                *in_sentence |= c.is_alphanumeric();
+                false
+            }
+        };
+        if end && *in_sentence {
+            ends += 1;
+            *in_sentence = false;
+        }
     }
+    ends
+}
+
+/// The width of `line` on a terminal, as `wc -L` measures it: tabs move
+/// to the next multiple of 8 and control characters take no room.
+fn display_width(line: &str) -> usize {
+    line.chars().fold(0, |width, c| match c {
+        '\t' => (width / 8 + 1) * 8,
+        c => width + c.width().unwrap_or(0),
+    })
+}
+
+/// Like [`count`], but only counts lines and bytes. It scans raw chunks
+/// for newlines instead of decoding lines, so it is much faster and
+/// accepts any bytes.
+pub fn count_lines_bytes(mut file: impl BufRead) -> Result<FileInfo, Error> {
+    let mut num_lines = 0;
+    let mut num_bytes = 0;
+    let mut last = None;
+
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        num_lines += memchr_iter(b'\n', buf).count();
+        num_bytes += buf.len();
+        last = buf.last().copied();
+        let len = buf.len();
+        file.consume(len);
+    }
+    // countと同じく、改行で終わらない最後の行も1行と数える
+    if last.is_some_and(|b| b != b'\n') {
+        num_lines += 1;
+    }
+
+    Ok(FileInfo {
+        num_lines,
+        num_bytes,
+        ..FileInfo::default()
+    })
 }
 
 fn format_field(value: usize, show: bool) -> String {
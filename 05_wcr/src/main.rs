@@ -1,6 +1,11 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = wcr::get_args().and_then(wcr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    // 進捗バーはrayonのスレッドからSTDERRに書くので、ロックを持ち続けない
+    let result = wcr::get_args()
+        .and_then(|config| wcr::run(config, &mut io::stdout().lock(), &mut io::stderr()));
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("wcr", &e)),
     }
 }
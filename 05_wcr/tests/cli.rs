@@ -1,35 +1,18 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
 use std::fs;
 
-type TestResult = Result<(), Box<dyn std::error::Error>>;
-
 const PRG: &str = "wcr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
 
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_chars_and_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "-c"])
+        .args(["-m", "-c"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
@@ -57,7 +40,7 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(bad)
         .assert()
-        .success()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -211,3 +194,249 @@ fn test_all_words_lines() -> TestResult {
 fn test_all_bytes_lines() -> TestResult {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn decompress_stdin() -> TestResult {
+    let input = fs::read("tests/inputs/atlamal.txt.xz")?;
+    let expected = fs::read_to_string("tests/expected/atlamal.txt.stdin.out")?;
+    Command::cargo_bin(PRG)?
+        .arg("--decompress")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=-")
+        .write_stdin(format!("{}\0{}\0{}\0", EMPTY, FOX, ATLAMAL))
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_skips_empty_name() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .arg("--files0-from=-")
+        .write_stdin(format!("\0{}", FOX))
+        .assert()
+        .code(1)
+        .stdout(expected)
+        .stderr("wcr: -: invalid zero-length file name\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_with_files() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from=-", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_jobs() -> TestResult {
+    // 並列に数えても引数の順に出力する
+    for jobs in ["1", "2", "8"] {
+        run(&["-j", jobs, EMPTY, FOX, ATLAMAL], "tests/expected/all.out")?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_jobs_keeps_error_order() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["-j", "4", EMPTY, &bad, FOX, ATLAMAL])
+        .assert()
+        .code(1)
+        .stdout(expected)
+        .stderr(predicate::str::is_match(format!("^wcr: {}: .*\n$", bad))?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_jobs() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-j", "0", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--jobs <N>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn lines_bytes_invalid_utf8() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-lc"])
+        .write_stdin(&b"a\xff\nb\xfe"[..])
+        .assert()
+        .success()
+        .stdout("       2       5\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn words_chars_invalid_utf8() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-lwm"])
+        .write_stdin(&b"a\xff b\n\xe6\x97\xa5x\xfe\n"[..])
+        .assert()
+        .success()
+        .stdout("       2       3       9\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format_json() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "json", "-l", FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"{"file":"tests/inputs/fox.txt","lines":1,"words":9,"bytes":48,"chars":48,"max_line":50}"#,
+            "\n",
+            r#"{"file":"tests/inputs/atlamal.txt","lines":4,"words":29,"bytes":177,"chars":159,"max_line":43}"#,
+            "\n",
+            r#"{"file":null,"lines":5,"words":38,"bytes":225,"chars":207,"max_line":50}"#,
+            "\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format_tsv_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=tsv"])
+        .write_stdin(fs::read_to_string(FOX)?)
+        .assert()
+        .success()
+        .stdout("file\tlines\twords\tbytes\tchars\tmax_line\n-\t1\t9\t48\t48\t50\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn word_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "--word-regex", r"[A-Za-z_][A-Za-z0-9_]*"])
+        .write_stdin("let x1 = foo_bar(2);\n// 日本語 ok\n")
+        .assert()
+        .success()
+        .stdout("       4\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn word_regex_cjk() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "--word-regex", r"\p{Han}|\S+"])
+        .write_stdin("日本語 abc\n")
+        .assert()
+        .success()
+        .stdout("       4\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_word_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--word-regex", "(", FOX])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '(' for '--word-regex <PATTERN>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn word_regex_skips_empty_matches() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "--word-regex", "a*"])
+        .write_stdin("baab a\n")
+        .assert()
+        .success()
+        .stdout("       2\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn progress_keeps_output() -> TestResult {
+    // 端末でなければバーは出ない
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--progress", EMPTY, FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn paragraphs_sentences() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--paragraphs", "--sentences"])
+        .write_stdin("Hello world. Pi is 3.14 ok!\nStill one? Yes\n\n  \n第二段落です。終わり\n")
+        .assert()
+        .success()
+        .stdout("       2       6\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn paragraphs_with_lines_total() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-l", "--paragraphs", EMPTY, FOX, ATLAMAL])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "       0       0 tests/inputs/empty.txt\n",
+            "       1       1 tests/inputs/fox.txt\n",
+            "       4       1 tests/inputs/atlamal.txt\n",
+            "       5       2 total\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sentences_format_tsv() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format=tsv", "--sentences"])
+        .write_stdin("Hello.\n\nWorld\n")
+        .assert()
+        .success()
+        .stdout("file\tlines\twords\tbytes\tchars\tsentences\tmax_line\n-\t3\t2\t14\t14\t2\t6\n");
+    Ok(())
+}
@@ -1,32 +1,30 @@
-use std::error::Error;
-use std::fs::File;
-use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, Write};
 
-use clap::Parser;
-
-type MyResult<T> = Result<T, Box<dyn Error>>;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{open, CommandExt, ToolError};
+use thiserror::Error;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Rust wc")]
 pub struct Args {
     #[arg(
     value_name = "FILE",
-    help = "Input file(s)",
+    help = tr_str("Input file(s)", "入力ファイル"),
     action = clap::ArgAction::Append,
     default_value = "-",
     )]
     files: Vec<String>,
-    #[arg(short = 'l', long = "lines", help = "Show line count")]
+    #[arg(short = 'l', long = "lines", help = tr_str("Show line count", "行数を表示する"))]
     lines: bool,
-    #[arg(short = 'w', long = "words", help = "Show word count")]
+    #[arg(short = 'w', long = "words", help = tr_str("Show word count", "単語数を表示する"))]
     words: bool,
-    #[arg(id = "bytes", short = 'c', long = "bytes", help = "Show byte count")]
+    #[arg(id = "bytes", short = 'c', long = "bytes", help = tr_str("Show byte count", "バイト数を表示する"))]
     bytes: bool,
     #[arg(
         short = 'm',
         long = "chars",
-        help = "Show character count",
+        help = tr_str("Show character count", "文字数を表示する"),
         conflicts_with = "bytes"
     )]
     chars: bool,
@@ -49,8 +47,17 @@ pub struct FileInfo {
     num_chars: usize,
 }
 
-pub fn get_args() -> MyResult<Config> {
-    let args = Args::parse();
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Args::command().get_tool_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     let files = args.files;
     let mut lines = args.lines;
     let mut words = args.words;
@@ -75,7 +82,7 @@ pub fn get_args() -> MyResult<Config> {
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<(), Error> {
     let mut total_lines = 0;
     let mut total_words = 0;
     let mut total_bytes = 0;
@@ -83,10 +90,11 @@ pub fn run(config: Config) -> MyResult<()> {
 
     for filename in &config.files {
         match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
+            Err(e) => writeln!(err, "{}: {}", filename, tr_io(&e))?,
             Ok(file) => {
                 if let Ok(info) = count(file) {
-                    println!(
+                    writeln!(
+                        out,
                         "{}{}{}{}{}",
                         format_field(info.num_lines, config.lines),
                         format_field(info.num_words, config.words),
@@ -97,7 +105,7 @@ pub fn run(config: Config) -> MyResult<()> {
                         } else {
                             format!(" {}", filename)
                         }
-                    );
+                    )?;
 
                     total_lines += info.num_lines;
                     total_words += info.num_words;
@@ -109,18 +117,19 @@ pub fn run(config: Config) -> MyResult<()> {
     }
 
     if config.files.len() > 1 {
-        println!(
+        writeln!(
+            out,
             "{}{}{}{} total",
             format_field(total_lines, config.lines),
             format_field(total_words, config.words),
             format_field(total_bytes, config.bytes),
             format_field(total_chars, config.chars),
-        )
+        )?;
     }
     Ok(())
 }
 
-pub fn count(mut file: impl BufRead) -> MyResult<FileInfo> {
+pub fn count(mut file: impl BufRead) -> io::Result<FileInfo> {
     let mut num_lines = 0;
     let mut num_words = 0;
     let mut num_bytes = 0;
@@ -148,13 +157,6 @@ pub fn count(mut file: impl BufRead) -> MyResult<FileInfo> {
     })
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
-}
-
 fn format_field(value: usize, show: bool) -> String {
     if show {
         format!("{:>8}", value)
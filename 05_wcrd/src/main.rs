@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = wcrd::get_args().and_then(wcrd::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = wcrd::get_args()
+        .and_then(|args| wcrd::run(args, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("wcrd", &e));
     }
 }
@@ -1,36 +1,19 @@
 use std::fs;
 
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-
-type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 const PRG: &str = "wcrd";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
 
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_chars_and_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "-c"])
+        .args(["-m", "-c"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(
@@ -1,99 +1,456 @@
-use std::error::Error;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
 use std::fs::File;
 use std::io;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, Write};
+use std::os::unix::fs::MetadataExt;
 
+use clap::builder::EnumValueParser;
 use clap::ArgAction::SetTrue;
-use clap::{Arg, Command};
-
-type MyResult<T> = Result<T, Box<dyn Error>>;
+use clap::{value_parser, Arg, Command, ValueEnum};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{open, parse_size, CommandExt, FileArg, RecordReader, Terminator, ToolError};
+use thiserror::Error;
 
 #[derive(Debug)]
 pub struct Config {
     in_file: String,
     out_file: Option<String>,
     count: bool,
+    count_width: usize,
+    percent: bool,
+    repeated: bool,
+    unique: bool,
+    all_repeated: Option<Delimit>,
+    ignore_case: bool,
+    skip_fields: usize,
+    skip_chars: usize,
+    check_chars: Option<usize>,
+    global: bool,
+    top: Option<usize>,
+    memory_limit: Option<u64>,
+    terminator: Terminator,
+}
+
+/// Where `--all-repeated` puts a blank line between the groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Delimit {
+    None,
+    Prepend,
+    Separate,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+    #[error("{path}: {}", tr_io(.source))]
+    Create { path: String, source: io::Error },
+    #[error("{}", tr_text(
+        "{0}: input file is output file",
+        "{0}: 入力ファイルが出力ファイルと同じです",
+        &[.0],
+    ))]
+    InputIsOutput(String),
+    #[error("{}", tr_text(
+        "counting all lines needs more than {0} bytes of memory; sort the input first",
+        "すべての行を数えるには{0}バイトを超えるメモリが必要です。先に入力をソートしてください",
+        &[.0],
+    ))]
+    MemoryLimit(u64),
 }
 
-pub fn get_args() -> MyResult<Config> {
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
     let matches = Command::new("uniqr")
         .version("0.1.0")
         .about("Rust uniq")
         .arg(
             Arg::new("in_file")
                 .value_name("IN_FILE")
-                .help("Input file [default: -]")
+                .help(tr_str("Input file [default: -]", "入力ファイル [既定: -]"))
                 .default_value("-"),
         )
         .arg(
             Arg::new("out_file")
                 .value_name("OUT_FILE")
-                .help("Output file"),
+                .help(tr_str("Output file", "出力ファイル")),
         )
         .arg(
             Arg::new("count")
                 .value_name("count")
                 .short('c')
                 .long("count")
-                .help("Show counts")
+                .help(tr_str("Show counts", "件数を表示する"))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("count_width")
+                .long("count-width")
+                .value_name("N")
+                .help(tr_str("Pad counts to N columns", "件数をN桁に揃える"))
+                .value_parser(value_parser!(usize))
+                .default_value("7"),
+        )
+        .arg(
+            Arg::new("percent")
+                .long("percent")
+                .help(tr_str(
+                    "Show each count's share of all lines (implies -c)",
+                    "各件数が全行に占める割合を表示する(-c も有効になる)",
+                ))
+                .action(SetTrue)
+                .conflicts_with("all_repeated"),
+        )
+        .arg(
+            Arg::new("repeated")
+                .short('d')
+                .long("repeated")
+                .help(tr_str(
+                    "Only print duplicate lines, one for each group",
+                    "重複した行だけを、各グループ1行ずつ表示する",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("unique")
+                .short('u')
+                .long("unique")
+                .help(tr_str(
+                    "Only print unique lines",
+                    "重複しない行だけを表示する",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("all_repeated")
+                .short('D')
+                .long("all-repeated")
+                .value_name("METHOD")
+                .help(tr_str(
+                    "Print all duplicate lines, delimiting groups with a blank line",
+                    "重複した行をすべて表示し、グループを空行で区切る",
+                ))
+                .value_parser(EnumValueParser::<Delimit>::new())
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("none")
+                .conflicts_with("count"),
+        )
+        .arg(
+            Arg::new("ignore_case")
+                .short('i')
+                .long("ignore-case")
+                .help(tr_str(
+                    "Ignore differences in case when comparing",
+                    "比較のとき大文字と小文字の違いを無視する",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("skip_fields")
+                .short('f')
+                .long("skip-fields")
+                .value_name("N")
+                .help(tr_str(
+                    "Avoid comparing the first N fields",
+                    "先頭のNフィールドを比較しない",
+                ))
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("skip_chars")
+                .short('s')
+                .long("skip-chars")
+                .value_name("N")
+                .help(tr_str(
+                    "Avoid comparing the first N characters",
+                    "先頭のN文字を比較しない",
+                ))
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("check_chars")
+                .short('w')
+                .long("check-chars")
+                .value_name("N")
+                .help(tr_str(
+                    "Compare no more than N characters",
+                    "N文字までしか比較しない",
+                ))
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("global")
+                .short('g')
+                .long("global")
+                .help(tr_str(
+                    "Remove duplicates anywhere, not only adjacent ones",
+                    "隣接する行だけでなく、どこにある重複も取り除く",
+                ))
+                .action(SetTrue)
+                .conflicts_with("all_repeated"),
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .value_name("N")
+                .help(tr_str(
+                    "Print the N most frequent lines anywhere in the input, with counts",
+                    "入力全体で多い順にN行を件数付きで表示する",
+                ))
+                .value_parser(value_parser!(usize))
+                .conflicts_with("all_repeated"),
+        )
+        .arg(
+            Arg::new("memory_limit")
+                .long("memory-limit")
+                .value_name("SIZE")
+                .help(tr_str(
+                    "Fail if --global or --top needs more than SIZE bytes",
+                    "--global や --top がSIZEバイトより多く必要なら失敗する",
+                ))
+                .value_parser(parse_size),
+        )
+        .arg(
+            Arg::new("zero_terminated")
+                .short('z')
+                .long("zero-terminated")
+                .help(tr_str(
+                    "Line delimiter is NUL, not newline",
+                    "行の区切りを改行ではなくNULにする",
+                ))
                 .action(SetTrue),
         )
-        .get_matches();
+        .get_tool_matches();
 
     Ok(Config {
         in_file: matches.get_one::<String>("in_file").unwrap().to_string(),
         out_file: matches.get_one::<String>("out_file").cloned(),
-        count: matches.get_flag("count"),
+        count: matches.get_flag("count") || matches.get_flag("percent"),
+        count_width: *matches.get_one("count_width").expect("has a default"),
+        percent: matches.get_flag("percent"),
+        repeated: matches.get_flag("repeated"),
+        unique: matches.get_flag("unique"),
+        all_repeated: matches.get_one("all_repeated").copied(),
+        ignore_case: matches.get_flag("ignore_case"),
+        skip_fields: matches.get_one("skip_fields").copied().unwrap_or(0),
+        skip_chars: matches.get_one("skip_chars").copied().unwrap_or(0),
+        check_chars: matches.get_one("check_chars").copied(),
+        global: matches.get_flag("global"),
+        top: matches.get_one("top").copied(),
+        memory_limit: matches.get_one("memory_limit").copied(),
+        terminator: if matches.get_flag("zero_terminated") {
+            Terminator::Nul
+        } else {
+            Terminator::Newline
+        },
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
-    let mut file = open(&config.in_file).map_err(|e| format!("{}: {}", config.in_file, e))?;
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let file = open(&config.in_file).map_err(|source| Error::Open {
+        path: config.in_file.clone(),
+        source,
+    })?;
+    let mut records = RecordReader::new(file, config.terminator);
 
-    let mut out_file: Box<dyn Write> = match &config.out_file {
-        Some(out_name) => Box::new(File::create(out_name)?),
-        _ => Box::new(io::stdout()),
+    let mut out_file: Box<dyn Write + '_> = match &config.out_file {
+        Some(out_name) => Box::new(create_output(out_name, &config.in_file)?),
+        _ => Box::new(out),
     };
+    if config.global || config.top.is_some() {
+        return run_global(&mut records, &config, &mut out_file);
+    }
 
-    let mut line = String::new();
-    let mut previous = String::new();
+    // 直前の行は終端ごと保持し、比較には終端を除いた長さを使う
+    let mut previous = vec![];
+    let mut previous_len = 0;
     let mut count: u64 = 0;
+    // -Dでは同じグループの2行目以降も出力するので溜めておく
+    let mut rest = vec![];
+    let mut first_group = true;
+    // --percentでは全体の行数がわかるまで出力を待つ
+    let mut pending: Vec<(Vec<u8>, u64)> = vec![];
+    let mut total: u64 = 0;
 
-    let mut print = |count: u64, text: &str| -> MyResult<()> {
-        if count > 0 {
-            if config.count {
-                write!(out_file, "{:>4} {}", count, text)?;
-            } else {
-                write!(out_file, "{}", text)?;
+    // -dと-uを両方指定すると何も出力しない
+    let repeated = config.repeated || config.all_repeated.is_some();
+    let selected = |count: u64| match count {
+        0 => false,
+        1 => !repeated,
+        _ => !config.unique,
+    };
+    let mut print = |count: u64, text: &[u8], rest: &[u8]| -> Result<(), Error> {
+        if !selected(count) {
+            return Ok(());
+        }
+        match config.all_repeated {
+            Some(delimit) => {
+                if delimit == Delimit::Prepend || (delimit == Delimit::Separate && !first_group) {
+                    out_file.write_all(&[config.terminator.byte()])?;
+                }
+                out_file.write_all(text)?;
+                out_file.write_all(rest)?;
+            }
+            None if config.percent => pending.push((text.to_vec(), count)),
+            None => {
+                if config.count {
+                    write_count(&mut out_file, &config, count, None)?;
+                }
+                out_file.write_all(text)?;
             }
         }
+        first_group = false;
         Ok(())
     };
 
-    loop {
-        let bytes = file.read_line(&mut line)?;
-        if bytes == 0 {
-            break;
+    // GNU uniqと同じく、大文字と小文字はASCIIの範囲だけ同一視する
+    let same = |a: &[u8], b: &[u8]| {
+        let (a, b) = (compare_key(a, &config), compare_key(b, &config));
+        if config.ignore_case {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
         }
+    };
 
-        if line.trim_end() != previous.trim_end() {
-            print(count, &previous)?;
-            previous = line.clone();
+    while let Some(record) = records.next_record()? {
+        // 最初の行は空行でも新しいグループとして保持する
+        if count == 0 || !same(record.body(), &previous[..previous_len]) {
+            print(count, &previous, &rest)?;
+            previous.clear();
+            previous.extend_from_slice(record.raw());
+            previous_len = record.body().len();
+            rest.clear();
             count = 0;
+        } else if config.all_repeated.is_some() {
+            rest.extend_from_slice(record.raw());
         }
-
         count += 1;
-        line.clear();
+        total += 1;
+    }
+
+    print(count, &previous, &rest)?;
+    for (text, count) in pending {
+        write_count(&mut out_file, &config, count, Some(total))?;
+        out_file.write_all(&text)?;
+    }
+
+    Ok(())
+}
+
+/// Creates the output file, refusing the input file since it would be
+/// truncated before it is read.
+fn create_output(path: &str, in_file: &str) -> Result<File, Error> {
+    if let Ok(meta) = fs::metadata(path) {
+        // ハードリンクやシンボリックリンク越しでも同じファイルとわかるように比べる
+        let same = FileArg::new(in_file)
+            .path()
+            .and_then(|input| fs::metadata(input).ok())
+            .is_some_and(|input| input.dev() == meta.dev() && input.ino() == meta.ino());
+        if same {
+            return Err(Error::InputIsOutput(in_file.to_string()));
+        }
+    }
+    File::create(path).map_err(|source| Error::Create {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// Runs `--global` and `--top`: each key is printed once, at its first
+/// line. Lines are written as they are read unless the counts are
+/// needed, in which case all groups are kept until the end.
+fn run_global(
+    records: &mut RecordReader<impl BufRead>,
+    config: &Config,
+    out: &mut impl Write,
+) -> Result<(), Error> {
+    let buffered = config.count || config.repeated || config.unique || config.top.is_some();
+    let mut seen: HashMap<Vec<u8>, usize> = HashMap::new();
+    // 最初の行とその数。出力を後回しにするときだけ使う
+    let mut groups: Vec<(Vec<u8>, u64)> = vec![];
+    let mut used: u64 = 0;
+
+    while let Some(record) = records.next_record()? {
+        let mut key = compare_key(record.body(), config).to_vec();
+        if config.ignore_case {
+            key.make_ascii_lowercase();
+        }
+        if let Some(&i) = seen.get(&key) {
+            if buffered {
+                groups[i].1 += 1;
+            }
+            continue;
+        }
+
+        // 正確な量ではなく、キーと行の長さに1件あたりの目安を足して見積もる
+        used += (key.len() + 64) as u64;
+        let index = groups.len();
+        if buffered {
+            used += record.raw().len() as u64;
+            groups.push((record.raw().to_vec(), 1));
+        } else {
+            out.write_all(record.raw())?;
+        }
+        if let Some(limit) = config.memory_limit.filter(|&limit| used > limit) {
+            return Err(Error::MemoryLimit(limit));
+        }
+        seen.insert(key, index);
     }
 
-    print(count, &previous)?;
+    let total = groups.iter().map(|&(_, count)| count).sum();
+    groups.retain(|&(_, count)| match count {
+        1 => !config.repeated,
+        _ => !config.unique,
+    });
+    if let Some(n) = config.top {
+        // 安定ソートなので、同じ数なら先に現れた行が前に来る
+        groups.sort_by_key(|&(_, count)| Reverse(count));
+        groups.truncate(n);
+    }
+    for (text, count) in groups {
+        if config.count || config.top.is_some() {
+            write_count(out, config, count, config.percent.then_some(total))?;
+        }
+        out.write_all(&text)?;
+    }
+    Ok(())
+}
 
+/// Writes the `-c` column, and with `total` the share of all lines.
+fn write_count(
+    out: &mut impl Write,
+    config: &Config,
+    count: u64,
+    total: Option<u64>,
+) -> io::Result<()> {
+    write!(out, "{:>1$} ", count, config.count_width)?;
+    if let Some(total) = total {
+        write!(out, "{:>6.2}% ", 100.0 * count as f64 / total as f64)?;
+    }
     Ok(())
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+/// The part of `line` that is compared: what is left after skipping
+/// `--skip-fields` fields, then `--skip-chars` characters, cut to
+/// `--check-chars` characters. A field is a run of blanks followed by
+/// non-blanks. Like GNU uniq, characters are counted as bytes.
+fn compare_key<'a>(line: &'a [u8], config: &Config) -> &'a [u8] {
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+    let mut start = 0;
+    for _ in 0..config.skip_fields {
+        start += line[start..].iter().take_while(|b| is_blank(b)).count();
+        start += line[start..].iter().take_while(|b| !is_blank(b)).count();
+    }
+    let key = &line[(start + config.skip_chars).min(line.len())..];
+    match config.check_chars {
+        Some(n) => &key[..n.min(key.len())],
+        None => key,
     }
 }
@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = uniqr::get_args().and_then(uniqr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = uniqr::get_args()
+        .and_then(|config| uniqr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("uniqr", &e));
     }
 }
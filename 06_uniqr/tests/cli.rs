@@ -1,10 +1,7 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, gen_text, Fixture, GnuCompare, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
 use std::fs;
-use tempfile::NamedTempFile;
-
-type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 struct Test {
     input: &'static str,
@@ -80,21 +77,6 @@ const T6: Test = Test {
     out_count: "tests/expected/t6.txt.c.out",
 };
 
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_bad_file() -> TestResult {
@@ -124,7 +106,7 @@ fn run(test: &Test) -> TestResult {
 fn run_count(test: &Test) -> TestResult {
     let expected = fs::read_to_string(test.out_count)?;
     Command::cargo_bin(PRG)?
-        .args(&[test.input, "-c"])
+        .args([test.input, "-c"])
         .assert()
         .success()
         .stdout(expected);
@@ -159,15 +141,15 @@ fn run_stdin_count(test: &Test) -> TestResult {
 // --------------------------------------------------
 fn run_outfile(test: &Test) -> TestResult {
     let expected = fs::read_to_string(test.out)?;
-    let outfile = NamedTempFile::new()?;
-    let outpath = &outfile.path().to_str().unwrap();
+    let fixture = Fixture::new()?;
+    let outpath = &fixture.path("out.txt");
     Command::cargo_bin(PRG)?
-        .args(&[test.input, outpath])
+        .args([test.input, outpath.as_str()])
         .assert()
         .success()
         .stdout("");
 
-    let contents = fs::read_to_string(&outpath)?;
+    let contents = fs::read_to_string(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -175,17 +157,17 @@ fn run_outfile(test: &Test) -> TestResult {
 
 // --------------------------------------------------
 fn run_outfile_count(test: &Test) -> TestResult {
-    let outfile = NamedTempFile::new()?;
-    let outpath = &outfile.path().to_str().unwrap();
+    let fixture = Fixture::new()?;
+    let outpath = &fixture.path("out.txt");
 
     Command::cargo_bin(PRG)?
-        .args(&[test.input, outpath, "--count"])
+        .args([test.input, outpath.as_str(), "--count"])
         .assert()
         .success()
         .stdout("");
 
     let expected = fs::read_to_string(test.out_count)?;
-    let contents = fs::read_to_string(&outpath)?;
+    let contents = fs::read_to_string(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -194,17 +176,17 @@ fn run_outfile_count(test: &Test) -> TestResult {
 // --------------------------------------------------
 fn run_stdin_outfile_count(test: &Test) -> TestResult {
     let input = fs::read_to_string(test.input)?;
-    let outfile = NamedTempFile::new()?;
-    let outpath = &outfile.path().to_str().unwrap();
+    let fixture = Fixture::new()?;
+    let outpath = &fixture.path("out.txt");
 
     Command::cargo_bin(PRG)?
-        .args(&["-", outpath, "-c"])
+        .args(["-", outpath.as_str(), "-c"])
         .write_stdin(input)
         .assert()
         .stdout("");
 
     let expected = fs::read_to_string(test.out_count)?;
-    let contents = fs::read_to_string(&outpath)?;
+    let contents = fs::read_to_string(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -605,3 +587,335 @@ fn t6_outfile_count() -> TestResult {
 fn t6_stdin_outfile_count() -> TestResult {
     run_stdin_outfile_count(&T6)
 }
+
+#[test]
+fn dies_input_is_output() -> TestResult {
+    let fixture = Fixture::new()?;
+    let file = fixture.file("a.txt", "a\na\n")?;
+    let link = fixture.symlink("link.txt", &file)?;
+    Command::cargo_bin(PRG)?
+        .args([&file, &link])
+        .assert()
+        .code(1)
+        .stderr(format!("uniqr: {}: input file is output file\n", file));
+    // 入力は切り詰められずに残る
+    assert_eq!(fs::read_to_string(&file)?, "a\na\n");
+    Ok(())
+}
+
+#[test]
+fn zero_terminated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-c"])
+        .write_stdin("a\nb\0a\nb\0c\0")
+        .assert()
+        .success()
+        .stdout("      2 a\nb\0      1 c\0");
+    Ok(())
+}
+
+#[test]
+fn zero_terminated_file_list() -> TestResult {
+    // find -print0 | sort -z の出力のように、名前に改行を含んでもよい
+    Command::cargo_bin(PRG)?
+        .args(["-z", "-d"])
+        .write_stdin("./a\0./b\nc\0./b\nc\0./d\0./d\0")
+        .assert()
+        .success()
+        .stdout("./b\nc\0./d\0");
+    Ok(())
+}
+
+#[test]
+fn invalid_utf8() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-c")
+        .write_stdin(&b"\xff\xfe\n\xff\xfe\n\xff\xfd\n"[..])
+        .assert()
+        .success()
+        .stdout(&b"      2 \xff\xfe\n      1 \xff\xfd\n"[..]);
+    Ok(())
+}
+
+#[test]
+fn crlf() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-c")
+        .write_stdin("a\r\na\nb")
+        .assert()
+        .success()
+        .stdout("      2 a\r\n      1 b");
+    Ok(())
+}
+
+#[test]
+fn repeated_count() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", "-c"])
+        .write_stdin("a\na\nb\nc\nc\nc\n")
+        .assert()
+        .success()
+        .stdout("      2 a\n      3 c\n");
+    Ok(())
+}
+
+#[test]
+fn unique() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--unique")
+        .write_stdin("a\na\nb\nc\nc\nc\n")
+        .assert()
+        .success()
+        .stdout("b\n");
+    Ok(())
+}
+
+#[test]
+fn repeated_and_unique() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", "-u"])
+        .write_stdin("a\na\nb\n")
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn leading_empty_lines() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-c")
+        .write_stdin("\n\nb\n")
+        .assert()
+        .success()
+        .stdout("      2 \n      1 b\n");
+    Ok(())
+}
+
+#[test]
+fn all_repeated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-D")
+        .write_stdin("a\na\nb\nc\nc\nC\n")
+        .assert()
+        .success()
+        .stdout("a\na\nc\nc\n");
+    Ok(())
+}
+
+#[test]
+fn all_repeated_separate() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--all-repeated=separate", "-i"])
+        .write_stdin("a\na\nb\nc\nC\nc\n")
+        .assert()
+        .success()
+        .stdout("a\na\n\nc\nC\nc\n");
+    Ok(())
+}
+
+#[test]
+fn all_repeated_prepend_zero_terminated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--all-repeated=prepend", "-z"])
+        .write_stdin("a\0a\0b\0")
+        .assert()
+        .success()
+        .stdout("\0a\0a\0");
+    Ok(())
+}
+
+#[test]
+fn dies_all_repeated_with_count() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-D", "-c"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn global() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--global")
+        .write_stdin("b\na\nb\nc\na\n")
+        .assert()
+        .success()
+        .stdout("b\na\nc\n");
+    Ok(())
+}
+
+#[test]
+fn global_count_repeated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-g", "-c", "-d", "-i"])
+        .write_stdin("b\na\nB\nc\nb\n")
+        .assert()
+        .success()
+        .stdout("      3 b\n");
+    Ok(())
+}
+
+#[test]
+fn dies_global_memory_limit() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-g", "--memory-limit", "1K"])
+        .write_stdin((0..100).map(|i| format!("{}\n", i)).collect::<String>())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "counting all lines needs more than 1024 bytes of memory",
+        ));
+    Ok(())
+}
+
+#[test]
+fn global_within_memory_limit() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-g", "--memory-limit", "1K"])
+        .write_stdin("a\na\nb\n")
+        .assert()
+        .success()
+        .stdout("a\nb\n");
+    Ok(())
+}
+
+#[test]
+fn top() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "2", "-i"])
+        .write_stdin("b\na\nc\nB\na\nc\nb\n")
+        .assert()
+        .success()
+        .stdout("      3 b\n      2 a\n");
+    Ok(())
+}
+
+#[test]
+fn top_skip_fields() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "5", "-f", "1"])
+        .write_stdin("1 GET /\n2 POST /login\n3 GET /\n")
+        .assert()
+        .success()
+        .stdout("      2 1 GET /\n      1 2 POST /login\n");
+    Ok(())
+}
+
+#[test]
+fn count_width() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "--count-width", "2"])
+        .write_stdin("a\na\nb\n".to_string() + &"c\n".repeat(100))
+        .assert()
+        .success()
+        .stdout(" 2 a\n 1 b\n100 c\n");
+    Ok(())
+}
+
+#[test]
+fn percent() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--percent", "-d"])
+        .write_stdin("a\na\na\nb\nc\nc\nc\nc\n")
+        .assert()
+        .success()
+        .stdout("      3  37.50% a\n      4  50.00% c\n");
+    Ok(())
+}
+
+#[test]
+fn top_percent() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--top", "1", "--percent"])
+        .write_stdin("a\nb\na\n")
+        .assert()
+        .success()
+        .stdout("      2  66.67% a\n");
+    Ok(())
+}
+
+#[test]
+fn ignore_case_keeps_first() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-i", "-c"])
+        .write_stdin("Foo\nfOO\nfoo\nbar\nBAR\n")
+        .assert()
+        .success()
+        .stdout("      3 Foo\n      2 bar\n");
+    Ok(())
+}
+
+#[test]
+fn skip_fields() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "-f", "1"])
+        .write_stdin("10:00 INFO started\n10:01 INFO started\n10:02 WARN started\n")
+        .assert()
+        .success()
+        .stdout("      2 10:00 INFO started\n      1 10:02 WARN started\n");
+    Ok(())
+}
+
+#[test]
+fn skip_chars() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--skip-chars", "2"])
+        .write_stdin("a1x\nb2x\nxx\nx\n")
+        .assert()
+        .success()
+        .stdout("a1x\nxx\n");
+    Ok(())
+}
+
+#[test]
+fn check_chars_after_skip() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "-i", "-f", "1", "-s", "1", "-w", "3"])
+        .write_stdin("1 abcX\n2 ABCy\n3 abd\n")
+        .assert()
+        .success()
+        .stdout("      2 1 abcX\n      1 3 abd\n");
+    Ok(())
+}
+
+#[test]
+fn dies_bad_skip_fields() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value 'x' for '--skip-fields <N>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn same_as_gnu() -> TestResult {
+    for args in [
+        vec![],
+        vec!["-c"],
+        vec!["-c", "-d", "-i"],
+        vec!["-d"],
+        vec!["-u"],
+        vec!["-i"],
+        vec!["-i", "-d"],
+        vec!["-f", "1"],
+        vec!["-s", "3"],
+        vec!["-f", "2", "-s", "1"],
+        vec!["-w", "3"],
+        vec!["-D"],
+        vec!["--all-repeated=separate", "-f", "1"],
+        vec!["--all-repeated=prepend", "-i"],
+        vec!["-f", "1", "-s", "1", "-w", "2", "-i"],
+    ] {
+        GnuCompare::new(PRG, "uniq")
+            .args(&args)
+            .stdin(gen_text(200, 1))
+            .assert()?;
+    }
+    Ok(())
+}
@@ -1,51 +1,64 @@
-use std::error::Error;
 use std::fs::File;
-use std::io;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{self, BufRead, Write};
 
 use clap::ArgAction::SetTrue;
-use clap::Parser;
-
-type MyResult<T> = Result<T, Box<dyn Error>>;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{open, CommandExt, ToolError};
+use thiserror::Error;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Rust uniq")]
 pub struct Args {
     #[arg(
         value_name = "IN_FILE",
-        help = "Input file [default: -]",
+        help = tr_str("Input file [default: -]", "入力ファイル [既定: -]"),
         default_value = "-"
     )]
     in_file: String,
-    #[arg(value_name = "OUT_FILE", help = "Output file")]
+    #[arg(value_name = "OUT_FILE", help = tr_str("Output file", "出力ファイル"))]
     out_file: Option<String>,
     #[arg(
     value_name = "count",
     short = 'c',
     long = "count",
-    help = "Show counts",
+    help = tr_str("Show counts", "件数を表示する"),
     action = SetTrue
     )]
     count: bool,
 }
 
-pub fn get_args() -> MyResult<Args> {
-    Ok(Args::parse())
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Args, Error> {
+    let matches = Args::command().get_tool_matches();
+    Ok(Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()))
 }
 
-pub fn run(args: Args) -> MyResult<()> {
-    let mut file = open(&args.in_file).map_err(|e| format!("{}: {}", args.in_file, e))?;
+pub fn run(args: Args, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let mut file = open(&args.in_file).map_err(|source| Error::Open {
+        path: args.in_file.clone(),
+        source,
+    })?;
 
-    let mut out_file: Box<dyn Write> = match &args.out_file {
+    let mut out_file: Box<dyn Write + '_> = match &args.out_file {
         Some(out_name) => Box::new(File::create(out_name)?),
-        _ => Box::new(io::stdout()),
+        _ => Box::new(out),
     };
 
     let mut line = String::new();
     let mut previous = String::new();
     let mut count: u64 = 0;
 
-    let mut print = |count: u64, text: &str| -> MyResult<()> {
+    let mut print = |count: u64, text: &str| -> Result<(), Error> {
         if count > 0 {
             if args.count {
                 write!(out_file, "{:>4} {}", count, text)?;
@@ -76,10 +89,3 @@ pub fn run(args: Args) -> MyResult<()> {
 
     Ok(())
 }
-
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
-}
@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = uniqrd::get_args().and_then(uniqrd::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = uniqrd::get_args()
+        .and_then(|args| uniqrd::run(args, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("uniqrd", &e));
     }
 }
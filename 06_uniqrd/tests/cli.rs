@@ -1,11 +1,8 @@
 use std::fs;
 
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, Fixture, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-use tempfile::NamedTempFile;
-
-type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 struct Test {
     input: &'static str,
@@ -81,21 +78,6 @@ const T6: Test = Test {
     out_count: "tests/expected/t6.txt.c.out",
 };
 
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_bad_file() -> TestResult {
@@ -125,7 +107,7 @@ fn run(test: &Test) -> TestResult {
 fn run_count(test: &Test) -> TestResult {
     let expected = fs::read_to_string(test.out_count)?;
     Command::cargo_bin(PRG)?
-        .args(&[test.input, "-c"])
+        .args([test.input, "-c"])
         .assert()
         .success()
         .stdout(expected);
@@ -160,15 +142,15 @@ fn run_stdin_count(test: &Test) -> TestResult {
 // --------------------------------------------------
 fn run_outfile(test: &Test) -> TestResult {
     let expected = fs::read_to_string(test.out)?;
-    let outfile = NamedTempFile::new()?;
-    let outpath = &outfile.path().to_str().unwrap();
+    let fixture = Fixture::new()?;
+    let outpath = &fixture.path("out.txt");
     Command::cargo_bin(PRG)?
-        .args(&[test.input, outpath])
+        .args([test.input, outpath.as_str()])
         .assert()
         .success()
         .stdout("");
 
-    let contents = fs::read_to_string(&outpath)?;
+    let contents = fs::read_to_string(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -176,17 +158,17 @@ fn run_outfile(test: &Test) -> TestResult {
 
 // --------------------------------------------------
 fn run_outfile_count(test: &Test) -> TestResult {
-    let outfile = NamedTempFile::new()?;
-    let outpath = &outfile.path().to_str().unwrap();
+    let fixture = Fixture::new()?;
+    let outpath = &fixture.path("out.txt");
 
     Command::cargo_bin(PRG)?
-        .args(&[test.input, outpath, "--count"])
+        .args([test.input, outpath.as_str(), "--count"])
         .assert()
         .success()
         .stdout("");
 
     let expected = fs::read_to_string(test.out_count)?;
-    let contents = fs::read_to_string(&outpath)?;
+    let contents = fs::read_to_string(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
@@ -195,17 +177,17 @@ fn run_outfile_count(test: &Test) -> TestResult {
 // --------------------------------------------------
 fn run_stdin_outfile_count(test: &Test) -> TestResult {
     let input = fs::read_to_string(test.input)?;
-    let outfile = NamedTempFile::new()?;
-    let outpath = &outfile.path().to_str().unwrap();
+    let fixture = Fixture::new()?;
+    let outpath = &fixture.path("out.txt");
 
     Command::cargo_bin(PRG)?
-        .args(&["-", outpath, "-c"])
+        .args(["-", outpath.as_str(), "-c"])
         .write_stdin(input)
         .assert()
         .stdout("");
 
     let expected = fs::read_to_string(test.out_count)?;
-    let contents = fs::read_to_string(&outpath)?;
+    let contents = fs::read_to_string(outpath)?;
     assert_eq!(&expected, &contents);
 
     Ok(())
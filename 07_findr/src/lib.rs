@@ -1,15 +1,31 @@
-use std::error::Error;
-
 use clap::builder::EnumValueParser;
-use clap::ArgAction::{Append, Set};
+use clap::ArgAction::{Append, Set, SetTrue};
 use clap::{Arg, Command, ValueEnum};
+use clir_common::i18n::{tr, tr_io, tr_str, tr_text, Msg};
+#[cfg(unix)]
+use clir_common::meta;
+use clir_common::posix::{basic_to_extended, bracket_to_regex};
+use clir_common::progress::{self, Progress};
+use clir_common::{args, warn_to, CommandExt, Terminator, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use regex::Regex;
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::FileType;
+use std::io;
+use std::io::Write;
+use std::iter;
+#[cfg(unix)]
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+use thiserror::Error;
 use walkdir::{DirEntry, WalkDir};
 
 use crate::EntryType::*;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
 #[derive(Debug, Eq, PartialEq, Clone, ValueEnum)]
 enum EntryType {
     #[value(name = "d")]
@@ -18,16 +34,262 @@ enum EntryType {
     File,
     #[value(name = "l")]
     Link,
+    #[cfg(unix)]
+    #[value(name = "s")]
+    Socket,
+    #[cfg(unix)]
+    #[value(name = "p")]
+    Fifo,
+    #[cfg(unix)]
+    #[value(name = "b")]
+    BlockDevice,
+    #[cfg(unix)]
+    #[value(name = "c")]
+    CharDevice,
+}
+
+impl EntryType {
+    fn matches(&self, file_type: FileType) -> bool {
+        match self {
+            Link => file_type.is_symlink(),
+            Dir => file_type.is_dir(),
+            File => file_type.is_file(),
+            #[cfg(unix)]
+            Socket => file_type.is_socket(),
+            #[cfg(unix)]
+            Fifo => file_type.is_fifo(),
+            #[cfg(unix)]
+            BlockDevice => file_type.is_block_device(),
+            #[cfg(unix)]
+            CharDevice => file_type.is_char_device(),
+        }
+    }
+}
+
+/// A `--size` test. As in GNU find, the length is rounded up to whole
+/// units before it is compared, so `-1k` only matches empty entries.
+#[derive(Debug, Clone)]
+struct SizeTest {
+    ordering: Ordering,
+    units: u64,
+    unit: u64,
+}
+
+impl SizeTest {
+    fn matches(&self, len: u64) -> bool {
+        len.div_ceil(self.unit).cmp(&self.units) == self.ordering
+    }
+}
+
+/// The command of `--exec`, program first. With `batch` (the `{} +`
+/// form) the paths are appended to it; otherwise it runs once per entry
+/// with every `{}` replaced by the path.
+#[derive(Debug, Clone)]
+struct Exec {
+    command: Vec<String>,
+    batch: bool,
+}
+
+/// How many bytes of paths are given to one `--exec ... {} +` command,
+/// well below the usual argument size limits.
+const EXEC_BATCH_BYTES: usize = 128 * 1024;
+
+/// How `--perm` compares the mode bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PermMatch {
+    /// `MODE`: exactly these bits.
+    Exact,
+    /// `-MODE`: at least all of these bits.
+    All,
+    /// `/MODE`: any of these bits, or anything if there are none.
+    Any,
+}
+
+#[derive(Debug, Clone)]
+struct PermTest {
+    kind: PermMatch,
+    mode: u32,
+}
+
+impl PermTest {
+    fn matches(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match self.kind {
+            PermMatch::Exact => mode == self.mode,
+            PermMatch::All => mode & self.mode == self.mode,
+            PermMatch::Any => self.mode == 0 || mode & self.mode != 0,
+        }
+    }
+}
+
+/// Whose ID an [`OwnerTest`] checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    User,
+    Group,
+}
+
+/// A `--user`, `--group`, `--uid` or `--gid` test. Names are resolved
+/// to IDs when the arguments are parsed.
+#[derive(Debug, Clone)]
+struct OwnerTest {
+    owner: Owner,
+    ordering: Ordering,
+    id: u32,
+}
+
+#[cfg(unix)]
+impl OwnerTest {
+    fn matches(&self, meta: &fs::Metadata) -> bool {
+        let id = match self.owner {
+            Owner::User => meta.uid(),
+            Owner::Group => meta.gid(),
+        };
+        id.cmp(&self.id) == self.ordering
+    }
+}
+
+/// The ignore files read by `--ignore`. A later one overrides an
+/// earlier one in the same directory.
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// The `.gitignore` and `.ignore` rules in effect while walking one
+/// starting point: those of the directories above the current entry,
+/// innermost last. Rules above the starting point are not read.
+#[derive(Debug, Default)]
+struct IgnoreStack {
+    matchers: Vec<(usize, Gitignore)>,
+    errors: Vec<ignore::Error>,
+}
+
+impl IgnoreStack {
+    /// Whether `entry` is ignored. Entries are expected in the order
+    /// WalkDir yields them, directories before their contents, and the
+    /// rules of a directory are read when it is reached.
+    fn ignored(&mut self, entry: &DirEntry) -> bool {
+        while self
+            .matchers
+            .last()
+            .is_some_and(|(depth, _)| *depth >= entry.depth())
+        {
+            self.matchers.pop();
+        }
+        let is_dir = entry.file_type().is_dir();
+        // Gitと同じく、.gitディレクトリそのものも見ない
+        if entry.depth() > 0 && is_dir && entry.file_name() == ".git" {
+            return true;
+        }
+        // 内側のディレクトリの規則ほど優先する
+        let ignored = self
+            .matchers
+            .iter()
+            .rev()
+            .map(|(_, matcher)| matcher.matched(entry.path(), is_dir))
+            .find(|m| !m.is_none())
+            .is_some_and(|m| m.is_ignore());
+        if is_dir && !ignored {
+            self.push(entry);
+        }
+        ignored
+    }
+
+    fn push(&mut self, dir: &DirEntry) {
+        let mut builder = GitignoreBuilder::new(dir.path());
+        for name in IGNORE_FILES {
+            let file = dir.path().join(name);
+            if file.is_file() {
+                self.errors.extend(builder.add(file));
+            }
+        }
+        match builder.build() {
+            Ok(matcher) if !matcher.is_empty() => self.matchers.push((dir.depth(), matcher)),
+            Ok(_) => {}
+            Err(e) => self.errors.push(e),
+        }
+    }
+}
+
+/// The syntax of `--regex` patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RegexType {
+    /// The syntax of the regex crate, which is close to ERE.
+    Rust,
+    /// POSIX extended regular expressions, read as Rust ones.
+    #[value(name = "posix-extended", alias = "egrep")]
+    PosixExtended,
+    /// POSIX basic regular expressions, where `\(`, `\{`, `\|`, `\+`
+    /// and `\?` are the operators.
+    #[value(name = "posix-basic")]
+    PosixBasic,
 }
 
 #[derive(Debug)]
 pub struct Config {
     paths: Vec<String>,
     names: Vec<Regex>,
+    path_globs: Vec<Regex>,
+    path_regexes: Vec<Regex>,
+    exclude_dirs: Vec<Regex>,
+    ignore: bool,
     entry_types: Vec<EntryType>,
+    target_types: Vec<EntryType>,
+    sizes: Vec<SizeTest>,
+    perms: Vec<PermTest>,
+    owners: Vec<OwnerTest>,
+    exec: Option<Exec>,
+    delete: bool,
+    depth: bool,
+    follow: bool,
+    long: bool,
+    terminator: Terminator,
+    progress: bool,
 }
 
-pub fn get_args() -> MyResult<Config> {
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_text("Invalid --name \"{0}\"", "--name が不正です \"{0}\"", &[.0]))]
+    InvalidName(String),
+    #[error("{}", tr_text("Invalid --path \"{0}\"", "--path が不正です \"{0}\"", &[.0]))]
+    InvalidPath(String),
+    #[error("{}", tr_text("Invalid --regex \"{0}\"", "--regex が不正です \"{0}\"", &[.0]))]
+    InvalidRegex(String),
+    #[error("{}", tr_text(
+        "Invalid --exclude-dir \"{0}\"",
+        "--exclude-dir が不正です \"{0}\"",
+        &[.0],
+    ))]
+    InvalidExcludeDir(String),
+    #[error("--exec: {0}")]
+    InvalidExec(&'static str),
+    #[error(
+        "{}",
+        tr_str(
+            "--delete without any test would delete everything; add --force to do so",
+            "条件なしの --delete はすべてを削除します。そうするには --force を付けてください",
+        )
+    )]
+    DeleteAll,
+    #[error("{program}: {}", tr_io(.source))]
+    Exec { program: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidName(_)
+            | Error::InvalidPath(_)
+            | Error::InvalidRegex(_)
+            | Error::InvalidExcludeDir(_)
+            | Error::InvalidExec(_)
+            | Error::DeleteAll => EXIT_USAGE,
+            Error::Exec { .. } | Error::Io(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
     let matches = Command::new("findr")
         .version("0.1.0")
         .about("Rust find")
@@ -42,21 +304,148 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("NAMES")
                 .short('n')
                 .long("name")
-                .help("Name")
+                .help(tr_str("Name", "名前"))
                 .action(Append)
                 .num_args(1..),
         )
+        .arg(
+            Arg::new("path_globs")
+                .value_name("GLOB")
+                .long("path")
+                .help(tr_str("Whole path matches GLOB, where * and ? also match /", "パス全体がGLOBに一致する(* と ? は / にも一致する)"))
+                .action(Append)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("path_regexes")
+                .value_name("PATTERN")
+                .long("regex")
+                .help(tr_str("Whole path matches PATTERN", "パス全体がPATTERNに一致する"))
+                .action(Append)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("regex_type")
+                .value_name("TYPE")
+                .long("regextype")
+                .help(tr_str("Syntax of --regex", "--regex の文法"))
+                .value_parser(EnumValueParser::<RegexType>::new())
+                .default_value("rust"),
+        )
+        .arg(
+            Arg::new("exclude_dirs")
+                .value_name("GLOB")
+                .long("exclude-dir")
+                .visible_alias("prune")
+                .help(tr_str("Do not descend into (or list) directories whose name matches GLOB", "名前がGLOBに一致するディレクトリには入らない(表示もしない)"))
+                .action(Append),
+        )
+        .arg(
+            Arg::new("ignore")
+                .long("ignore")
+                .help(tr_str("Skip entries matched by .gitignore or .ignore files, and .git directories", ".gitignore や .ignore に一致するエントリと .git ディレクトリを飛ばす"))
+                .action(SetTrue),
+        )
         .arg(
             Arg::new("types")
                 .value_name("TYPE")
                 .short('t')
                 .long("type")
-                .help("Entry type [possible value: f, d, l]")
+                .help(tr_str("Entry type", "エントリの種類"))
+                .value_parser(EnumValueParser::<EntryType>::new())
+                .action(Set)
+                .num_args(1..),
+        )
+        .arg(
+            Arg::new("xtypes")
+                .value_name("TYPE")
+                .long("xtype")
+                .help(tr_str("Like --type, but symlinks are classified by their target; broken ones are l", "--type と同じだが、シンボリックリンクはリンク先で分類する(壊れたものは l)"))
                 .value_parser(EnumValueParser::<EntryType>::new())
                 .action(Set)
                 .num_args(1..),
         )
-        .get_matches();
+        .arg(
+            Arg::new("sizes")
+                .value_name("[+-]N[cwbkMG]")
+                .long("size")
+                .help(tr_str("Size is more than (+), less than (-) or exactly N units [default unit: b]", "サイズがN単位より大きい(+)、小さい(-)、またはちょうど等しい [既定の単位: b]"))
+                .value_parser(parse_size_test)
+                .allow_hyphen_values(true)
+                .action(Append),
+        )
+        .arg(
+            Arg::new("perms")
+                .value_name("[-/]MODE")
+                .long("perm")
+                .help(tr_str("Permission bits are exactly MODE, include all of -MODE or any of /MODE (octal or symbolic)", "許可ビットがMODEと等しい、-MODEをすべて含む、または/MODEのどれかを含む(8進数または記号)"))
+                .value_parser(parse_perm_test)
+                .allow_hyphen_values(true)
+                .action(Append),
+        )
+        .args(owner_args())
+        .arg(
+            Arg::new("exec")
+                .value_name("COMMAND")
+                .long("exec")
+                .help(tr_str("Run COMMAND on each entry instead of printing it; {} is the path, and the command ends with ';' or '{} +'", "各エントリを表示せずにCOMMANDを実行する({} はパスで、コマンドは ';' か '{} +' で終わる)"))
+                .num_args(1..)
+                .value_terminator(";")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("follow")
+                .short('L')
+                .long("follow")
+                .help(tr_str("Follow symbolic links", "シンボリックリンクをたどる"))
+                .action(SetTrue)
+                .overrides_with("no_follow"),
+        )
+        .arg(
+            Arg::new("no_follow")
+                .short('P')
+                .long("no-follow")
+                .help(tr_str("Do not follow symbolic links (default)", "シンボリックリンクをたどらない(既定)"))
+                .action(SetTrue)
+                .overrides_with("follow"),
+        )
+        .arg(
+            Arg::new("print0")
+                .short('0')
+                .long("print0")
+                .help(tr_str("End each path with NUL instead of newline, for xargs -0", "xargs -0 用に、各パスを改行ではなくNULで終える"))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("long")
+                .long("ls")
+                .help(tr_str("List each entry like ls -dils: inode, blocks, mode, links, owner, size, time", "各エントリを ls -dils のように表示する(iノード、ブロック、モード、リンク、所有者、サイズ、時刻)"))
+                .action(SetTrue)
+                .conflicts_with_all(["exec", "print0"]),
+        )
+        .arg(
+            Arg::new("depth")
+                .short('d')
+                .long("depth")
+                .help(tr_str("List the contents of each directory before the directory itself (implied by --delete)", "ディレクトリの中身をディレクトリ自体より先に表示する(--delete で有効になる)"))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("delete")
+                .long("delete")
+                .help(tr_str("Delete matching entries instead of printing them, contents first", "一致したエントリを表示せずに中身から先に削除する"))
+                .action(SetTrue)
+                .conflicts_with_all(["exec", "follow", "long"]),
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .help(tr_str("Allow --delete without any test", "条件なしの --delete を許す"))
+                .action(SetTrue)
+                .requires("delete"),
+        )
+        .arg(progress::arg())
+        .get_tool_matches_from(terminate_exec(args())?);
 
     let paths = matches
         .get_many::<String>("paths")
@@ -64,39 +453,167 @@ pub fn get_args() -> MyResult<Config> {
         .map(|f| f.to_string())
         .collect();
 
-    let names = matches
+    let names: Vec<Regex> = matches
         .get_many::<String>("names")
         .map(|vals| {
             vals.into_iter()
-                .map(|name| Regex::new(name).map_err(|_| format!("Invalid --name \"{}\"", name)))
+                .map(|name| Regex::new(name).map_err(|_| Error::InvalidName(name.to_string())))
                 .collect()
         })
         .transpose()?
         .unwrap_or_default();
 
-    let entry_types = matches
+    // どちらもパス全体に一致させるので、両端に錨を付ける
+    let path_globs: Vec<Regex> = matches
+        .get_many::<String>("path_globs")
+        .map(|vals| {
+            vals.map(|glob| {
+                Regex::new(&format!("(?s)^{}$", glob_to_regex(glob)))
+                    .map_err(|_| Error::InvalidPath(glob.to_string()))
+            })
+            .collect()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let regex_type = *matches.get_one("regex_type").expect("has a default");
+    let path_regexes: Vec<Regex> = matches
+        .get_many::<String>("path_regexes")
+        .map(|vals| {
+            vals.map(|pattern| {
+                let translated = match regex_type {
+                    RegexType::Rust | RegexType::PosixExtended => pattern.to_string(),
+                    RegexType::PosixBasic => basic_to_extended(pattern),
+                };
+                Regex::new(&format!("^(?:{})$", translated))
+                    .map_err(|_| Error::InvalidRegex(pattern.to_string()))
+            })
+            .collect()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let exclude_dirs: Vec<Regex> = matches
+        .get_many::<String>("exclude_dirs")
+        .map(|vals| {
+            vals.map(|glob| {
+                Regex::new(&format!("(?s)^{}$", glob_to_regex(glob)))
+                    .map_err(|_| Error::InvalidExcludeDir(glob.to_string()))
+            })
+            .collect()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let entry_types: Vec<EntryType> = matches
         .get_many::<EntryType>("types")
         .map(|vals| vals.into_iter().cloned().collect())
         .unwrap_or_default();
 
+    let target_types: Vec<EntryType> = matches
+        .get_many::<EntryType>("xtypes")
+        .map(|vals| vals.into_iter().cloned().collect())
+        .unwrap_or_default();
+
+    let exec = matches
+        .get_many::<String>("exec")
+        .map(|vals| parse_exec(vals.cloned().collect()))
+        .transpose()?;
+
+    let sizes: Vec<SizeTest> = matches
+        .get_many::<SizeTest>("sizes")
+        .map(|vals| vals.cloned().collect())
+        .unwrap_or_default();
+
+    let perms: Vec<PermTest> = matches
+        .get_many::<PermTest>("perms")
+        .map(|vals| vals.cloned().collect())
+        .unwrap_or_default();
+
+    // 同じ引数を複数回指定でき、すべてを満たすものだけを残す
+    let owners: Vec<OwnerTest> = ["user", "group", "uid", "gid"]
+        .iter()
+        .filter_map(|id| matches.try_get_many::<OwnerTest>(id).ok().flatten())
+        .flat_map(|vals| vals.cloned())
+        .collect();
+
+    let delete = matches.get_flag("delete");
+    // 条件を付け忘れた --delete で木全体を消さないようにする
+    if delete
+        && names.is_empty()
+        && path_globs.is_empty()
+        && path_regexes.is_empty()
+        && entry_types.is_empty()
+        && target_types.is_empty()
+        && sizes.is_empty()
+        && perms.is_empty()
+        && owners.is_empty()
+        && !matches.get_flag("force")
+    {
+        return Err(Error::DeleteAll);
+    }
+
     Ok(Config {
         paths,
         names,
+        path_globs,
+        path_regexes,
+        exclude_dirs,
+        ignore: matches.get_flag("ignore"),
         entry_types,
+        target_types,
+        sizes,
+        perms,
+        owners,
+        exec,
+        delete,
+        // 消すときは中身を先に処理し、空になったディレクトリを消せるようにする
+        depth: delete || matches.get_flag("depth"),
+        follow: matches.get_flag("follow"),
+        long: matches.get_flag("long"),
+        terminator: if matches.get_flag("print0") {
+            Terminator::Nul
+        } else {
+            Terminator::Newline
+        },
+        progress: matches.get_flag("progress"),
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    // 一件ずつ書き出すので、行ごとに書き込まないようにまとめる
+    let mut out = io::BufWriter::new(out);
+    let mut status = 0;
+    let progress = Progress::new(config.progress, "findr", "entries");
     let type_filter = |entry: &DirEntry| {
         config.entry_types.is_empty()
             || config
                 .entry_types
                 .iter()
-                .any(|entry_type| match entry_type {
-                    Link => entry.file_type().is_symlink(),
-                    Dir => entry.file_type().is_dir(),
-                    File => entry.file_type().is_file(),
-                })
+                .any(|entry_type| entry_type.matches(entry.file_type()))
+    };
+    // リンク先をたどれない壊れたリンクは、リンクのまま扱う
+    let xtype_filter = |entry: &DirEntry| {
+        if config.target_types.is_empty() {
+            return true;
+        }
+        // リンクをたどるときは、逆にリンクそのものの種類を見る
+        let mut file_type = entry.file_type();
+        if config.follow {
+            if entry.path_is_symlink() {
+                if let Ok(meta) = fs::symlink_metadata(entry.path()) {
+                    file_type = meta.file_type();
+                }
+            }
+        } else if file_type.is_symlink() {
+            if let Ok(meta) = fs::metadata(entry.path()) {
+                file_type = meta.file_type();
+            }
+        }
+        config
+            .target_types
+            .iter()
+            .any(|entry_type| entry_type.matches(file_type))
     };
     let name_filter = |entry: &DirEntry| {
         config.names.is_empty()
@@ -105,22 +622,551 @@ pub fn run(config: Config) -> MyResult<()> {
                 .iter()
                 .any(|re| re.is_match(&entry.file_name().to_string_lossy()))
     };
-    for path in config.paths {
-        let entries = WalkDir::new(path)
+    // --pathと--regexはそれぞれ--nameと同じく、どれかに一致すればよい
+    let path_filter = |entry: &DirEntry| {
+        let path = entry.path().to_string_lossy();
+        [&config.path_globs, &config.path_regexes]
+            .iter()
+            .all(|res| res.is_empty() || res.iter().any(|re| re.is_match(&path)))
+    };
+    // --sizeを複数指定したときはすべてを満たすものだけを残す
+    let size_filter = |entry: &DirEntry| {
+        config.sizes.is_empty()
+            || entry
+                .metadata()
+                .is_ok_and(|meta| config.sizes.iter().all(|size| size.matches(meta.len())))
+    };
+    let perm_filter = |entry: &DirEntry| {
+        config.perms.is_empty()
+            || entry
+                .metadata()
+                .is_ok_and(|meta| config.perms.iter().all(|perm| perm.matches(mode(&meta))))
+    };
+    let owner_filter = |entry: &DirEntry| {
+        if config.owners.is_empty() {
+            return true;
+        }
+        #[cfg(unix)]
+        return entry
+            .metadata()
+            .is_ok_and(|meta| config.owners.iter().all(|owner| owner.matches(&meta)));
+        #[cfg(not(unix))]
+        return false;
+    };
+    // 除外したディレクトリは中に入らないよう、歩く前に刈り取る。
+    // 起点に指定されたパスは除外しない
+    let excluded = |entry: &DirEntry| {
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && config
+                .exclude_dirs
+                .iter()
+                .any(|re| re.is_match(&entry.file_name().to_string_lossy()))
+    };
+    // {} + の形では、溜めたパスをまとめて渡す
+    let mut batch: Vec<PathBuf> = vec![];
+    let mut batch_bytes = 0;
+    let now = SystemTime::now();
+    for path in &config.paths {
+        let mut ignores = IgnoreStack::default();
+        let walked = WalkDir::new(path)
+            .follow_links(config.follow)
             .into_iter()
-            .filter_map(|e| match e {
-                Err(e) => {
-                    eprintln!("{}", e);
-                    None
+            .filter_entry(|entry| !(excluded(entry) || config.ignore && ignores.ignored(entry)))
+            .inspect(|_| progress.inc(1));
+        // 中身を先に出すときも、WalkDirのcontents_firstでは除外したディレクトリの
+        // 中身まで出てくるので、上から順に歩きながら並べ替える
+        let walked: Box<dyn Iterator<Item = walkdir::Result<DirEntry>>> = if config.depth {
+            Box::new(contents_first(walked))
+        } else {
+            Box::new(walked)
+        };
+        for entry in walked {
+            let entry = match entry {
+                Ok(entry) => entry,
+                // リンクのループも他のエラーと同じく、警告してその先には進まない
+                Err(e) => match config.follow.then(|| broken_link(&e)).flatten() {
+                    Some(entry) => entry,
+                    None => {
+                        warn_to(err, "findr", &e);
+                        status = EXIT_FAILURE;
+                        continue;
+                    }
+                },
+            };
+            if !(type_filter(&entry)
+                && xtype_filter(&entry)
+                && name_filter(&entry)
+                && path_filter(&entry)
+                && size_filter(&entry)
+                && perm_filter(&entry)
+                && owner_filter(&entry))
+            {
+                continue;
+            }
+            if config.delete {
+                if let Err(e) = delete(&entry) {
+                    warn_to(
+                        err,
+                        "findr",
+                        &format_args!("{}: {}", entry.path().display(), tr_io(&e)),
+                    );
+                    status = EXIT_FAILURE;
+                }
+                continue;
+            }
+            match &config.exec {
+                Some(exec) if exec.batch => {
+                    batch_bytes += entry.path().as_os_str().len() + 1;
+                    batch.push(entry.into_path());
+                    if batch_bytes >= EXEC_BATCH_BYTES {
+                        out.flush()?;
+                        status = status.max(exec.run_batch(&batch, err));
+                        batch.clear();
+                        batch_bytes = 0;
+                    }
+                }
+                Some(exec) => {
+                    out.flush()?;
+                    // GNU findと同じく、コマンドが失敗しても起動できなくても終了ステータスは変えない
+                    if let Err(e) = exec.run(&[entry.path()]) {
+                        warn_to(err, "findr", &e);
+                    }
                 }
-                Ok(entry) => Some(entry),
+                None if config.long => match long_line(&entry, now) {
+                    Ok(line) => writeln!(out, "{}", line)?,
+                    Err(e) => {
+                        warn_to(err, "findr", &e);
+                        status = EXIT_FAILURE;
+                    }
+                },
+                // 名前をそのまま渡せるよう、表示用に変換しない
+                None if config.terminator == Terminator::Nul => {
+                    out.write_all(entry.path().as_os_str().as_encoded_bytes())?;
+                    out.write_all(b"\0")?;
+                }
+                None => writeln!(out, "{}", entry.path().display())?,
+            }
+        }
+        // 読めないignoreファイルや不正な行は警告するが、残りの規則は使う
+        for e in ignores.errors {
+            warn_to(err, "findr", &e);
+            status = EXIT_FAILURE;
+        }
+    }
+    if let (Some(exec), false) = (&config.exec, batch.is_empty()) {
+        out.flush()?;
+        status = status.max(exec.run_batch(&batch, err));
+    }
+    out.flush()?;
+    progress.finish();
+    Ok(status)
+}
+
+/// Reorders `entries`, listed from the top as WalkDir yields them, so
+/// that every directory comes after its contents. Only the directories
+/// above the current entry are held back, so the output still streams.
+fn contents_first(
+    entries: impl Iterator<Item = walkdir::Result<DirEntry>>,
+) -> impl Iterator<Item = walkdir::Result<DirEntry>> {
+    // エラーも、それが起きた深さの項目として並べる
+    let depth = |entry: &walkdir::Result<DirEntry>| match entry {
+        Ok(entry) => entry.depth(),
+        Err(e) => e.depth(),
+    };
+    let mut entries = entries.peekable();
+    let mut parents = vec![];
+    iter::from_fn(move || loop {
+        let next_depth = entries.peek().map(depth);
+        if let Some(parent) =
+            parents.pop_if(|parent| next_depth.is_none_or(|next| next <= depth(parent)))
+        {
+            return Some(parent);
+        }
+        parents.push(entries.next()?);
+    })
+}
+
+/// The entry of a broken symlink that `e` is about, if any. WalkDir
+/// reports them as errors when following links, but GNU find lists them
+/// as links, so they are read again without following.
+fn broken_link(e: &walkdir::Error) -> Option<DirEntry> {
+    let path = e.path()?;
+    let is_link = fs::symlink_metadata(path).ok()?.file_type().is_symlink();
+    if !is_link || fs::metadata(path).is_ok() {
+        return None;
+    }
+    WalkDir::new(path)
+        .follow_root_links(false)
+        .into_iter()
+        .next()?
+        .ok()
+}
+
+/// The `--ls` line of `entry`. Other systems have no inodes or owners,
+/// so only the path is shown there.
+#[cfg(unix)]
+fn long_line(entry: &DirEntry, now: SystemTime) -> Result<String, walkdir::Error> {
+    Ok(meta::long_line(entry.path(), &entry.metadata()?, now))
+}
+
+#[cfg(not(unix))]
+fn long_line(entry: &DirEntry, _now: SystemTime) -> Result<String, walkdir::Error> {
+    Ok(entry.path().display().to_string())
+}
+
+/// Removes `entry`: a directory only if it is empty, anything else
+/// (including a symlink to a directory) by unlinking it. Like GNU find,
+/// a starting point of `.` is left alone.
+fn delete(entry: &DirEntry) -> io::Result<()> {
+    if entry.depth() == 0 && entry.path() == Path::new(".") {
+        return Ok(());
+    }
+    if entry.file_type().is_dir() {
+        fs::remove_dir(entry.path())
+    } else {
+        fs::remove_file(entry.path())
+    }
+}
+
+impl Exec {
+    /// Runs the command once for `paths`, returning whether it
+    /// succeeded. It fails only if the command cannot be started.
+    fn run(&self, paths: &[&Path]) -> Result<bool, Error> {
+        let mut args = self.command.iter();
+        let program = args.next().expect("checked when parsed");
+        let mut command = process::Command::new(program);
+        if self.batch {
+            command.args(args).args(paths);
+        } else {
+            command.args(args.map(|arg| substitute(arg, paths[0])));
+        }
+        command
+            .status()
+            .map(|status| status.success())
+            .map_err(|source| Error::Exec {
+                program: program.clone(),
+                source,
             })
-            .filter(type_filter)
-            .filter(name_filter)
-            .map(|entry| entry.path().display().to_string())
-            .collect::<Vec<_>>();
+    }
 
-        println!("{}", entries.join("\n"));
+    /// Runs a `{} +` command for `paths`, returning the exit status
+    /// findr should use: as in GNU find, a failed command is an error.
+    fn run_batch(&self, paths: &[PathBuf], err: &mut impl Write) -> i32 {
+        let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+        match self.run(&paths) {
+            Ok(true) => 0,
+            Ok(false) => EXIT_FAILURE,
+            Err(e) => {
+                warn_to(err, "findr", &e);
+                EXIT_FAILURE
+            }
+        }
     }
-    Ok(())
+}
+
+/// Replaces every `{}` in `arg` with `path`, without going through a
+/// `String` so that any path can be passed.
+fn substitute(arg: &str, path: &Path) -> OsString {
+    let mut parts = arg.split("{}");
+    let mut result = OsString::from(parts.next().unwrap_or_default());
+    for part in parts {
+        result.push(path);
+        result.push(part);
+    }
+    result
+}
+
+/// Checks that the command of `--exec` ends with `;` or `{} +`, as in
+/// find, and adds a `;` after the `{} +` form so that clap stops there
+/// too. Without this clap would take the rest of the arguments as the
+/// command.
+fn terminate_exec(args: Vec<OsString>) -> Result<Vec<OsString>, Error> {
+    let mut terminated = Vec::with_capacity(args.len() + 1);
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            terminated.push(arg);
+            terminated.extend(args);
+            break;
+        }
+        let exec = arg == "--exec" || arg.to_str().is_some_and(|a| a.starts_with("--exec="));
+        terminated.push(arg);
+        if !exec {
+            continue;
+        }
+        let mut braces = false;
+        loop {
+            let Some(arg) = args.next() else {
+                return Err(Error::InvalidExec(tr_str(
+                    "missing ';' or '{} +' after the command",
+                    "コマンドの後に ';' か '{} +' がありません",
+                )));
+            };
+            let end = arg == ";";
+            let plus = braces && arg == "+";
+            braces = arg == "{}";
+            terminated.push(arg);
+            if plus {
+                terminated.push(";".into());
+            }
+            if end || plus {
+                break;
+            }
+        }
+    }
+    Ok(terminated)
+}
+
+/// Makes an [`Exec`] from the values of `--exec`. The `;` that ends the
+/// command has already been removed by clap, and [`terminate_exec`] made
+/// sure a `{} +` can only come last.
+fn parse_exec(mut command: Vec<String>) -> Result<Exec, Error> {
+    let batch = command.ends_with(&["{}".to_string(), "+".to_string()]);
+    if batch {
+        command.truncate(command.len() - 2);
+    }
+    if command.is_empty() {
+        return Err(Error::InvalidExec(tr_str(
+            "missing command",
+            "コマンドがありません",
+        )));
+    }
+    Ok(Exec { command, batch })
+}
+
+/// The arguments that test the owner of an entry. They only exist on
+/// Unix, where entries have numeric owners.
+#[cfg(unix)]
+fn owner_args() -> Vec<Arg> {
+    let arg = |id: &'static str, value_name: &'static str, help: &'static str| {
+        Arg::new(id)
+            .long(id)
+            .value_name(value_name)
+            .help(help)
+            .allow_hyphen_values(true)
+            .action(Append)
+    };
+    vec![
+        arg(
+            "user",
+            "NAME",
+            tr_str(
+                "Owned by the user NAME or numeric ID",
+                "ユーザーNAME(または数値ID)が所有する",
+            ),
+        )
+        .value_parser(|val: &str| parse_owner(Owner::User, val)),
+        arg(
+            "group",
+            "NAME",
+            tr_str(
+                "Owned by the group NAME or numeric ID",
+                "グループNAME(または数値ID)が所有する",
+            ),
+        )
+        .value_parser(|val: &str| parse_owner(Owner::Group, val)),
+        arg(
+            "uid",
+            "[+-]N",
+            tr_str(
+                "Numeric user ID is more than (+), less than (-) or exactly N",
+                "数値のユーザーIDがNより大きい(+)、小さい(-)、または等しい",
+            ),
+        )
+        .value_parser(|val: &str| parse_id_test(Owner::User, val)),
+        arg(
+            "gid",
+            "[+-]N",
+            tr_str(
+                "Numeric group ID is more than (+), less than (-) or exactly N",
+                "数値のグループIDがNより大きい(+)、小さい(-)、または等しい",
+            ),
+        )
+        .value_parser(|val: &str| parse_id_test(Owner::Group, val)),
+    ]
+}
+
+#[cfg(not(unix))]
+fn owner_args() -> Vec<Arg> {
+    vec![]
+}
+
+/// Parses a `--user` or `--group` value, a name or a numeric ID.
+#[cfg(unix)]
+fn parse_owner(owner: Owner, val: &str) -> Result<OwnerTest, String> {
+    let id = match (val.parse(), owner) {
+        (Ok(id), _) => Some(id),
+        (Err(_), Owner::User) => uzers::get_user_by_name(val).map(|user| user.uid()),
+        (Err(_), Owner::Group) => uzers::get_group_by_name(val).map(|group| group.gid()),
+    };
+    let id = id.ok_or_else(|| match owner {
+        Owner::User => tr_text(
+            "no such user \"{0}\"",
+            "そのようなユーザーはありません \"{0}\"",
+            &[&val],
+        ),
+        Owner::Group => tr_text(
+            "no such group \"{0}\"",
+            "そのようなグループはありません \"{0}\"",
+            &[&val],
+        ),
+    })?;
+    Ok(OwnerTest {
+        owner,
+        ordering: Ordering::Equal,
+        id,
+    })
+}
+
+/// Parses a `--uid` or `--gid` value: an optional `+` or `-` and an ID.
+#[cfg(unix)]
+fn parse_id_test(owner: Owner, val: &str) -> Result<OwnerTest, String> {
+    let (ordering, rest) = match val.as_bytes().first() {
+        Some(b'+') => (Ordering::Greater, &val[1..]),
+        Some(b'-') => (Ordering::Less, &val[1..]),
+        _ => (Ordering::Equal, val),
+    };
+    let id = rest.parse().map_err(|_| tr(Msg::InvalidInteger, &[&val]))?;
+    Ok(OwnerTest {
+        owner,
+        ordering,
+        id,
+    })
+}
+
+/// Turns a shell glob into a regex for the regex crate. As for GNU
+/// find's `-path`, `*` and `?` also match `/` and leading dots.
+fn glob_to_regex(glob: &str) -> String {
+    let chars = glob.chars().collect::<Vec<_>>();
+    let mut re = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                re.push_str(&regex::escape(&chars[i].to_string()));
+            }
+            '[' => {
+                if let Some((class, len)) = bracket_to_regex(&chars[i..], &['!', '^']) {
+                    re.push_str(&class);
+                    i += len;
+                    continue;
+                }
+                re.push_str("\\[");
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+    re
+}
+
+/// The permission bits of an entry. Other systems only tell whether it
+/// is read-only, so that is turned into a Unix-like mode.
+#[cfg(unix)]
+fn mode(meta: &fs::Metadata) -> u32 {
+    meta.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn mode(meta: &fs::Metadata) -> u32 {
+    if meta.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
+
+/// Parses a `--perm` value: `-` or `/` for [`PermMatch::All`] or
+/// [`PermMatch::Any`], then an octal mode such as `644` or a symbolic
+/// one such as `u+w,go=r`. As in GNU find, a symbolic mode starts from
+/// no bits and ignores the umask.
+fn parse_perm_test(val: &str) -> Result<PermTest, String> {
+    let error = || format!("invalid mode \"{}\"", val);
+    let (kind, rest) = match val.as_bytes().first() {
+        Some(b'-') => (PermMatch::All, &val[1..]),
+        Some(b'/') => (PermMatch::Any, &val[1..]),
+        _ => (PermMatch::Exact, val),
+    };
+    if !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()) {
+        return match u32::from_str_radix(rest, 8) {
+            Ok(mode) if mode <= 0o7777 => Ok(PermTest { kind, mode }),
+            _ => Err(error()),
+        };
+    }
+
+    let mut mode = 0;
+    for clause in rest.split(',') {
+        let ops = clause.trim_start_matches(['u', 'g', 'o', 'a']);
+        let who = clause[..clause.len() - ops.len()]
+            .chars()
+            .fold(0, |who, c| {
+                who | match c {
+                    'u' => 0o4700,
+                    'g' => 0o2070,
+                    'o' => 0o1007,
+                    _ => 0o7777,
+                }
+            });
+        let who = if who == 0 { 0o7777 } else { who };
+        if ops.is_empty() {
+            return Err(error());
+        }
+        // "u+w-x" のように、ひとつの対象に演算を続けて書ける
+        let mut chars = ops.chars().peekable();
+        while let Some(op) = chars.next() {
+            let mut bits = 0;
+            while let Some(&c) = chars.peek() {
+                bits |= match c {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' | 'X' => 0o111,
+                    's' => 0o6000,
+                    't' => 0o1000,
+                    _ => break,
+                };
+                chars.next();
+            }
+            let bits = bits & who;
+            match op {
+                '+' => mode |= bits,
+                '-' => mode &= !bits,
+                '=' => mode = (mode & !who) | bits,
+                _ => return Err(error()),
+            }
+        }
+    }
+    Ok(PermTest { kind, mode })
+}
+
+/// Parses a `--size` value: an optional `+` or `-`, a number and a unit
+/// of `c` (bytes), `w` (2 bytes), `b` (512 bytes), `k`, `M` or `G`.
+fn parse_size_test(val: &str) -> Result<SizeTest, String> {
+    let error = || tr(Msg::InvalidSize, &[&val]);
+    let (ordering, rest) = match val.as_bytes().first() {
+        Some(b'+') => (Ordering::Greater, &val[1..]),
+        Some(b'-') => (Ordering::Less, &val[1..]),
+        _ => (Ordering::Equal, val),
+    };
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (num, unit) = rest.split_at(digits);
+    let unit = match unit {
+        "" | "b" => 512,
+        "c" => 1,
+        "w" => 2,
+        "k" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return Err(error()),
+    };
+    Ok(SizeTest {
+        ordering,
+        units: num.parse().map_err(|_| error())?,
+        unit,
+    })
 }
@@ -1,6 +1,10 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = findr::get_args().and_then(findr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = findr::get_args()
+        .and_then(|config| findr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("findr", &e)),
     }
 }
@@ -1,28 +1,11 @@
 use std::{borrow::Cow, fs};
 
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, gnu, Fixture, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-
-type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 const PRG: &str = "findr";
 
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn skips_bad_dir() -> TestResult {
@@ -31,7 +14,7 @@ fn skips_bad_dir() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -40,7 +23,7 @@ fn skips_bad_dir() -> TestResult {
 #[test]
 fn dies_bad_name() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["--name", "*.csv"])
+        .args(["--name", "*.csv"])
         .assert()
         .failure()
         .stderr(predicate::str::contains("Invalid --name \"*.csv\""));
@@ -52,7 +35,7 @@ fn dies_bad_name() -> TestResult {
 fn dies_bad_type() -> TestResult {
     let expected = "error: invalid value 'x' for '--type <TYPE>...'";
     Command::cargo_bin(PRG)?
-        .args(&["--type", "x"])
+        .args(["--type", "x"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -68,7 +51,7 @@ fn format_file_name(expected_file: &str) -> Cow<str> {
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -124,6 +107,18 @@ fn path_a_b_d() -> TestResult {
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn progress_path1() -> TestResult {
+    // 端末でなければ進捗は表示されず、出力も変わらない
+    let out = Command::cargo_bin(PRG)?
+        .args(["--progress", "tests/inputs"])
+        .output()?;
+    assert!(out.status.success());
+    assert!(out.stderr.is_empty());
+    run(&["--progress", "tests/inputs"], "tests/expected/path1.txt")
+}
+
 // --------------------------------------------------
 #[test]
 fn type_f() -> TestResult {
@@ -295,7 +290,7 @@ fn unreadable_dir() -> TestResult {
     //permissions.set_mode(0o000);
 
     std::process::Command::new("chmod")
-        .args(&["000", dirname])
+        .args(["000", dirname])
         .status()
         .expect("failed");
 
@@ -315,3 +310,826 @@ fn unreadable_dir() -> TestResult {
     assert!(stderr.contains("cant-touch-this: Permission denied"));
     Ok(())
 }
+
+// --------------------------------------------------
+fn sorted_lines(out: &[u8]) -> Vec<String> {
+    let mut lines: Vec<String> = String::from_utf8_lossy(out)
+        .lines()
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    lines.sort();
+    lines
+}
+
+// --------------------------------------------------
+fn same_as_find(root: &str, args: &[&str], find_args: &[&str]) -> TestResult {
+    let Some(find) = gnu("find") else {
+        return Ok(());
+    };
+    let out = Command::cargo_bin(PRG)?.arg(root).args(args).output()?;
+    let expected = std::process::Command::new(find)
+        .arg(root)
+        .args(find_args)
+        .output()?;
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        sorted_lines(&expected.stdout),
+        "findr {:?} and find {:?} differ",
+        args,
+        find_args
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+fn size_fixture() -> Result<Fixture, Box<dyn std::error::Error>> {
+    let fixture = Fixture::new()?;
+    fixture.file("empty", "")?;
+    fixture.file("small", "x".repeat(100))?;
+    fixture.file("one_k", "x".repeat(1024))?;
+    fixture.file("sub/big", "x".repeat(3000))?;
+    Ok(fixture)
+}
+
+// --------------------------------------------------
+#[test]
+fn size() -> TestResult {
+    let fixture = size_fixture()?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    for size in [
+        "+1k", "-1k", "1k", "100c", "-101c", "+2", "1", "-3w", "+0", "0M", "-1G",
+    ] {
+        same_as_find(
+            root,
+            &["--type", "f", "--size", size],
+            &["-type", "f", "-size", size],
+        )?;
+    }
+    same_as_find(
+        root,
+        &["--size", "+0", "--size", "-3k"],
+        &["-size", "+0", "-size", "-3k"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn size_rounds_up() -> TestResult {
+    let fixture = size_fixture()?;
+    let out = Command::cargo_bin(PRG)?
+        .args([&fixture.path(""), "--type", "f", "--size", "-1k"])
+        .output()?;
+    assert_eq!(sorted_lines(&out.stdout), [fixture.path("empty")]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_size() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--size", "+1x"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid size \"+1x\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_each() -> TestResult {
+    same_as_find(
+        "tests/inputs/a",
+        &["--exec", "echo", "found", "<{}>", ";"],
+        &["-exec", "echo", "found", "<{}>", ";"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_batch() -> TestResult {
+    let out = Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "--type", "f", "--exec", "echo", "{}", "+"])
+        .output()?;
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout)?;
+    let mut paths = stdout.split_whitespace().collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "tests/inputs/a/a.txt",
+            "tests/inputs/a/b/b.csv",
+            "tests/inputs/a/b/c/c.mp3"
+        ]
+    );
+    assert_eq!(stdout.lines().count(), 1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_status() -> TestResult {
+    // ';' の形ではコマンドの失敗は終了ステータスに影響しないが、'+' の形では影響する
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "--exec", "false", ";"])
+        .assert()
+        .success()
+        .stdout("");
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "--exec", "false", "{}", "+"])
+        .assert()
+        .code(1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_missing_command() -> TestResult {
+    // ';' の形ではコマンドを起動できなくても終了ステータスは変えない
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "--exec", &bad, "{}", ";"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_match(format!(
+            "^findr: {}: .* [(]os error 2[)]\n$",
+            bad
+        ))?);
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "--exec", &bad, "{}", "+"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_match(format!(
+            "^findr: {}: .* [(]os error 2[)]\n$",
+            bad
+        ))?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_plus_ends_command() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([
+            "tests/inputs/a",
+            "--exec",
+            "echo",
+            "{}",
+            "+",
+            "--name",
+            "a.txt",
+        ])
+        .assert()
+        .success()
+        .stdout("tests/inputs/a/a.txt\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_exec_unterminated() -> TestResult {
+    for args in [
+        &["tests/inputs/a", "--exec", "echo", "{}", "foo", "+"][..],
+        &["tests/inputs/a", "--exec", "echo", "{}"],
+        &["tests/inputs/a", "--exec"],
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .code(2)
+            .stdout("")
+            .stderr("findr: --exec: missing ';' or '{} +' after the command\n");
+    }
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "--exec", "{}", "+"])
+        .assert()
+        .code(2)
+        .stderr("findr: --exec: missing command\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("keep.txt", "")?;
+    fixture.file("a.tmp", "")?;
+    fixture.file("sub/b.tmp", "")?;
+    fixture.dir("empty.tmp")?;
+    Command::cargo_bin(PRG)?
+        .args([&fixture.path(""), "--name", "\\.tmp$", "--delete"])
+        .assert()
+        .success()
+        .stdout("");
+    let out = Command::cargo_bin(PRG)?.arg(fixture.path("")).output()?;
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        [
+            fixture.path(""),
+            fixture.path("keep.txt"),
+            fixture.path("sub")
+        ]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_non_empty_dir() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("dir/keep.txt", "")?;
+    Command::cargo_bin(PRG)?
+        .args([
+            &fixture.path(""),
+            "--type",
+            "d",
+            "--name",
+            "^dir$",
+            "--delete",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(format!(
+            "findr: {}: Directory not empty",
+            fixture.path("dir")
+        )));
+    assert!(fs::metadata(fixture.path("dir/keep.txt")).is_ok());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_delete_without_filter() -> TestResult {
+    let fixture = Fixture::new()?;
+    let file = fixture.file("a.txt", "")?;
+    Command::cargo_bin(PRG)?
+        .args([&fixture.path(""), "--delete"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("add --force to do so"));
+    assert!(fs::metadata(&file).is_ok());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_force() -> TestResult {
+    let fixture = Fixture::new()?;
+    let root = fixture.dir("root")?;
+    fixture.file("root/a/b.txt", "")?;
+    Command::cargo_bin(PRG)?
+        .args([&root, "--delete", "--force"])
+        .assert()
+        .success();
+    assert!(fs::metadata(&root).is_err());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn print0() -> TestResult {
+    let fixture = Fixture::new()?;
+    let file = fixture.file("a b\nc.txt", "")?;
+    Command::cargo_bin(PRG)?
+        .args([&fixture.path(""), "--type", "f", "-0"])
+        .assert()
+        .success()
+        .stdout(format!("{}\0", file));
+    same_as_find(
+        &fixture.path(""),
+        &["--print0", "--name", "txt"],
+        &["-name", "*txt*", "-print0"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn type_special() -> TestResult {
+    use std::os::unix::net::UnixListener;
+
+    let fixture = Fixture::new()?;
+    fixture.file("file", "")?;
+    let _socket = UnixListener::bind(fixture.path("socket"))?;
+    std::process::Command::new("mkfifo")
+        .arg(fixture.path("fifo"))
+        .status()?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    for (types, find_types) in [("s", "s"), ("p", "p"), ("s p f", "s,p,f")] {
+        let mut args = vec!["--type"];
+        args.extend(types.split(' '));
+        same_as_find(root, &args, &["-type", find_types])?;
+    }
+    same_as_find("/dev/null", &["--type", "c"], &["-type", "c"])
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn xtype() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("dir/file", "")?;
+    fixture.symlink("to_dir", "dir")?;
+    fixture.symlink("to_file", "dir/file")?;
+    fixture.symlink("broken", "missing")?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    for xtype in ["d", "f", "l"] {
+        same_as_find(root, &["--xtype", xtype], &["-xtype", xtype])?;
+    }
+    let out = Command::cargo_bin(PRG)?
+        .args([root, "--xtype", "l"])
+        .output()?;
+    assert_eq!(sorted_lines(&out.stdout), [fixture.path("broken")]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn perm() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Fixture::new()?;
+    for mode in [0o644, 0o600, 0o755, 0o4755, 0o666, 0o000] {
+        let file = fixture.file(&format!("{:o}", mode), "")?;
+        fs::set_permissions(file, fs::Permissions::from_mode(mode))?;
+    }
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    for perm in [
+        "644",
+        "0644",
+        "-600",
+        "/111",
+        "/0",
+        "-u+x",
+        "u=rw,go=r",
+        "/u+s",
+        "-a+r",
+        "/go+w",
+        "-u=rwx,g+x",
+        "u+rw-x,g+r,o+r",
+    ] {
+        same_as_find(
+            root,
+            &["--type", "f", "--perm", perm],
+            &["-type", "f", "-perm", perm],
+        )?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_perm() -> TestResult {
+    for perm in ["888", "u+q", "-", "17777", "u"] {
+        Command::cargo_bin(PRG)?
+            .args(["--perm", perm])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains(format!(
+                "invalid mode \"{}\"",
+                perm
+            )));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn owner() -> TestResult {
+    let fixture = Fixture::new()?;
+    for (name, uid, gid) in [("root", 0, 0), ("daemon", 1, 1), ("bin", 2, 4)] {
+        let file = fixture.file(name, "")?;
+        // 所有者を変えられるのはrootだけなので、それ以外では比べない
+        if std::os::unix::fs::chown(&file, Some(uid), Some(gid)).is_err() {
+            return Ok(());
+        }
+    }
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    for (test, value) in [
+        ("user", "root"),
+        ("user", "daemon"),
+        ("user", "2"),
+        ("group", "adm"),
+        ("group", "0"),
+        ("uid", "+0"),
+        ("uid", "-2"),
+        ("uid", "1"),
+        ("gid", "+1"),
+    ] {
+        same_as_find(
+            root,
+            &["--type", "f", &format!("--{}", test), value],
+            &["-type", "f", &format!("-{}", test), value],
+        )?;
+    }
+    same_as_find(
+        root,
+        &["--uid", "+0", "--group", "adm"],
+        &["-uid", "+0", "-group", "adm"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn dies_bad_owner() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--user", &bad])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(format!(
+            "no such user \"{}\"",
+            bad
+        )));
+    Command::cargo_bin(PRG)?
+        .args(["--gid", "+x"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("\"+x\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn owner_ja() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("--help")
+        .env("LC_ALL", "ja_JP.UTF-8")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "ユーザーNAME(または数値ID)が所有する",
+        ))
+        .stdout(predicate::str::contains("数値のグループIDがNより大きい"));
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--group", &bad])
+        .env("LC_ALL", "ja_JP.UTF-8")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(format!(
+            "そのようなグループはありません \"{}\"",
+            bad
+        )));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn path_glob() -> TestResult {
+    for glob in [
+        "*/b/*",
+        "tests/inputs/?/*.csv",
+        "*[cd].mp3",
+        "*/[!a-c]*",
+        "*[[:digit:]]*",
+        "tests/inputs",
+        "*[]]*",
+        "*\\.txt",
+    ] {
+        same_as_find("tests/inputs", &["--path", glob], &["-path", glob])?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn path_glob_special_chars() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("a+b (1)/c.txt", "")?;
+    fixture.file("a&&b/d.txt", "")?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    for glob in ["*+b (1)/*", "*[&]&b*", "*[a-b]*.txt"] {
+        same_as_find(root, &["--path", glob], &["-path", glob])?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn regex() -> TestResult {
+    for (regex_type, pattern) in [
+        ("posix-extended", ".*/(b|e)/.*"),
+        ("posix-extended", ".*\\.(csv|tsv)"),
+        ("egrep", "tests/inputs/[a-d]+"),
+        ("posix-basic", ".*/\\(b\\|e\\)/.*"),
+        ("posix-basic", ".*/[a-c]\\{1,\\}\\.csv"),
+        ("posix-basic", ".*(x)?"),
+    ] {
+        same_as_find(
+            "tests/inputs",
+            &["--regextype", regex_type, "--regex", pattern],
+            &["-regextype", regex_type, "-regex", pattern],
+        )?;
+    }
+    // 既定ではRustの構文で、パス全体に一致させる
+    let out = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--regex", r".*/\w\.mp3", "--type", "f"])
+        .output()?;
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        ["tests/inputs/a/b/c/c.mp3", "tests/inputs/d/e/e.mp3"]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_regex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--regex", "("])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid --regex \"(\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn follow() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("dir/file", "")?;
+    fixture.symlink("to_dir", "dir")?;
+    fixture.symlink("broken", "missing")?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    // GNU findの-Lはパスより前に置くので、同じ意味の-followで比べる
+    for (args, find_args) in [
+        (&["-L"][..], &["-follow"][..]),
+        (&["-L", "--type", "l"], &["-follow", "-type", "l"]),
+        (&["-L", "--type", "d"], &["-follow", "-type", "d"]),
+        (&["-L", "--xtype", "l"], &["-follow", "-xtype", "l"]),
+    ] {
+        same_as_find(root, args, find_args)?;
+    }
+    // 壊れたリンクはリンクのまま列挙する
+    let out = Command::cargo_bin(PRG)?
+        .args(["-L", root, "--type", "l"])
+        .output()?;
+    assert_eq!(sorted_lines(&out.stdout), [fixture.path("broken")]);
+    let out = Command::cargo_bin(PRG)?
+        .args(["-L", "-P", root, "--type", "f"])
+        .output()?;
+    assert_eq!(sorted_lines(&out.stdout), [fixture.path("dir/file")]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn follow_loop() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("dir/file", "")?;
+    fixture.symlink("dir/loop", "..")?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    let out = Command::cargo_bin(PRG)?.args(["-L", root]).output()?;
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8(out.stderr)?.contains("loop"));
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        [
+            root.to_string(),
+            fixture.path("dir"),
+            fixture.path("dir/file")
+        ]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_delete_follow() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-L", "--delete", "--name", "x"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exclude_dir() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("src/main.rs", "")?;
+    fixture.file(".git/config", "")?;
+    fixture.file("node_modules/pkg/index.js", "")?;
+    fixture.file("sub/node_modules/x.js", "")?;
+    fixture.file("target", "")?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    same_as_find(
+        root,
+        &["--exclude-dir", ".git", "--exclude-dir", "node_*"],
+        &[
+            "(", "-name", ".git", "-o", "-name", "node_*", ")", "-type", "d", "-prune", "-o",
+            "-print",
+        ],
+    )?;
+    // ディレクトリだけを除外し、同じ名前のファイルは残す
+    let out = Command::cargo_bin(PRG)?
+        .args([root, "--prune", "target", "--type", "f"])
+        .output()?;
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        [
+            fixture.path(".git/config"),
+            fixture.path("node_modules/pkg/index.js"),
+            fixture.path("src/main.rs"),
+            fixture.path("sub/node_modules/x.js"),
+            fixture.path("target"),
+        ]
+    );
+    // 起点そのものは除外しない
+    let out = Command::cargo_bin(PRG)?
+        .args([&fixture.path("src"), "--exclude-dir", "src"])
+        .output()?;
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        [fixture.path("src"), fixture.path("src/main.rs")]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn long() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("dir/file", "x".repeat(5000))?;
+    let old = fixture.file("old", "")?;
+    fs::File::options()
+        .write(true)
+        .open(&old)?
+        .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_577_934_245))?;
+    fixture.symlink("link", "dir/file")?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    same_as_find(root, &["--ls"], &["-ls"])?;
+    same_as_find(root, &["--ls", "--type", "l"], &["-type", "l", "-ls"])?;
+    let out = Command::cargo_bin(PRG)?.args([&old, "--ls"]).output()?;
+    let line = String::from_utf8(out.stdout)?;
+    // 古いファイルは時刻の代わりに年を出す
+    assert!(line.contains(" -rw-"), "{}", line);
+    assert!(
+        line.trim_end().ends_with(&format!("  2020 {}", old)),
+        "{}",
+        line
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_long_print0() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--ls", "-0"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_exclude_dir() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("keep/a.tmp", "")?;
+    fixture.file("keep/sub/b.tmp", "")?;
+    fixture.file("other/c.tmp", "")?;
+    fixture.file("other/sub/d.tmp", "")?;
+    Command::cargo_bin(PRG)?
+        .args([
+            &fixture.path(""),
+            "--name",
+            "\\.tmp$",
+            "--exclude-dir",
+            "keep",
+            "--delete",
+        ])
+        .assert()
+        .success();
+    // 除外したディレクトリの中身は消さない
+    let out = Command::cargo_bin(PRG)?
+        .args([&fixture.path(""), "--type", "f"])
+        .output()?;
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        [fixture.path("keep/a.tmp"), fixture.path("keep/sub/b.tmp")]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ignore() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file(".gitignore", "*.log\nbuild/\n!keep.log\n")?;
+    fixture.file(".git/HEAD", "")?;
+    fixture.file("main.rs", "")?;
+    fixture.file("debug.log", "")?;
+    fixture.file("keep.log", "")?;
+    fixture.file("build/out.o", "")?;
+    fixture.file("src/build", "")?;
+    fixture.file("src/lib.rs", "")?;
+    fixture.file("src/.ignore", "secret.txt\n")?;
+    fixture.file("src/secret.txt", "")?;
+    fixture.file("src/inner/.gitignore", "!*.log\n")?;
+    fixture.file("src/inner/trace.log", "")?;
+    fixture.file("other/secret.txt", "")?;
+    let out = Command::cargo_bin(PRG)?
+        .args([&fixture.path(""), "--ignore", "--type", "f"])
+        .output()?;
+    assert!(out.status.success());
+    // build/はディレクトリだけに、.ignoreはその下だけに効き、内側の!で戻せる
+    assert_eq!(
+        sorted_lines(&out.stdout),
+        [
+            fixture.path(".gitignore"),
+            fixture.path("keep.log"),
+            fixture.path("main.rs"),
+            fixture.path("other/secret.txt"),
+            fixture.path("src/.ignore"),
+            fixture.path("src/build"),
+            fixture.path("src/inner/.gitignore"),
+            fixture.path("src/inner/trace.log"),
+            fixture.path("src/lib.rs"),
+        ]
+    );
+    // --ignoreがなければすべて出す
+    let out = Command::cargo_bin(PRG)?
+        .args([&fixture.path(""), "--type", "f"])
+        .output()?;
+    assert_eq!(sorted_lines(&out.stdout).len(), 13);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ignore_relative_path() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file(".gitignore", "/top.txt\n")?;
+    fixture.file("top.txt", "")?;
+    fixture.file("sub/top.txt", "")?;
+    let out = Command::cargo_bin(PRG)?
+        .current_dir(fixture.path(""))
+        .args([".", "--ignore", "--type", "f"])
+        .output()?;
+    // /で始まる規則は.gitignoreのあるディレクトリの直下だけに一致する
+    assert_eq!(sorted_lines(&out.stdout), ["./.gitignore", "./sub/top.txt"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn depth() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("a/b/c.txt", "")?;
+    fixture.file("a/d.txt", "")?;
+    fixture.file("skip/e.txt", "")?;
+    fixture.file("f.txt", "")?;
+    let root = fixture.path("");
+    let root = root.trim_end_matches('/');
+    same_as_find(root, &["--depth"], &["-depth"])?;
+    for args in [&["--depth"][..], &["-d", "--exclude-dir", "skip"]] {
+        let out = Command::cargo_bin(PRG)?.arg(root).args(args).output()?;
+        let lines = String::from_utf8(out.stdout)?;
+        let lines: Vec<_> = lines.lines().collect();
+        // どのディレクトリも、その中身をすべて出したあとに出る
+        for (i, line) in lines.iter().enumerate() {
+            let prefix = format!("{}/", line);
+            assert!(
+                lines[i..].iter().all(|later| !later.starts_with(&prefix)),
+                "{} is listed before its contents: {:?}",
+                line,
+                lines
+            );
+        }
+        assert_eq!(lines.last(), Some(&root));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_match() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--name", "no-such-name"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
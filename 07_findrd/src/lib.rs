@@ -1,17 +1,18 @@
-use std::error::Error;
 use std::ffi::OsStr;
+use std::io::{self, Write};
 
 use clap::builder::{EnumValueParser, TypedValueParser};
 use clap::error::{ContextKind, ContextValue, ErrorKind};
 use clap::ArgAction::{Append, Set};
-use clap::{Arg, Command, Parser, ValueEnum};
+use clap::{Arg, Command, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{CommandExt, ToolError};
 use regex::Regex;
+use thiserror::Error;
 use walkdir::{DirEntry, WalkDir};
 
 use crate::EntryType::*;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
 #[derive(Debug, Eq, PartialEq, Clone, ValueEnum)]
 enum EntryType {
     #[value(name = "d")]
@@ -70,7 +71,7 @@ pub struct Args {
     value_name = "NAMES",
     short = 'n',
     long = "name",
-    help = "Name",
+    help = tr_str("Name", "名前"),
     action = Append,
     num_args = 1..,
     value_parser = RegexValueParser
@@ -80,7 +81,7 @@ pub struct Args {
     value_name = "TYPE",
     short = 't',
     long = "type",
-    help = "Entry type [possible value: f, d, l]",
+    help = tr_str("Entry type [possible value: f, d, l]", "エントリの種類 [取りうる値: f, d, l]"),
     value_parser = EnumValueParser::<EntryType>::new(),
     action = Set,
     num_args = 1..
@@ -95,8 +96,17 @@ pub struct Config {
     entry_types: Vec<EntryType>,
 }
 
-pub fn get_args() -> MyResult<Config> {
-    let args = Args::parse();
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Args::command().get_tool_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
     Ok(Config {
         paths: args.paths,
@@ -105,7 +115,7 @@ pub fn get_args() -> MyResult<Config> {
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<(), Error> {
     let type_filter = |entry: &DirEntry| {
         config.entry_types.is_empty()
             || config
@@ -129,7 +139,7 @@ pub fn run(config: Config) -> MyResult<()> {
             .into_iter()
             .filter_map(|e| match e {
                 Err(e) => {
-                    eprintln!("{}", e);
+                    let _ = writeln!(err, "{}", e);
                     None
                 }
                 Ok(entry) => Some(entry),
@@ -139,7 +149,7 @@ pub fn run(config: Config) -> MyResult<()> {
             .map(|entry| entry.path().display().to_string())
             .collect::<Vec<_>>();
 
-        println!("{}", entries.join("\n"));
+        writeln!(out, "{}", entries.join("\n"))?;
     }
     Ok(())
 }
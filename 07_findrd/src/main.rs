@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = findrd::get_args().and_then(findrd::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = findrd::get_args()
+        .and_then(|args| findrd::run(args, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("findrd", &e));
     }
 }
@@ -1,19 +1,21 @@
-use std::error::Error;
-use std::fs::File;
 use std::io;
-use std::io::{BufRead, BufReader};
-use std::num::NonZeroUsize;
+use std::io::{BufRead, Write};
 use std::ops::Range;
 
-use clap::{Arg, Command};
+use clap::builder::EnumValueParser;
+use clap::ArgAction::SetTrue;
+use clap::{Arg, Command, ValueEnum};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{
+    parse_pos, warn_to, CommandExt, FileArg, ParseError, PositionList, ToolError, EXIT_FAILURE,
+    EXIT_USAGE,
+};
 use csv::{ReaderBuilder, StringRecord, WriterBuilder};
 use regex::Regex;
+use thiserror::Error;
 
 use crate::Extract::{Bytes, Chars, Fields};
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-type PositionList = Vec<Range<usize>>;
-
 #[derive(Debug)]
 pub enum Extract {
     Fields(PositionList),
@@ -21,21 +23,116 @@ pub enum Extract {
     Chars(PositionList),
 }
 
+/// How lines are split into fields.
+#[derive(Debug)]
+pub enum Delimiter {
+    /// A single byte, split as CSV so that quoted fields stay whole.
+    Byte(u8),
+    /// A longer string, split wherever it appears.
+    Text(String),
+    /// Runs of blanks, ignoring those at either end, like `awk`.
+    Whitespace,
+    /// Every match of a regex.
+    Regex(Regex),
+}
+
+/// When `--fields` output with a single-byte delimiter quotes fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QuoteStyle {
+    /// Never, even if the field contains the delimiter or a quote.
+    Never,
+    /// Only fields that contain the delimiter, a quote or a newline.
+    Necessary,
+    /// Every field.
+    Always,
+}
+
+impl From<QuoteStyle> for csv::QuoteStyle {
+    fn from(style: QuoteStyle) -> csv::QuoteStyle {
+        match style {
+            QuoteStyle::Never => csv::QuoteStyle::Never,
+            QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+            QuoteStyle::Always => csv::QuoteStyle::Always,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
-    files: Vec<String>,
-    delimiter: u8,
+    files: Vec<FileArg>,
+    delimiter: Delimiter,
+    output_delimiter: Option<String>,
+    quote_style: QuoteStyle,
+    raw: bool,
     extract: Extract,
 }
 
-pub fn get_args() -> MyResult<Config> {
+impl Config {
+    /// What the selected fields are joined with: `--output-delimiter`,
+    /// or else the delimiter itself where it is a fixed string.
+    fn output_delimiter(&self) -> &str {
+        match (&self.output_delimiter, &self.delimiter) {
+            (Some(output), _) => output,
+            (None, Delimiter::Byte(_)) => unreachable!("single-byte delimiters are written as CSV"),
+            (None, Delimiter::Text(text)) => text,
+            (None, Delimiter::Whitespace) => " ",
+            (None, Delimiter::Regex(_)) => "\t",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error("{}", tr_text(
+        "--delim \"{0}\" must not be empty",
+        "--delim \"{0}\" は空にできません",
+        &[.0],
+    ))]
+    BadDelimiter(String),
+    #[error("{}", tr_text(
+        "Invalid --delim-regex \"{0}\"",
+        "--delim-regex が不正です \"{0}\"",
+        &[.0],
+    ))]
+    BadDelimiterRegex(String),
+    #[error(transparent)]
+    BadPosition(#[from] ParseError),
+    #[error(
+        "{}",
+        tr_str(
+            "Must have --fields, --bytes, or --chars",
+            "--fields、--bytes、--chars のどれかが必要です",
+        )
+    )]
+    MissingExtract,
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::BadDelimiter(_)
+            | Error::BadDelimiterRegex(_)
+            | Error::BadPosition(_)
+            | Error::MissingExtract => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
     let matches = Command::new("cutr")
         .version("0.1.0")
         .about("Rust cut")
         .arg(
             Arg::new("files")
                 .value_name("FILES")
-                .help("file(s)")
+                .help(tr_str("file(s)", "ファイル"))
                 .num_args(0..)
                 .default_value("-"),
         )
@@ -44,15 +141,61 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("DELIMITER")
                 .short('d')
                 .long("delim")
-                .help("Field delimiter")
+                .help(tr_str("Field delimiter, one or more characters; \\t is a tab", "フィールドの区切り(1文字以上、\\t はタブ)"))
                 .default_value("\t"),
         )
+        .arg(
+            Arg::new("whitespace")
+                .short('w')
+                .long("whitespace")
+                .help(tr_str("Split fields on runs of spaces and tabs, and join them with a space", "連続する空白やタブでフィールドを分け、空白1つでつなぐ"))
+                .action(SetTrue)
+                .conflicts_with_all(["delimiter", "bytes", "chars"]),
+        )
+        .arg(
+            Arg::new("delimiter_regex")
+                .value_name("PATTERN")
+                .long("delim-regex")
+                .help(tr_str("Split fields on each match of PATTERN", "PATTERNに一致するたびにフィールドを分ける"))
+                .conflicts_with_all(["delimiter", "whitespace", "bytes", "chars"]),
+        )
+        .arg(
+            Arg::new("output_delimiter")
+                .value_name("STRING")
+                .long("output-delimiter")
+                .help(tr_str("Join the selected fields with STRING [default: the delimiter, or a tab for --delim-regex]", "選んだフィールドをSTRINGでつなぐ [既定: 区切り文字、--delim-regex のときはタブ]"))
+                .conflicts_with_all(["bytes", "chars"]),
+        )
+        .arg(
+            Arg::new("quote_style")
+                .value_name("STYLE")
+                .long("quote-style")
+                .help(tr_str("When to quote output fields split as CSV", "CSVとして分けた出力フィールドを引用符で囲む条件"))
+                .value_parser(EnumValueParser::<QuoteStyle>::new())
+                .default_value("necessary")
+                .conflicts_with_all(["whitespace", "delimiter_regex", "bytes", "chars"]),
+        )
+        .arg(
+            Arg::new("no_quote")
+                .long("no-quote")
+                .help(tr_str("Never quote output fields, the same as --quote-style never", "出力フィールドを引用符で囲まない(--quote-style never と同じ)"))
+                .action(SetTrue)
+                .conflicts_with("quote_style"),
+        )
+        .arg(
+            Arg::new("raw")
+                .long("raw")
+                .help(tr_str("Split on every delimiter without reading quotes, and print lines without one as they are, like GNU cut", "GNU cut のように、引用符を解釈せずすべての区切りで分け、区切りのない行はそのまま表示する"))
+                .action(SetTrue)
+                .conflicts_with_all(["whitespace", "delimiter_regex", "quote_style", "no_quote"]),
+        )
         .arg(
             Arg::new("fields")
                 .value_name("FIELDS")
                 .short('f')
                 .long("fields")
-                .help("Selected fields")
+                .help(tr_str("Selected fields", "選ぶフィールド"))
+                .allow_hyphen_values(true)
                 .conflicts_with_all(["chars", "bytes"]),
         )
         .arg(
@@ -60,7 +203,8 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("BYTES")
                 .short('b')
                 .long("bytes")
-                .help("Selected bytes")
+                .help(tr_str("Selected bytes", "選ぶバイト"))
+                .allow_hyphen_values(true)
                 .conflicts_with_all(["fields", "chars"]),
         )
         .arg(
@@ -68,19 +212,30 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("CHARS")
                 .short('c')
                 .long("chars")
-                .help("Selected characters")
+                .help(tr_str("Selected characters", "選ぶ文字"))
+                .allow_hyphen_values(true)
                 .conflicts_with_all(["fields", "bytes"]),
         )
-        .get_matches();
-
-    let delimiter = matches.get_one::<String>("delimiter").unwrap().to_string();
-    let delim_bytes = delimiter.as_bytes();
-    if delim_bytes.len() != 1 {
-        return Err(From::from(format!(
-            "--delim \"{}\" must be a single byte",
-            delimiter
-        )));
-    }
+        .get_tool_matches();
+
+    let delimiter = unescape(matches.get_one::<String>("delimiter").unwrap());
+    let delimiter_regex = matches
+        .get_one::<String>("delimiter_regex")
+        .map(|pattern| match Regex::new(pattern) {
+            // 空文字に一致する正規表現では、どこでも区切れてしまう
+            Ok(re) if !re.is_match("") => Ok(re),
+            _ => Err(Error::BadDelimiterRegex(pattern.to_string())),
+        })
+        .transpose()?;
+    let raw = matches.get_flag("raw");
+    let delimiter = match (delimiter_regex, delimiter.as_bytes()) {
+        _ if matches.get_flag("whitespace") => Delimiter::Whitespace,
+        (Some(re), _) => Delimiter::Regex(re),
+        (None, []) => return Err(Error::BadDelimiter(delimiter)),
+        // --rawでは引用符を解釈しないので、CSVとしては読まない
+        (None, &[byte]) if !raw => Delimiter::Byte(byte),
+        (None, _) => Delimiter::Text(delimiter),
+    };
 
     let fields = matches
         .get_one::<String>("fields")
@@ -102,245 +257,186 @@ pub fn get_args() -> MyResult<Config> {
     } else if let Some(char_pos) = chars {
         Chars(char_pos)
     } else {
-        return Err(From::from("Must have --fields, --bytes, or --chars"));
+        return Err(Error::MissingExtract);
     };
     let files = matches
         .get_many::<String>("files")
         .expect("files required")
-        .map(|v| v.to_string())
+        .map(FileArg::from)
         .collect::<Vec<_>>();
 
     Ok(Config {
         files,
-        delimiter: *delim_bytes.first().unwrap(),
+        delimiter,
+        output_delimiter: matches
+            .get_one::<String>("output_delimiter")
+            .map(|output| unescape(output)),
+        quote_style: if matches.get_flag("no_quote") {
+            QuoteStyle::Never
+        } else {
+            *matches.get_one("quote_style").expect("has a default")
+        },
+        raw,
         extract,
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
     for filename in &config.files {
-        match open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
-            Ok(file) => match &config.extract {
-                Fields(file_pos) => {
+        match filename.open() {
+            Err(source) => {
+                warn_to(
+                    err,
+                    "cutr",
+                    &Error::Open {
+                        path: filename.to_string(),
+                        source,
+                    },
+                );
+                status = EXIT_FAILURE;
+            }
+            Ok(file) => match (&config.extract, &config.delimiter) {
+                (
+                    Fields(field_pos),
+                    Delimiter::Text(_) | Delimiter::Whitespace | Delimiter::Regex(_),
+                ) => {
+                    for line in file.lines() {
+                        let line = line?;
+                        let fields = split_fields(&line, &config.delimiter);
+                        // GNU cutと同じく、区切りのない行はそのまま出す
+                        if config.raw && fields.len() == 1 {
+                            writeln!(out, "{}", line)?;
+                            continue;
+                        }
+                        let selected = field_pos
+                            .iter()
+                            .flat_map(|range| {
+                                fields.get(clamp(range, fields.len())).unwrap_or_default()
+                            })
+                            .copied()
+                            .collect::<Vec<_>>();
+                        writeln!(out, "{}", selected.join(config.output_delimiter()))?;
+                    }
+                }
+                (Fields(file_pos), &Delimiter::Byte(delimiter)) => {
+                    // 行ごとにフィールドの数が違ってもよい
                     let mut reader = ReaderBuilder::new()
-                        .delimiter(config.delimiter)
+                        .delimiter(delimiter)
                         .has_headers(false)
+                        .flexible(true)
                         .from_reader(file);
 
+                    // 出力の区切りを変えるとき、または引用符を付けないときは、
+                    // CSVの書き手を通さずにつなぐ(空の選択で `""` を出さないため)
+                    if config.output_delimiter.is_some() || config.quote_style == QuoteStyle::Never
+                    {
+                        let output_delimiter = match &config.output_delimiter {
+                            Some(output) => output.clone(),
+                            None => char::from(delimiter).to_string(),
+                        };
+                        for record in reader.records() {
+                            let record = record?;
+                            let selected = extract_fields(&record, file_pos);
+                            writeln!(out, "{}", selected.join(&output_delimiter))?;
+                        }
+                        continue;
+                    }
                     let mut wtr = WriterBuilder::new()
-                        .delimiter(config.delimiter)
-                        .from_writer(io::stdout());
+                        .delimiter(delimiter)
+                        .flexible(true)
+                        .quote_style(config.quote_style.into())
+                        .from_writer(&mut *out);
 
                     for record in reader.records() {
                         let record = record?;
                         wtr.write_record(extract_fields(&record, file_pos))?;
                     }
+                    wtr.flush()?;
                 }
-                Bytes(byte_pos) => {
+                (Bytes(byte_pos), _) => {
                     for line in file.lines() {
-                        println!("{}", extract_bytes(&line?, byte_pos));
+                        writeln!(out, "{}", extract_bytes(&line?, byte_pos))?;
                     }
                 }
-                Chars(char_pos) => {
+                (Chars(char_pos), _) => {
                     for line in file.lines() {
-                        println!("{}", extract_chars(&line?, char_pos));
+                        writeln!(out, "{}", extract_chars(&line?, char_pos))?;
                     }
                 }
             },
         }
     }
-    Ok(())
-}
-
-fn parse_pos(range: &str) -> MyResult<PositionList> {
-    let range_re = Regex::new(r"^(\d+)-(\d+)$").unwrap();
-    range
-        .split(',')
-        .map(|val| {
-            parse_index(val).map(|n| n..n + 1).or_else(|e| {
-                range_re.captures(val).ok_or(e).and_then(|captures| {
-                    let n1 = parse_index(&captures[1])?;
-                    let n2 = parse_index(&captures[2])?;
-                    if n1 >= n2 {
-                        return Err(format!(
-                            "First number in range ({}) \
-                        must be lower than second number ({})",
-                            n1 + 1,
-                            n2 + 1
-                        ));
-                    }
-                    Ok(n1..n2 + 1)
-                })
-            })
-        })
-        .collect::<Result<_, _>>()
-        .map_err(From::from)
-}
-
-fn parse_index(input: &str) -> Result<usize, String> {
-    let value_error = || format!("illegal list value: \"{}\"", input);
-    input
-        .starts_with('+')
-        .then(|| Err(value_error()))
-        .unwrap_or_else(|| {
-            input
-                .parse::<NonZeroUsize>()
-                .map(|n| usize::from(n) - 1)
-                .map_err(|_| value_error())
-        })
+    Ok(status)
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
+/// Clamps `range` to a record of `len` items. Open-ended ranges such as
+/// `3-` end at `usize::MAX` until they meet a record.
+fn clamp(range: &Range<usize>, len: usize) -> Range<usize> {
+    range.start.min(len)..range.end.min(len)
 }
 
-fn extract_chars(line: &str, char_pos: &[Range<usize>]) -> String {
+pub fn extract_chars(line: &str, char_pos: &[Range<usize>]) -> String {
     let chars: Vec<_> = line.chars().collect();
     char_pos
         .iter()
-        .cloned()
-        .flat_map(|range| range.filter_map(|i| chars.get(i)))
+        .flat_map(|range| chars.get(clamp(range, chars.len())).unwrap_or_default())
         .collect()
 }
 
-fn extract_bytes(line: &str, byte_pos: &[Range<usize>]) -> String {
+pub fn extract_bytes(line: &str, byte_pos: &[Range<usize>]) -> String {
     let bytes = line.as_bytes();
     let selected: Vec<_> = byte_pos
         .iter()
-        .cloned()
-        .flat_map(|range| range.filter_map(|i| bytes.get(i)).copied())
+        .flat_map(|range| bytes.get(clamp(range, bytes.len())).unwrap_or_default())
+        .copied()
         .collect();
     String::from_utf8_lossy(&selected).into_owned()
 }
 
-fn extract_fields<'a>(record: &'a StringRecord, field_pos: &[Range<usize>]) -> Vec<&'a str> {
+/// Splits `line` on a delimiter other than a single byte.
+pub fn split_fields<'a>(line: &'a str, delimiter: &Delimiter) -> Vec<&'a str> {
+    match delimiter {
+        Delimiter::Byte(_) => unreachable!("single-byte delimiters are split as CSV"),
+        Delimiter::Text(text) => line.split(text.as_str()).collect(),
+        Delimiter::Whitespace => line.split_ascii_whitespace().collect(),
+        Delimiter::Regex(re) => re.split(line).collect(),
+    }
+}
+
+pub fn extract_fields<'a>(record: &'a StringRecord, field_pos: &[Range<usize>]) -> Vec<&'a str> {
     field_pos
         .iter()
-        .cloned()
-        .flat_map(|range| range.filter_map(|i| record.get(i)))
+        .flat_map(|range| clamp(range, record.len()).filter_map(|i| record.get(i)))
         .collect()
 }
+/// Turns `\t` and `\\` in a `--delim` value into a tab and a backslash.
+fn unescape(delimiter: &str) -> String {
+    let mut out = String::new();
+    let mut chars = delimiter.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('t')) => {
+                out.push('\t');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 #[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
 mod unit_tests {
     use csv::StringRecord;
+    use regex::Regex;
 
-    use super::{extract_bytes, extract_chars, extract_fields, parse_pos};
-
-    #[test]
-    fn test_parse_pos() {
-        // 空文字はエラー
-        assert!(parse_pos("").is_err());
-
-        // ゼロはエラー
-        let res = parse_pos("0");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"0\"",);
-
-        let res = parse_pos("0-1");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"0\"",);
-
-        // 数字の前に「+」が付く場合はエラー
-        let res = parse_pos("+1");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"+1\"",);
-
-        let res = parse_pos("+1-2");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"+1-2\"",);
-
-        let res = parse_pos("1-+2");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"1-+2\"",);
-
-        // 数字はエラー
-        let res = parse_pos("a");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a\"",);
-
-        let res = parse_pos("1,a");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a\"",);
-
-        let res = parse_pos("1-a");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"1-a\"",);
-
-        let res = parse_pos("a-1");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a-1\"",);
-
-        // エラーになる範囲
-        let res = parse_pos("-");
-        assert!(res.is_err());
-
-        let res = parse_pos(",");
-        assert!(res.is_err());
-
-        let res = parse_pos("1,");
-        assert!(res.is_err());
-
-        let res = parse_pos("1-");
-        assert!(res.is_err());
-
-        let res = parse_pos("1-1-1");
-        assert!(res.is_err());
-
-        let res = parse_pos("1-1-a");
-        assert!(res.is_err());
-
-        // 最初の数字は2番目より小さい必要がある
-        let res = parse_pos("1-1");
-        assert!(res.is_err());
-        assert_eq!(
-            res.unwrap_err().to_string(),
-            "First number in range (1) must be lower than second number (1)"
-        );
-
-        let res = parse_pos("2-1");
-        assert!(res.is_err());
-        assert_eq!(
-            res.unwrap_err().to_string(),
-            "First number in range (2) must be lower than second number (1)"
-        );
-
-        // 以下のケースは受け入れられる
-        let res = parse_pos("1");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![0..1]);
-
-        let res = parse_pos("01");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![0..1]);
-
-        let res = parse_pos("1,3");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![0..1, 2..3]);
-
-        let res = parse_pos("001,0003");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![0..1, 2..3]);
-
-        let res = parse_pos("1-3");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![0..3]);
-
-        let res = parse_pos("0001-03");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![0..3]);
-
-        let res = parse_pos("1,7,3-5");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![0..1, 6..7, 2..5]);
-
-        let res = parse_pos("15,19-20");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), vec![14..15, 18..20]);
-    }
+    use super::{extract_bytes, extract_chars, extract_fields, split_fields, unescape, Delimiter};
 
     #[test]
     fn text_extract_chars() {
@@ -350,6 +446,8 @@ mod unit_tests {
         assert_eq!(extract_chars("ábc", &[0..3]), "ábc".to_string());
         assert_eq!(extract_chars("ábc", &[2..3, 1..2]), "cb".to_string());
         assert_eq!(extract_chars("ábc", &[0..1, 1..2, 4..5]), "áb".to_string());
+        assert_eq!(extract_chars("ábc", &[1..usize::MAX]), "bc".to_string());
+        assert_eq!(extract_chars("ábc", &[5..usize::MAX]), "".to_string());
     }
 
     #[test]
@@ -360,6 +458,7 @@ mod unit_tests {
         assert_eq!(extract_bytes("ábc", &[0..4]), "ábc".to_string());
         assert_eq!(extract_bytes("ábc", &[3..4, 2..3]), "cb".to_string());
         assert_eq!(extract_bytes("ábc", &[0..2, 5..6]), "á".to_string());
+        assert_eq!(extract_bytes("ábc", &[2..usize::MAX]), "bc".to_string());
     }
 
     #[test]
@@ -370,5 +469,31 @@ mod unit_tests {
         assert_eq!(extract_fields(&rec, &[0..1, 2..3]), &["Captain", "12345"]);
         assert_eq!(extract_fields(&rec, &[0..1, 3..4]), &["Captain"]);
         assert_eq!(extract_fields(&rec, &[1..2, 0..1]), &["Sham", "Captain"]);
+        assert_eq!(extract_fields(&rec, &[1..usize::MAX]), &["Sham", "12345"]);
+        assert_eq!(extract_fields(&rec, &[0..2]), &["Captain", "Sham"]);
+    }
+
+    #[test]
+    fn test_split_fields() {
+        let text = Delimiter::Text("::".to_string());
+        assert_eq!(split_fields("a::b::::c", &text), &["a", "b", "", "c"]);
+        assert_eq!(split_fields("abc", &text), &["abc"]);
+        assert_eq!(
+            split_fields("  a \t b  c ", &Delimiter::Whitespace),
+            &["a", "b", "c"]
+        );
+        assert!(split_fields("   ", &Delimiter::Whitespace).is_empty());
+
+        let re = Delimiter::Regex(Regex::new(r"\s*[,;]\s*").unwrap());
+        assert_eq!(split_fields("a , b;c", &re), &["a", "b", "c"]);
+        assert_eq!(split_fields(",a", &re), &["", "a"]);
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("\\t"), "\t");
+        assert_eq!(unescape("a\\\\t"), "a\\t");
+        assert_eq!(unescape("\\n"), "\\n");
+        assert_eq!(unescape(","), ",");
     }
 }
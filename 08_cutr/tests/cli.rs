@@ -1,44 +1,23 @@
 use std::fs;
 
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, random_string, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-
-type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 const PRG: &str = "cutr";
 const CSV: &str = "tests/inputs/movies1.csv";
 const TSV: &str = "tests/inputs/movies1.tsv";
 const BOOKS: &str = "tests/inputs/books.tsv";
 
-// --------------------------------------------------
-fn random_string() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(7)
-        .map(char::from)
-        .collect()
-}
-
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename = random_string();
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&["-f", "1", CSV, &bad, TSV])
+        .args(["-f", "1", CSV, &bad, TSV])
         .assert()
-        .success()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -94,24 +73,27 @@ fn dies_bad_digit_chars() -> TestResult {
 fn dies_empty_delimiter() -> TestResult {
     dies(
         &[CSV, "-f", "1", "-d", ""],
-        "--delim \"\" must be a single byte",
+        "--delim \"\" must not be empty",
     )
 }
 
 // --------------------------------------------------
 #[test]
-fn dies_bad_delimiter() -> TestResult {
-    dies(
-        &[CSV, "-f", "1", "-d", ",,"],
-        "--delim \",,\" must be a single byte",
-    )
+fn dies_whitespace_with_delimiter() -> TestResult {
+    dies(&[CSV, "-f", "1", "-w", "-d", ","], "cannot be used with")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_whitespace_with_chars() -> TestResult {
+    dies(&[CSV, "-c", "1", "-w"], "cannot be used with")
 }
 
 // --------------------------------------------------
 #[test]
 fn dies_chars_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -121,7 +103,7 @@ fn dies_chars_bytes_fields() -> TestResult {
 #[test]
 fn dies_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-f", "1", "-b", "1"])
+        .args([CSV, "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -131,7 +113,7 @@ fn dies_bytes_fields() -> TestResult {
 #[test]
 fn dies_chars_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1"])
+        .args([CSV, "-c", "1", "-f", "1"])
         .assert()
         .failure();
     Ok(())
@@ -141,7 +123,7 @@ fn dies_chars_fields() -> TestResult {
 #[test]
 fn dies_chars_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -338,3 +320,264 @@ fn tsv_c1_8() -> TestResult {
 fn repeated_value() -> TestResult {
     run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1,1.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_open() -> TestResult {
+    run(&[TSV, "-f", "2-"], "tests/expected/movies1.tsv.f2-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f_open_2() -> TestResult {
+    run(&[TSV, "-f", "-2"], "tests/expected/movies1.tsv.f-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_f2_open() -> TestResult {
+    run(
+        &[CSV, "-f", "2-", "-d", ","],
+        "tests/expected/movies1.csv.f2-.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c5_open() -> TestResult {
+    run(&[TSV, "-c", "5-"], "tests/expected/movies1.tsv.c5-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c_open_3() -> TestResult {
+    run(&[TSV, "-c", "-3"], "tests/expected/movies1.tsv.c-3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b5_open() -> TestResult {
+    run(&[TSV, "-b", "5-"], "tests/expected/movies1.tsv.b5-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn open_range_uneven_records() -> TestResult {
+    // 行ごとにフィールドの数が違っても、それぞれの末尾まで取り出す
+    Command::cargo_bin(PRG)?
+        .args(["-f", "2-"])
+        .write_stdin("a\tb\tc\td\n1\t2\n")
+        .assert()
+        .success()
+        .stdout("b\tc\td\n2\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_range_without_endpoints() -> TestResult {
+    dies(&[CSV, "-f", "-"], "illegal list value: \"-\"")
+}
+
+// --------------------------------------------------
+#[test]
+fn escaped_tab_delimiter() -> TestResult {
+    run(
+        &[TSV, "-f", "2", "-d", "\\t"],
+        "tests/expected/movies1.tsv.f2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multi_char_delimiter() -> TestResult {
+    // 複数文字の区切りは引用符を解釈せず、そのまま区切る
+    Command::cargo_bin(PRG)?
+        .args(["-d", "::", "-f", "3,1"])
+        .write_stdin("a::b::c\n\"x::y\"::z\nnone\n")
+        .assert()
+        .success()
+        .stdout("c::a\nz::\"x\nnone\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn non_ascii_delimiter() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", "・", "-f", "2-"])
+        .write_stdin("東京・大阪・名古屋\n")
+        .assert()
+        .success()
+        .stdout("大阪・名古屋\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn whitespace() -> TestResult {
+    // awkと同じく、行頭と行末の空白は無視し、連続した空白を1つの区切りとみなす
+    Command::cargo_bin(PRG)?
+        .args(["-w", "-f", "2-3"])
+        .write_stdin("  root   1  0.0 init\nuser\t42\t\t1.5 sh\n\n")
+        .assert()
+        .success()
+        .stdout("1 0.0\n42 1.5\n\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delim_regex() -> TestResult {
+    // 既定ではタブでつなぐ
+    Command::cargo_bin(PRG)?
+        .args(["--delim-regex", r"\s*,\s*", "-f", "2-"])
+        .write_stdin("a , b,c ,  d\nnone\n")
+        .assert()
+        .success()
+        .stdout("b\tc\td\n\n");
+    Command::cargo_bin(PRG)?
+        .args([
+            "--delim-regex",
+            "::",
+            "-f",
+            "3,1",
+            "--output-delimiter",
+            " | ",
+        ])
+        .write_stdin("a::b::c\n")
+        .assert()
+        .success()
+        .stdout("c | a\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_delimiter() -> TestResult {
+    // 1バイトの区切りでもCSVとして引用符を解釈し、指定した区切りでつなぐ
+    Command::cargo_bin(PRG)?
+        .args([CSV, "-d", ",", "-f", "1,2", "--output-delimiter", "\\t"])
+        .assert()
+        .success()
+        .stdout("title\tyear\nThe Blues Brothers\t1980\nLes Misérables\t2012\n");
+    Command::cargo_bin(PRG)?
+        .args(["-w", "-f", "1,2", "--output-delimiter", ","])
+        .write_stdin(" a  b c\n")
+        .assert()
+        .success()
+        .stdout("a,b\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_delim_regex() -> TestResult {
+    dies(
+        &[CSV, "-f", "1", "--delim-regex", "("],
+        "Invalid --delim-regex \"(\"",
+    )?;
+    dies(
+        &[CSV, "-f", "1", "--delim-regex", "x*"],
+        "Invalid --delim-regex \"x*\"",
+    )?;
+    dies(
+        &[CSV, "-f", "1", "--delim-regex", ",", "-d", ","],
+        "cannot be used with",
+    )?;
+    dies(
+        &[CSV, "-c", "1", "--output-delimiter", ","],
+        "cannot be used with",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_movies2() -> TestResult {
+    run(
+        &["tests/inputs/movies2.csv", "-d", ",", "-f", "1,3", "--raw"],
+        "tests/expected/movies2.csv.f1,3.dcomma.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_books() -> TestResult {
+    run(
+        &["tests/inputs/books.csv", "-d", ",", "-f", "2-", "--raw"],
+        "tests/expected/books.csv.f2-.dcomma.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_line_without_delimiter() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", ",", "-f", "2", "--raw"])
+        .write_stdin("a,b\nno delimiter\n\"x,y\"\n")
+        .assert()
+        .success()
+        .stdout("b\nno delimiter\ny\"\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn quote_style() -> TestResult {
+    let movies2 = "tests/inputs/movies2.csv";
+    for (args, expected) in [
+        (
+            &["--quote-style", "always"][..],
+            "\"title\"\n\"To Sir, with Love\"\n",
+        ),
+        (
+            &["--quote-style", "necessary"],
+            "title\n\"To Sir, with Love\"\n",
+        ),
+        (&["--quote-style", "never"], "title\nTo Sir, with Love\n"),
+        (&["--no-quote"], "title\nTo Sir, with Love\n"),
+    ] {
+        let out = Command::cargo_bin(PRG)?
+            .args([movies2, "-d", ",", "-f", "1"])
+            .args(args)
+            .output()?;
+        let out = String::from_utf8(out.stdout)?;
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(
+            format!("{}\n{}\n", lines[0], lines[lines.len() - 1]),
+            expected,
+            "{:?}",
+            args
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_quote_empty_selection() -> TestResult {
+    for flag in [&["--no-quote"][..], &["--quote-style", "never"]] {
+        Command::cargo_bin(PRG)?
+            .args(["-d", ",", "-f", "5"])
+            .args(flag)
+            .write_stdin("a,b\n")
+            .assert()
+            .success()
+            .stdout("\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_quote_style() -> TestResult {
+    dies(
+        &[CSV, "-f", "1", "--quote-style", "sometimes"],
+        "invalid value",
+    )?;
+    dies(
+        &[CSV, "-f", "1", "--no-quote", "--quote-style", "always"],
+        "cannot be used with",
+    )?;
+    dies(&[CSV, "-f", "1", "--raw", "-w"], "cannot be used with")
+}
@@ -1,38 +1,64 @@
-use std::error::Error;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::{fs, io, mem};
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
 
 use clap::ArgAction::SetTrue;
 use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{
+    warn_to, CommandExt, FileArg, RecordReader, Terminator, ToolError, EXIT_FAILURE, EXIT_USAGE,
+};
 use regex::{Regex, RegexBuilder};
+use thiserror::Error;
 use walkdir::WalkDir;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
 #[derive(Debug)]
 pub struct Config {
     pattern: Regex,
-    files: Vec<String>,
+    files: Vec<FileArg>,
     recursive: bool,
     count: bool,
     invert_match: bool,
+    terminator: Terminator,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+    #[error("{path}: {}", tr_io(.source))]
+    Metadata { path: String, source: io::Error },
+    #[error("{}", tr_text("{0} is a directory", "{0} はディレクトリです", &[.0]))]
+    IsDirectory(String),
+    #[error("{}", tr_text("Invalid pattern \"{0}\"", "パターンが不正です \"{0}\"", &[.0]))]
+    InvalidPattern(String),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidPattern(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
 }
 
-pub fn get_args() -> MyResult<Config> {
-    let matches = Command::new("findr")
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("grepr")
         .version("0.1.0")
         .about("Rust grep")
         .arg(
             Arg::new("pattern")
                 .value_name("PATTERN")
-                .help("Search pattern")
+                .help(tr_str("Search pattern", "検索パターン"))
                 .required(true),
         )
         .arg(
             Arg::new("files")
                 .value_name("FILE")
-                .help("Input file(s)")
+                .help(tr_str("Input file(s)", "入力ファイル"))
                 .num_args(1..)
                 .default_value("-"),
         )
@@ -40,7 +66,7 @@ pub fn get_args() -> MyResult<Config> {
             Arg::new("insensitive")
                 .short('i')
                 .long("insensitive")
-                .help("Case-insensitive")
+                .help(tr_str("Case-insensitive", "大文字と小文字を区別しない"))
                 .num_args(0)
                 .action(SetTrue),
         )
@@ -48,7 +74,7 @@ pub fn get_args() -> MyResult<Config> {
             Arg::new("recursive")
                 .short('r')
                 .long("recursive")
-                .help("Recursive search")
+                .help(tr_str("Recursive search", "再帰的に検索する"))
                 .num_args(0)
                 .action(SetTrue),
         )
@@ -56,7 +82,7 @@ pub fn get_args() -> MyResult<Config> {
             Arg::new("count")
                 .short('c')
                 .long("count")
-                .help("Count occurrences")
+                .help(tr_str("Count occurrences", "出現回数を数える"))
                 .num_args(0)
                 .action(SetTrue),
         )
@@ -64,21 +90,31 @@ pub fn get_args() -> MyResult<Config> {
             Arg::new("invert")
                 .short('v')
                 .long("invert-match")
-                .help("Invert match")
+                .help(tr_str("Invert match", "一致しないものを選ぶ"))
                 .num_args(0)
                 .action(SetTrue),
         )
-        .get_matches();
+        .arg(
+            Arg::new("null_data")
+                .short('z')
+                .long("null-data")
+                .help(tr_str(
+                    "Lines are terminated by NUL, not newline",
+                    "行が改行ではなくNULで終わる",
+                ))
+                .num_args(0)
+                .action(SetTrue),
+        )
+        .get_tool_matches();
 
-    let pattern = matches.get_one::<String>("pattern").unwrap();
-    let pattern = RegexBuilder::new(pattern)
-        .case_insensitive(matches.get_flag("insensitive"))
-        .build()
-        .map_err(|_| format!("Invalid pattern \"{}\"", pattern))?;
+    let pattern = build_pattern(
+        matches.get_one::<String>("pattern").unwrap(),
+        matches.get_flag("insensitive"),
+    )?;
     let files = matches
         .get_many::<String>("files")
         .unwrap()
-        .map(|f| f.to_string())
+        .map(FileArg::from)
         .collect();
 
     Ok(Config {
@@ -87,32 +123,61 @@ pub fn get_args() -> MyResult<Config> {
         recursive: matches.get_flag("recursive"),
         count: matches.get_flag("count"),
         invert_match: matches.get_flag("invert"),
+        terminator: if matches.get_flag("null_data") {
+            Terminator::Nul
+        } else {
+            Terminator::Newline
+        },
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut errors = false;
+    let mut selected = false;
     let entries = find_files(&config.files, config.recursive);
     let num_files = entries.len();
-    let print = |fname: &str, val: &str| {
+    // 照合は文字列で行うが、出力は読んだバイト列をそのまま書く
+    let mut print = |fname: &str, val: &[u8]| -> io::Result<()> {
         if num_files > 1 {
-            print!("{}:{}", fname, val);
-        } else {
-            print!("{}", val);
+            write!(out, "{}:", fname)?;
         }
+        out.write_all(val)
     };
     for entry in entries {
         match entry {
-            Err(e) => eprintln!("{}", e),
-            Ok(filename) => match open(&filename) {
-                Err(e) => eprintln!("{}: {}", filename, e),
-                Ok(file) => match find_lines(file, &config.pattern, config.invert_match) {
-                    Err(e) => eprintln!("{}", e),
+            Err(e) => {
+                warn_to(err, "grepr", &e);
+                errors = true;
+            }
+            Ok(filename) => match filename.open() {
+                Err(source) => {
+                    warn_to(
+                        err,
+                        "grepr",
+                        &Error::Open {
+                            path: filename.to_string(),
+                            source,
+                        },
+                    );
+                    errors = true;
+                }
+                Ok(file) => match find_lines(
+                    file,
+                    &config.pattern,
+                    config.invert_match,
+                    config.terminator,
+                ) {
+                    Err(e) => {
+                        warn_to(err, "grepr", &e);
+                        errors = true;
+                    }
                     Ok(matches) => {
+                        selected |= !matches.is_empty();
                         if config.count {
-                            print(&filename, &format!("{}\n", matches.len()));
+                            print(filename.name(), format!("{}\n", matches.len()).as_bytes())?;
                         } else {
                             for line in &matches {
-                                print(&filename, line);
+                                print(filename.name(), line)?;
                             }
                         }
                     }
@@ -120,71 +185,109 @@ pub fn run(config: Config) -> MyResult<()> {
             },
         }
     }
-    Ok(())
+    // grepと同じく、エラーがあれば2、選ばれた行がなければ1を返す
+    Ok(if errors {
+        2
+    } else if selected {
+        0
+    } else {
+        1
+    })
 }
 
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
+pub fn build_pattern(pattern: &str, insensitive: bool) -> Result<Regex, Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(insensitive)
+        .build()
+        .map_err(|_| Error::InvalidPattern(pattern.to_string()))
 }
 
-fn find_files(paths: &[String], recursive: bool) -> Vec<MyResult<String>> {
+pub fn is_match(line: &str, pattern: &Regex, invert_match: bool) -> bool {
+    pattern.is_match(line) ^ invert_match
+}
+
+fn find_files(files: &[FileArg], recursive: bool) -> Vec<Result<FileArg, Error>> {
     let mut results = vec![];
 
-    for path in paths {
-        match path.as_str() {
-            "-" => results.push(Ok(path.to_string())),
-            _ => match fs::metadata(path) {
-                Ok(metadata) => {
-                    if metadata.is_dir() {
-                        if recursive {
-                            for entry in WalkDir::new(path)
-                                .into_iter()
-                                .flatten()
-                                .filter(|e| e.file_type().is_file())
-                            {
-                                results.push(Ok(entry.path().display().to_string()));
-                            }
-                        } else {
-                            results.push(Err(From::from(format!("{} is a directory", path))));
-                        }
-                    } else if metadata.is_file() {
-                        results.push(Ok(path.to_string()));
+    for file in files {
+        let Some(path) = file.path() else {
+            results.push(Ok(file.clone()));
+            continue;
+        };
+        match fs::metadata(path) {
+            Ok(metadata) => {
+                if !metadata.is_dir() {
+                    results.push(Ok(file.clone()));
+                } else if recursive {
+                    for entry in WalkDir::new(path)
+                        .into_iter()
+                        .flatten()
+                        .filter(|e| e.file_type().is_file())
+                    {
+                        results.push(Ok(FileArg::new(entry.path().display().to_string())));
                     }
+                } else {
+                    results.push(Err(Error::IsDirectory(file.to_string())));
                 }
-                Err(e) => results.push(Err(From::from(format!("{}: {}", path, e)))),
-            },
+            }
+            Err(source) => results.push(Err(Error::Metadata {
+                path: file.to_string(),
+                source,
+            })),
         }
     }
     results
 }
 
-fn find_lines<T: BufRead>(
-    mut file: T,
+/// Returns the records of `file` that match `pattern` (or don't, with
+/// `invert_match`), terminators included.
+pub fn find_lines<T: BufRead>(
+    file: T,
     pattern: &Regex,
     invert_match: bool,
-) -> MyResult<Vec<String>> {
+    terminator: Terminator,
+) -> Result<Vec<Vec<u8>>, Error> {
     let mut matches = vec![];
-    let mut line = String::new();
+    let mut records = RecordReader::new(file, terminator);
 
-    loop {
-        let bytes = file.read_line(&mut line)?;
-        if bytes == 0 {
-            break;
-        }
-        if pattern.is_match(&line) ^ invert_match {
-            matches.push(mem::take(&mut line))
+    while let Some(record) = records.next_record()? {
+        // 終端を除いた部分で照合し、出力は読んだままにする
+        if is_match(
+            &String::from_utf8_lossy(record.body()),
+            pattern,
+            invert_match,
+        ) {
+            matches.push(record.raw().to_vec());
         }
-        line.clear();
     }
     Ok(matches)
 }
+
+/// Entry points for the fuzz targets in `fuzz/`, which cannot reach the
+/// private matcher otherwise.
+#[cfg(fuzzing)]
+pub mod fuzz {
+    use clir_common::Terminator;
+    use regex::Regex;
+
+    use super::find_lines;
+
+    pub fn find_lines_in(data: &[u8], pattern: &Regex, invert_match: bool, nul: bool) {
+        let terminator = if nul {
+            Terminator::Nul
+        } else {
+            Terminator::Newline
+        };
+        let matches = find_lines(data, pattern, invert_match, terminator).unwrap();
+        assert!(matches.len() <= data.len() + 1);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
 
+    use clir_common::{FileArg, Terminator};
     use rand::distributions::Alphanumeric;
     use rand::Rng;
     use regex::{Regex, RegexBuilder};
@@ -193,22 +296,22 @@ mod tests {
 
     #[test]
     fn test_find_files() {
-        let files = find_files(&["./tests/inputs/fox.txt".to_string()], false);
+        let files = find_files(&[FileArg::from("./tests/inputs/fox.txt")], false);
         assert_eq!(files.len(), 1);
-        assert_eq!(files[0].as_ref().unwrap(), "./tests/inputs/fox.txt");
+        assert_eq!(files[0].as_ref().unwrap().name(), "./tests/inputs/fox.txt");
 
         // recursiveなしの場合、ディレクトリを拒否する
-        let files = find_files(&["./tests/inputs".to_string()], false);
+        let files = find_files(&[FileArg::from("./tests/inputs")], false);
         assert_eq!(files.len(), 1);
         if let Err(e) = &files[0] {
             assert_eq!(e.to_string(), "./tests/inputs is a directory");
         }
 
         // ディレクトリ内の4つのファイルを再帰的に検索できることを確認する
-        let res = find_files(&["./tests/inputs".to_string()], true);
+        let res = find_files(&[FileArg::from("./tests/inputs")], true);
         let mut files: Vec<String> = res
             .iter()
-            .map(|r| r.as_ref().unwrap().replace("\\", "/"))
+            .map(|r| r.as_ref().unwrap().name().replace("\\", "/"))
             .collect();
         files.sort();
         assert_eq!(files.len(), 4);
@@ -230,9 +333,13 @@ mod tests {
             .collect();
 
         // エラーとして不正なファイルを返すことを確認する
-        let files = find_files(&[bad], false);
+        let files = find_files(&[FileArg::new(bad)], false);
         assert_eq!(files.len(), 1);
-        assert!(files[0].is_err())
+        assert!(files[0].is_err());
+
+        // 標準入力と記述子は調べずにそのまま渡す
+        let files = find_files(&[FileArg::from("-"), FileArg::from("/dev/fd/9")], false);
+        assert!(files.iter().all(|f| f.is_ok()));
     }
 
     #[test]
@@ -241,12 +348,12 @@ mod tests {
         // 「or」というパターンは「Lorem」という1行にマッチするはず
         let re1 = Regex::new("or").unwrap();
 
-        let matches = find_lines(Cursor::new(&text), &re1, false);
+        let matches = find_lines(Cursor::new(&text), &re1, false, Terminator::Newline);
         assert!(matches.is_ok());
         assert_eq!(matches.unwrap().len(), 1);
 
         // マッチを反転させた場合、残りの2行にマッチするはず
-        let matches = find_lines(Cursor::new(&text), &re1, true);
+        let matches = find_lines(Cursor::new(&text), &re1, true, Terminator::Newline);
         assert!(matches.is_ok());
         assert_eq!(matches.unwrap().len(), 2);
 
@@ -257,13 +364,27 @@ mod tests {
             .unwrap();
 
         // 「Lorem」と「DOLOR」の２行にマッチするはず
-        let matches = find_lines(Cursor::new(&text), &re2, false);
+        let matches = find_lines(Cursor::new(&text), &re2, false, Terminator::Newline);
         assert!(matches.is_ok());
         assert_eq!(matches.unwrap().len(), 2);
 
         // マッチを反転させた場合、残りの1行にマッチするはず
-        let matches = find_lines(Cursor::new(&text), &re2, true);
+        let matches = find_lines(Cursor::new(&text), &re2, true, Terminator::Newline);
         assert!(matches.is_ok());
         assert_eq!(matches.unwrap().len(), 1);
+
+        // CRLFの「\r」は行末の一部として扱う
+        let re3 = Regex::new("m$").unwrap();
+        let matches = find_lines(Cursor::new(b"Ipsum\r\n"), &re3, false, Terminator::Newline);
+        assert_eq!(matches.unwrap(), vec![b"Ipsum\r\n"]);
+
+        // NUL区切りでは改行も行の一部になる
+        let matches = find_lines(
+            Cursor::new(b"Lorem\nIpsum\0DOLOR\0"),
+            &re1,
+            false,
+            Terminator::Nul,
+        );
+        assert_eq!(matches.unwrap(), vec![b"Lorem\nIpsum\0"]);
     }
 }
@@ -1,6 +1,10 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = grepr::get_args().and_then(grepr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = grepr::get_args()
+        .and_then(|config| grepr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("grepr", &e)),
     }
 }
@@ -1,11 +1,9 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, Fixture, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
 use std::{fs, path::Path};
 use sys_info::os_type;
 
-type TestResult = Result<(), Box<dyn std::error::Error>>;
-
 const PRG: &str = "grepr";
 const BUSTLE: &str = "tests/inputs/bustle.txt";
 const EMPTY: &str = "tests/inputs/empty.txt";
@@ -13,21 +11,6 @@ const FOX: &str = "tests/inputs/fox.txt";
 const NOBODY: &str = "tests/inputs/nobody.txt";
 const INPUTS_DIR: &str = "tests/inputs";
 
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_no_args() -> TestResult {
@@ -42,7 +25,7 @@ fn dies_no_args() -> TestResult {
 #[test]
 fn dies_bad_pattern() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["*foo", FOX])
+        .args(["*foo", FOX])
         .assert()
         .failure()
         .stderr(predicate::str::contains("Invalid pattern \"*foo\""));
@@ -55,12 +38,24 @@ fn warns_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&["foo", &bad])
+        .args(["foo", &bad])
         .assert()
+        .code(2)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn no_match_exits_one() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["cat", FOX])
+        .assert()
+        .code(1)
+        .stdout("");
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
     let windows_file = format!("{}.windows", expected_file);
@@ -70,7 +65,7 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
         expected_file
     };
 
-    let expected = fs::read_to_string(&expected_file)?;
+    let expected = fs::read_to_string(expected_file)?;
 
     Command::cargo_bin(PRG)?
         .args(args)
@@ -232,7 +227,7 @@ fn warns_dir_not_recursive() -> TestResult {
     let stdout = "tests/inputs/fox.txt:\
         The quick brown fox jumps over the lazy dog.";
     Command::cargo_bin(PRG)?
-        .args(&["fox", INPUTS_DIR, FOX])
+        .args(["fox", INPUTS_DIR, FOX])
         .assert()
         .stderr(predicate::str::contains("tests/inputs is a directory"))
         .stdout(predicate::str::contains(stdout));
@@ -267,9 +262,112 @@ fn stdin_insensitive_count() -> TestResult {
     let expected = fs::read_to_string(expected_file)?;
 
     Command::cargo_bin(PRG)?
-        .args(&["-ci", "the", "-"])
+        .args(["-ci", "the", "-"])
         .write_stdin(input)
         .assert()
         .stdout(expected);
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+fn invalid_utf8_passes_through() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("a")
+        .write_stdin(&b"a\xffb\nc\n"[..])
+        .assert()
+        .success()
+        .stdout(&b"a\xffb\n"[..]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn null_data() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "y$", "-"])
+        .write_stdin("a\nb\0bb\nyy\0by\r\n\0")
+        .assert()
+        .success()
+        .stdout("bb\nyy\0");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_anchor() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["y$", "-"])
+        .write_stdin("by\r\nyes\r\n")
+        .assert()
+        .success()
+        .stdout("by\r\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn config_defaults() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file(
+        "clir/config.toml",
+        "[grepr]\nargs = [\"-i\"]\n\n[catr]\nargs = [\"-n\"]\n",
+    )?;
+
+    // 設定ファイルの「-i」に加えてコマンドラインの「-c」も効く
+    Command::cargo_bin(PRG)?
+        .env("XDG_CONFIG_HOME", fixture.root())
+        .args(["-c", "the", BUSTLE])
+        .assert()
+        .success()
+        .stdout("3\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_config() -> TestResult {
+    let fixture = Fixture::new()?;
+    fixture.file("clir/config.toml", "[grepr]\nargs = \"-i\"\n")?;
+
+    Command::cargo_bin(PRG)?
+        .env("XDG_CONFIG_HOME", fixture.root())
+        .args(["the", BUSTLE])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::starts_with("grepr: "))
+        .stderr(predicate::str::contains("config.toml"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn env_defaults() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("GREPR_OPTIONS", "-i")
+        .args(["-c", "the", BUSTLE])
+        .assert()
+        .success()
+        .stdout("3\n");
+
+    // --no-env で環境変数を無視する
+    Command::cargo_bin(PRG)?
+        .env("GREPR_OPTIONS", "-i")
+        .args(["--no-env", "-c", "the", BUSTLE])
+        .assert()
+        .success()
+        .stdout("1\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_env() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("GREPR_OPTIONS", "-e 'the")
+        .args(["the", BUSTLE])
+        .assert()
+        .code(2)
+        .stderr("grepr: $GREPR_OPTIONS: unbalanced quotes\n");
+    Ok(())
+}
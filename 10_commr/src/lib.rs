@@ -1,16 +1,17 @@
 use std::cmp::Ordering::{Equal, Greater, Less};
-use std::error::Error;
-use std::fs::File;
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::Write;
 
-use clap::{Arg, Command};
 use clap::ArgAction::{SetFalse, SetTrue};
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{
+    open, CommandExt, RecordReader, Terminator, ToolError, EXIT_FAILURE, EXIT_USAGE,
+};
+use thiserror::Error;
 
 use crate::Column::{Col1, Col2, Col3};
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
 #[derive(Debug)]
 pub struct Config {
     file1: String,
@@ -20,6 +21,32 @@ pub struct Config {
     show_col3: bool,
     insensitive: bool,
     delimiter: String,
+    terminator: Terminator,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+    #[error(
+        "{}",
+        tr_str(
+            "Both input files cannot be STDIN (\"-\")",
+            "両方の入力ファイルを標準入力 (\"-\") にはできません",
+        )
+    )]
+    BothStdin,
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::BothStdin => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
 }
 
 enum Column<'a> {
@@ -28,7 +55,7 @@ enum Column<'a> {
     Col3(&'a str),
 }
 
-pub fn get_args() -> MyResult<Config> {
+pub fn get_args() -> Result<Config, Error> {
     let matches = Command::new("commr")
         .version("0.1.0")
         .about("Rust comm")
@@ -36,36 +63,39 @@ pub fn get_args() -> MyResult<Config> {
             Arg::new("file1")
                 .value_name("FILE1")
                 .required(true)
-                .help("Input file 1"),
+                .help(tr_str("Input file 1", "入力ファイル1")),
         )
         .arg(
             Arg::new("file2")
                 .value_name("FILE2")
                 .required(true)
-                .help("Input file 2"),
+                .help(tr_str("Input file 2", "入力ファイル2")),
         )
         .arg(
             Arg::new("insensitive")
                 .short('i')
-                .help("Case-insensitive comparison of lines")
+                .help(tr_str(
+                    "Case-insensitive comparison of lines",
+                    "大文字と小文字を区別せずに行を比較する",
+                ))
                 .action(SetTrue),
         )
         .arg(
             Arg::new("suppress_col1")
                 .short('1')
-                .help("Suppress printing of column 1")
+                .help(tr_str("Suppress printing of column 1", "1列目を表示しない"))
                 .action(SetFalse),
         )
         .arg(
             Arg::new("suppress_col2")
                 .short('2')
-                .help("Suppress printing of column 2")
+                .help(tr_str("Suppress printing of column 2", "2列目を表示しない"))
                 .action(SetFalse),
         )
         .arg(
             Arg::new("suppress_col3")
                 .short('3')
-                .help("Suppress printing of column 3")
+                .help(tr_str("Suppress printing of column 3", "3列目を表示しない"))
                 .action(SetFalse),
         )
         .arg(
@@ -73,11 +103,21 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("DELIM")
                 .long("output-delimiter")
                 .short('d')
-                .help("Output delimiter")
+                .help(tr_str("Output delimiter", "出力の区切り"))
                 .required(false)
                 .default_value("\t"),
         )
-        .get_matches();
+        .arg(
+            Arg::new("zero_terminated")
+                .short('z')
+                .long("zero-terminated")
+                .help(tr_str(
+                    "Line delimiter is NUL, not newline",
+                    "行の区切りを改行ではなくNULにする",
+                ))
+                .action(SetTrue),
+        )
+        .get_tool_matches();
 
     Ok(Config {
         file1: matches.get_one::<String>("file1").unwrap().to_string(),
@@ -87,26 +127,32 @@ pub fn get_args() -> MyResult<Config> {
         show_col3: matches.get_flag("suppress_col3"),
         insensitive: matches.get_flag("insensitive"),
         delimiter: matches.get_one::<String>("delimiter").unwrap().to_string(),
+        terminator: if matches.get_flag("zero_terminated") {
+            Terminator::Nul
+        } else {
+            Terminator::Newline
+        },
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
     let file1 = &config.file1;
     let file2 = &config.file2;
 
     if file1 == "-" && file2 == "-" {
-        return Err(From::from("Both input files cannot be STDIN (\"-\")"));
+        return Err(Error::BothStdin);
     }
 
-    let case = |line: String| {
+    let case = |line: &[u8]| {
+        let line = String::from_utf8_lossy(line);
         if config.insensitive {
             line.to_lowercase()
         } else {
-            line
+            line.into_owned()
         }
     };
 
-    let print = |col: Column| {
+    let mut print = |col: Column| {
         let mut columns = vec![];
         match col {
             Col1(val) => {
@@ -136,39 +182,51 @@ pub fn run(config: Config) -> MyResult<()> {
         }
 
         if !columns.is_empty() {
-            println!("{}", columns.join(&config.delimiter));
+            write!(out, "{}", columns.join(&config.delimiter))?;
+            out.write_all(&[config.terminator.byte()])?;
         }
+        Ok::<(), Error>(())
     };
-    let mut lines1 = open(file1)?.lines().map_while(Result::ok).map(case);
-    let mut lines2 = open(file2)?.lines().map_while(Result::ok).map(case);
+    let open = |filename: &str| {
+        open(filename).map_err(|source| Error::Open {
+            path: filename.to_string(),
+            source,
+        })
+    };
+    let mut records1 = RecordReader::new(open(file1)?, config.terminator);
+    let mut records2 = RecordReader::new(open(file2)?, config.terminator);
+    let mut next1 =
+        || -> io::Result<Option<String>> { Ok(records1.next_record()?.map(|r| case(r.body()))) };
+    let mut next2 =
+        || -> io::Result<Option<String>> { Ok(records2.next_record()?.map(|r| case(r.body()))) };
 
-    let mut line1 = lines1.next();
-    let mut line2 = lines2.next();
+    let mut line1 = next1()?;
+    let mut line2 = next2()?;
 
     while line1.is_some() || line2.is_some() {
         match (&line1, &line2) {
             (Some(val1), Some(val2)) => match val1.cmp(val2) {
                 Equal => {
-                    print(Col3(val1));
-                    line1 = lines1.next();
-                    line2 = lines2.next();
+                    print(Col3(val1))?;
+                    line1 = next1()?;
+                    line2 = next2()?;
                 }
                 Less => {
-                    print(Col1(val1));
-                    line1 = lines1.next();
+                    print(Col1(val1))?;
+                    line1 = next1()?;
                 }
                 Greater => {
-                    print(Col2(val2));
-                    line2 = lines2.next();
+                    print(Col2(val2))?;
+                    line2 = next2()?;
                 }
             },
             (Some(val1), None) => {
-                print(Col1(val1));
-                line1 = lines1.next();
+                print(Col1(val1))?;
+                line1 = next1()?;
             }
             (None, Some(val2)) => {
-                print(Col2(val2));
-                line2 = lines2.next();
+                print(Col2(val2))?;
+                line2 = next2()?;
             }
             _ => (),
         }
@@ -176,12 +234,3 @@ pub fn run(config: Config) -> MyResult<()> {
 
     Ok(())
 }
-
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(
-            File::open(filename).map_err(|e| format!("{}: {}", filename, e))?,
-        ))),
-    }
-}
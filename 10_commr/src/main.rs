@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = commr::get_args().and_then(commr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = commr::get_args()
+        .and_then(|config| commr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("commr", &e));
     }
 }
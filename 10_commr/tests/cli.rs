@@ -1,8 +1,8 @@
 use std::fs;
 
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, Fixture, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
 
 const PRG: &str = "commr";
 const EMPTY: &str = "tests/inputs/empty.txt";
@@ -10,8 +10,6 @@ const FILE1: &str = "tests/inputs/file1.txt";
 const FILE2: &str = "tests/inputs/file2.txt";
 const BLANK: &str = "tests/inputs/blank.txt";
 
-type TestResult = Result<(), Box<dyn std::error::Error>>;
-
 // --------------------------------------------------
 #[test]
 fn dies_no_args() -> TestResult {
@@ -22,30 +20,15 @@ fn dies_no_args() -> TestResult {
     Ok(())
 }
 
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename: String = rand::thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(7)
-            .map(char::from)
-            .collect();
-
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_bad_file1() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&[&bad, FILE1])
+        .args([&bad, FILE1])
         .assert()
-        .failure()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -56,7 +39,7 @@ fn dies_bad_file2() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&[FILE1, &bad])
+        .args([FILE1, &bad])
         .assert()
         .failure()
         .stderr(predicate::str::is_match(expected)?);
@@ -68,7 +51,7 @@ fn dies_bad_file2() -> TestResult {
 fn dies_both_stdin() -> TestResult {
     let expected = "Both input files cannot be STDIN (\"-\")";
     Command::cargo_bin(PRG)?
-        .args(&["-", "-"])
+        .args(["-", "-"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -337,6 +320,34 @@ fn blank_file1() -> TestResult {
     run(&[BLANK, FILE1], "tests/expected/blank_file1.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn zero_terminated() -> TestResult {
+    let fixture = Fixture::new()?;
+    let file1 = fixture.file("file1.txt", "a\0b\nc\0")?;
+    Command::cargo_bin(PRG)?
+        .args(["-z", file1.as_str(), "-"])
+        .write_stdin("b\nc\0d\0")
+        .assert()
+        .success()
+        .stdout("a\0\t\tb\nc\0\td\0");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf() -> TestResult {
+    let fixture = Fixture::new()?;
+    let file1 = fixture.file("file1.txt", "a\r\nb\r\n")?;
+    Command::cargo_bin(PRG)?
+        .args([file1.as_str(), "-"])
+        .write_stdin("b\nc\n")
+        .assert()
+        .success()
+        .stdout("a\n\t\tb\n\tc\n");
+    Ok(())
+}
+
 //// --------------------------------------------------
 //#[test]
 //fn file1_blanks() -> TestResult {
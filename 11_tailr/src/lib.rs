@@ -1,16 +1,19 @@
-use std::error::Error;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
 
-use clap::{Arg, ArgAction, Command};
 use clap::ArgAction::SetTrue;
+use clap::{Arg, ArgAction, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{warn_to, CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use thiserror::Error;
 
 use crate::TakeValue::{PlusZero, TakeNum};
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
+/// How many lines or bytes to print: from the end (`-n 3`), from a
+/// 1-based position (`-n +3`), or everything (`-n +0`).
 #[derive(Debug, PartialEq)]
-enum TakeValue {
+pub enum TakeValue {
     PlusZero,
     TakeNum(i64),
 }
@@ -21,16 +24,38 @@ pub struct Config {
     lines: TakeValue,
     bytes: Option<TakeValue>,
     quiet: bool,
+    decompress: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+    #[error("{}", tr_text("illegal line count -- {0}", "行数が不正です -- {0}", &[.0]))]
+    IllegalLineCount(String),
+    #[error("{}", tr_text("illegal byte count -- {0}", "バイト数が不正です -- {0}", &[.0]))]
+    IllegalByteCount(String),
 }
 
-pub fn get_args() -> MyResult<Config> {
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::IllegalLineCount(_) | Error::IllegalByteCount(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
     let matches = Command::new("tailr")
         .version("0.1.0")
         .about("Rust tail")
         .arg(
             Arg::new("files")
                 .value_name("FILE")
-                .help("Input file(s)")
+                .help(tr_str("Input file(s)", "入力ファイル"))
                 .required(true)
                 .action(ArgAction::Append),
         )
@@ -40,7 +65,7 @@ pub fn get_args() -> MyResult<Config> {
                 .short('n')
                 .value_name("LINES")
                 .default_value("10")
-                .help("Number of lines")
+                .help(tr_str("Number of lines", "行数"))
                 .allow_negative_numbers(true),
         )
         .arg(
@@ -49,17 +74,26 @@ pub fn get_args() -> MyResult<Config> {
                 .short('c')
                 .value_name("BYTES")
                 .conflicts_with("lines")
-                .help("Number of bytes")
+                .help(tr_str("Number of bytes", "バイト数"))
                 .allow_negative_numbers(true),
         )
         .arg(
             Arg::new("quiet")
                 .long("quiet")
                 .short('q')
-                .help("Suppress headers")
+                .help(tr_str("Suppress headers", "ヘッダを表示しない"))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("decompress")
+                .long("decompress")
+                .help(tr_str(
+                    "Decompress gzip, bzip2, xz and zstd input",
+                    "gzip、bzip2、xz、zstdの入力を展開する",
+                ))
                 .action(SetTrue),
         )
-        .get_matches();
+        .get_tool_matches();
     let files = matches
         .get_many::<String>("files")
         .expect("files required")
@@ -70,55 +104,69 @@ pub fn get_args() -> MyResult<Config> {
         .map(|s| s.as_str())
         .map(parse_num)
         .transpose()
-        .map_err(|e| format!("illegal line count -- {}", e))?
+        .map_err(Error::IllegalLineCount)?
         .unwrap();
     let bytes = matches
         .get_one::<String>("bytes")
         .map(|s| s.as_str())
         .map(parse_num)
         .transpose()
-        .map_err(|e| format!("illegal byte count -- {}", e))?;
+        .map_err(Error::IllegalByteCount)?;
 
     Ok(Config {
         files,
         lines,
         bytes,
         quiet: matches.get_flag("quiet"),
+        decompress: matches.get_flag("decompress"),
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
     let num_files = config.files.len();
     for (file_num, filename) in config.files.iter().enumerate() {
-        match File::open(filename) {
-            Err(err) => eprintln!("{}: {}", filename, err),
+        match open(filename, config.decompress) {
+            Err(source) => {
+                warn_to(
+                    err,
+                    "tailr",
+                    &Error::Open {
+                        path: filename.to_string(),
+                        source,
+                    },
+                );
+                status = EXIT_FAILURE;
+            }
             Ok(file) => {
                 if !config.quiet && num_files > 1 {
-                    println!(
+                    writeln!(
+                        out,
                         "{}==> {} <==",
                         if file_num > 0 { "\n" } else { "" },
                         filename
-                    )
+                    )?;
                 }
-                let (total_lines, total_bytes) = count_lines_bytes(filename)?;
-                let file = BufReader::new(file);
+                let (total_lines, total_bytes) = count_lines_bytes(filename, config.decompress)?;
                 if let Some(num_bytes) = &config.bytes {
-                    print_bytes(file, num_bytes, total_bytes)?;
+                    print_bytes(out, file, num_bytes, total_bytes)?;
                 } else {
-                    print_lines(file, &config.lines, total_lines)?;
+                    print_lines(out, file, &config.lines, total_lines)?;
                 }
             }
         }
     }
-    Ok(())
+    Ok(status)
 }
 
-fn parse_num(val: &str) -> MyResult<TakeValue> {
+/// Parses a `-n`/`-c` value. Unsigned numbers count from the end.
+pub fn parse_num(val: &str) -> Result<TakeValue, String> {
     let sings: &[char] = &['+', '-'];
-    let res = val
-        .starts_with(sings)
-        .then(|| val.parse())
-        .unwrap_or_else(|| val.parse().map(i64::wrapping_neg));
+    let res = if val.starts_with(sings) {
+        val.parse()
+    } else {
+        val.parse().map(i64::wrapping_neg)
+    };
 
     match res {
         Ok(num) => {
@@ -128,12 +176,21 @@ fn parse_num(val: &str) -> MyResult<TakeValue> {
                 Ok(TakeNum(num))
             }
         }
-        _ => Err(From::from(val)),
+        _ => Err(val.to_string()),
+    }
+}
+
+fn open(filename: &str, decompress: bool) -> io::Result<Box<dyn BufRead>> {
+    let file: Box<dyn BufRead> = Box::new(BufReader::new(File::open(filename)?));
+    if decompress {
+        clir_common::decompress(file, filename)
+    } else {
+        Ok(file)
     }
 }
 
-fn count_lines_bytes(filename: &str) -> MyResult<(i64, i64)> {
-    let mut file = BufReader::new(File::open(filename)?);
+fn count_lines_bytes(filename: &str, decompress: bool) -> Result<(i64, i64), Error> {
+    let mut file = open(filename, decompress)?;
     let mut num_lines = 0;
     let mut num_bytes = 0;
     let mut buf = Vec::new();
@@ -149,7 +206,14 @@ fn count_lines_bytes(filename: &str) -> MyResult<(i64, i64)> {
     Ok((num_lines, num_bytes))
 }
 
-fn print_lines(mut file: impl BufRead, num_lines: &TakeValue, total_lines: i64) -> MyResult<()> {
+/// Writes the lines of `file` selected by `num_lines`, given the total
+/// number of lines in it.
+pub fn print_lines(
+    out: &mut impl Write,
+    mut file: impl BufRead,
+    num_lines: &TakeValue,
+    total_lines: i64,
+) -> Result<(), Error> {
     if let Some(start) = get_start_index(num_lines, total_lines) {
         let mut line_num = 0;
         let mut buf = Vec::new();
@@ -159,7 +223,7 @@ fn print_lines(mut file: impl BufRead, num_lines: &TakeValue, total_lines: i64)
                 break;
             }
             if line_num >= start {
-                print!("{}", String::from_utf8_lossy(&buf))
+                write!(out, "{}", String::from_utf8_lossy(&buf))?;
             }
             line_num += 1;
             buf.clear();
@@ -169,22 +233,26 @@ fn print_lines(mut file: impl BufRead, num_lines: &TakeValue, total_lines: i64)
     Ok(())
 }
 
-fn print_bytes<T: Read + Seek>(
+/// Like [`print_lines`], for bytes.
+pub fn print_bytes<T: Read>(
+    out: &mut impl Write,
     mut file: T,
     num_bytes: &TakeValue,
     total_bytes: i64,
-) -> MyResult<()> {
+) -> Result<(), Error> {
     if let Some(start) = get_start_index(num_bytes, total_bytes) {
-        file.seek(SeekFrom::Start(start))?;
+        // 展開したストリームはシークできないので読み飛ばす
+        io::copy(&mut file.by_ref().take(start), &mut io::sink())?;
         let mut buffer = Vec::new();
         file.read_to_end(&mut buffer)?;
         if !buffer.is_empty() {
-            print!("{}", String::from_utf8_lossy(&buffer));
+            write!(out, "{}", String::from_utf8_lossy(&buffer))?;
         }
     }
 
     Ok(())
 }
+
 fn get_start_index(take_val: &TakeValue, total: i64) -> Option<u64> {
     match take_val {
         PlusZero => {
@@ -205,8 +273,24 @@ fn get_start_index(take_val: &TakeValue, total: i64) -> Option<u64> {
     }
 }
 
+/// Entry points for the fuzz targets in `fuzz/`, which cannot reach the
+/// private parsers otherwise.
+#[cfg(fuzzing)]
+pub mod fuzz {
+    use super::{get_start_index, parse_num};
+
+    pub fn parse_num_and_seek(val: &str, total: i64) {
+        if let Ok(take_val) = parse_num(val) {
+            if let Some(start) = get_start_index(&take_val, total.max(0)) {
+                assert!(start < total as u64);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use proptest::prelude::*;
     use rstest::rstest;
 
     use crate::{count_lines_bytes, get_start_index, parse_num, TakeValue};
@@ -241,11 +325,11 @@ mod test {
 
     #[test]
     fn test_count_lines_bytes() {
-        let res = count_lines_bytes("tests/inputs/one.txt");
+        let res = count_lines_bytes("tests/inputs/one.txt", false);
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), (1, 24));
 
-        let res = count_lines_bytes("tests/inputs/ten.txt");
+        let res = count_lines_bytes("tests/inputs/ten.txt", false);
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), (10, 49));
     }
@@ -270,4 +354,50 @@ mod test {
     ) {
         assert_eq!(get_start_index(take_val, total), expected);
     }
+
+    proptest! {
+        #[test]
+        fn prop_parse_num_round_trip(num: i64) {
+            let signed = if num >= 0 {
+                format!("+{}", num)
+            } else {
+                num.to_string()
+            };
+            let expected = if num == 0 { PlusZero } else { TakeNum(num) };
+            prop_assert_eq!(parse_num(&signed).unwrap(), expected);
+
+            // 符号のない数は末尾から数える
+            if num > 0 {
+                prop_assert_eq!(parse_num(&num.to_string()).unwrap(), TakeNum(-num));
+            }
+        }
+
+        #[test]
+        fn prop_parse_num_error_echoes_input(val in "\\PC*") {
+            if let Err(e) = parse_num(&val) {
+                prop_assert_eq!(e, val);
+            }
+        }
+
+        #[test]
+        fn prop_get_start_index_in_bounds(num: i64, total in 0..i64::MAX) {
+            match get_start_index(&TakeNum(num), total) {
+                Some(start) => {
+                    prop_assert!(start < total as u64);
+                    if num > 0 {
+                        prop_assert_eq!(start, num as u64 - 1);
+                    } else {
+                        // 末尾から |num| 個を超えて遡らない
+                        prop_assert!(total as u64 - start <= num.unsigned_abs());
+                    }
+                }
+                None => prop_assert!(num == 0 || total == 0 || num > total),
+            }
+        }
+
+        #[test]
+        fn prop_get_start_index_plus_zero(total in 0..i64::MAX) {
+            prop_assert_eq!(get_start_index(&PlusZero, total), (total > 0).then_some(0));
+        }
+    }
 }
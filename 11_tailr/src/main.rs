@@ -1,6 +1,10 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = tailr::get_args().and_then(tailr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = tailr::get_args()
+        .and_then(|config| tailr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("tailr", &e)),
     }
 }
@@ -1,11 +1,9 @@
-use std::fs::{self, File};
+use std::fs::File;
 use std::io::Read;
 
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, random_string, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
-
-type TestResult = Result<(), Box<dyn std::error::Error>>;
 
 const PRG: &str = "tailr";
 const EMPTY: &str = "tests/inputs/empty.txt";
@@ -14,25 +12,6 @@ const TWO: &str = "tests/inputs/two.txt";
 const THREE: &str = "tests/inputs/three.txt";
 const TEN: &str = "tests/inputs/ten.txt";
 
-// --------------------------------------------------
-fn random_string() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(7)
-        .map(char::from)
-        .collect()
-}
-
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename = random_string();
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
-
 // --------------------------------------------------
 #[test]
 fn dies_no_args() -> TestResult {
@@ -50,7 +29,7 @@ fn dies_bad_bytes() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal byte count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-c", &bad, EMPTY])
+        .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -64,7 +43,7 @@ fn dies_bad_lines() -> TestResult {
     let bad = random_string();
     let expected = format!("illegal line count -- {}", &bad);
     Command::cargo_bin(PRG)?
-        .args(&["-n", &bad, EMPTY])
+        .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -79,7 +58,7 @@ fn dies_bytes_and_lines() -> TestResult {
                used with '--bytes <BYTES>'";
 
     Command::cargo_bin(PRG)?
-        .args(&["-n", "1", "-c", "2"])
+        .args(["-n", "1", "-c", "2"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(msg));
@@ -93,8 +72,9 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&[ONE, &bad, TWO])
+        .args([ONE, &bad, TWO])
         .assert()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
 
     Ok(())
@@ -830,3 +810,21 @@ fn multiple_files_c_plus_3() -> TestResult {
         "tests/expected/all.c+3.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn decompress_bytes() -> TestResult {
+    run(
+        &["--decompress", "-c", "8", "tests/inputs/ten.txt.zst"],
+        "tests/expected/ten.txt.c8.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_lines() -> TestResult {
+    run(
+        &["--decompress", "-n", "+2", "tests/inputs/ten.txt.zst"],
+        "tests/expected/ten.txt.n+2.out",
+    )
+}
@@ -1,38 +1,75 @@
-use std::error::Error;
 use std::ffi::OsStr;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io;
+use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
+use std::slice;
 
+use chrono::{Datelike, Local, NaiveDate};
 use clap::{Arg, ArgAction, Command};
-use rand::{rngs::StdRng, SeedableRng};
-use rand::prelude::SliceRandom;
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{warn_to, CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use thiserror::Error;
 use walkdir::WalkDir;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
-
 #[derive(Debug)]
 pub struct Config {
     sources: Vec<String>,
     pattern: Option<Regex>,
     seed: Option<u64>,
+    json: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("{}", tr_text("Invalid --pattern \"{0}\"", "--pattern が不正です \"{0}\"", &[.0]))]
+    InvalidPattern(String),
+    #[error("hostname: {0}")]
+    Hostname(sys_info::Error),
+    #[error("{}", tr_text(
+        "{0}: invalid UTF-8, decoding as Latin-1",
+        "{0}: UTF-8として不正なのでLatin-1として読みます",
+        &[.0],
+    ))]
+    InvalidUtf8(String),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidPattern(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub struct Fortune {
     source: String,
+    index: usize,
     text: String,
 }
-pub fn get_args() -> MyResult<Config> {
+pub fn get_args() -> Result<Config, Error> {
     let matches = Command::new("fortuner")
         .version("0.1.0")
         .about("Rust fortune")
         .arg(
             Arg::new("sources")
                 .value_name("FILE")
-                .help("Input files or directories")
+                .help(tr_str(
+                    "Input files or directories",
+                    "入力ファイルまたはディレクトリ",
+                ))
                 .required(true)
                 .action(ArgAction::Append),
         )
@@ -41,13 +78,16 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("PATTERN")
                 .short('m')
                 .long("pattern")
-                .help("Pattern"),
+                .help(tr_str("Pattern", "パターン")),
         )
         .arg(
             Arg::new("insensitive")
                 .short('i')
                 .long("insensitive")
-                .help("Case-insensitive pattern matching")
+                .help(tr_str(
+                    "Case-insensitive pattern matching",
+                    "大文字と小文字を区別せずにパターンを照合する",
+                ))
                 .num_args(0),
         )
         .arg(
@@ -55,10 +95,36 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("SEED")
                 .short('s')
                 .long("seed")
-                .help("Random seed")
+                .help(tr_str("Random seed", "乱数のシード"))
                 .value_parser(parse_u64),
         )
-        .get_matches();
+        .arg(
+            Arg::new("daily")
+                .long("daily")
+                .help(tr_str(
+                    "Pick the same fortune for the whole day",
+                    "その日のあいだ同じfortuneを選ぶ",
+                ))
+                .conflicts_with("seed")
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("per_host")
+                .long("per-host")
+                .help(tr_str(
+                    "Mix the hostname into the --daily seed",
+                    "--daily のシードにホスト名を混ぜる",
+                ))
+                .requires("daily")
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .help(tr_str("Print fortunes as JSON", "fortuneをJSONで表示する"))
+                .num_args(0),
+        )
+        .get_tool_matches();
 
     let sources = matches
         .get_many::<String>("sources")
@@ -71,38 +137,61 @@ pub fn get_args() -> MyResult<Config> {
             RegexBuilder::new(val)
                 .case_insensitive(matches.get_flag("insensitive"))
                 .build()
-                .map_err(|_| format!("Invalid --pattern \"{}\"", val))
+                .map_err(|_| Error::InvalidPattern(val.to_string()))
         })
         .transpose()?;
+    let seed = if matches.get_flag("daily") {
+        let hostname = if matches.get_flag("per_host") {
+            Some(sys_info::hostname().map_err(Error::Hostname)?)
+        } else {
+            None
+        };
+        Some(daily_seed(Local::now().date_naive(), hostname.as_deref()))
+    } else {
+        matches.get_one::<u64>("seed").copied()
+    };
+
     Ok(Config {
         sources,
         pattern,
-        seed: matches.get_one::<u64>("seed").copied(),
+        seed,
+        json: matches.get_flag("json"),
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<(), Error> {
     let files = find_files(&config.sources)?;
-    let fortunes = read_fortunes(&files)?;
     if let Some(pattern) = config.pattern {
-        let mut prev_source = None;
-        for fortune in fortunes
+        let fortunes = read_fortunes(&files, err)?;
+        let matches: Vec<_> = fortunes
             .iter()
             .filter(|fortune| pattern.is_match(&fortune.text))
-        {
-            if prev_source.as_ref().map_or(true, |s| s != &fortune.source) {
-                eprintln!("({})\n%", fortune.source);
-                prev_source = Some(fortune.source.clone())
+            .collect();
+        if config.json {
+            writeln!(out, "{}", serde_json::to_string(&matches)?)?;
+        } else {
+            let mut prev_source = None;
+            for fortune in matches {
+                if prev_source.as_ref() != Some(&fortune.source) {
+                    writeln!(err, "({})\n%", fortune.source)?;
+                    prev_source = Some(fortune.source.clone())
+                }
+                writeln!(out, "{}\n%", fortune.text)?;
             }
-            println!("{}\n%", fortune.text);
         }
     } else {
-        println!(
-            "{}",
-            pick_fortune(&fortunes, config.seed)
-                .or_else(|| Some("No fortunes found".to_string()))
-                .unwrap()
-        )
+        let fortune = pick_fortune(FortuneReader::new(&files, err), config.seed)?;
+        if config.json {
+            writeln!(out, "{}", serde_json::to_string(&Vec::from_iter(fortune))?)?;
+        } else {
+            writeln!(
+                out,
+                "{}",
+                fortune
+                    .as_ref()
+                    .map_or("No fortunes found", |f| f.text.as_str())
+            )?;
+        }
     }
     Ok(())
 }
@@ -112,13 +201,28 @@ fn parse_u64(val: &str) -> Result<u64, String> {
         .map_err(|_| format!("\"{}\" not a valid integer", val))
 }
 
-fn find_files(paths: &[String]) -> MyResult<Vec<PathBuf>> {
+fn daily_seed(date: NaiveDate, hostname: Option<&str>) -> u64 {
+    let mut seed = date.num_days_from_ce() as u64;
+    if let Some(host) = hostname {
+        for byte in host.bytes() {
+            seed = (seed ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+    seed
+}
+
+fn find_files(paths: &[String]) -> Result<Vec<PathBuf>, Error> {
     let dat = OsStr::new("dat");
     let mut files = vec![];
 
     for path in paths {
         match fs::metadata(path) {
-            Err(e) => return Err(format!("{}: {}", path, e).into()),
+            Err(source) => {
+                return Err(Error::Open {
+                    path: path.to_string(),
+                    source,
+                })
+            }
             Ok(_) => files.extend(
                 WalkDir::new(path)
                     .into_iter()
@@ -134,47 +238,172 @@ fn find_files(paths: &[String]) -> MyResult<Vec<PathBuf>> {
     Ok(files)
 }
 
-fn read_fortunes(paths: &[PathBuf]) -> MyResult<Vec<Fortune>> {
-    let mut fortunes = vec![];
-    let mut buffer = vec![];
+fn read_fortunes(paths: &[PathBuf], err: &mut dyn Write) -> Result<Vec<Fortune>, Error> {
+    FortuneReader::new(paths, err).collect()
+}
 
-    for path in paths {
-        let basename = path.file_name().unwrap().to_string_lossy().into_owned();
-        let file = File::open(path)
-            .map_err(|e| format!("{}: {}", path.to_string_lossy().into_owned(), e))?;
+struct CookieFile<'a> {
+    path: &'a PathBuf,
+    source: String,
+    reader: BufReader<File>,
+    index: usize,
+    warned: bool,
+}
+
+struct FortuneReader<'a> {
+    paths: slice::Iter<'a, PathBuf>,
+    current: Option<CookieFile<'a>>,
+    buffer: Vec<String>,
+    bytes: Vec<u8>,
+    err: &'a mut dyn Write,
+}
+
+impl<'a> FortuneReader<'a> {
+    fn new(paths: &'a [PathBuf], err: &'a mut dyn Write) -> Self {
+        FortuneReader {
+            paths: paths.iter(),
+            current: None,
+            buffer: vec![],
+            bytes: vec![],
+            err,
+        }
+    }
+
+    fn open_next(&mut self) -> Option<Result<(), Error>> {
+        let path = self.paths.next()?;
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(source) => {
+                return Some(Err(Error::Open {
+                    path: path.to_string_lossy().into_owned(),
+                    source,
+                }))
+            }
+        };
+        self.current = Some(CookieFile {
+            path,
+            source: path.file_name().unwrap().to_string_lossy().into_owned(),
+            reader: BufReader::new(file),
+            index: 0,
+            warned: false,
+        });
+        Some(Ok(()))
+    }
+}
+
+impl Iterator for FortuneReader<'_> {
+    type Item = Result<Fortune, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.current.is_none() {
+                if let Err(e) = self.open_next()? {
+                    return Some(Err(e));
+                }
+            }
+            let file = self.current.as_mut().unwrap();
+
+            self.bytes.clear();
+            match file.reader.read_until(b'\n', &mut self.bytes) {
+                Err(e) => return Some(Err(e.into())),
+                Ok(0) => {
+                    self.current = None;
+                    continue;
+                }
+                Ok(_) => {}
+            }
+            let line = decode_line(&self.bytes).unwrap_or_else(|| {
+                if !file.warned {
+                    warn_to(
+                        self.err,
+                        "fortuner",
+                        &Error::InvalidUtf8(file.path.to_string_lossy().into_owned()),
+                    );
+                    file.warned = true;
+                }
+                decode_latin1(&self.bytes)
+            });
 
-        for line in BufReader::new(file).lines().map_while(Result::ok) {
             if line == "%" {
-                if !buffer.is_empty() {
-                    fortunes.push(Fortune {
-                        source: basename.clone(),
-                        text: buffer.join("\n"),
-                    });
-                    buffer.clear();
+                if !self.buffer.is_empty() {
+                    let fortune = Fortune {
+                        source: file.source.clone(),
+                        index: file.index,
+                        text: self.buffer.join("\n"),
+                    };
+                    self.buffer.clear();
+                    file.index += 1;
+                    return Some(Ok(fortune));
                 }
             } else {
-                buffer.push(line.to_string());
+                self.buffer.push(line);
             }
         }
     }
+}
 
-    Ok(fortunes)
+fn trim_newline(bytes: &[u8]) -> &[u8] {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes.strip_suffix(b"\r").unwrap_or(bytes)
 }
 
-fn pick_fortune(fortunes: &[Fortune], seed: Option<u64>) -> Option<String> {
-    if let Some(val) = seed {
-        let mut rng = StdRng::seed_from_u64(val);
-        fortunes.choose(&mut rng).map(|f| f.text.to_string())
-    } else {
-        let mut rng = rand::thread_rng();
-        fortunes.choose(&mut rng).map(|f| f.text.to_string())
+fn decode_line(bytes: &[u8]) -> Option<String> {
+    String::from_utf8(trim_newline(bytes).to_vec()).ok()
+}
+
+fn decode_latin1(bytes: &[u8]) -> String {
+    trim_newline(bytes).iter().map(|&b| b as char).collect()
+}
+
+fn pick_fortune(
+    fortunes: impl IntoIterator<Item = Result<Fortune, Error>>,
+    seed: Option<u64>,
+) -> Result<Option<Fortune>, Error> {
+    let mut rng: Box<dyn RngCore> = match seed {
+        Some(val) => Box::new(StdRng::seed_from_u64(val)),
+        None => Box::new(rand::thread_rng()),
+    };
+    let mut chosen = None;
+    for (seen, fortune) in fortunes.into_iter().enumerate() {
+        let fortune = fortune?;
+        if rng.gen_range(0..=seen) == 0 {
+            chosen = Some(fortune);
+        }
     }
+    Ok(chosen)
 }
+/// Entry points for the fuzz targets in `fuzz/`, which cannot reach the
+/// private reader otherwise.
+#[cfg(fuzzing)]
+pub mod fuzz {
+    use std::path::PathBuf;
+    use std::{env, fs, io, process};
+
+    use super::read_fortunes;
+
+    /// Parses `data` as a `%`-separated cookie file. The reader only works
+    /// on files, so the data goes through a scratch file first.
+    pub fn read_cookie_file(data: &[u8]) {
+        let path: PathBuf = env::temp_dir().join(format!("fortuner-fuzz-{}", process::id()));
+        fs::write(&path, data).unwrap();
+        let fortunes = read_fortunes(&[path], &mut io::sink()).unwrap();
+        assert!(fortunes
+            .iter()
+            .all(|f| f.text.split('\n').all(|line| line != "%")));
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::io;
     use std::path::PathBuf;
 
-    use crate::{find_files, Fortune, parse_u64, pick_fortune, read_fortunes};
+    use chrono::NaiveDate;
+
+    use crate::{
+        daily_seed, decode_latin1, decode_line, find_files, parse_u64, pick_fortune, read_fortunes,
+        Fortune,
+    };
 
     #[test]
     fn test_parse_u64() {
@@ -191,6 +420,20 @@ mod tests {
         assert_eq!(res.unwrap(), 4);
     }
 
+    #[test]
+    fn test_daily_seed() {
+        let day1 = NaiveDate::from_ymd_opt(2024, 6, 9).unwrap();
+        let day2 = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+
+        // 同じ日なら同じシード、違う日なら違うシードになる
+        assert_eq!(daily_seed(day1, None), daily_seed(day1, None));
+        assert_ne!(daily_seed(day1, None), daily_seed(day2, None));
+
+        // ホスト名を指定するとホストごとにシードが変わる
+        assert_eq!(daily_seed(day1, Some("a")), daily_seed(day1, Some("a")));
+        assert_ne!(daily_seed(day1, Some("a")), daily_seed(day1, Some("b")));
+    }
+
     #[test]
     fn test_find_files() {
         // 存在するファイルを検索できることを確認する
@@ -200,7 +443,7 @@ mod tests {
         let files = res.unwrap();
         assert_eq!(files.len(), 1);
         assert_eq!(
-            files.get(0).unwrap().to_string_lossy(),
+            files.first().unwrap().to_string_lossy(),
             "./tests/inputs/jokes"
         );
 
@@ -240,7 +483,7 @@ mod tests {
 
     #[test]
     fn test_read_fortunes() {
-        let res = read_fortunes(&[PathBuf::from("./tests/inputs/jokes")]);
+        let res = read_fortunes(&[PathBuf::from("./tests/inputs/jokes")], &mut io::sink());
         assert!(res.is_ok());
 
         if let Ok(fortunes) = res {
@@ -257,35 +500,54 @@ mod tests {
             )
         }
 
-        let res = read_fortunes(&[
-            PathBuf::from("./tests/inputs/jokes"),
-            PathBuf::from("./tests/inputs/quotes"),
-        ]);
+        let res = read_fortunes(
+            &[
+                PathBuf::from("./tests/inputs/jokes"),
+                PathBuf::from("./tests/inputs/quotes"),
+            ],
+            &mut io::sink(),
+        );
         assert!(res.is_ok());
         assert_eq!(res.unwrap().len(), 11);
     }
 
+    #[test]
+    fn test_decode_line() {
+        assert_eq!(decode_line(b"caf\xc3\xa9\n"), Some("café".to_string()));
+        assert_eq!(decode_line(b"%\r\n"), Some("%".to_string()));
+
+        // UTF-8として不正なバイト列はNoneになり、Latin-1としてデコードできる
+        assert_eq!(decode_line(b"caf\xe9\n"), None);
+        assert_eq!(decode_latin1(b"caf\xe9\n"), "café".to_string());
+    }
+
     #[test]
     fn test_pick_fortune() {
-        let fortunes = &[
+        let fortunes = [
             Fortune {
                 source: "fortunes".to_string(),
+                index: 0,
                 text: "You cannot achieve the impossible without \
             attempting the absurd."
                     .to_string(),
             },
             Fortune {
                 source: "fortunes".to_string(),
+                index: 1,
                 text: "Assumption is the mother of all screw-ups.".to_string(),
             },
             Fortune {
                 source: "fortunes".to_string(),
+                index: 2,
                 text: "Neckties strangle clear thinking.".to_string(),
             },
         ];
         assert_eq!(
-            pick_fortune(fortunes, Some(1)).unwrap(),
-            "Neckties strangle clear thinking.".to_string()
+            pick_fortune(fortunes.map(Ok), Some(1))
+                .unwrap()
+                .unwrap()
+                .text,
+            "Assumption is the mother of all screw-ups.".to_string()
         );
     }
 }
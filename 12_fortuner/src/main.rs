@@ -1,6 +1,10 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = fortuner::get_args().and_then(fortuner::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = fortuner::get_args().and_then(|config| {
+        fortuner::run(config, &mut io::stdout().lock(), &mut io::stderr().lock())
+    });
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("fortuner", &e));
     }
 }
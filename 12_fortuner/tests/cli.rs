@@ -1,35 +1,15 @@
 use assert_cmd::Command;
+use clir_testkit::{gen_bad_file, random_string, TestResult};
 use predicates::prelude::*;
-use rand::{distributions::Alphanumeric, Rng};
 use std::fs;
 
-type TestResult = Result<(), Box<dyn std::error::Error>>;
-
 const PRG: &str = "fortuner";
 const FORTUNE_DIR: &str = "./tests/inputs";
 const EMPTY_DIR: &str = "./tests/inputs/empty";
 const JOKES: &str = "./tests/inputs/jokes";
 const LITERATURE: &str = "./tests/inputs/literature";
 const QUOTES: &str = "./tests/inputs/quotes";
-
-// --------------------------------------------------
-fn random_string() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(7)
-        .map(char::from)
-        .collect()
-}
-
-// --------------------------------------------------
-fn gen_bad_file() -> String {
-    loop {
-        let filename = random_string();
-        if fs::metadata(&filename).is_err() {
-            return filename;
-        }
-    }
-}
+const LATIN1: &str = "./tests/legacy/latin1";
 
 // --------------------------------------------------
 #[test]
@@ -37,7 +17,7 @@ fn dies_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&[LITERATURE, &bad])
+        .args([LITERATURE, &bad])
         .assert()
         .failure()
         .stderr(predicate::str::is_match(expected)?);
@@ -50,7 +30,7 @@ fn dies_bad_seed() -> TestResult {
     let bad = random_string();
     let expected = format!("\"{}\" not a valid integer", &bad);
     Command::cargo_bin(PRG)?
-        .args(&[LITERATURE, "--seed", &bad])
+        .args([LITERATURE, "--seed", &bad])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -78,7 +58,7 @@ fn no_fortunes_found() -> TestResult {
 fn quotes_seed_1() -> TestResult {
     run(
         &[QUOTES, "-s", "1"],
-        "You can observe a lot just by watching.\n-- Yogi Berra\n",
+        "I can live for two months on a good compliment.\n-- Mark Twain\n",
     )
 }
 
@@ -87,7 +67,8 @@ fn quotes_seed_1() -> TestResult {
 fn jokes_seed_1() -> TestResult {
     run(
         &[JOKES, "-s", "1"],
-        "Q: What happens when frogs park illegally?\nA: They get toad.\n",
+        "Q: What do you call a deer wearing an eye patch?\n\
+        A: A bad idea (bad-eye deer).\n",
     )
 }
 
@@ -96,8 +77,7 @@ fn jokes_seed_1() -> TestResult {
 fn dir_seed_10() -> TestResult {
     run(
         &[FORTUNE_DIR, "-s", "10"],
-        "Q: Why did the fungus and the alga marry?\n\
-        A: Because they took a lichen to each other!\n",
+        "A is for Apple.\n\t\t-- Hester Pryne\n",
     )
 }
 
@@ -173,3 +153,71 @@ fn mark_twain_lower_i() -> TestResult {
         "tests/expected/twain_lower_i.err",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn latin1_cookies() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-m", "Caf", LATIN1])
+        .assert()
+        .success()
+        .stdout("Café au lait.\n%\n")
+        .stderr(predicate::str::contains(
+            "invalid UTF-8, decoding as Latin-1",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn daily_is_stable() -> TestResult {
+    let first = Command::cargo_bin(PRG)?
+        .args([FORTUNE_DIR, "--daily"])
+        .output()?;
+    let second = Command::cargo_bin(PRG)?
+        .args([FORTUNE_DIR, "--daily"])
+        .output()?;
+    assert!(first.status.success());
+    assert_eq!(first.stdout, second.stdout);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_daily_with_seed() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([FORTUNE_DIR, "--daily", "--seed", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn json_seed_1() -> TestResult {
+    run(
+        &[JOKES, "-s", "1", "--json"],
+        "[{\"source\":\"jokes\",\"index\":5,\
+        \"text\":\"Q: What do you call a deer wearing an eye patch?\\n\
+        A: A bad idea (bad-eye deer).\"}]\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn json_pattern() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-m", "Yogi Berra", "--json", FORTUNE_DIR])
+        .assert()
+        .success()
+        .stderr("")
+        .stdout(predicate::str::starts_with("[{\"source\":\"quotes\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn json_no_fortunes() -> TestResult {
+    run(&[EMPTY_DIR, "--json"], "[]\n")
+}
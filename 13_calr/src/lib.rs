@@ -1,10 +1,12 @@
-use std::error::Error;
-use std::str::FromStr;
-
 use ansi_term::Style;
 use chrono::{Datelike, Local, NaiveDate};
 use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{parse_int, CommandExt, ParseError, ToolError, EXIT_FAILURE, EXIT_USAGE};
 use itertools::izip;
+use std::io;
+use std::io::Write;
+use thiserror::Error;
 
 const MONTH_NAMES: [&str; 12] = [
     "January",
@@ -30,9 +32,38 @@ pub struct Config {
     today: NaiveDate,
 }
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("{}", tr_text(
+        "year \"{0}\" not in the range 1 through 9999",
+        "年 \"{0}\" が1から9999の範囲にありません",
+        &[.0],
+    ))]
+    YearRange(String),
+    #[error("{}", tr_text(
+        "month \"{0}\" not in the range 1 through 12",
+        "月 \"{0}\" が1から12の範囲にありません",
+        &[.0],
+    ))]
+    MonthRange(String),
+    #[error("{}", tr_text("Invalid month \"{0}\"", "月が不正です \"{0}\"", &[.0]))]
+    InvalidMonth(String),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => EXIT_FAILURE,
+            _ => EXIT_USAGE,
+        }
+    }
+}
 
-pub fn get_args() -> MyResult<Config> {
+pub fn get_args() -> Result<Config, Error> {
     let matches = Command::new("calr")
         .about("Rust cal")
         .version("0.1.0")
@@ -40,7 +71,10 @@ pub fn get_args() -> MyResult<Config> {
             Arg::new("month")
                 .value_name("MONTH")
                 .short('m')
-                .help("Month name or number(1-12)")
+                .help(tr_str(
+                    "Month name or number(1-12)",
+                    "月の名前または番号(1-12)",
+                ))
                 .num_args(1),
         )
         .arg(
@@ -48,12 +82,16 @@ pub fn get_args() -> MyResult<Config> {
                 .value_name("SHOW_YEAR")
                 .short('y')
                 .long("year")
-                .help("Show whole current year")
+                .help(tr_str("Show whole current year", "今年1年分を表示する"))
                 .conflicts_with_all(["month", "year"])
                 .num_args(0),
         )
-        .arg(Arg::new("year").value_name("YEAR").help("Year (1-9999)"))
-        .get_matches();
+        .arg(
+            Arg::new("year")
+                .value_name("YEAR")
+                .help(tr_str("Year (1-9999)", "年 (1-9999)")),
+        )
+        .get_tool_matches();
 
     let mut month = matches
         .get_one::<String>("month")
@@ -79,14 +117,14 @@ pub fn get_args() -> MyResult<Config> {
     })
 }
 
-pub fn run(config: Config) -> MyResult<()> {
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
     match config.month {
         Some(month) => {
             let lines = format_month(config.year, month, true, config.today);
-            println!("{}", lines.join("\n"));
+            writeln!(out, "{}", lines.join("\n"))?;
         }
         None => {
-            println!("{:>32}", config.year);
+            writeln!(out, "{:>32}", config.year)?;
             let months: Vec<_> = (1..=12)
                 .map(|month| format_month(config.year, month, false, config.today))
                 .collect();
@@ -94,10 +132,10 @@ pub fn run(config: Config) -> MyResult<()> {
             for (i, chunk) in months.chunks(3).enumerate() {
                 if let [m1, m2, m3] = chunk {
                     for lines in izip!(m1, m2, m3) {
-                        println!("{}{}{}", lines.0, lines.1, lines.2);
+                        writeln!(out, "{}{}{}", lines.0, lines.1, lines.2)?;
                     }
                     if i < 3 {
-                        println!();
+                        writeln!(out)?;
                     }
                 }
             }
@@ -106,27 +144,22 @@ pub fn run(config: Config) -> MyResult<()> {
     Ok(())
 }
 
-fn parse_int<T: FromStr>(val: &str) -> MyResult<T> {
-    val.parse()
-        .map_err(|_| format!("Invalid integer \"{}\"", val).into())
-}
-fn parse_year(year: &str) -> MyResult<i32> {
-    parse_int(year).and_then(|num| {
-        if (1..=9999).contains(&num) {
-            Ok(num)
-        } else {
-            Err(format!("year \"{}\" not in the range 1 through 9999", year).into())
-        }
-    })
+fn parse_year(year: &str) -> Result<i32, Error> {
+    let num = parse_int(year)?;
+    if (1..=9999).contains(&num) {
+        Ok(num)
+    } else {
+        Err(Error::YearRange(year.to_string()))
+    }
 }
 
-fn parse_month(month: &str) -> MyResult<u32> {
+fn parse_month(month: &str) -> Result<u32, Error> {
     match parse_int(month) {
         Ok(num) => {
             if (1..=12).contains(&num) {
                 Ok(num)
             } else {
-                Err(format!("month \"{}\" not in the range 1 through 12", month).into())
+                Err(Error::MonthRange(month.to_string()))
             }
         }
         _ => {
@@ -146,7 +179,7 @@ fn parse_month(month: &str) -> MyResult<u32> {
             if matches.len() == 1 {
                 Ok(matches[0] as u32)
             } else {
-                Err(format!("Invalid month \"{}\"", month).into())
+                Err(Error::InvalidMonth(month.to_string()))
             }
         }
     }
@@ -211,25 +244,7 @@ fn last_day_in_month(year: i32, month: u32) -> NaiveDate {
 mod tests {
     use chrono::NaiveDate;
 
-    use crate::{format_month, last_day_in_month, parse_int, parse_month, parse_year};
-
-    #[test]
-    fn test_parse_int() {
-        // 正の整数をusizeとして解析する
-        let res = parse_int::<usize>("1");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), 1usize);
-
-        // 負の数をi32として解析する
-        let res = parse_int::<i32>("-1");
-        assert!(res.is_ok());
-        assert_eq!(res.unwrap(), -1i32);
-
-        // 数字以外の文字列を解析すると失敗する
-        let res = parse_int::<i64>("foo");
-        assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "Invalid integer \"foo\"");
-    }
+    use crate::{format_month, last_day_in_month, parse_month, parse_year};
 
     #[test]
     fn test_parse_year() {
@@ -1,6 +1,9 @@
+use std::io;
+
 fn main() {
-    if let Err(e) = calr::get_args().and_then(calr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let result = calr::get_args()
+        .and_then(|config| calr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("calr", &e));
     }
 }
@@ -13,8 +13,8 @@ fn dies_year_0() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg("0")
         .assert()
-        .failure()
-        .stderr("year \"0\" not in the range 1 through 9999\n");
+        .code(2)
+        .stderr("calr: year \"0\" not in the range 1 through 9999\n");
     Ok(())
 }
 
@@ -25,7 +25,7 @@ fn dies_year_13() -> TestResult {
         .arg("10000")
         .assert()
         .failure()
-        .stderr("year \"10000\" not in the range 1 through 9999\n");
+        .stderr("calr: year \"10000\" not in the range 1 through 9999\n");
     Ok(())
 }
 
@@ -36,7 +36,7 @@ fn dies_invalid_year() -> TestResult {
         .arg("foo")
         .assert()
         .failure()
-        .stderr("Invalid integer \"foo\"\n");
+        .stderr("calr: Invalid integer \"foo\"\n");
     Ok(())
 }
 
@@ -44,10 +44,10 @@ fn dies_invalid_year() -> TestResult {
 #[test]
 fn dies_month_0() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "0"])
+        .args(["-m", "0"])
         .assert()
         .failure()
-        .stderr("month \"0\" not in the range 1 through 12\n");
+        .stderr("calr: month \"0\" not in the range 1 through 12\n");
     Ok(())
 }
 
@@ -55,10 +55,10 @@ fn dies_month_0() -> TestResult {
 #[test]
 fn dies_month_13() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "13"])
+        .args(["-m", "13"])
         .assert()
         .failure()
-        .stderr("month \"13\" not in the range 1 through 12\n");
+        .stderr("calr: month \"13\" not in the range 1 through 12\n");
     Ok(())
 }
 
@@ -66,10 +66,10 @@ fn dies_month_13() -> TestResult {
 #[test]
 fn dies_invalid_month() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["-m", "foo"])
+        .args(["-m", "foo"])
         .assert()
         .failure()
-        .stderr("Invalid month \"foo\"\n");
+        .stderr("calr: Invalid month \"foo\"\n");
     Ok(())
 }
 
@@ -78,7 +78,7 @@ fn dies_invalid_month() -> TestResult {
 fn dies_y_and_month() -> TestResult {
     let expected = "the argument '-m <MONTH>' cannot be used with '--year'";
     Command::cargo_bin(PRG)?
-        .args(&["-m", "1", "-y"])
+        .args(["-m", "1", "-y"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -90,7 +90,7 @@ fn dies_y_and_month() -> TestResult {
 fn dies_y_and_year() -> TestResult {
     let expected = "the argument '--year' cannot be used with '[YEAR]'";
     Command::cargo_bin(PRG)?
-        .args(&["-y", "2000"])
+        .args(["-y", "2000"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -117,7 +117,7 @@ fn month_num() -> TestResult {
 
     for (num, month) in expected {
         Command::cargo_bin(PRG)?
-            .args(&["-m", num])
+            .args(["-m", num])
             .assert()
             .success()
             .stdout(predicates::str::contains(month.to_string()));
@@ -144,7 +144,7 @@ fn partial_month() -> TestResult {
 
     for (arg, month) in expected {
         Command::cargo_bin(PRG)?
-            .args(&["-m", arg])
+            .args(["-m", arg])
             .assert()
             .success()
             .stdout(predicates::str::contains(month.to_string()));
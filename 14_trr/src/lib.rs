@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+use std::io::{BufRead, BufWriter, Write};
+
+use clap::ArgAction::SetTrue;
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use thiserror::Error;
+
+const CLASSES: [&str; 12] = [
+    "alnum", "alpha", "blank", "cntrl", "digit", "graph", "lower", "print", "punct", "space",
+    "upper", "xdigit",
+];
+
+#[derive(Debug)]
+pub struct Config {
+    set1: Vec<char>,
+    set2: Option<Vec<char>>,
+    complement: bool,
+    delete: bool,
+    squeeze: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(
+        "{}",
+        tr_str(
+            "extra operand: SET2 is not allowed with -d",
+            "余分なオペランドです: -d のときSET2は指定できません",
+        )
+    )]
+    ExtraOperand,
+    #[error(
+        "{}",
+        tr_str("missing operand after SET1", "SET1の後にオペランドがありません")
+    )]
+    MissingOperand,
+    #[error("{}", tr_str("SET2 must be non-empty", "SET2は空にできません"))]
+    EmptySet2,
+    #[error("{}", tr_text(
+        "range-endpoints of \"{0}-{1}\" are in reverse collating sequence order",
+        "範囲 \"{0}-{1}\" の端点が照合順序の逆になっています",
+        &[.0, .1],
+    ))]
+    ReverseRange(char, char),
+    #[error("{}", tr_text(
+        "invalid character class \"{0}\" (expected one of: {1})",
+        "文字クラス \"{0}\" が不正です (次のどれか: {1})",
+        &[.0, &CLASSES.join(", ")],
+    ))]
+    InvalidClass(String),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => EXIT_FAILURE,
+            _ => EXIT_USAGE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("trr")
+        .version("0.1.0")
+        .about("Rust tr")
+        .arg(
+            Arg::new("set1")
+                .value_name("SET1")
+                .help(tr_str(
+                    "Characters to translate, delete or squeeze",
+                    "変換・削除・圧縮する文字",
+                ))
+                .required(true),
+        )
+        .arg(
+            Arg::new("set2")
+                .value_name("SET2")
+                .help(tr_str("Replacement characters", "置き換える文字")),
+        )
+        .arg(
+            Arg::new("complement")
+                .short('c')
+                .short_alias('C')
+                .long("complement")
+                .help(tr_str("Use the complement of SET1", "SET1の補集合を使う"))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("delete")
+                .short('d')
+                .long("delete")
+                .help(tr_str("Delete characters in SET1", "SET1の文字を削除する"))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("squeeze")
+                .short('s')
+                .long("squeeze-repeats")
+                .help(tr_str(
+                    "Squeeze repeated characters",
+                    "繰り返す文字を1つにまとめる",
+                ))
+                .action(SetTrue),
+        )
+        .get_tool_matches();
+
+    let delete = matches.get_flag("delete");
+    let squeeze = matches.get_flag("squeeze");
+    let set1 = parse_set(matches.get_one::<String>("set1").unwrap())?;
+    let set2 = matches
+        .get_one::<String>("set2")
+        .map(|s| parse_set(s))
+        .transpose()?;
+
+    match &set2 {
+        Some(_) if delete && !squeeze => return Err(Error::ExtraOperand),
+        None if !delete && !squeeze => return Err(Error::MissingOperand),
+        None if delete && squeeze => return Err(Error::MissingOperand),
+        Some(set2) if !delete && set2.is_empty() && !set1.is_empty() => {
+            return Err(Error::EmptySet2)
+        }
+        _ => {}
+    }
+
+    Ok(Config {
+        set1,
+        set2,
+        complement: matches.get_flag("complement"),
+        delete,
+        squeeze,
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut out = BufWriter::new(out);
+    let mut buf = vec![];
+    let mut line = vec![];
+
+    // ASCIIだけの集合ならバイト単位で変換し、UTF-8でない入力もそのまま通す
+    let needs_chars = config
+        .set1
+        .iter()
+        .chain(config.set2.iter().flatten())
+        .any(|c| !c.is_ascii());
+    if needs_chars {
+        let mut tr = Translator::new(&config, |c| c);
+        let mut encoded = [0; 4];
+        while input.read_until(b'\n', &mut buf)? > 0 {
+            for chunk in buf.utf8_chunks() {
+                for c in chunk.valid().chars() {
+                    if let Some(c) = tr.translate(c) {
+                        line.extend_from_slice(c.encode_utf8(&mut encoded).as_bytes());
+                    }
+                }
+                // 文字にならないバイトは変換せずに書き出す
+                if !chunk.invalid().is_empty() {
+                    line.extend_from_slice(chunk.invalid());
+                    tr.last = None;
+                }
+            }
+            out.write_all(&line)?;
+            buf.clear();
+            line.clear();
+        }
+    } else {
+        let mut tr = Translator::new(&config, |c| c as u8);
+        while input.read_until(b'\n', &mut buf)? > 0 {
+            line.extend(buf.iter().filter_map(|&b| tr.translate(b)));
+            out.write_all(&line)?;
+            buf.clear();
+            line.clear();
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// The sets of a [`Config`] in the unit the input is translated in:
+/// bytes, or chars when a set holds non-ASCII characters.
+struct Translator<T> {
+    set1: Vec<T>,
+    set2: Option<Vec<T>>,
+    translation: Option<HashMap<T, T>>,
+    replacement: Option<T>,
+    complement: bool,
+    delete: bool,
+    squeeze: bool,
+    last: Option<T>,
+}
+
+impl<T: Copy + Eq + Hash> Translator<T> {
+    fn new(config: &Config, unit: impl Fn(char) -> T) -> Self {
+        let set1: Vec<T> = config.set1.iter().map(|&c| unit(c)).collect();
+        let set2: Option<Vec<T>> = config
+            .set2
+            .as_ref()
+            .map(|set2| set2.iter().map(|&c| unit(c)).collect());
+        let translation = match (&set2, config.delete) {
+            (Some(set2), false) => Some(translation_map(&set1, set2)),
+            _ => None,
+        };
+        let replacement = set2.as_ref().and_then(|set2| set2.last().copied());
+        Translator {
+            set1,
+            set2,
+            translation,
+            replacement,
+            complement: config.complement,
+            delete: config.delete,
+            squeeze: config.squeeze,
+            last: None,
+        }
+    }
+
+    fn in_set1(&self, c: T) -> bool {
+        self.set1.contains(&c) != self.complement
+    }
+
+    fn should_squeeze(&self, c: T) -> bool {
+        match &self.set2 {
+            Some(set2) => set2.contains(&c),
+            None => self.in_set1(c),
+        }
+    }
+
+    /// Returns what `c` becomes, or `None` when it is deleted or squeezed.
+    fn translate(&mut self, c: T) -> Option<T> {
+        if self.delete && self.in_set1(c) {
+            return None;
+        }
+        let c = match &self.translation {
+            Some(_) if self.complement => {
+                if self.in_set1(c) {
+                    self.replacement.unwrap_or(c)
+                } else {
+                    c
+                }
+            }
+            Some(map) => *map.get(&c).unwrap_or(&c),
+            None => c,
+        };
+        if self.squeeze && self.last == Some(c) && self.should_squeeze(c) {
+            return None;
+        }
+        self.last = Some(c);
+        Some(c)
+    }
+}
+
+fn translation_map<T: Copy + Eq + Hash>(set1: &[T], set2: &[T]) -> HashMap<T, T> {
+    set1.iter()
+        .enumerate()
+        .map(|(i, &c)| (c, set2[i.min(set2.len() - 1)]))
+        .collect()
+}
+
+fn parse_set(spec: &str) -> Result<Vec<char>, Error> {
+    let tokens = unescape(spec);
+    let literal = |i: usize, c: char| tokens.get(i) == Some(&(c, false));
+    let mut set = vec![];
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if literal(i, '[') && literal(i + 1, ':') {
+            if let Some(end) =
+                (i + 2..tokens.len()).find(|&j| literal(j, ':') && literal(j + 1, ']'))
+            {
+                let name: String = tokens[i + 2..end].iter().map(|(c, _)| c).collect();
+                set.extend(expand_class(&name)?);
+                i = end + 2;
+                continue;
+            }
+        }
+        if literal(i + 1, '-') && i + 2 < tokens.len() {
+            let (lo, hi) = (tokens[i].0, tokens[i + 2].0);
+            if lo > hi {
+                return Err(Error::ReverseRange(lo, hi));
+            }
+            set.extend(lo..=hi);
+            i += 3;
+            continue;
+        }
+        set.push(tokens[i].0);
+        i += 1;
+    }
+    Ok(set)
+}
+
+fn unescape(spec: &str) -> Vec<(char, bool)> {
+    let mut tokens = vec![];
+    let mut chars = spec.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            tokens.push((c, false));
+            continue;
+        }
+        let escaped = match chars.next() {
+            None => '\\',
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('a') => '\x07',
+            Some('b') => '\x08',
+            Some('f') => '\x0c',
+            Some('v') => '\x0b',
+            Some(d @ '0'..='7') => {
+                let mut code = d.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(n) if code * 8 + n <= 0o377 => {
+                            code = code * 8 + n;
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                char::from_u32(code).unwrap()
+            }
+            Some(other) => other,
+        };
+        tokens.push((escaped, true));
+    }
+    tokens
+}
+
+fn expand_class(name: &str) -> Result<Vec<char>, Error> {
+    let test: fn(&char) -> bool = match name {
+        "alnum" => char::is_ascii_alphanumeric,
+        "alpha" => char::is_ascii_alphabetic,
+        "blank" => |c| *c == ' ' || *c == '\t',
+        "cntrl" => char::is_ascii_control,
+        "digit" => char::is_ascii_digit,
+        "graph" => char::is_ascii_graphic,
+        "lower" => char::is_ascii_lowercase,
+        "print" => |c| c.is_ascii_graphic() || *c == ' ',
+        "punct" => char::is_ascii_punctuation,
+        "space" => |c| c.is_ascii_whitespace() || *c == '\x0b',
+        "upper" => char::is_ascii_uppercase,
+        "xdigit" => char::is_ascii_hexdigit,
+        _ => return Err(Error::InvalidClass(name.to_string())),
+    };
+    Ok((0..=127u8).map(char::from).filter(test).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_set, translation_map, unescape};
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("ab"), vec![('a', false), ('b', false)]);
+        assert_eq!(
+            unescape(r"\n\t\\"),
+            vec![('\n', true), ('\t', true), ('\\', true)]
+        );
+        assert_eq!(unescape(r"\101\0"), vec![('A', true), ('\0', true)]);
+        assert_eq!(unescape(r"\-"), vec![('-', true)]);
+    }
+
+    #[test]
+    fn test_parse_set() {
+        assert_eq!(parse_set("abc").unwrap(), vec!['a', 'b', 'c']);
+        assert_eq!(parse_set("a-e").unwrap(), vec!['a', 'b', 'c', 'd', 'e']);
+        assert_eq!(
+            parse_set("x-z0-2").unwrap(),
+            vec!['x', 'y', 'z', '0', '1', '2']
+        );
+
+        // 先頭や末尾の「-」は文字として扱う
+        assert_eq!(parse_set("-a").unwrap(), vec!['-', 'a']);
+        assert_eq!(parse_set("a-").unwrap(), vec!['a', '-']);
+
+        // エスケープされた「-」は範囲にならない
+        assert_eq!(parse_set(r"a\-c").unwrap(), vec!['a', '-', 'c']);
+
+        // 文字クラスを展開する
+        assert_eq!(parse_set("[:digit:]").unwrap().len(), 10);
+        assert_eq!(parse_set("[:upper:]").unwrap().first(), Some(&'A'));
+        assert_eq!(parse_set("[:lower:]").unwrap().last(), Some(&'z'));
+        assert_eq!(parse_set("[:blank:]").unwrap(), vec!['\t', ' ']);
+
+        // 逆順の範囲と不明なクラスはエラー
+        let res = parse_set("z-a");
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "range-endpoints of \"z-a\" are in reverse collating sequence order"
+        );
+        assert!(parse_set("[:foo:]").is_err());
+    }
+
+    #[test]
+    fn test_translation_map() {
+        let map = translation_map(&['a', 'b', 'c'], &['x', 'y']);
+        assert_eq!(map.get(&'a'), Some(&'x'));
+        assert_eq!(map.get(&'b'), Some(&'y'));
+        // SET2が短い場合は最後の文字で埋める
+        assert_eq!(map.get(&'c'), Some(&'y'));
+        assert_eq!(map.get(&'d'), None);
+    }
+}
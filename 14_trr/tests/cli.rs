@@ -0,0 +1,158 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "trr";
+const FOX: &str = "tests/inputs/fox.txt";
+const SPACES: &str = "tests/inputs/spaces.txt";
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_missing_set2() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("abc")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("missing operand after SET1"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_delete_with_set2() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", "abc", "xyz"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("extra operand"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_class() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["[:foo:]", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid character class \"foo\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run(args: &[&str], input_file: &str, expected: &str) -> TestResult {
+    let input = fs::read_to_string(input_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn translate_range() -> TestResult {
+    run(
+        &["a-z", "A-Z"],
+        FOX,
+        "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG.\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn translate_class() -> TestResult {
+    run(
+        &["[:lower:]", "[:upper:]"],
+        FOX,
+        "THE QUICK BROWN FOX JUMPS OVER THE LAZY DOG.\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn translate_short_set2() -> TestResult {
+    run(
+        &["aeiou", "*"],
+        FOX,
+        "Th* q**ck br*wn f*x j*mps *v*r th* l*zy d*g.\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn delete() -> TestResult {
+    run(&["-d", "aeiou"], FOX, "Th qck brwn fx jmps vr th lzy dg.\n")
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_complement() -> TestResult {
+    run(
+        &["-cd", "[:alpha:]\\n"],
+        FOX,
+        "Thequickbrownfoxjumpsoverthelazydog\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn squeeze() -> TestResult {
+    run(&["-s", " \\n"], SPACES, "aaa bbb\nccc\tddd\n")
+}
+
+// --------------------------------------------------
+#[test]
+fn squeeze_translate() -> TestResult {
+    run(&["-s", "[:blank:]", " "], SPACES, "aaa bbb\n\n\nccc ddd\n")
+}
+
+// --------------------------------------------------
+#[test]
+fn complement_translate() -> TestResult {
+    run(
+        &["-cs", "[:alpha:]\\n", "_"],
+        FOX,
+        "The_quick_brown_fox_jumps_over_the_lazy_dog_\n",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_squeeze() -> TestResult {
+    run(&["-ds", "b", "a"], SPACES, "a   \n\n\nccc\tddd\n")
+}
+
+// --------------------------------------------------
+#[test]
+fn invalid_utf8_passes_through() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["a", "x"])
+        .write_stdin(&b"a\xffb\n"[..])
+        .assert()
+        .success()
+        .stdout(&b"x\xffb\n"[..]);
+
+    // 集合に多バイト文字があっても、文字にならないバイトは残す
+    Command::cargo_bin(PRG)?
+        .args(["é", "e"])
+        .write_stdin(&b"\xc3\xa9\xff\n"[..])
+        .assert()
+        .success()
+        .stdout(&b"e\xff\n"[..]);
+    Ok(())
+}
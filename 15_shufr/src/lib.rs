@@ -0,0 +1,278 @@
+use std::io::{self, BufRead, BufWriter, Write};
+
+use clap::ArgAction::{Append, SetTrue};
+use clap::{value_parser, Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{open, CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use rand::seq::{index, SliceRandom};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use thiserror::Error;
+
+use crate::Input::{Echo, Range, Reader};
+
+#[derive(Debug, PartialEq)]
+enum Input {
+    Reader(String),
+    Echo(Vec<String>),
+    Range(u64, u64),
+}
+
+#[derive(Debug)]
+pub struct Config {
+    input: Input,
+    head_count: Option<usize>,
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_text("extra operand \"{0}\"", "余分なオペランドです \"{0}\"", &[.0]))]
+    ExtraOperand(String),
+    #[error("{}", tr_text("invalid input range: \"{0}\"", "入力の範囲が不正です: \"{0}\"", &[.0]))]
+    InvalidRange(String),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::ExtraOperand(_) | Error::InvalidRange(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("shufr")
+        .version("0.1.0")
+        .about("Rust shuf")
+        .arg(
+            Arg::new("args")
+                .value_name("FILE|ARG")
+                .help(tr_str(
+                    "Input file, or lines to shuffle with -e",
+                    "入力ファイル(-e のときはシャッフルする行)",
+                ))
+                .action(Append),
+        )
+        .arg(
+            Arg::new("echo")
+                .short('e')
+                .long("echo")
+                .help(tr_str(
+                    "Treat each ARG as an input line",
+                    "各ARGを入力の行として扱う",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("input_range")
+                .value_name("LO-HI")
+                .short('i')
+                .long("input-range")
+                .help(tr_str(
+                    "Treat each number LO through HI as an input line",
+                    "LOからHIまでの各数を入力の行として扱う",
+                ))
+                .conflicts_with_all(["echo", "args"]),
+        )
+        .arg(
+            Arg::new("head_count")
+                .value_name("COUNT")
+                .short('n')
+                .long("head-count")
+                .help(tr_str(
+                    "Output at most COUNT lines",
+                    "最大COUNT行を出力する",
+                ))
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("seed")
+                .value_name("SEED")
+                .long("seed")
+                .help(tr_str("Random seed", "乱数のシード"))
+                .value_parser(value_parser!(u64)),
+        )
+        .get_tool_matches();
+
+    let args: Vec<String> = matches
+        .get_many::<String>("args")
+        .map(|vals| vals.cloned().collect())
+        .unwrap_or_default();
+
+    let input = if let Some(range) = matches.get_one::<String>("input_range") {
+        let (lo, hi) = parse_range(range)?;
+        Range(lo, hi)
+    } else if matches.get_flag("echo") {
+        Echo(args)
+    } else {
+        match args.as_slice() {
+            [] => Reader("-".to_string()),
+            [file] => Reader(file.to_string()),
+            [_, extra, ..] => return Err(Error::ExtraOperand(extra.to_string())),
+        }
+    };
+
+    Ok(Config {
+        input,
+        head_count: matches.get_one::<usize>("head_count").copied(),
+        seed: matches.get_one::<u64>("seed").copied(),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let mut rng: Box<dyn RngCore> = match config.seed {
+        Some(val) => Box::new(StdRng::seed_from_u64(val)),
+        None => Box::new(rand::thread_rng()),
+    };
+    let mut out = BufWriter::new(out);
+
+    match config.input {
+        Reader(filename) => {
+            let file = open(&filename).map_err(|source| Error::Open {
+                path: filename.clone(),
+                source,
+            })?;
+            let lines = match config.head_count {
+                Some(count) => sample_lines(file, count, &mut rng)?,
+                None => {
+                    let mut lines = read_lines(file)?;
+                    lines.shuffle(&mut rng);
+                    lines
+                }
+            };
+            for line in lines {
+                out.write_all(&line)?;
+            }
+        }
+        Echo(mut lines) => {
+            lines.shuffle(&mut rng);
+            for line in lines.iter().take(config.head_count.unwrap_or(usize::MAX)) {
+                writeln!(out, "{}", line)?;
+            }
+        }
+        Range(lo, hi) => {
+            let len = (hi + 1 - lo) as usize;
+            let count = config.head_count.unwrap_or(len).min(len);
+            for i in index::sample(&mut rng, len, count) {
+                writeln!(out, "{}", lo + i as u64)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn parse_range(range: &str) -> Result<(u64, u64), Error> {
+    let error = || Error::InvalidRange(range.to_string());
+    let (lo, hi) = range.split_once('-').ok_or_else(error)?;
+    let lo: u64 = lo.parse().map_err(|_| error())?;
+    let hi: u64 = hi.parse().map_err(|_| error())?;
+    if hi == u64::MAX || lo > hi + 1 {
+        return Err(error());
+    }
+    Ok((lo, hi))
+}
+
+fn read_line(file: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut line = vec![];
+    if file.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with(b"\n") {
+        line.push(b'\n');
+    }
+    Ok(Some(line))
+}
+
+fn read_lines(mut file: impl BufRead) -> io::Result<Vec<Vec<u8>>> {
+    let mut lines = vec![];
+    while let Some(line) = read_line(&mut file)? {
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+fn sample_lines(
+    mut file: impl BufRead,
+    count: usize,
+    rng: &mut impl Rng,
+) -> io::Result<Vec<Vec<u8>>> {
+    let mut reservoir = Vec::with_capacity(count.min(1024));
+    let mut seen = 0;
+    while let Some(line) = read_line(&mut file)? {
+        if reservoir.len() < count {
+            reservoir.push(line);
+        } else {
+            let i = rng.gen_range(0..=seen);
+            if i < count {
+                reservoir[i] = line;
+            }
+        }
+        seen += 1;
+    }
+    reservoir.shuffle(rng);
+    Ok(reservoir)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{parse_range, read_lines, sample_lines};
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("1-10").unwrap(), (1, 10));
+        assert_eq!(parse_range("5-5").unwrap(), (5, 5));
+
+        // 空の範囲も受け入れる
+        assert_eq!(parse_range("5-4").unwrap(), (5, 4));
+
+        let res = parse_range("10-1");
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "invalid input range: \"10-1\""
+        );
+
+        assert!(parse_range("1").is_err());
+        assert!(parse_range("a-b").is_err());
+        assert!(parse_range("-1-5").is_err());
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let lines = read_lines(Cursor::new("a\nb\r\nc")).unwrap();
+        assert_eq!(
+            lines,
+            vec![b"a\n".to_vec(), b"b\r\n".to_vec(), b"c\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_sample_lines() {
+        let text: String = (1..=100).map(|n| format!("{}\n", n)).collect();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        // 要求した数だけ重複なしで取り出せる
+        let mut lines = sample_lines(Cursor::new(&text), 5, &mut rng).unwrap();
+        assert_eq!(lines.len(), 5);
+        lines.sort();
+        lines.dedup();
+        assert_eq!(lines.len(), 5);
+
+        // 入力より多い数を指定すると入力全体を返す
+        let lines = sample_lines(Cursor::new("a\nb\n"), 5, &mut rng).unwrap();
+        assert_eq!(lines.len(), 2);
+
+        let lines = sample_lines(Cursor::new(&text), 0, &mut rng).unwrap();
+        assert!(lines.is_empty());
+    }
+}
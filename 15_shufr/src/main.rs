@@ -0,0 +1,9 @@
+use std::io;
+
+fn main() {
+    let result = shufr::get_args()
+        .and_then(|config| shufr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("shufr", &e));
+    }
+}
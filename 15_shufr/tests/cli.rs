@@ -0,0 +1,141 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "shufr";
+const TEN: &str = "tests/inputs/ten.txt";
+const EMPTY: &str = "tests/inputs/empty.txt";
+
+// --------------------------------------------------
+fn output_lines(args: &[&str]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let output = Command::cargo_bin(PRG)?.args(args).output()?;
+    assert!(output.status.success());
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|l| l.to_string())
+        .collect())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_range() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-i", "10-1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid input range: \"10-1\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_extra_operand() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([TEN, EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("extra operand"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("does-not-exist")
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(
+            "does-not-exist: .* [(]os error 2[)]",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn shuffles_file() -> TestResult {
+    let mut lines = output_lines(&[TEN])?;
+    lines.sort();
+    let mut expected: Vec<String> = fs::read_to_string(TEN)?
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    expected.sort();
+    assert_eq!(lines, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn shuffles_stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .write_stdin("only\n")
+        .assert()
+        .success()
+        .stdout("only\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([EMPTY, "-n", "3"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn head_count() -> TestResult {
+    let lines = output_lines(&[TEN, "-n", "3"])?;
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|l| l.starts_with("line ")));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn seed_is_reproducible() -> TestResult {
+    let first = output_lines(&[TEN, "--seed", "42"])?;
+    let second = output_lines(&[TEN, "--seed", "42"])?;
+    assert_eq!(first, second);
+
+    let first = output_lines(&[TEN, "-n", "4", "--seed", "7"])?;
+    let second = output_lines(&[TEN, "-n", "4", "--seed", "7"])?;
+    assert_eq!(first, second);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn echo_mode() -> TestResult {
+    let mut lines = output_lines(&["-e", "a", "b", "c"])?;
+    lines.sort();
+    assert_eq!(lines, vec!["a", "b", "c"]);
+
+    let lines = output_lines(&["-e", "-n", "1", "a", "b", "c"])?;
+    assert_eq!(lines.len(), 1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn input_range() -> TestResult {
+    let mut lines = output_lines(&["-i", "1-10"])?;
+    lines.sort_by_key(|l| l.parse::<u64>().unwrap());
+    let expected: Vec<String> = (1..=10).map(|n| n.to_string()).collect();
+    assert_eq!(lines, expected);
+
+    let lines = output_lines(&["-i", "5-1000000", "-n", "3"])?;
+    assert_eq!(lines.len(), 3);
+    assert!(lines
+        .iter()
+        .map(|l| l.parse::<u64>().unwrap())
+        .all(|n| (5..=1000000).contains(&n)));
+    Ok(())
+}
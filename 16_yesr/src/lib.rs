@@ -0,0 +1,98 @@
+use std::io::{self, Write};
+
+use clap::ArgAction::Append;
+use clap::{value_parser, Arg, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{CommandExt, ToolError};
+use thiserror::Error;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub struct Config {
+    text: String,
+    count: Option<u64>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("yesr")
+        .version("0.1.0")
+        .about("Rust yes")
+        .arg(
+            Arg::new("text")
+                .value_name("STRING")
+                .help(tr_str("Text to repeat", "繰り返すテキスト"))
+                .action(Append)
+                .default_value("y"),
+        )
+        .arg(
+            Arg::new("count")
+                .value_name("COUNT")
+                .short('n')
+                .long("count")
+                .help(tr_str("Stop after COUNT lines", "COUNT行で止める"))
+                .value_parser(value_parser!(u64)),
+        )
+        .get_tool_matches();
+
+    let text = matches
+        .get_many::<String>("text")
+        .unwrap()
+        .map(|v| v.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(Config {
+        text,
+        count: matches.get_one::<u64>("count").copied(),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let line = format!("{}\n", config.text);
+    let buffer = fill_buffer(line.as_bytes(), BUFFER_SIZE);
+    let lines_per_buffer = (buffer.len() / line.len()) as u64;
+
+    match config.count {
+        None => loop {
+            out.write_all(&buffer)?;
+        },
+        Some(count) => {
+            for _ in 0..count / lines_per_buffer {
+                out.write_all(&buffer)?;
+            }
+            let rest = (count % lines_per_buffer) as usize * line.len();
+            out.write_all(&buffer[..rest])?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn fill_buffer(line: &[u8], size: usize) -> Vec<u8> {
+    line.repeat((size / line.len()).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fill_buffer;
+
+    #[test]
+    fn test_fill_buffer() {
+        // 行の途中で切れないように行単位で埋める
+        let buffer = fill_buffer(b"y\n", 7);
+        assert_eq!(buffer, b"y\ny\ny\n");
+
+        // バッファより長い行でも1行は入る
+        let buffer = fill_buffer(b"hello\n", 4);
+        assert_eq!(buffer, b"hello\n");
+    }
+}
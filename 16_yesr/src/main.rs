@@ -0,0 +1,9 @@
+use std::io;
+
+fn main() {
+    let result = yesr::get_args()
+        .and_then(|config| yesr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("yesr", &e));
+    }
+}
@@ -0,0 +1,74 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::io::Read;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Stdio;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "yesr";
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_count() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--count", "foo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'foo'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run(args: &[&str], expected: &str) -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn count() -> TestResult {
+    run(&["-n", "3"], "y\ny\ny\n")
+}
+
+// --------------------------------------------------
+#[test]
+fn count_zero() -> TestResult {
+    run(&["--count", "0"], "")
+}
+
+// --------------------------------------------------
+#[test]
+fn text() -> TestResult {
+    run(&["hello", "world", "-n", "2"], "hello world\nhello world\n")
+}
+
+// --------------------------------------------------
+#[test]
+fn count_spans_buffers() -> TestResult {
+    let expected = "yes\n".repeat(100_003);
+    run(&["yes", "-n", "100003"], &expected)
+}
+
+// --------------------------------------------------
+#[test]
+fn runs_until_reader_closes() -> TestResult {
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut buf = [0; 1024];
+    child.stdout.as_mut().unwrap().read_exact(&mut buf)?;
+    assert!(buf.chunks(2).all(|c| c == b"y\n"));
+
+    // 読み手が閉じたらSIGPIPEで黙って終わる
+    drop(child.stdout.take());
+    let output = child.wait_with_output()?;
+    assert_eq!(output.status.signal(), Some(13));
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use std::io::{self, BufRead, BufWriter, Write};
+
+use clap::ArgAction::{Append, SetTrue};
+use clap::{value_parser, Arg, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{open, warn_to, CommandExt, ToolError, EXIT_FAILURE};
+use thiserror::Error;
+use unicode_width::UnicodeWidthChar;
+
+use crate::Unit::{Bytes, Chars, Columns};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Unit {
+    Chars,
+    Bytes,
+    Columns,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    files: Vec<String>,
+    width: usize,
+    unit: Unit,
+    spaces: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("foldr")
+        .version("0.1.0")
+        .about("Rust fold")
+        .arg(
+            Arg::new("files")
+                .value_name("FILE")
+                .help(tr_str("Input file(s)", "入力ファイル"))
+                .action(Append)
+                .default_value("-"),
+        )
+        .arg(
+            Arg::new("width")
+                .value_name("WIDTH")
+                .short('w')
+                .long("width")
+                .help(tr_str(
+                    "Use WIDTH columns instead of 80",
+                    "80桁の代わりにWIDTH桁を使う",
+                ))
+                .default_value("80")
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("bytes")
+                .short('b')
+                .long("bytes")
+                .help(tr_str(
+                    "Count bytes rather than columns",
+                    "桁数ではなくバイト数で数える",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("display_width")
+                .short('D')
+                .long("display-width")
+                .help(tr_str(
+                    "Count wide (CJK) characters as two columns",
+                    "全角(CJK)文字を2桁として数える",
+                ))
+                .conflicts_with("bytes")
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("spaces")
+                .short('s')
+                .long("spaces")
+                .help(tr_str("Break at spaces", "空白で折り返す"))
+                .action(SetTrue),
+        )
+        .get_tool_matches();
+
+    let files = matches
+        .get_many::<String>("files")
+        .expect("files required")
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>();
+
+    let unit = if matches.get_flag("bytes") {
+        Bytes
+    } else if matches.get_flag("display_width") {
+        Columns
+    } else {
+        Chars
+    };
+
+    Ok(Config {
+        files,
+        width: *matches.get_one::<u64>("width").expect("illegal state") as usize,
+        unit,
+        spaces: matches.get_flag("spaces"),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    let mut out = BufWriter::new(out);
+
+    for filename in &config.files {
+        match open(filename) {
+            Err(e) => {
+                warn_to(err, "foldr", &format_args!("{}: {}", filename, tr_io(&e)));
+                status = EXIT_FAILURE;
+            }
+            Ok(mut file) => {
+                let mut line = vec![];
+                loop {
+                    line.clear();
+                    if file.read_until(b'\n', &mut line)? == 0 {
+                        break;
+                    }
+                    let has_newline = line.ends_with(b"\n");
+                    if has_newline {
+                        line.pop();
+                    }
+                    let folded = match config.unit {
+                        Bytes => fold_bytes(&line, config.width, config.spaces),
+                        unit => fold_chars(&line, config.width, config.spaces, unit),
+                    };
+                    out.write_all(&folded)?;
+                    if has_newline {
+                        out.write_all(b"\n")?;
+                    }
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(status)
+}
+
+fn fold_bytes(line: &[u8], width: usize, spaces: bool) -> Vec<u8> {
+    fold(line, width, spaces, |b| b == b' ', |col, _| col + 1).join(&b'\n')
+}
+
+fn fold_chars(line: &[u8], width: usize, spaces: bool, unit: Unit) -> Vec<u8> {
+    let chars: Vec<char> = String::from_utf8_lossy(line).chars().collect();
+    fold(
+        &chars,
+        width,
+        spaces,
+        |c| c == ' ',
+        |col, c| advance(col, c, unit),
+    )
+    .join(&'\n')
+    .into_iter()
+    .collect::<String>()
+    .into_bytes()
+}
+
+fn advance(col: usize, c: char, unit: Unit) -> usize {
+    match c {
+        '\t' => col + 8 - col % 8,
+        '\x08' => col.saturating_sub(1),
+        '\r' => 0,
+        _ if unit == Columns => col + c.width().unwrap_or(0),
+        _ => col + 1,
+    }
+}
+
+fn fold<T: Copy>(
+    units: &[T],
+    width: usize,
+    spaces: bool,
+    is_blank: impl Fn(T) -> bool,
+    advance: impl Fn(usize, T) -> usize,
+) -> Vec<Vec<T>> {
+    let column = |segment: &[T]| segment.iter().fold(0, |col, &u| advance(col, u));
+    let mut segments = vec![];
+    let mut current: Vec<T> = vec![];
+    let mut col = 0;
+
+    for &unit in units {
+        while advance(col, unit) > width && !current.is_empty() {
+            let split = if spaces {
+                current.iter().rposition(|&u| is_blank(u)).map(|i| i + 1)
+            } else {
+                None
+            };
+            match split {
+                Some(i) => {
+                    let rest = current.split_off(i);
+                    segments.push(current);
+                    current = rest;
+                }
+                None => segments.push(std::mem::take(&mut current)),
+            }
+            col = column(&current);
+        }
+        current.push(unit);
+        col = advance(col, unit);
+    }
+    segments.push(current);
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{advance, fold, Unit};
+
+    fn fold_str(text: &str, width: usize, spaces: bool) -> Vec<String> {
+        let chars: Vec<char> = text.chars().collect();
+        fold(
+            &chars,
+            width,
+            spaces,
+            |c| c == ' ',
+            |col, c| advance(col, c, Unit::Chars),
+        )
+        .into_iter()
+        .map(|segment| segment.into_iter().collect())
+        .collect()
+    }
+
+    #[test]
+    fn test_advance() {
+        assert_eq!(advance(0, 'a', Unit::Chars), 1);
+        assert_eq!(advance(3, '\t', Unit::Chars), 8);
+        assert_eq!(advance(8, '\t', Unit::Chars), 16);
+        assert_eq!(advance(0, '\x08', Unit::Chars), 0);
+        assert_eq!(advance(5, '\r', Unit::Chars), 0);
+
+        // 全角文字は表示幅モードでは2桁になる
+        assert_eq!(advance(0, '日', Unit::Chars), 1);
+        assert_eq!(advance(0, '日', Unit::Columns), 2);
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold_str("", 5, false), vec![""]);
+        assert_eq!(fold_str("abcde", 5, false), vec!["abcde"]);
+        assert_eq!(fold_str("abcdefg", 5, false), vec!["abcde", "fg"]);
+
+        // 空白の直後で折り返す
+        assert_eq!(fold_str("ab cdefg", 5, true), vec!["ab ", "cdefg"]);
+
+        // 空白がない場合は幅で折り返す
+        assert_eq!(fold_str("abcdefgh", 5, true), vec!["abcde", "fgh"]);
+    }
+}
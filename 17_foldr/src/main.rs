@@ -0,0 +1,10 @@
+use std::io;
+
+fn main() {
+    let result = foldr::get_args()
+        .and_then(|config| foldr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("foldr", &e)),
+    }
+}
@@ -0,0 +1,134 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "foldr";
+const FOX: &str = "tests/inputs/fox.txt";
+const LOREM: &str = "tests/inputs/lorem.txt";
+const CJK: &str = "tests/inputs/cjk.txt";
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_width() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-w", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '0'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["does-not-exist", FOX])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_match(
+            "does-not-exist: .* [(]os error 2[)]",
+        )?)
+        .stdout("The quick brown fox jumps over the lazy dog.\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run_stdin(args: &[&str], input_file: &str, expected_file: &str) -> TestResult {
+    let input = fs::read_to_string(input_file)?;
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fox() -> TestResult {
+    run(&[FOX], "tests/expected/fox.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_w20() -> TestResult {
+    run(&["-w", "20", FOX], "tests/expected/fox.txt.w20.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_s_w20() -> TestResult {
+    run(&["-s", "-w", "20", FOX], "tests/expected/fox.txt.s.w20.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn lorem() -> TestResult {
+    run(&[LOREM], "tests/expected/lorem.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn lorem_w20() -> TestResult {
+    run(
+        &["--width", "20", LOREM],
+        "tests/expected/lorem.txt.w20.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn lorem_s_w20() -> TestResult {
+    run(
+        &["--spaces", "-w", "20", LOREM],
+        "tests/expected/lorem.txt.s.w20.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn lorem_b_w20() -> TestResult {
+    run(
+        &["-b", "-w", "20", LOREM],
+        "tests/expected/lorem.txt.b.w20.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn lorem_stdin() -> TestResult {
+    run_stdin(&["-w", "20"], LOREM, "tests/expected/lorem.txt.w20.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn cjk_w20() -> TestResult {
+    run(&["-w", "20", CJK], "tests/expected/cjk.txt.w20.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn cjk_b_w20() -> TestResult {
+    run(&["-b", "-w", "20", CJK], "tests/expected/cjk.txt.b.w20.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn cjk_display_w20() -> TestResult {
+    run(&["-D", "-w", "20", CJK], "tests/expected/cjk.txt.d.w20.out")
+}
@@ -0,0 +1,259 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use clap::ArgAction::{Append, SetTrue};
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{warn_to, CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use thiserror::Error;
+
+use crate::SizeOp::{AtLeast, AtMost, Extend, Reduce, RoundDown, RoundUp, Set};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum SizeOp {
+    Set(u64),
+    Extend(u64),
+    Reduce(u64),
+    AtMost(u64),
+    AtLeast(u64),
+    RoundDown(u64),
+    RoundUp(u64),
+}
+
+#[derive(Debug)]
+pub struct Config {
+    files: Vec<String>,
+    size: Option<SizeOp>,
+    reference: Option<String>,
+    no_create: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_text("invalid --size: {0}", "--size が不正です: {0}", &[.0]))]
+    InvalidSize(String),
+    #[error(
+        "{}",
+        tr_str(
+            "you must specify either --size or --reference",
+            "--size か --reference のどちらかを指定してください",
+        )
+    )]
+    MissingSize,
+    #[error(
+        "{}",
+        tr_str(
+            "you must specify a relative --size with --reference",
+            "--reference には相対的な --size を指定してください",
+        )
+    )]
+    AbsoluteWithReference,
+    #[error("{}", tr_text(
+        "cannot stat {0}: {1}",
+        "{0} の情報を取得できません: {1}",
+        &[.path, &tr_io(.source)],
+    ))]
+    Stat { path: String, source: io::Error },
+    #[error("{}", tr_str("size overflow", "サイズがあふれました"))]
+    Overflow,
+    #[error("{}", tr_str("division by zero", "0で割りました"))]
+    DivisionByZero,
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidSize(_) | Error::MissingSize | Error::AbsoluteWithReference => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("truncater")
+        .version("0.1.0")
+        .about("Rust truncate")
+        .arg(
+            Arg::new("files")
+                .value_name("FILE")
+                .help(tr_str("File(s) to resize", "サイズを変えるファイル"))
+                .required(true)
+                .action(Append),
+        )
+        .arg(
+            Arg::new("size")
+                .value_name("SIZE")
+                .short('s')
+                .long("size")
+                .help(tr_str(
+                    "Set or adjust the file size by SIZE bytes",
+                    "ファイルサイズをSIZEバイトに設定または調整する",
+                ))
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("reference")
+                .value_name("RFILE")
+                .short('r')
+                .long("reference")
+                .help(tr_str("Base size on RFILE", "RFILEのサイズを基準にする")),
+        )
+        .arg(
+            Arg::new("no_create")
+                .short('c')
+                .long("no-create")
+                .help(tr_str("Do not create any files", "ファイルを作成しない"))
+                .action(SetTrue),
+        )
+        .get_tool_matches();
+
+    let size = matches
+        .get_one::<String>("size")
+        .map(|s| parse_size(s))
+        .transpose()
+        .map_err(Error::InvalidSize)?;
+    let reference = matches.get_one::<String>("reference").cloned();
+
+    match (&size, &reference) {
+        (None, None) => return Err(Error::MissingSize),
+        (Some(Set(_)), Some(_)) => return Err(Error::AbsoluteWithReference),
+        _ => {}
+    }
+
+    Ok(Config {
+        files: matches
+            .get_many::<String>("files")
+            .expect("files required")
+            .map(|v| v.to_string())
+            .collect(),
+        size,
+        reference,
+        no_create: matches.get_flag("no_create"),
+    })
+}
+
+pub fn run(config: Config, _out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    let reference_size = config
+        .reference
+        .as_ref()
+        .map(|path| {
+            fs::metadata(path)
+                .map(|m| m.len())
+                .map_err(|source| Error::Stat {
+                    path: path.to_string(),
+                    source,
+                })
+        })
+        .transpose()?;
+
+    for filename in &config.files {
+        if let Err(e) = resize(filename, &config, reference_size) {
+            warn_to(err, "truncater", &format_args!("{}: {}", filename, e));
+            status = EXIT_FAILURE;
+        }
+    }
+    Ok(status)
+}
+
+fn resize(filename: &str, config: &Config, reference_size: Option<u64>) -> Result<(), Error> {
+    if config.no_create && !Path::new(filename).exists() {
+        return Ok(());
+    }
+    let file = OpenOptions::new()
+        .write(true)
+        .create(!config.no_create)
+        .truncate(false)
+        .open(filename)?;
+    let current = reference_size.unwrap_or(file.metadata()?.len());
+    let new_size = match config.size {
+        Some(op) => apply(op, current)?,
+        None => current,
+    };
+    file.set_len(new_size)?;
+    Ok(())
+}
+
+fn apply(op: SizeOp, current: u64) -> Result<u64, Error> {
+    let overflow = || Error::Overflow;
+    match op {
+        Set(n) => Ok(n),
+        Extend(n) => current.checked_add(n).ok_or_else(overflow),
+        Reduce(n) => Ok(current.saturating_sub(n)),
+        AtMost(n) => Ok(current.min(n)),
+        AtLeast(n) => Ok(current.max(n)),
+        RoundDown(0) | RoundUp(0) => Err(Error::DivisionByZero),
+        RoundDown(n) => Ok(current / n * n),
+        RoundUp(n) => current.div_ceil(n).checked_mul(n).ok_or_else(overflow),
+    }
+}
+
+fn parse_size(val: &str) -> Result<SizeOp, String> {
+    let (op, rest): (fn(u64) -> SizeOp, &str) = match val.chars().next() {
+        Some('+') => (Extend, &val[1..]),
+        Some('-') => (Reduce, &val[1..]),
+        Some('<') => (AtMost, &val[1..]),
+        Some('>') => (AtLeast, &val[1..]),
+        Some('/') => (RoundDown, &val[1..]),
+        Some('%') => (RoundUp, &val[1..]),
+        _ => (Set, val),
+    };
+    clir_common::parse_size(rest)
+        .map(op)
+        .map_err(|_| format!("\"{}\"", val))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SizeOp::*;
+    use super::{apply, parse_size};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("0"), Ok(Set(0)));
+        assert_eq!(parse_size("100"), Ok(Set(100)));
+        assert_eq!(parse_size("+10"), Ok(Extend(10)));
+        assert_eq!(parse_size("-10"), Ok(Reduce(10)));
+        assert_eq!(parse_size("<10"), Ok(AtMost(10)));
+        assert_eq!(parse_size(">10"), Ok(AtLeast(10)));
+        assert_eq!(parse_size("/4"), Ok(RoundDown(4)));
+        assert_eq!(parse_size("%4"), Ok(RoundUp(4)));
+
+        // 接尾辞は1024倍、「B」付きは1000倍
+        assert_eq!(parse_size("1K"), Ok(Set(1024)));
+        assert_eq!(parse_size("1k"), Ok(Set(1024)));
+        assert_eq!(parse_size("1KiB"), Ok(Set(1024)));
+        assert_eq!(parse_size("1KB"), Ok(Set(1000)));
+        assert_eq!(parse_size("+2M"), Ok(Extend(2 * 1024 * 1024)));
+        assert_eq!(parse_size("1GB"), Ok(Set(1_000_000_000)));
+
+        assert_eq!(parse_size(""), Err("\"\"".to_string()));
+        assert_eq!(parse_size("foo"), Err("\"foo\"".to_string()));
+        assert!(parse_size("1X").is_err());
+        assert!(parse_size("1KX").is_err());
+        assert!(parse_size("+").is_err());
+        assert!(parse_size("20E").is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(apply(Set(5), 10).unwrap(), 5);
+        assert_eq!(apply(Extend(5), 10).unwrap(), 15);
+        assert_eq!(apply(Reduce(5), 10).unwrap(), 5);
+        assert_eq!(apply(Reduce(50), 10).unwrap(), 0);
+        assert_eq!(apply(AtMost(5), 10).unwrap(), 5);
+        assert_eq!(apply(AtMost(50), 10).unwrap(), 10);
+        assert_eq!(apply(AtLeast(5), 10).unwrap(), 10);
+        assert_eq!(apply(AtLeast(50), 10).unwrap(), 50);
+        assert_eq!(apply(RoundDown(4), 10).unwrap(), 8);
+        assert_eq!(apply(RoundUp(4), 10).unwrap(), 12);
+        assert_eq!(apply(RoundUp(4), 8).unwrap(), 8);
+
+        assert!(apply(RoundUp(0), 10).is_err());
+        assert!(apply(Extend(u64::MAX), 10).is_err());
+    }
+}
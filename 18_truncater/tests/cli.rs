@@ -0,0 +1,148 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, TestResult};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+const PRG: &str = "truncater";
+
+// --------------------------------------------------
+fn size_of(path: &str) -> u64 {
+    fs::metadata(path).unwrap().len()
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_no_size() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("foo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "you must specify either --size or --reference",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_size() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-s", "10X", "foo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid --size: \"10X\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_absolute_size_with_reference() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-s", "10", "-r", "Cargo.toml", "foo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("relative --size"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn creates_file() -> TestResult {
+    let fixture = Fixture::new()?;
+    let path = fixture.path("new.txt");
+    Command::cargo_bin(PRG)?
+        .args(["-s", "1K", &path])
+        .assert()
+        .success();
+    assert_eq!(size_of(&path), 1024);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_create() -> TestResult {
+    let fixture = Fixture::new()?;
+    let path = fixture.path("new.txt");
+    Command::cargo_bin(PRG)?
+        .args(["-c", "-s", "10", &path])
+        .assert()
+        .success()
+        .stderr("");
+    assert!(!Path::new(&path).exists());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn absolute_and_relative_sizes() -> TestResult {
+    let fixture = Fixture::new()?;
+    let path = fixture.file("file.txt", [b'x'; 100])?;
+
+    for (size, expected) in [
+        ("50", 50),
+        ("+25", 75),
+        ("-5", 70),
+        ("<60", 60),
+        (">64", 64),
+        ("%10", 70),
+        ("/16", 64),
+        ("2KB", 2000),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(["--size", size, &path])
+            .assert()
+            .success();
+        assert_eq!(size_of(&path), expected, "--size {}", size);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reference_file() -> TestResult {
+    let fixture = Fixture::new()?;
+    let reference = fixture.file("ref.txt", [b'x'; 42])?;
+    let path1 = fixture.file("one.txt", [b'x'; 5])?;
+    let path2 = fixture.file("two.txt", [b'x'; 500])?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-r", &reference, &path1, &path2])
+        .assert()
+        .success();
+    assert_eq!(size_of(&path1), 42);
+    assert_eq!(size_of(&path2), 42);
+
+    Command::cargo_bin(PRG)?
+        .args(["-r", &reference, "-s", "+8", &path1])
+        .assert()
+        .success();
+    assert_eq!(size_of(&path1), 50);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_reference() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "does-not-exist", "foo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot stat does-not-exist"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file() -> TestResult {
+    let fixture = Fixture::new()?;
+    let path = fixture.file("a.txt", [b'x'; 10])?;
+    let bad = fixture.path("missing/b.txt");
+    Command::cargo_bin(PRG)?
+        .args(["-s", "5", &bad, &path])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("missing/b.txt: "));
+    assert_eq!(size_of(&path), 5);
+    Ok(())
+}
@@ -0,0 +1,411 @@
+use std::fs;
+use std::fs::Metadata;
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::str::Chars;
+use std::time::UNIX_EPOCH;
+
+use chrono::{DateTime, Local};
+use clap::ArgAction::{Append, SetTrue};
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::meta::{group_name, mode_string, user_name};
+use clir_common::{warn_to, CommandExt, ToolError, EXIT_FAILURE};
+use thiserror::Error;
+
+const DEFAULT_FORMAT: &str = "  File: %N\n  Size: %s\tBlocks: %b\tIO Block: %o\t%F\n\
+Device: %Dh/%dd\tInode: %i\tLinks: %h\n\
+Access: (%04a/%A)\tUid: (%u/%U)\tGid: (%g/%G)\n\
+Access: %x\nModify: %y\nChange: %z\n";
+
+#[derive(Debug)]
+pub struct Config {
+    files: Vec<String>,
+    format: String,
+    dereference: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("statr")
+        .version("0.1.0")
+        .about("Rust stat")
+        .arg(
+            Arg::new("files")
+                .value_name("FILE")
+                .help(tr_str("File(s) to inspect", "調べるファイル"))
+                .required(true)
+                .action(Append),
+        )
+        .arg(
+            Arg::new("dereference")
+                .short('L')
+                .long("dereference")
+                .help(tr_str(
+                    "Follow symbolic links",
+                    "シンボリックリンクをたどる",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("format")
+                .value_name("FORMAT")
+                .short('c')
+                .long("format")
+                .help(tr_str(
+                    "Use FORMAT instead of the default, followed by a newline",
+                    "既定の代わりにFORMATを使い、改行を付ける",
+                )),
+        )
+        .arg(
+            Arg::new("printf")
+                .value_name("FORMAT")
+                .long("printf")
+                .help(tr_str(
+                    "Like --format, but interpret backslash escapes and omit the newline",
+                    "--format と同じだが、バックスラッシュのエスケープを解釈し改行を付けない",
+                ))
+                .conflicts_with("format"),
+        )
+        .get_tool_matches();
+
+    let format = if let Some(format) = matches.get_one::<String>("format") {
+        format!("{}\n", format)
+    } else if let Some(format) = matches.get_one::<String>("printf") {
+        unescape(format)
+    } else {
+        DEFAULT_FORMAT.to_string()
+    };
+
+    Ok(Config {
+        files: matches
+            .get_many::<String>("files")
+            .expect("files required")
+            .map(|v| v.to_string())
+            .collect(),
+        format,
+        dereference: matches.get_flag("dereference"),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    for filename in &config.files {
+        let metadata = if config.dereference {
+            fs::metadata(filename)
+        } else {
+            fs::symlink_metadata(filename)
+        };
+        match metadata {
+            Err(e) => {
+                warn_to(err, "statr", &format_args!("{}: {}", filename, tr_io(&e)));
+                status = EXIT_FAILURE;
+            }
+            Ok(metadata) => write!(
+                out,
+                "{}",
+                format_metadata(&config.format, Path::new(filename), &metadata)
+            )?,
+        }
+    }
+    Ok(status)
+}
+
+pub fn format_metadata(format: &str, path: &Path, metadata: &Metadata) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let mut spec = Spec::default();
+        let mut text = String::from('%');
+        while let Some(flag) = chars.next_if(|c| "-#0".contains(*c)) {
+            match flag {
+                '-' => spec.left = true,
+                '#' => spec.alt = true,
+                _ => spec.zero = true,
+            }
+            text.push(flag);
+        }
+        let mut fits = true;
+        let width = read_number(&mut chars, &mut text);
+        fits &= width.is_some();
+        spec.width = width.unwrap_or(0);
+        if chars.next_if_eq(&'.').is_some() {
+            text.push('.');
+            let precision = read_number(&mut chars, &mut text);
+            fits &= precision.is_some();
+            spec.precision = precision;
+        }
+        let Some(directive) = chars.next() else {
+            out.push_str(&text);
+            break;
+        };
+        let field = match directive {
+            '%' => {
+                out.push('%');
+                continue;
+            }
+            'n' => Field::Text(path.display().to_string()),
+            'N' => Field::Text(quoted_name(path, metadata)),
+            's' => Field::Num(metadata.len().to_string()),
+            'b' => Field::Num(metadata.blocks().to_string()),
+            'B' => Field::Num("512".to_string()),
+            'o' => Field::Num(metadata.blksize().to_string()),
+            'F' => Field::Text(file_type_name(metadata).to_string()),
+            'a' => Field::Octal(format!("{:o}", metadata.mode() & 0o7777)),
+            'A' => Field::Text(mode_string(metadata)),
+            'f' => Field::Hex(format!("{:x}", metadata.mode())),
+            'u' => Field::Num(metadata.uid().to_string()),
+            'U' => Field::Text(name_or_unknown(user_name(metadata.uid()))),
+            'g' => Field::Num(metadata.gid().to_string()),
+            'G' => Field::Text(name_or_unknown(group_name(metadata.gid()))),
+            'i' => Field::Num(metadata.ino().to_string()),
+            'h' => Field::Num(metadata.nlink().to_string()),
+            'd' => Field::Num(metadata.dev().to_string()),
+            'D' => Field::Hex(format!("{:x}", metadata.dev())),
+            't' => Field::Hex(format!("{:x}", libc::major(metadata.rdev() as libc::dev_t))),
+            'T' => Field::Hex(format!("{:x}", libc::minor(metadata.rdev() as libc::dev_t))),
+            'x' => Field::Text(format_time(metadata.atime(), metadata.atime_nsec())),
+            'y' => Field::Text(format_time(metadata.mtime(), metadata.mtime_nsec())),
+            'z' => Field::Text(format_time(metadata.ctime(), metadata.ctime_nsec())),
+            'w' => Field::Text(
+                birth(metadata).map_or("-".to_string(), |(secs, nsecs)| format_time(secs, nsecs)),
+            ),
+            'X' => Field::Num(metadata.atime().to_string()),
+            'Y' => Field::Num(metadata.mtime().to_string()),
+            'Z' => Field::Num(metadata.ctime().to_string()),
+            'W' => Field::Num(birth(metadata).map_or(0, |(secs, _)| secs).to_string()),
+            other => {
+                out.push_str(&text);
+                out.push(other);
+                continue;
+            }
+        };
+        // 大きすぎる幅や精度は GNU stat と同じく何も出力しない
+        if fits {
+            out.push_str(&spec.apply(field));
+        }
+    }
+    out
+}
+
+/// Reads the digits of a field width or precision, also adding them to
+/// `text`, the directive as written. Values beyond C's `INT_MAX` are
+/// `None`.
+fn read_number(chars: &mut Peekable<Chars>, text: &mut String) -> Option<usize> {
+    let mut val = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        val.push(c);
+    }
+    text.push_str(&val);
+    if val.is_empty() {
+        return Some(0);
+    }
+    val.parse::<i32>().ok().map(|val| val as usize)
+}
+
+/// The flags, field width and precision of a directive, which work as
+/// in printf.
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    alt: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+/// A directive's value, which decides how the flags apply to it.
+enum Field {
+    Num(String),
+    Octal(String),
+    Hex(String),
+    Text(String),
+}
+
+impl Spec {
+    fn apply(&self, field: Field) -> String {
+        let (sign, mut digits, prefix) = match field {
+            Field::Text(text) => {
+                let text = match self.precision {
+                    Some(p) => text.chars().take(p).collect(),
+                    None => text,
+                };
+                return self.pad("", &text, ' ');
+            }
+            Field::Num(num) => match num.strip_prefix('-') {
+                Some(digits) => ("-", digits.to_string(), ""),
+                None => ("", num, ""),
+            },
+            Field::Octal(digits) => ("", digits, ""),
+            Field::Hex(digits) => ("", digits, if self.alt { "0x" } else { "" }),
+        };
+        if let Some(p) = self.precision {
+            digits = format!("{:0>1$}", digits, p);
+        }
+        if self.alt && prefix.is_empty() && !digits.starts_with('0') && sign.is_empty() {
+            digits.insert(0, '0');
+        }
+        let fill = if self.zero && self.precision.is_none() {
+            '0'
+        } else {
+            ' '
+        };
+        self.pad(&format!("{}{}", sign, prefix), &digits, fill)
+    }
+
+    /// Pads to the field width; zeros go after the sign or prefix.
+    fn pad(&self, prefix: &str, body: &str, fill: char) -> String {
+        let len = prefix.chars().count() + body.chars().count();
+        let padding = self.width.saturating_sub(len);
+        if self.left {
+            format!("{}{}{}", prefix, body, " ".repeat(padding))
+        } else if fill == '0' {
+            format!("{}{}{}", prefix, "0".repeat(padding), body)
+        } else {
+            format!("{}{}{}", " ".repeat(padding), prefix, body)
+        }
+    }
+}
+
+pub fn file_type_name(metadata: &Metadata) -> &'static str {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        "symbolic link"
+    } else if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_fifo() {
+        "fifo"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() {
+        "block special file"
+    } else if file_type.is_char_device() {
+        "character special file"
+    } else if metadata.len() == 0 {
+        "regular empty file"
+    } else {
+        "regular file"
+    }
+}
+
+fn name_or_unknown(name: Option<String>) -> String {
+    name.unwrap_or_else(|| "UNKNOWN".to_string())
+}
+
+fn quoted_name(path: &Path, metadata: &Metadata) -> String {
+    let name = format!("'{}'", path.display());
+    if metadata.file_type().is_symlink() {
+        if let Ok(target) = fs::read_link(path) {
+            return format!("{} -> '{}'", name, target.display());
+        }
+    }
+    name
+}
+
+/// When the file was created, if the file system records it.
+fn birth(metadata: &Metadata) -> Option<(i64, i64)> {
+    let since_epoch = metadata.created().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((
+        since_epoch.as_secs() as i64,
+        since_epoch.subsec_nanos() as i64,
+    ))
+}
+
+fn format_time(secs: i64, nsecs: i64) -> String {
+    DateTime::from_timestamp(secs, nsecs as u32)
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S%.9f %z")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn unescape(format: &str) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            Some('"') => out.push('"'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::Path;
+
+    use super::{format_metadata, unescape};
+
+    #[test]
+    fn test_format_metadata() {
+        let path = Path::new("tests/inputs/hello.txt");
+        let metadata = fs::metadata(path).unwrap();
+
+        assert_eq!(
+            format_metadata("%n: %s bytes, %F", path, &metadata),
+            "tests/inputs/hello.txt: 6 bytes, regular file"
+        );
+        assert_eq!(format_metadata("100%%", path, &metadata), "100%");
+        assert_eq!(
+            format_metadata("%N", path, &metadata),
+            "'tests/inputs/hello.txt'"
+        );
+
+        // 未知の指示子はそのまま出力する
+        assert_eq!(format_metadata("%q %-5q", path, &metadata), "%q %-5q");
+
+        let mode = metadata.mode();
+        assert_eq!(
+            format_metadata("%a|%04a|%#a|%#f", path, &metadata),
+            format!(
+                "{:o}|{:04o}|0{:o}|0x{:x}",
+                mode & 0o7777,
+                mode & 0o7777,
+                mode & 0o7777,
+                mode
+            )
+        );
+        assert_eq!(
+            format_metadata("[%5s|%-5s|%05s|%.3s|%.2N|%t %T]", path, &metadata),
+            "[    6|6    |00006|006|'t|0 0]"
+        );
+        assert_eq!(format_metadata("[%99999999999s]", path, &metadata), "[]");
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"a\tb\n"), "a\tb\n");
+        assert_eq!(unescape(r"\\"), "\\");
+        assert_eq!(unescape(r"\q"), "\\q");
+    }
+}
@@ -0,0 +1,111 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, GnuCompare, TestResult};
+use predicates::prelude::*;
+
+const PRG: &str = "statr";
+const HELLO: &str = "tests/inputs/hello.txt";
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "%n", "does-not-exist", HELLO])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_match(
+            "does-not-exist: .* [(]os error 2[)]",
+        )?)
+        .stdout(format!("{}\n", HELLO));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn default_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg(HELLO)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("  File: '{}'\n", HELLO)))
+        .stdout(predicate::str::contains("  Size: 6\t"))
+        .stdout(predicate::str::contains("regular file\n"))
+        .stdout(predicate::str::is_match(r"Modify: \d{4}-\d{2}-\d{2} ")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--format", "%n|%s|%F", HELLO, "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}|6|regular file\ntests/inputs|{}|directory\n",
+            HELLO,
+            std::fs::metadata("tests/inputs")?.len()
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--printf", r"%s\t%n\n", HELLO])
+        .assert()
+        .success()
+        .stdout(format!("6\t{}\n", HELLO));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dereference() -> TestResult {
+    let fixture = Fixture::new()?;
+    let target = std::fs::canonicalize(HELLO)?;
+    let link = fixture.symlink("link", &target.display().to_string())?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-c", "%F", &link])
+        .assert()
+        .success()
+        .stdout("symbolic link\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["-L", "-c", "%F %s", &link])
+        .assert()
+        .success()
+        .stdout("regular file 6\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn same_as_gnu() -> TestResult {
+    let formats = [
+        "%a",
+        "%04a|%#a|%-5a|%#5a",
+        "%f|%#f|%t|%T",
+        "%W|%w",
+        "%10.10A|%-8s|%08s|%.9s|%.0n",
+    ];
+    for format in formats {
+        for file in [HELLO, "tests/inputs", "/dev/null"] {
+            GnuCompare::new(PRG, "stat")
+                .args(["-c", format, file])
+                .assert()?;
+        }
+    }
+    Ok(())
+}
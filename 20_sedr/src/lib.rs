@@ -0,0 +1,730 @@
+use std::ffi::OsString;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use clap::ArgAction::{Append, SetTrue};
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::posix::basic_to_extended;
+use clir_common::{args, open, warn_to, CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use regex::{Regex, RegexBuilder};
+use thiserror::Error;
+
+#[derive(Debug)]
+enum Address {
+    Line(usize),
+    Last,
+    Pattern(Regex),
+}
+
+#[derive(Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Group(usize),
+}
+
+#[derive(Debug)]
+enum Action {
+    Substitute {
+        pattern: Regex,
+        replacement: Vec<Part>,
+        global: bool,
+        occurrence: usize,
+        print: bool,
+    },
+    Print,
+    Delete,
+}
+
+#[derive(Debug)]
+struct Instruction {
+    start: Option<Address>,
+    end: Option<Address>,
+    action: Action,
+    active: bool,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    script: Vec<Instruction>,
+    files: Vec<String>,
+    quiet: bool,
+    in_place: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(
+        "{}",
+        tr_str(
+            "couldn't edit -: not a regular file",
+            "-を編集できません: 通常のファイルではありません",
+        )
+    )]
+    StdinInPlace,
+    #[error("{}", tr_str("unexpected `,'", "予期しない `,' があります"))]
+    UnexpectedComma,
+    #[error("{}", tr_text("unknown command: `{0}'", "不明なコマンドです: `{0}'", &[.0]))]
+    UnknownCommand(char),
+    #[error("{}", tr_str("missing command", "コマンドがありません"))]
+    MissingCommand,
+    #[error(
+        "{}",
+        tr_str("extra characters after command", "コマンドの後に余分な文字があります")
+    )]
+    ExtraCharacters,
+    #[error(
+        "{}",
+        tr_str("invalid usage of line address 0", "行アドレス0の使い方が不正です")
+    )]
+    LineZero,
+    #[error("{}", tr_text("invalid line address \"{0}\"", "行アドレスが不正です \"{0}\"", &[.0]))]
+    InvalidLineAddress(String),
+    #[error(
+        "{}",
+        tr_str("unterminated address regex", "アドレスの正規表現が終わっていません")
+    )]
+    UnterminatedAddressRegex,
+    #[error(
+        "{}",
+        tr_str("unterminated `s' command", "`s' コマンドが終わっていません")
+    )]
+    UnterminatedSubstitute,
+    #[error(
+        "{}",
+        tr_str(
+            "number option to `s' command may not be zero",
+            "`s' コマンドの数値オプションに0は使えません",
+        )
+    )]
+    ZeroOccurrence,
+    #[error("{}", tr_text("invalid number \"{0}\"", "数値が不正です \"{0}\"", &[.0]))]
+    InvalidNumber(String),
+    #[error("{}", tr_text("Invalid pattern \"{0}\"", "パターンが不正です \"{0}\"", &[.0]))]
+    InvalidPattern(String),
+    #[error("{}", tr_text(
+        "invalid reference \\{0} on `s' command's RHS",
+        "`s' コマンドの置換文字列の参照 \\{0} が不正です",
+        &[.0],
+    ))]
+    InvalidReference(usize),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => EXIT_FAILURE,
+            _ => EXIT_USAGE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("sedr")
+        .version("0.1.0")
+        .about("Rust sed")
+        .arg(
+            Arg::new("script")
+                .value_name("SCRIPT")
+                .help(tr_str("Editing commands", "編集コマンド"))
+                .required(true),
+        )
+        .arg(
+            Arg::new("files")
+                .value_name("FILE")
+                .help(tr_str("Input file(s)", "入力ファイル"))
+                .action(Append)
+                .default_value("-"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('n')
+                .long("quiet")
+                .visible_alias("silent")
+                .help(tr_str(
+                    "Suppress automatic printing of pattern space",
+                    "パターンスペースを自動で表示しない",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("extended")
+                .short('E')
+                .short_alias('r')
+                .long("regexp-extended")
+                .help(tr_str(
+                    "Use extended regular expressions",
+                    "拡張正規表現を使う",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("in_place")
+                .value_name("SUFFIX")
+                .short('i')
+                .long("in-place")
+                .help(tr_str(
+                    "Edit files in place, making a backup if SUFFIX is given",
+                    "ファイルを直接編集する(SUFFIXがあればバックアップを作る)",
+                ))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(""),
+        )
+        .get_tool_matches_from(args().into_iter().map(attach_suffix).collect());
+
+    let script = parse_script(
+        matches.get_one::<String>("script").unwrap(),
+        matches.get_flag("extended"),
+    )?;
+    let files: Vec<String> = matches
+        .get_many::<String>("files")
+        .unwrap()
+        .map(|v| v.to_string())
+        .collect();
+    let in_place = matches.get_one::<String>("in_place").cloned();
+
+    if in_place.is_some() && files.iter().any(|f| f == "-") {
+        return Err(Error::StdinInPlace);
+    }
+
+    Ok(Config {
+        script,
+        files,
+        quiet: matches.get_flag("quiet"),
+        in_place,
+    })
+}
+
+// sedと同じく「-i.bak」の形でサフィックスを指定できるようにする
+fn attach_suffix(arg: OsString) -> OsString {
+    match arg.to_str().and_then(|a| a.strip_prefix("-i")) {
+        Some(suffix) if !suffix.is_empty() && !suffix.starts_with('=') => {
+            format!("--in-place={}", suffix).into()
+        }
+        _ => arg,
+    }
+}
+
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    let Config {
+        mut script,
+        files,
+        quiet,
+        in_place,
+    } = config;
+
+    match in_place {
+        Some(suffix) => {
+            for filename in &files {
+                if let Err(e) = edit_in_place(filename, &suffix, &mut script, quiet) {
+                    warn_to(err, "sedr", &format_args!("{}: {}", filename, tr_io(&e)));
+                    status = EXIT_FAILURE;
+                }
+            }
+        }
+        None => {
+            let lines = files
+                .iter()
+                .filter_map(|filename| match open(filename) {
+                    Err(e) => {
+                        warn_to(err, "sedr", &format_args!("{}: {}", filename, tr_io(&e)));
+                        status = EXIT_FAILURE;
+                        None
+                    }
+                    Ok(file) => Some(file),
+                })
+                .flat_map(read_lines);
+            let mut out = BufWriter::new(out);
+            edit(lines, &mut script, quiet, &mut out)?;
+            out.flush()?;
+        }
+    }
+    Ok(status)
+}
+
+fn read_lines(mut reader: Box<dyn BufRead>) -> impl Iterator<Item = io::Result<String>> {
+    std::iter::from_fn(move || {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(line)),
+            Err(e) => Some(Err(e)),
+        }
+    })
+}
+
+fn edit_in_place(
+    filename: &str,
+    suffix: &str,
+    script: &mut [Instruction],
+    quiet: bool,
+) -> io::Result<()> {
+    let file = BufReader::new(File::open(filename)?);
+    let mut buf = vec![];
+    // 行番号と範囲の状態はファイルごとにリセットする
+    for instruction in script.iter_mut() {
+        instruction.active = false;
+    }
+    edit(read_lines(Box::new(file)), script, quiet, &mut buf)?;
+    if !suffix.is_empty() {
+        fs::copy(filename, format!("{}{}", filename, suffix))?;
+    }
+    fs::write(filename, buf)?;
+    Ok(())
+}
+
+/// Writes lines, deferring the newline of an unterminated last line so
+/// that later output still starts on its own line.
+struct Output<'a, W: Write> {
+    writer: &'a mut W,
+    missing_newline: bool,
+}
+
+impl<W: Write> Output<'_, W> {
+    fn write(&mut self, text: &str, newline: bool) -> io::Result<()> {
+        if self.missing_newline {
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.write_all(text.as_bytes())?;
+        if newline {
+            self.writer.write_all(b"\n")?;
+        }
+        self.missing_newline = !newline;
+        Ok(())
+    }
+}
+
+fn edit(
+    lines: impl Iterator<Item = io::Result<String>>,
+    script: &mut [Instruction],
+    quiet: bool,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let mut out = Output {
+        writer,
+        missing_newline: false,
+    };
+    let mut lines = lines.peekable();
+    let mut line_num = 0;
+
+    'lines: while let Some(line) = lines.next() {
+        let mut line = line?;
+        let newline = line.ends_with('\n');
+        if newline {
+            line.pop();
+        }
+        line_num += 1;
+        let is_last = lines.peek().is_none();
+
+        for instruction in script.iter_mut() {
+            if !instruction.selects(line_num, is_last, &line) {
+                continue;
+            }
+            match &instruction.action {
+                Action::Print => out.write(&line, newline)?,
+                Action::Delete => continue 'lines,
+                Action::Substitute {
+                    pattern,
+                    replacement,
+                    global,
+                    occurrence,
+                    print,
+                } => {
+                    if let Some(new_line) =
+                        substitute(&line, pattern, replacement, *global, *occurrence)
+                    {
+                        line = new_line;
+                        if *print {
+                            out.write(&line, newline)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        if !quiet {
+            out.write(&line, newline)?;
+        }
+    }
+    Ok(())
+}
+
+impl Address {
+    fn matches(&self, line_num: usize, is_last: bool, line: &str) -> bool {
+        match self {
+            Address::Line(n) => line_num == *n,
+            Address::Last => is_last,
+            Address::Pattern(re) => re.is_match(line),
+        }
+    }
+}
+
+impl Instruction {
+    fn selects(&mut self, line_num: usize, is_last: bool, line: &str) -> bool {
+        let start = match &self.start {
+            None => return true,
+            Some(start) => start,
+        };
+        let end = match &self.end {
+            None => return start.matches(line_num, is_last, line),
+            Some(end) => end,
+        };
+        if self.active {
+            let done = match end {
+                Address::Line(n) => line_num >= *n,
+                Address::Last => is_last,
+                Address::Pattern(re) => re.is_match(line),
+            };
+            self.active = !done;
+            true
+        } else if start.matches(line_num, is_last, line) {
+            // 終了アドレスの正規表現は開始行の次の行から調べる
+            self.active = match end {
+                Address::Line(n) => *n > line_num,
+                Address::Last => !is_last,
+                Address::Pattern(_) => true,
+            };
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn substitute(
+    line: &str,
+    pattern: &Regex,
+    replacement: &[Part],
+    global: bool,
+    occurrence: usize,
+) -> Option<String> {
+    let mut result = String::new();
+    let mut last = 0;
+    let mut replaced = false;
+
+    for (i, caps) in pattern.captures_iter(line).enumerate() {
+        let count = i + 1;
+        if count < occurrence {
+            continue;
+        }
+        if count > occurrence && !global {
+            break;
+        }
+        let whole = caps.get(0).unwrap();
+        result.push_str(&line[last..whole.start()]);
+        for part in replacement {
+            match part {
+                Part::Literal(text) => result.push_str(text),
+                Part::Group(n) => result.push_str(caps.get(*n).map_or("", |m| m.as_str())),
+            }
+        }
+        last = whole.end();
+        replaced = true;
+    }
+
+    if replaced {
+        result.push_str(&line[last..]);
+        Some(result)
+    } else {
+        None
+    }
+}
+
+/// Parses a script whose regexes are POSIX basic ones, or extended ones
+/// with `extended`.
+fn parse_script(script: &str, extended: bool) -> Result<Vec<Instruction>, Error> {
+    let mut chars = script.chars().peekable();
+    let mut instructions = vec![];
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ';').is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let start = parse_address(&mut chars, extended)?;
+        let end = match start {
+            Some(_) if chars.next_if_eq(&',').is_some() => {
+                Some(parse_address(&mut chars, extended)?.ok_or(Error::UnexpectedComma)?)
+            }
+            _ => None,
+        };
+        skip_blanks(&mut chars);
+
+        let action = match chars.next() {
+            Some('p') => Action::Print,
+            Some('d') => Action::Delete,
+            Some('s') => parse_substitute(&mut chars, extended)?,
+            Some(c) => return Err(Error::UnknownCommand(c)),
+            None => return Err(Error::MissingCommand),
+        };
+        skip_blanks(&mut chars);
+        match chars.peek() {
+            None | Some(';') | Some('\n') => {}
+            Some(_) => return Err(Error::ExtraCharacters),
+        }
+
+        instructions.push(Instruction {
+            start,
+            end,
+            action,
+            active: false,
+        });
+    }
+    Ok(instructions)
+}
+
+fn skip_blanks(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+}
+
+fn parse_address(chars: &mut Peekable<Chars>, extended: bool) -> Result<Option<Address>, Error> {
+    match chars.peek() {
+        Some(c) if c.is_ascii_digit() => {
+            let mut digits = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                digits.push(c);
+            }
+            match digits.parse() {
+                Ok(0) => Err(Error::LineZero),
+                Ok(n) => Ok(Some(Address::Line(n))),
+                Err(_) => Err(Error::InvalidLineAddress(digits)),
+            }
+        }
+        Some('$') => {
+            chars.next();
+            Ok(Some(Address::Last))
+        }
+        Some('/') => {
+            chars.next();
+            let pattern = read_until(chars, '/').ok_or(Error::UnterminatedAddressRegex)?;
+            Ok(Some(Address::Pattern(build_regex(
+                &pattern, extended, false,
+            )?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_substitute(chars: &mut Peekable<Chars>, extended: bool) -> Result<Action, Error> {
+    let delim = match chars.next() {
+        Some(c) if c != '\\' && c != '\n' => c,
+        _ => return Err(Error::UnterminatedSubstitute),
+    };
+    let pattern = read_until(chars, delim).ok_or(Error::UnterminatedSubstitute)?;
+    let replacement = read_until(chars, delim).ok_or(Error::UnterminatedSubstitute)?;
+
+    let mut global = false;
+    let mut print = false;
+    let mut insensitive = false;
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| "gpiI0123456789".contains(*c)) {
+        match c {
+            'g' => global = true,
+            'p' => print = true,
+            'i' | 'I' => insensitive = true,
+            _ => number.push(c),
+        }
+    }
+    let occurrence = match number.parse() {
+        _ if number.is_empty() => 1,
+        Ok(0) => return Err(Error::ZeroOccurrence),
+        Ok(n) => n,
+        Err(_) => return Err(Error::InvalidNumber(number)),
+    };
+
+    let pattern = build_regex(&pattern, extended, insensitive)?;
+    let replacement = parse_replacement(&replacement, pattern.captures_len())?;
+    Ok(Action::Substitute {
+        pattern,
+        replacement,
+        global,
+        occurrence,
+        print,
+    })
+}
+
+fn read_until(chars: &mut Peekable<Chars>, delim: char) -> Option<String> {
+    let mut text = String::new();
+    loop {
+        match chars.next()? {
+            c if c == delim => return Some(text),
+            '\\' => match chars.next()? {
+                c if c == delim => text.push(c),
+                c => {
+                    text.push('\\');
+                    text.push(c);
+                }
+            },
+            c => text.push(c),
+        }
+    }
+}
+
+fn build_regex(pattern: &str, extended: bool, insensitive: bool) -> Result<Regex, Error> {
+    let translated = if extended {
+        pattern.to_string()
+    } else {
+        basic_to_extended(pattern)
+    };
+    RegexBuilder::new(&translated)
+        .case_insensitive(insensitive)
+        .build()
+        .map_err(|_| Error::InvalidPattern(pattern.to_string()))
+}
+
+fn parse_replacement(replacement: &str, captures_len: usize) -> Result<Vec<Part>, Error> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut chars = replacement.chars();
+
+    while let Some(c) = chars.next() {
+        let group = match c {
+            '&' => 0,
+            '\\' => match chars.next() {
+                Some(d @ '0'..='9') => d.to_digit(10).unwrap() as usize,
+                Some('n') => {
+                    literal.push('\n');
+                    continue;
+                }
+                Some('t') => {
+                    literal.push('\t');
+                    continue;
+                }
+                Some(other) => {
+                    literal.push(other);
+                    continue;
+                }
+                None => {
+                    literal.push('\\');
+                    continue;
+                }
+            },
+            _ => {
+                literal.push(c);
+                continue;
+            }
+        };
+        if group >= captures_len {
+            return Err(Error::InvalidReference(group));
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(Part::Group(group));
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Part::{Group, Literal};
+    use super::{edit, parse_replacement, parse_script, substitute, Action, Address};
+    use regex::Regex;
+
+    fn run(script: &str, input: &str, quiet: bool) -> String {
+        let mut script = parse_script(script, false).unwrap();
+        let lines = input.split_inclusive('\n').map(|l| Ok(l.to_string()));
+        let mut out = vec![];
+        edit(lines, &mut script, quiet, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = parse_script("2,/end/s|a\\|b|c|2gp; $p", false).unwrap();
+        assert_eq!(script.len(), 2);
+        assert!(matches!(script[0].start, Some(Address::Line(2))));
+        assert!(matches!(&script[0].end, Some(Address::Pattern(re)) if re.as_str() == "end"));
+        match &script[0].action {
+            Action::Substitute {
+                pattern,
+                replacement,
+                global,
+                occurrence,
+                print,
+            } => {
+                assert_eq!(pattern.as_str(), r"a\|b");
+                assert_eq!(replacement, &[Literal("c".to_string())]);
+                assert!(*global);
+                assert_eq!(*occurrence, 2);
+                assert!(*print);
+            }
+            _ => panic!("expected substitution"),
+        }
+        assert!(matches!(script[1].start, Some(Address::Last)));
+        assert!(matches!(script[1].action, Action::Print));
+
+        assert!(parse_script("0p", false).is_err());
+        assert!(parse_script("s/a/b", false).is_err());
+        assert!(parse_script("s/a/b/0", false).is_err());
+        assert!(parse_script("s/a/\\1/", false).is_err());
+        assert!(parse_script("s/(/x/", false).is_ok());
+        assert!(parse_script(r"s/\(/x/", false).is_err());
+        assert!(parse_script("s/(/x/", true).is_err());
+        assert!(parse_script(r"s/\(a\)/\2/", false).is_err());
+        assert!(parse_script(r"s/(a)(b)/\2/", true).is_ok());
+        assert!(parse_script("x", false).is_err());
+        assert!(parse_script("p x", false).is_err());
+        assert!(parse_script("1,p", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_replacement() {
+        assert_eq!(
+            parse_replacement(r"[&]\1\&\n", 2).unwrap(),
+            vec![
+                Literal("[".to_string()),
+                Group(0),
+                Literal("]".to_string()),
+                Group(1),
+                Literal("&\n".to_string()),
+            ]
+        );
+        assert!(parse_replacement(r"\2", 2).is_err());
+    }
+
+    #[test]
+    fn test_substitute() {
+        let re = Regex::new("o").unwrap();
+        let o = [Literal("0".to_string())];
+        assert_eq!(substitute("foo boo", &re, &o, false, 1).unwrap(), "f0o boo");
+        assert_eq!(substitute("foo boo", &re, &o, true, 1).unwrap(), "f00 b00");
+        assert_eq!(substitute("foo boo", &re, &o, false, 3).unwrap(), "foo b0o");
+        assert_eq!(substitute("foo boo", &re, &o, true, 3).unwrap(), "foo b00");
+        assert_eq!(substitute("foo boo", &re, &o, false, 5), None);
+        assert_eq!(substitute("bar", &re, &o, true, 1), None);
+    }
+
+    #[test]
+    fn test_edit() {
+        let input = "one\ntwo\nthree\nfour\nfive\n";
+        assert_eq!(run("2,3d", input, false), "one\nfour\nfive\n");
+        assert_eq!(run("$p", input, true), "five\n");
+        assert_eq!(run("/two/,/four/p", input, true), "two\nthree\nfour\n");
+
+        // 終了行が開始行以前なら1行だけ選択する
+        assert_eq!(run("3,1p", input, true), "three\n");
+
+        // 範囲は終了後に再び開始できる
+        assert_eq!(
+            run("/o/,/e/p", "a\nfoo\nbar\nbe\nno\nx\n", true),
+            "foo\nbar\nbe\nno\nx\n"
+        );
+
+        // 末尾に改行のない行は改行なしで出力する
+        assert_eq!(run("p", "a\nb", false), "a\na\nb\nb");
+        assert_eq!(run("p", "a\nb", true), "a\nb");
+    }
+}
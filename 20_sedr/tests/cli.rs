@@ -0,0 +1,154 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, GnuCompare, TestResult};
+use predicates::prelude::*;
+use std::fs;
+
+const PRG: &str = "sedr";
+const FOX: &str = "tests/inputs/fox.txt";
+const NUMBERS: &str = "tests/inputs/numbers.txt";
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_script() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["s/fox/dog", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unterminated `s' command"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["s/fox/cat/", "does-not-exist", FOX])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_match(
+            "does-not-exist: .* [(]os error 2[)]",
+        )?)
+        .stdout("The quick brown cat jumps over the lazy dog.\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn substitute() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([r"s/\(\w\+\) \(\w\+\)/\2 \1/g; s/o/0/3", FOX])
+        .assert()
+        .success()
+        .stdout("quick The fox brown 0ver jumps lazy the dog.\n");
+
+    for flag in ["-E", "-r", "--regexp-extended"] {
+        Command::cargo_bin(PRG)?
+            .args([flag, r"s/(\w+) (\w+)/\2 \1/g; s/o/0/3", FOX])
+            .assert()
+            .success()
+            .stdout("quick The fox brown 0ver jumps lazy the dog.\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn same_as_gnu() -> TestResult {
+    let cases: &[(&[&str], &str)] = &[
+        (&[r"s/\(1\)\(.\)/\2\1/"], "12 ab\n"),
+        (&["-E", r"s/(1)(.)/\2\1/"], "12 ab\n"),
+        (&["s/a+b/X/"], "a+b aab\n"),
+        (&[r"s/a\+b/X/"], "a+b aab\n"),
+        (&["-E", "s/a+b/X/"], "a+b aab\n"),
+        (&["s/a{2}/X/g"], "aa a{2}\n"),
+        (&[r"s/a\{2\}/X/g"], "aa a{2}\n"),
+        (&["-r", "s/a{2}/X/g"], "aa a{2}\n"),
+        (&["s/a?|(b)/X/g"], "a?|(b) ab\n"),
+        (&[r"s/a\|b/X/g"], "abc\n"),
+        (&["s/*a/X/"], "b*a\n"),
+        (&["-n", r"/^o/s/\(o\)\{1,\}s/[\1&]/p"], "oslo\nono\n"),
+    ];
+    for (args, stdin) in cases {
+        GnuCompare::new(PRG, "sed")
+            .args(*args)
+            .stdin(*stdin)
+            .assert()?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["s/THE/[&]/gi"])
+        .write_stdin(fs::read_to_string(FOX)?)
+        .assert()
+        .success()
+        .stdout("[The] quick brown fox jumps over [the] lazy dog.\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn addresses() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "1p; /three/,$s/e/E/p", NUMBERS])
+        .assert()
+        .success()
+        .stdout("one\nthrEe\nfivE\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["2,4d", NUMBERS])
+        .assert()
+        .success()
+        .stdout("one\nfive\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn line_numbers_span_files() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-n", "2p;$p", FOX, NUMBERS])
+        .assert()
+        .success()
+        .stdout("one\nfive\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn in_place() -> TestResult {
+    let fixture = Fixture::new()?;
+    let path = fixture.file("numbers.txt", fs::read(NUMBERS)?)?;
+
+    Command::cargo_bin(PRG)?
+        .args(["-i", "1d;s/o/0/g", &path])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(fs::read_to_string(&path)?, "tw0\nthree\nf0ur\nfive\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["-i.bak", "$d", &path])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(fs::read_to_string(&path)?, "tw0\nthree\nf0ur\n");
+    assert_eq!(
+        fs::read_to_string(format!("{}.bak", path))?,
+        "tw0\nthree\nf0ur\nfive\n"
+    );
+    Ok(())
+}
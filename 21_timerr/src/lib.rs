@@ -0,0 +1,301 @@
+use std::fs::OpenOptions;
+use std::io::{self, ErrorKind, Write};
+use std::mem::MaybeUninit;
+use std::os::unix::process::ExitStatusExt;
+use std::process;
+use std::time::{Duration, Instant};
+
+use clap::ArgAction::SetTrue;
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{warn_to, CommandExt, ToolError};
+use thiserror::Error;
+
+const DEFAULT_FORMAT: &str = "%Uuser %Ssystem %Eelapsed %PCPU (%Mmaxresident)k";
+const PORTABLE_FORMAT: &str = "real %e\nuser %U\nsys %S";
+
+#[derive(Debug)]
+pub struct Config {
+    command: Vec<String>,
+    format: String,
+    output: Option<String>,
+    append: bool,
+}
+
+#[derive(Debug)]
+struct Usage {
+    command: String,
+    elapsed: Duration,
+    user: Duration,
+    system: Duration,
+    max_rss: i64,
+    status: i32,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Create { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("timerr")
+        .version("0.1.0")
+        .about("Rust time")
+        .arg(
+            Arg::new("command")
+                .value_name("COMMAND")
+                .help(tr_str(
+                    "Command to run, with its arguments",
+                    "実行するコマンドとその引数",
+                ))
+                .required(true)
+                .num_args(1..)
+                .trailing_var_arg(true)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("format")
+                .value_name("FORMAT")
+                .short('f')
+                .long("format")
+                .help(tr_str("Use FORMAT for the report", "報告にFORMATを使う")),
+        )
+        .arg(
+            Arg::new("portability")
+                .short('p')
+                .long("portability")
+                .help(tr_str(
+                    "Use the POSIX output format",
+                    "POSIXの出力形式を使う",
+                ))
+                .action(SetTrue)
+                .conflicts_with("format"),
+        )
+        .arg(
+            Arg::new("output")
+                .value_name("FILE")
+                .short('o')
+                .long("output")
+                .help(tr_str(
+                    "Write the report to FILE instead of stderr",
+                    "報告を標準エラー出力ではなくFILEに書く",
+                )),
+        )
+        .arg(
+            Arg::new("append")
+                .short('a')
+                .long("append")
+                .help(tr_str(
+                    "Append to the output file instead of overwriting it",
+                    "出力ファイルを上書きせずに追記する",
+                ))
+                .action(SetTrue)
+                .requires("output"),
+        )
+        .get_tool_matches();
+
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => format.to_string(),
+        None if matches.get_flag("portability") => PORTABLE_FORMAT.to_string(),
+        None => DEFAULT_FORMAT.to_string(),
+    };
+
+    Ok(Config {
+        command: matches
+            .get_many::<String>("command")
+            .expect("command required")
+            .map(|v| v.to_string())
+            .collect(),
+        format,
+        output: matches.get_one::<String>("output").cloned(),
+        append: matches.get_flag("append"),
+    })
+}
+
+pub fn run(config: Config, _out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let start = Instant::now();
+    let child = process::Command::new(&config.command[0])
+        .args(&config.command[1..])
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            warn_to(
+                err,
+                "timerr",
+                &tr_text(
+                    "cannot run {0}: {1}",
+                    "{0} を実行できません: {1}",
+                    &[&config.command[0], &tr_io(&e)],
+                ),
+            );
+            return Ok(if e.kind() == ErrorKind::NotFound {
+                127
+            } else {
+                126
+            });
+        }
+    };
+    let exit_status = child.wait()?;
+    let elapsed = start.elapsed();
+    let (user, system, max_rss) = child_rusage();
+
+    let mut report = String::new();
+    let status = match (exit_status.code(), exit_status.signal()) {
+        (Some(code), _) => {
+            if code != 0 {
+                report.push_str(&format!("Command exited with non-zero status {}\n", code));
+            }
+            code
+        }
+        (None, Some(signal)) => {
+            report.push_str(&format!("Command terminated by signal {}\n", signal));
+            128 + signal
+        }
+        (None, None) => 1,
+    };
+
+    let usage = Usage {
+        command: config.command.join(" "),
+        elapsed,
+        user,
+        system,
+        max_rss,
+        status,
+    };
+    report.push_str(&format_usage(&config.format, &usage));
+    report.push('\n');
+
+    match &config.output {
+        Some(path) => OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(config.append)
+            .truncate(!config.append)
+            .open(path)
+            .map_err(|source| Error::Create {
+                path: path.to_string(),
+                source,
+            })?
+            .write_all(report.as_bytes())?,
+        None => err.write_all(report.as_bytes())?,
+    }
+    Ok(status)
+}
+
+fn child_rusage() -> (Duration, Duration, i64) {
+    let mut usage = MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage only writes into the provided struct.
+    let usage = unsafe {
+        if libc::getrusage(libc::RUSAGE_CHILDREN, usage.as_mut_ptr()) != 0 {
+            return (Duration::ZERO, Duration::ZERO, 0);
+        }
+        usage.assume_init()
+    };
+    let to_duration = |tv: libc::timeval| {
+        Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+    };
+    (
+        to_duration(usage.ru_utime),
+        to_duration(usage.ru_stime),
+        usage.ru_maxrss,
+    )
+}
+
+fn format_usage(format: &str, usage: &Usage) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => match chars.next() {
+                None => out.push('%'),
+                Some('%') => out.push('%'),
+                Some('e') => out.push_str(&format!("{:.2}", usage.elapsed.as_secs_f64())),
+                Some('E') => out.push_str(&format_elapsed(usage.elapsed)),
+                Some('U') => out.push_str(&format!("{:.2}", usage.user.as_secs_f64())),
+                Some('S') => out.push_str(&format!("{:.2}", usage.system.as_secs_f64())),
+                Some('P') => out.push_str(&format_percent(usage)),
+                Some('M') => out.push_str(&usage.max_rss.to_string()),
+                Some('C') => out.push_str(&usage.command),
+                Some('x') => out.push_str(&usage.status.to_string()),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+            },
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('\\') => out.push('\\'),
+                Some(other) => {
+                    out.push('\\');
+                    out.push(other);
+                }
+                None => out.push('\\'),
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let centis = elapsed.as_millis() / 10;
+    let (hours, minutes) = (centis / 360_000, centis / 6000 % 60);
+    let seconds = centis % 6000;
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds / 100)
+    } else {
+        format!("{}:{:02}.{:02}", minutes, seconds / 100, seconds % 100)
+    }
+}
+
+fn format_percent(usage: &Usage) -> String {
+    let elapsed = usage.elapsed.as_secs_f64();
+    if elapsed > 0.0 {
+        let cpu = (usage.user + usage.system).as_secs_f64();
+        format!("{:.0}%", cpu / elapsed * 100.0)
+    } else {
+        "?%".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_elapsed, format_usage, Usage};
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(1234)), "0:01.23");
+        assert_eq!(format_elapsed(Duration::from_millis(754_560)), "12:34.56");
+        assert_eq!(format_elapsed(Duration::from_secs(3723)), "1:02:03");
+    }
+
+    #[test]
+    fn test_format_usage() {
+        let usage = Usage {
+            command: "sleep 1".to_string(),
+            elapsed: Duration::from_millis(2000),
+            user: Duration::from_millis(700),
+            system: Duration::from_millis(300),
+            max_rss: 1024,
+            status: 3,
+        };
+        assert_eq!(
+            format_usage(r"%C\t%e %U %S %P %Mk %x%%", &usage),
+            "sleep 1\t2.00 0.70 0.30 50% 1024k 3%"
+        );
+        assert_eq!(format_usage("%E", &usage), "0:02.00");
+
+        // 未知の指示子はそのまま出力する
+        assert_eq!(format_usage("%q", &usage), "%q");
+    }
+}
@@ -0,0 +1,10 @@
+use std::io;
+
+fn main() {
+    match timerr::get_args()
+        .and_then(|config| timerr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()))
+    {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("timerr", &e)),
+    }
+}
@@ -0,0 +1,91 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, TestResult};
+use predicates::prelude::*;
+use std::fs;
+
+const PRG: &str = "timerr";
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn command_not_found() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("does-not-exist")
+        .assert()
+        .code(127)
+        .stderr(predicate::str::contains("cannot run does-not-exist"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn default_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["echo", "-n", "hello"])
+        .assert()
+        .success()
+        .stdout("hello")
+        .stderr(predicate::str::is_match(
+            r"^\d+\.\d\duser \d+\.\d\dsystem \d+:\d\d\.\d\delapsed \d+%CPU \(\d+maxresident\)k\n$",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn portability() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-p", "true"])
+        .assert()
+        .success()
+        .stderr(predicate::str::is_match(
+            r"^real \d+\.\d\d\nuser \d+\.\d\d\nsys \d+\.\d\d\n$",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exit_status() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "%C exited %x", "sh", "-c", "exit 3"])
+        .assert()
+        .code(3)
+        .stderr("Command exited with non-zero status 3\nsh -c exit 3 exited 3\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_file() -> TestResult {
+    let fixture = Fixture::new()?;
+    let path = fixture.path("times.txt");
+
+    for _ in 0..2 {
+        Command::cargo_bin(PRG)?
+            .args(["-o", &path, "-a", "-f", "%C", "true"])
+            .assert()
+            .success()
+            .stderr("");
+    }
+    assert_eq!(fs::read_to_string(&path)?, "true\ntrue\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["-o", &path, "-f", "%x", "false"])
+        .assert()
+        .code(1);
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        "Command exited with non-zero status 1\n1\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDateTime, NaiveTime};
+use clap::ArgAction::{Append, SetTrue};
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{parse_duration, CommandExt, ParseError, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use thiserror::Error;
+
+#[derive(Debug)]
+pub struct Config {
+    duration: Duration,
+    verbose: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    InvalidDuration(#[from] ParseError),
+    #[error("{}", tr_text("invalid time \"{0}\"", "時刻が不正です \"{0}\"", &[.0]))]
+    InvalidTime(String),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => EXIT_FAILURE,
+            _ => EXIT_USAGE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("sleepr")
+        .version("0.1.0")
+        .about("Rust sleep")
+        .arg(
+            Arg::new("durations")
+                .value_name("DURATION")
+                .help(tr_str(
+                    "Time to sleep, e.g. 1.5s, 2m or 1h30m (summed)",
+                    "待つ時間(例: 1.5s、2m、1h30m、合計される)",
+                ))
+                .action(Append)
+                .required_unless_present("until"),
+        )
+        .arg(
+            Arg::new("until")
+                .value_name("HH:MM")
+                .short('u')
+                .long("until")
+                .help(tr_str(
+                    "Sleep until the next occurrence of this local time",
+                    "次にこの現地時刻になるまで待つ",
+                ))
+                .conflicts_with("durations"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help(tr_str("Show a countdown", "カウントダウンを表示する"))
+                .action(SetTrue),
+        )
+        .get_tool_matches();
+
+    let duration = match matches.get_one::<String>("until") {
+        Some(until) => parse_until(until, Local::now().naive_local())?,
+        None => matches
+            .get_many::<String>("durations")
+            .unwrap()
+            .try_fold(Duration::ZERO, |total, s| {
+                parse_duration(s).map(|duration| total.saturating_add(duration))
+            })?,
+    };
+
+    Ok(Config {
+        duration,
+        verbose: matches.get_flag("verbose"),
+    })
+}
+
+pub fn run(config: Config, _out: &mut impl Write, err: &mut impl Write) -> Result<(), Error> {
+    if !config.verbose {
+        thread::sleep(config.duration);
+        return Ok(());
+    }
+
+    // 終了時刻を足し算で求めると、とても長い時間であふれる
+    let start = Instant::now();
+    loop {
+        let remaining = config.duration.saturating_sub(start.elapsed());
+        let remaining_text = format_remaining(remaining);
+        write!(
+            err,
+            "\r{}",
+            tr_text(
+                "sleeping: {0} remaining",
+                "待機中: 残り {0}",
+                &[&remaining_text]
+            )
+        )?;
+        err.flush()?;
+        if remaining.is_zero() {
+            break;
+        }
+        // 次の表示が秒の境目に来るように端数だけ眠る
+        let fraction = Duration::from_nanos(remaining.subsec_nanos() as u64);
+        thread::sleep(if fraction.is_zero() {
+            Duration::from_secs(1)
+        } else {
+            fraction
+        });
+    }
+    writeln!(err)?;
+    Ok(())
+}
+
+fn parse_until(val: &str, now: NaiveDateTime) -> Result<Duration, Error> {
+    let invalid = || Error::InvalidTime(val.to_string());
+    let time = NaiveTime::parse_from_str(val, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(val, "%H:%M"))
+        .map_err(|_| invalid())?;
+    let mut target = now.date().and_time(time);
+    if target <= now {
+        target += chrono::Duration::days(1);
+    }
+    (target - now).to_std().map_err(|_| invalid())
+}
+
+fn format_remaining(remaining: Duration) -> String {
+    // 0.2秒残りでも「0:00:01」と表示するよう切り上げる
+    let secs = remaining
+        .as_secs()
+        .saturating_add(u64::from(remaining.subsec_nanos() > 0));
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::NaiveDate;
+
+    use super::{format_remaining, parse_until};
+
+    #[test]
+    fn test_parse_until() {
+        let now = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(
+            parse_until("13:30", now).unwrap(),
+            Duration::from_secs(5400)
+        );
+        assert_eq!(
+            parse_until("12:00:30", now).unwrap(),
+            Duration::from_secs(30)
+        );
+
+        // 過ぎた時刻は翌日として扱う
+        assert_eq!(
+            parse_until("11:00", now).unwrap(),
+            Duration::from_secs(23 * 3600)
+        );
+        assert_eq!(
+            parse_until("12:00", now).unwrap(),
+            Duration::from_secs(24 * 3600)
+        );
+
+        assert!(parse_until("25:00", now).is_err());
+        assert!(parse_until("noon", now).is_err());
+    }
+
+    #[test]
+    fn test_format_remaining() {
+        assert_eq!(format_remaining(Duration::ZERO), "0:00:00");
+        assert_eq!(format_remaining(Duration::from_millis(200)), "0:00:01");
+        assert_eq!(format_remaining(Duration::from_secs(3723)), "1:02:03");
+        assert_eq!(format_remaining(Duration::MAX), "5124095576030431:00:15");
+    }
+}
@@ -0,0 +1,98 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::time::{Duration, Instant};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "sleepr";
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_duration() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("1x")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid time interval \"1x\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_until() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--until", "25:00"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid time \"25:00\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_duration_with_until() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["1s", "--until", "12:00"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sums_durations() -> TestResult {
+    let start = Instant::now();
+    Command::cargo_bin(PRG)?
+        .args(["100ms", "0.1s", "0.1"])
+        .assert()
+        .success()
+        .stdout("");
+    assert!(start.elapsed() >= Duration::from_millis(300));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn verbose() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-v", "1.2s"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("\rsleeping: 0:00:02 remaining"))
+        .stderr(predicate::str::contains("\rsleeping: 0:00:01 remaining"))
+        .stderr(predicate::str::ends_with("\rsleeping: 0:00:00 remaining\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn huge_durations() -> TestResult {
+    // 事実上無限に眠るので、あふれずに眠り続けていることだけを確かめる
+    Command::cargo_bin(PRG)?
+        .args(["18000000000000000000s", "18000000000000000000s"])
+        .timeout(Duration::from_millis(500))
+        .assert()
+        .interrupted()
+        .stderr("");
+
+    Command::cargo_bin(PRG)?
+        .args(["-v", "18000000000000000000s"])
+        .timeout(Duration::from_millis(500))
+        .assert()
+        .interrupted()
+        .stderr(predicate::str::starts_with(
+            "\rsleeping: 5000000000000000:00:00 remaining",
+        ));
+    Ok(())
+}
@@ -0,0 +1,670 @@
+use std::fs;
+use std::io;
+use std::io::{IsTerminal, Read, Write};
+use std::ops::Range;
+
+use ansi_term::{Colour, Style};
+use chrono::{DateTime, Local};
+use clap::ArgAction::SetTrue;
+use clap::{value_parser, Arg, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{CommandExt, ToolError};
+
+use crate::Edit::{Delete, Equal, Insert};
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+#[derive(Debug, PartialEq)]
+struct Hunk<'a> {
+    start1: usize,
+    start2: usize,
+    edits: &'a [Edit],
+}
+
+#[derive(Debug)]
+pub struct Config {
+    file1: String,
+    file2: String,
+    context: usize,
+    brief: bool,
+    color: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+// diffと同じく、違いがあった場合の1と区別するためエラーはすべて2で終わる
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        2
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("diffr")
+        .version("0.1.0")
+        .about("Rust diff")
+        .arg(
+            Arg::new("file1")
+                .value_name("FILE1")
+                .help(tr_str("Original file", "元のファイル"))
+                .required(true),
+        )
+        .arg(
+            Arg::new("file2")
+                .value_name("FILE2")
+                .help(tr_str("New file", "新しいファイル"))
+                .required(true),
+        )
+        .arg(
+            Arg::new("unified")
+                .short('u')
+                .help(tr_str(
+                    "Output 3 lines of unified context (the default)",
+                    "unified形式で前後3行を出力する(既定)",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("context")
+                .value_name("NUM")
+                .short('U')
+                .long("unified")
+                .help(tr_str(
+                    "Output NUM lines of unified context",
+                    "unified形式で前後NUM行を出力する",
+                ))
+                .value_parser(value_parser!(u64))
+                .default_value("3"),
+        )
+        .arg(
+            Arg::new("brief")
+                .short('q')
+                .long("brief")
+                .help(tr_str(
+                    "Report only whether the files differ",
+                    "ファイルが異なるかどうかだけを報告する",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("color")
+                .value_name("WHEN")
+                .long("color")
+                .help(tr_str(
+                    "Colorize the output with word-level highlighting",
+                    "単語単位で強調して出力に色を付ける",
+                ))
+                .value_parser(["never", "always", "auto"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("auto")
+                .default_value("never"),
+        )
+        .get_tool_matches();
+
+    let color = match matches.get_one::<String>("color").unwrap().as_str() {
+        "always" => true,
+        "auto" => io::stdout().is_terminal(),
+        _ => false,
+    };
+
+    Ok(Config {
+        file1: matches.get_one::<String>("file1").unwrap().to_string(),
+        file2: matches.get_one::<String>("file2").unwrap().to_string(),
+        context: *matches.get_one::<u64>("context").unwrap() as usize,
+        brief: matches.get_flag("brief"),
+        color,
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<i32, Error> {
+    let bytes1 = read_file(&config.file1)?;
+    let bytes2 = read_file(&config.file2)?;
+    if bytes1 == bytes2 {
+        return Ok(0);
+    }
+    if config.brief {
+        writeln!(out, "Files {} and {} differ", config.file1, config.file2)?;
+        return Ok(1);
+    }
+    // UTF-8でないファイルは行単位で比べず、異なることだけを報告する
+    let (Ok(text1), Ok(text2)) = (std::str::from_utf8(&bytes1), std::str::from_utf8(&bytes2))
+    else {
+        writeln!(
+            out,
+            "Binary files {} and {} differ",
+            config.file1, config.file2
+        )?;
+        return Ok(1);
+    };
+
+    let lines1: Vec<&str> = text1.split_inclusive('\n').collect();
+    let lines2: Vec<&str> = text2.split_inclusive('\n').collect();
+    let edits = diff(&lines1, &lines2);
+
+    let header = |prefix: &str, filename: &str| {
+        let line = format!("{} {}\t{}", prefix, filename, modified_time(filename));
+        if config.color {
+            Style::new().bold().paint(line).to_string()
+        } else {
+            line
+        }
+    };
+    writeln!(out, "{}", header("---", &config.file1))?;
+    writeln!(out, "{}", header("+++", &config.file2))?;
+    for hunk in hunks(&edits, config.context) {
+        write!(
+            out,
+            "{}",
+            format_hunk(&hunk, &lines1, &lines2, config.color)
+        )?;
+    }
+    Ok(1)
+}
+
+fn read_file(filename: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![];
+    match filename {
+        "-" => io::stdin().read_to_end(&mut bytes),
+        _ => fs::File::open(filename).and_then(|mut file| file.read_to_end(&mut bytes)),
+    }
+    .map_err(|source| Error::Open {
+        path: filename.to_string(),
+        source,
+    })?;
+    Ok(bytes)
+}
+
+fn modified_time(filename: &str) -> String {
+    let time: DateTime<Local> = match fs::metadata(filename).and_then(|m| m.modified()) {
+        Ok(time) if filename != "-" => time.into(),
+        _ => Local::now(),
+    };
+    time.format("%Y-%m-%d %H:%M:%S%.9f %z").to_string()
+}
+
+/// Computes the shortest edit script turning `a` into `b` with the
+/// linear-space variant of Myers' O(ND) algorithm: each step finds the
+/// middle snake of the optimal path and recurses on both halves.
+fn diff<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    let max_d = (a.len() + b.len()).div_ceil(2) + 1;
+    let mut vf = V::new(max_d);
+    let mut vb = V::new(max_d);
+    let mut edits = Vec::with_capacity(a.len().max(b.len()));
+    conquer(a, 0..a.len(), b, 0..b.len(), &mut vf, &mut vb, &mut edits);
+
+    // 同じ変更箇所の中では削除を追加より先に並べる
+    for run in edits.split_mut(|edit| matches!(edit, Equal(..))) {
+        run.sort_by_key(|edit| matches!(edit, Insert(_)));
+    }
+    edits
+}
+
+/// A frontier of furthest-reaching x positions indexed by diagonal `k`,
+/// which may be negative.
+struct V {
+    offset: isize,
+    v: Vec<usize>,
+}
+
+impl V {
+    fn new(max_d: usize) -> Self {
+        V {
+            offset: max_d as isize,
+            v: vec![0; 2 * max_d + 1],
+        }
+    }
+}
+
+impl std::ops::Index<isize> for V {
+    type Output = usize;
+
+    fn index(&self, k: isize) -> &usize {
+        &self.v[(k + self.offset) as usize]
+    }
+}
+
+impl std::ops::IndexMut<isize> for V {
+    fn index_mut(&mut self, k: isize) -> &mut usize {
+        &mut self.v[(k + self.offset) as usize]
+    }
+}
+
+fn conquer<T: PartialEq>(
+    a: &[T],
+    mut range1: Range<usize>,
+    b: &[T],
+    mut range2: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+    edits: &mut Vec<Edit>,
+) {
+    while !range1.is_empty() && !range2.is_empty() && a[range1.start] == b[range2.start] {
+        edits.push(Equal(range1.start, range2.start));
+        range1.start += 1;
+        range2.start += 1;
+    }
+    let mut suffix = 0;
+    while !range1.is_empty() && !range2.is_empty() && a[range1.end - 1] == b[range2.end - 1] {
+        range1.end -= 1;
+        range2.end -= 1;
+        suffix += 1;
+    }
+
+    if range1.is_empty() {
+        edits.extend(range2.clone().map(Insert));
+    } else if range2.is_empty() {
+        edits.extend(range1.clone().map(Delete));
+    } else {
+        let (x, y) = middle_snake(a, range1.clone(), b, range2.clone(), vf, vb);
+        conquer(a, range1.start..x, b, range2.start..y, vf, vb, edits);
+        conquer(a, x..range1.end, b, y..range2.end, vf, vb, edits);
+    }
+    edits.extend((0..suffix).map(|i| Equal(range1.end + i, range2.end + i)));
+}
+
+/// Runs the search from both ends of the two ranges at once and returns
+/// where the forward and backward paths meet, as positions in `a` and `b`.
+/// Both ranges must be non-empty and differ in their first and last items.
+fn middle_snake<T: PartialEq>(
+    a: &[T],
+    range1: Range<usize>,
+    b: &[T],
+    range2: Range<usize>,
+    vf: &mut V,
+    vb: &mut V,
+) -> (usize, usize) {
+    let (n, m) = (range1.len(), range2.len());
+    let delta = n as isize - m as isize;
+    let odd = delta & 1 == 1;
+    vf[1] = 0;
+    vb[1] = 0;
+
+    let max_d = ((n + m).div_ceil(2) + 1) as isize;
+    for d in 0..max_d {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vf[k - 1] < vf[k + 1]) {
+                vf[k + 1]
+            } else {
+                vf[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[range1.start + x] == b[range2.start + y] {
+                x += 1;
+                y += 1;
+            }
+            vf[k] = x;
+            if odd && (k - delta).abs() < d && vf[k] + vb[delta - k] >= n {
+                return (range1.start + x0, range2.start + y0);
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && vb[k - 1] < vb[k + 1]) {
+                vb[k + 1]
+            } else {
+                vb[k - 1] + 1
+            };
+            let mut y = (x as isize - k) as usize;
+            while x < n && y < m && a[range1.end - x - 1] == b[range2.end - y - 1] {
+                x += 1;
+                y += 1;
+            }
+            vb[k] = x;
+            if !odd && (k - delta).abs() <= d && vb[k] + vf[delta - k] >= n {
+                return (range1.end - x, range2.end - y);
+            }
+        }
+    }
+    unreachable!("the forward and backward paths always meet")
+}
+
+/// Splits the edit script into hunks with `context` unchanged lines around
+/// each change, merging changes whose context would overlap.
+fn hunks(edits: &[Edit], context: usize) -> Vec<Hunk<'_>> {
+    // 各編集の直前までに消費した行数
+    let positions: Vec<(usize, usize)> = edits
+        .iter()
+        .scan((0, 0), |pos, edit| {
+            let before = *pos;
+            match edit {
+                Equal(..) => *pos = (pos.0 + 1, pos.1 + 1),
+                Delete(_) => pos.0 += 1,
+                Insert(_) => pos.1 += 1,
+            }
+            Some(before)
+        })
+        .collect();
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut hunks = vec![];
+    let mut iter = changes.iter().peekable();
+    while let Some(&first) = iter.next() {
+        let mut last = first;
+        while let Some(&&next) = iter.peek() {
+            if next - last > 2 * context + 1 {
+                break;
+            }
+            last = next;
+            iter.next();
+        }
+        let start = first.saturating_sub(context);
+        let end = (last + context + 1).min(edits.len());
+        hunks.push(Hunk {
+            start1: positions[start].0,
+            start2: positions[start].1,
+            edits: &edits[start..end],
+        });
+    }
+    hunks
+}
+
+fn format_hunk(hunk: &Hunk, lines1: &[&str], lines2: &[&str], color: bool) -> String {
+    let len1 = hunk
+        .edits
+        .iter()
+        .filter(|e| !matches!(e, Insert(_)))
+        .count();
+    let len2 = hunk
+        .edits
+        .iter()
+        .filter(|e| !matches!(e, Delete(_)))
+        .count();
+
+    let header = format!(
+        "@@ -{} +{} @@",
+        range(hunk.start1, len1),
+        range(hunk.start2, len2)
+    );
+    let mut out = if color {
+        Colour::Cyan.paint(header).to_string()
+    } else {
+        header
+    };
+    out.push('\n');
+
+    let edits = hunk.edits;
+    let mut i = 0;
+    while i < edits.len() {
+        if let Equal(a, _) = edits[i] {
+            out.push_str(&format_line(' ', lines1[a], None, color));
+            i += 1;
+            continue;
+        }
+        let deleted: Vec<&str> = edits[i..]
+            .iter()
+            .map_while(|e| match e {
+                Delete(a) => Some(lines1[*a]),
+                _ => None,
+            })
+            .collect();
+        let inserted: Vec<&str> = edits[i + deleted.len()..]
+            .iter()
+            .map_while(|e| match e {
+                Insert(b) => Some(lines2[*b]),
+                _ => None,
+            })
+            .collect();
+        i += deleted.len() + inserted.len();
+
+        // 削除行と追加行を順に対応付けて単語単位で強調する
+        for (n, line) in deleted.iter().enumerate() {
+            let pair = inserted
+                .get(n)
+                .filter(|_| color)
+                .map(|other| (*line, *other));
+            out.push_str(&format_line('-', line, pair, color));
+        }
+        for (n, line) in inserted.iter().enumerate() {
+            let pair = deleted
+                .get(n)
+                .filter(|_| color)
+                .map(|other| (*other, *line));
+            out.push_str(&format_line('+', line, pair, color));
+        }
+    }
+    out
+}
+
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+fn format_line(prefix: char, line: &str, pair: Option<(&str, &str)>, color: bool) -> String {
+    let (text, newline) = match line.strip_suffix('\n') {
+        Some(text) => (text, true),
+        None => (line, false),
+    };
+    let colour = match prefix {
+        '-' => Some(Colour::Red),
+        '+' => Some(Colour::Green),
+        _ => None,
+    };
+
+    let mut out = match (colour, color) {
+        (Some(colour), true) => {
+            let body = match pair {
+                Some((old, new)) => highlight_words(old, new, prefix == '-', colour),
+                None => colour.paint(text).to_string(),
+            };
+            format!("{}{}", colour.paint(prefix.to_string()), body)
+        }
+        _ => format!("{}{}", prefix, text),
+    };
+    out.push('\n');
+    if !newline {
+        out.push_str("\\ No newline at end of file\n");
+    }
+    out
+}
+
+fn highlight_words(old: &str, new: &str, deleted: bool, colour: Colour) -> String {
+    let old_words = tokenize(old.trim_end_matches('\n'));
+    let new_words = tokenize(new.trim_end_matches('\n'));
+    let plain = Style::new().fg(colour);
+    let changed = plain.reverse();
+
+    // 変更の有無が同じ単語をまとめてから色を付ける
+    let mut runs: Vec<(bool, String)> = vec![];
+    for edit in diff(&old_words, &new_words) {
+        let (is_changed, word) = match edit {
+            Equal(i, _) if deleted => (false, old_words[i]),
+            Equal(_, j) => (false, new_words[j]),
+            Delete(i) if deleted => (true, old_words[i]),
+            Insert(j) if !deleted => (true, new_words[j]),
+            _ => continue,
+        };
+        match runs.last_mut() {
+            Some((last, text)) if *last == is_changed => text.push_str(word),
+            _ => runs.push((is_changed, word.to_string())),
+        }
+    }
+    runs.into_iter()
+        .map(|(is_changed, text)| {
+            let style = if is_changed { changed } else { plain };
+            style.paint(text).to_string()
+        })
+        .collect()
+}
+
+/// Splits a line into runs of word characters, runs of whitespace and
+/// single punctuation characters.
+fn tokenize(line: &str) -> Vec<&str> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut prev = None;
+    for (i, c) in line.char_indices() {
+        let cls = class(c);
+        if i > start && (prev != Some(cls) || cls == 2) {
+            tokens.push(&line[start..i]);
+            start = i;
+        }
+        prev = Some(cls);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Edit::{Delete, Equal, Insert};
+    use super::{diff, hunks, tokenize, Hunk};
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff::<char>(&[], &[]), vec![]);
+        assert_eq!(diff(&['a'], &[]), vec![Delete(0)]);
+        assert_eq!(diff(&[], &['a']), vec![Insert(0)]);
+
+        let a: Vec<char> = "ABCABBA".chars().collect();
+        let b: Vec<char> = "CBABAC".chars().collect();
+        let edits = diff(&a, &b);
+
+        // Myersの例では編集距離は5になる
+        assert_eq!(edits.iter().filter(|e| !matches!(e, Equal(..))).count(), 5);
+        let mut rebuilt = vec![];
+        for edit in &edits {
+            match edit {
+                Equal(i, j) => {
+                    assert_eq!(a[*i], b[*j]);
+                    rebuilt.push(b[*j]);
+                }
+                Insert(j) => rebuilt.push(b[*j]),
+                Delete(_) => {}
+            }
+        }
+        assert_eq!(rebuilt, b);
+    }
+
+    #[test]
+    fn test_diff_minimal() {
+        // 動的計画法で求めたLCSの長さと比べ、編集が最短であることを確かめる
+        let lcs = |a: &[u8], b: &[u8]| {
+            let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+            for i in 0..a.len() {
+                for j in 0..b.len() {
+                    table[i + 1][j + 1] = if a[i] == b[j] {
+                        table[i][j] + 1
+                    } else {
+                        table[i][j + 1].max(table[i + 1][j])
+                    };
+                }
+            }
+            table[a.len()][b.len()]
+        };
+        let mut seed = 12345u32;
+        let mut next = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    b'a' + (seed >> 16) as u8 % 3
+                })
+                .collect()
+        };
+        for round in 0..200 {
+            let a = next(round % 13);
+            let b = next(round % 7 + round % 5);
+            let edits = diff(&a, &b);
+            let equal = edits.iter().filter(|e| matches!(e, Equal(..))).count();
+            assert_eq!(equal, lcs(&a, &b), "{:?} {:?}", a, b);
+            assert_eq!(edits.len(), a.len() + b.len() - equal);
+            let (mut i, mut j) = (0, 0);
+            for edit in edits {
+                match edit {
+                    Equal(x, y) => {
+                        assert_eq!((x, y), (i, j));
+                        assert_eq!(a[x], b[y]);
+                        (i, j) = (i + 1, j + 1);
+                    }
+                    Delete(x) => {
+                        assert_eq!(x, i);
+                        i += 1;
+                    }
+                    Insert(y) => {
+                        assert_eq!(y, j);
+                        j += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hunks() {
+        let edits = [
+            Equal(0, 0),
+            Delete(1),
+            Equal(2, 1),
+            Equal(3, 2),
+            Equal(4, 3),
+            Insert(4),
+        ];
+        assert_eq!(
+            hunks(&edits, 1),
+            vec![
+                Hunk {
+                    start1: 0,
+                    start2: 0,
+                    edits: &edits[0..3],
+                },
+                Hunk {
+                    start1: 4,
+                    start2: 3,
+                    edits: &edits[4..6],
+                },
+            ]
+        );
+
+        // 文脈が重なる変更は1つにまとめる
+        assert_eq!(
+            hunks(&edits, 2),
+            vec![Hunk {
+                start1: 0,
+                start2: 0,
+                edits: &edits[..],
+            }]
+        );
+        assert_eq!(hunks(&edits[..1], 3), vec![]);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("foo_bar  baz(1, 2);"),
+            vec!["foo_bar", "  ", "baz", "(", "1", ",", " ", "2", ")", ";"]
+        );
+        assert_eq!(tokenize(""), Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,158 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "diffr";
+const OLD: &str = "tests/inputs/old.txt";
+const NEW: &str = "tests/inputs/new.txt";
+const FOX: &str = "tests/inputs/fox.txt";
+const FOX2: &str = "tests/inputs/fox2.txt";
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([OLD, "does-not-exist"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::is_match(
+            "does-not-exist: .* [(]os error 2[)]",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn identical() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([OLD, OLD])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn brief() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-q", OLD, NEW])
+        .assert()
+        .code(1)
+        .stdout(format!("Files {} and {} differ\n", OLD, NEW));
+
+    Command::cargo_bin(PRG)?
+        .args(["--brief", OLD, OLD])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-", OLD])
+        .write_stdin(&b"\xff\xfe\n"[..])
+        .assert()
+        .code(1)
+        .stdout(format!("Binary files - and {} differ\n", OLD));
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read_to_string(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output()?;
+    assert_eq!(output.status.code(), Some(1));
+
+    // ヘッダーの更新時刻は環境によって変わるので本文だけ比べる
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut lines = stdout.split_inclusive('\n');
+    let file1 = args[args.len() - 2];
+    let file2 = args[args.len() - 1];
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with(&format!("--- {}\t", file1)));
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with(&format!("+++ {}\t", file2)));
+    assert_eq!(lines.collect::<String>(), expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn old_new() -> TestResult {
+    run(&["-u", OLD, NEW], "tests/expected/old.new.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn old_new_u1() -> TestResult {
+    run(&["-U", "1", OLD, NEW], "tests/expected/old.new.U1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn old_new_u0() -> TestResult {
+    run(
+        &["--unified", "0", OLD, NEW],
+        "tests/expected/old.new.U0.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_fox2() -> TestResult {
+    run(&[FOX, FOX2], "tests/expected/fox.fox2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/old.new.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["-", NEW])
+        .write_stdin(fs::read_to_string(OLD)?)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::ends_with(expected));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn color() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--color=always", FOX, FOX2])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "\u{1b}[36m@@ -1,2 +1,2 @@\u{1b}[0m\n",
+        ))
+        .stdout(predicate::str::contains("\u{1b}[7;31mbrown\u{1b}[0m"))
+        .stdout(predicate::str::contains("\u{1b}[7;32mred\u{1b}[0m"));
+
+    Command::cargo_bin(PRG)?
+        .args(["--color", FOX, FOX2])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("\u{1b}[").not());
+    Ok(())
+}
@@ -0,0 +1,513 @@
+use std::io;
+use std::io::{BufRead, IsTerminal, Write};
+use std::time::Duration;
+
+use clap::ArgAction::SetTrue;
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{open, CommandExt, ToolError, EXIT_FAILURE};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::{cursor, execute, queue, terminal};
+use regex::Regex;
+use thiserror::Error;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug)]
+pub struct Config {
+    file: String,
+    line_numbers: bool,
+    follow: bool,
+    pattern: Option<Regex>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error(transparent)]
+    Pattern(#[from] grepr::Error),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::Pattern(e) => e.exit_code(),
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("pagr")
+        .version("0.1.0")
+        .about("Rust pager")
+        .arg(
+            Arg::new("file")
+                .value_name("FILE")
+                .help(tr_str("Input file", "入力ファイル"))
+                .default_value("-"),
+        )
+        .arg(
+            Arg::new("line_numbers")
+                .short('N')
+                .long("line-numbers")
+                .help(tr_str("Show line numbers", "行番号を表示する"))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("follow")
+                .short('F')
+                .long("follow")
+                .help(tr_str(
+                    "Keep reading as the file grows",
+                    "ファイルが伸びるのに合わせて読み続ける",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("pattern")
+                .value_name("PATTERN")
+                .short('p')
+                .long("pattern")
+                .help(tr_str(
+                    "Start at the first line matching PATTERN",
+                    "PATTERNに一致する最初の行から始める",
+                )),
+        )
+        .get_tool_matches();
+
+    let pattern = matches
+        .get_one::<String>("pattern")
+        .map(|p| grepr::build_pattern(p, false))
+        .transpose()?;
+
+    Ok(Config {
+        file: matches.get_one::<String>("file").unwrap().to_string(),
+        line_numbers: matches.get_flag("line_numbers"),
+        follow: matches.get_flag("follow"),
+        pattern,
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let mut input = open(&config.file).map_err(|source| Error::Open {
+        path: config.file.clone(),
+        source,
+    })?;
+
+    // 端末でなければページングせずにそのまま書き出す
+    if !io::stdout().is_terminal() {
+        let mut line = String::new();
+        let mut line_num = 0;
+        while input.read_line(&mut line)? > 0 {
+            line_num += 1;
+            if config.line_numbers {
+                write!(out, "{:>6} ", line_num)?;
+            }
+            out.write_all(line.as_bytes())?;
+            line.clear();
+        }
+        return Ok(());
+    }
+
+    let mut pager = Pager::new(config.line_numbers);
+    pager.follow = config.follow;
+    pager.read_more(&mut input)?;
+    if let Some(pattern) = config.pattern {
+        pager.pattern = Some(pattern);
+        pager.search_forward(0);
+    }
+    if pager.follow {
+        pager.scroll_to_end();
+    }
+
+    let _screen = Screen::enter()?;
+    let mut redraw = true;
+    loop {
+        if redraw {
+            let (width, height) = terminal::size()?;
+            pager.height = (height as usize).saturating_sub(1).max(1);
+            pager.render(out, width as usize)?;
+        }
+
+        // 入力がなければ追記を確認するだけで再描画しない
+        if !event::poll(POLL_INTERVAL)? {
+            redraw = pager.follow && pager.read_more(&mut input)? > 0;
+            if redraw {
+                pager.scroll_to_end();
+            }
+            continue;
+        }
+        redraw = true;
+        match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if let Some(prompt) = pager.prompt.as_mut() {
+                    match key.code {
+                        KeyCode::Enter => pager.submit_search(),
+                        KeyCode::Esc => pager.prompt = None,
+                        KeyCode::Backspace if prompt.is_empty() => pager.prompt = None,
+                        KeyCode::Backspace => {
+                            prompt.pop();
+                        }
+                        KeyCode::Char(c) => prompt.push(c),
+                        _ => {}
+                    }
+                } else if !pager.handle_key(key) {
+                    break;
+                }
+            }
+            Event::Resize(..) => {}
+            _ => redraw = false,
+        }
+    }
+    Ok(())
+}
+
+/// Switches the terminal to raw mode on an alternate screen and restores
+/// it when dropped, including on early returns.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[derive(Debug)]
+struct Pager {
+    lines: Vec<String>,
+    partial: String,
+    top: usize,
+    height: usize,
+    line_numbers: bool,
+    follow: bool,
+    pattern: Option<Regex>,
+    prompt: Option<String>,
+    message: Option<String>,
+}
+
+impl Pager {
+    fn new(line_numbers: bool) -> Pager {
+        Pager {
+            lines: vec![],
+            partial: String::new(),
+            top: 0,
+            height: 1,
+            line_numbers,
+            follow: false,
+            pattern: None,
+            prompt: None,
+            message: None,
+        }
+    }
+
+    /// Appends whatever the reader has available, holding back an
+    /// unterminated last line until the rest of it arrives.
+    fn read_more(&mut self, input: &mut dyn BufRead) -> io::Result<usize> {
+        let mut buf = String::new();
+        input.read_to_string(&mut buf)?;
+        let before = self.lines.len();
+        self.partial.push_str(&buf);
+        while let Some(pos) = self.partial.find('\n') {
+            let rest = self.partial.split_off(pos + 1);
+            let mut line = std::mem::replace(&mut self.partial, rest);
+            line.pop();
+            self.lines.push(line.trim_end_matches('\r').to_string());
+        }
+        if !self.follow && !self.partial.is_empty() {
+            self.lines.push(std::mem::take(&mut self.partial));
+        }
+        Ok(self.lines.len() - before)
+    }
+
+    fn max_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        self.top = (self.top + n).min(self.max_top());
+    }
+
+    fn scroll_up(&mut self, n: usize) {
+        self.top = self.top.saturating_sub(n);
+    }
+
+    fn scroll_to_end(&mut self) {
+        self.top = self.max_top();
+    }
+
+    /// Returns false when the user asked to quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.message = None;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => return false,
+            KeyCode::Char('c') if ctrl => return false,
+            KeyCode::Char('f') if ctrl => self.scroll_down(self.height),
+            KeyCode::Char('b') if ctrl => self.scroll_up(self.height),
+            KeyCode::Char(' ') | KeyCode::Char('f') | KeyCode::PageDown => {
+                self.scroll_down(self.height)
+            }
+            KeyCode::Char('b') | KeyCode::PageUp => self.scroll_up(self.height),
+            KeyCode::Char('d') => self.scroll_down(self.height / 2),
+            KeyCode::Char('u') => self.scroll_up(self.height / 2),
+            KeyCode::Char('j') | KeyCode::Enter | KeyCode::Down => self.scroll_down(1),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_up(1),
+            KeyCode::Char('g') | KeyCode::Char('<') | KeyCode::Home => self.top = 0,
+            KeyCode::Char('G') | KeyCode::Char('>') | KeyCode::End => self.scroll_to_end(),
+            KeyCode::Char('F') => {
+                self.follow = !self.follow;
+                if self.follow {
+                    self.scroll_to_end();
+                }
+            }
+            KeyCode::Char('/') => self.prompt = Some(String::new()),
+            KeyCode::Char('n') => self.search_forward(self.top + 1),
+            KeyCode::Char('N') => self.search_backward(self.top),
+            _ => {}
+        }
+        true
+    }
+
+    fn submit_search(&mut self) {
+        let text = self.prompt.take().unwrap_or_default();
+        if !text.is_empty() {
+            match grepr::build_pattern(&text, false) {
+                Ok(pattern) => self.pattern = Some(pattern),
+                Err(e) => {
+                    self.message = Some(e.to_string());
+                    return;
+                }
+            }
+        }
+        self.search_forward(self.top + 1);
+    }
+
+    fn search_forward(&mut self, from: usize) {
+        let found = self.pattern.as_ref().map(|pattern| {
+            (from..self.lines.len()).find(|&i| grepr::is_match(&self.lines[i], pattern, false))
+        });
+        self.jump_to(found);
+    }
+
+    fn search_backward(&mut self, before: usize) {
+        let found = self.pattern.as_ref().map(|pattern| {
+            (0..before.min(self.lines.len()))
+                .rev()
+                .find(|&i| grepr::is_match(&self.lines[i], pattern, false))
+        });
+        self.jump_to(found);
+    }
+
+    fn jump_to(&mut self, found: Option<Option<usize>>) {
+        match found {
+            None => self.message = Some("No previous search pattern".to_string()),
+            Some(None) => self.message = Some("Pattern not found".to_string()),
+            Some(Some(line)) => self.top = line,
+        }
+    }
+
+    fn status(&self) -> String {
+        if let Some(prompt) = &self.prompt {
+            format!("/{}", prompt)
+        } else if let Some(message) = &self.message {
+            message.to_string()
+        } else if self.follow {
+            "Waiting for data... (press F to stop)".to_string()
+        } else if self.top >= self.max_top() {
+            "(END)".to_string()
+        } else {
+            ":".to_string()
+        }
+    }
+
+    fn render(&self, out: &mut impl Write, width: usize) -> io::Result<()> {
+        queue!(
+            out,
+            cursor::MoveTo(0, 0),
+            terminal::Clear(terminal::ClearType::All)
+        )?;
+        let end = (self.top + self.height).min(self.lines.len());
+        for (row, i) in (self.top..end).enumerate() {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            let number = self.line_numbers.then_some(i + 1);
+            for (text, highlight) in
+                format_line(&self.lines[i], number, width, self.pattern.as_ref())
+            {
+                if highlight {
+                    queue!(
+                        out,
+                        SetAttribute(Attribute::Reverse),
+                        Print(text),
+                        SetAttribute(Attribute::Reset)
+                    )?;
+                } else {
+                    queue!(out, Print(text))?;
+                }
+            }
+        }
+        queue!(
+            out,
+            cursor::MoveTo(0, self.height as u16),
+            SetAttribute(Attribute::Reverse),
+            Print(self.status()),
+            SetAttribute(Attribute::Reset)
+        )?;
+        out.flush()
+    }
+}
+
+/// Cuts a line to the screen width and splits it into plain and
+/// highlighted segments for the current search pattern.
+fn format_line(
+    line: &str,
+    number: Option<usize>,
+    width: usize,
+    pattern: Option<&Regex>,
+) -> Vec<(String, bool)> {
+    let mut text: String = match number {
+        Some(n) => format!("{:>6} {}", n, line.replace('\t', "    ")),
+        None => line.replace('\t', "    "),
+    };
+    if let Some((cut, _)) = text.char_indices().nth(width) {
+        text.truncate(cut);
+    }
+
+    let prefix = if number.is_some() {
+        7.min(text.len())
+    } else {
+        0
+    };
+    let mut segments = vec![(text[..prefix].to_string(), false)];
+    let mut last = prefix;
+    if let Some(pattern) = pattern {
+        for m in pattern.find_iter(&text[prefix..]) {
+            let (start, end) = (prefix + m.start(), prefix + m.end());
+            if start == end {
+                continue;
+            }
+            segments.push((text[last..start].to_string(), false));
+            segments.push((text[start..end].to_string(), true));
+            last = end;
+        }
+    }
+    segments.push((text[last..].to_string(), false));
+    segments.retain(|(text, _)| !text.is_empty());
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use regex::Regex;
+
+    use super::{format_line, Pager};
+
+    fn pager(lines: usize, height: usize) -> Pager {
+        let text: String = (1..=lines).map(|i| format!("line {}\n", i)).collect();
+        let mut pager = Pager::new(false);
+        pager.read_more(&mut Cursor::new(text)).unwrap();
+        pager.height = height;
+        pager
+    }
+
+    fn press(pager: &mut Pager, code: KeyCode) -> bool {
+        pager.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_paging() {
+        let mut pager = pager(25, 10);
+        press(&mut pager, KeyCode::Char(' '));
+        assert_eq!(pager.top, 10);
+
+        // 最後のページを越えてスクロールしない
+        press(&mut pager, KeyCode::Char(' '));
+        assert_eq!(pager.top, 15);
+        assert_eq!(pager.status(), "(END)");
+
+        press(&mut pager, KeyCode::Char('b'));
+        assert_eq!(pager.top, 5);
+        press(&mut pager, KeyCode::Up);
+        assert_eq!(pager.top, 4);
+        press(&mut pager, KeyCode::Char('g'));
+        assert_eq!(pager.top, 0);
+        press(&mut pager, KeyCode::Char('b'));
+        assert_eq!(pager.top, 0);
+        press(&mut pager, KeyCode::Char('G'));
+        assert_eq!(pager.top, 15);
+
+        assert!(!press(&mut pager, KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn test_search() {
+        let mut pager = pager(30, 5);
+        press(&mut pager, KeyCode::Char('n'));
+        assert_eq!(pager.status(), "No previous search pattern");
+
+        pager.prompt = Some(r"line 2\d".to_string());
+        pager.submit_search();
+        assert_eq!(pager.top, 19);
+        press(&mut pager, KeyCode::Char('n'));
+        assert_eq!(pager.top, 20);
+        press(&mut pager, KeyCode::Char('N'));
+        assert_eq!(pager.top, 19);
+        press(&mut pager, KeyCode::Char('N'));
+        assert_eq!(pager.status(), "Pattern not found");
+        assert_eq!(pager.top, 19);
+
+        pager.prompt = Some("(".to_string());
+        pager.submit_search();
+        assert_eq!(pager.status(), "Invalid pattern \"(\"");
+    }
+
+    #[test]
+    fn test_read_more() {
+        let mut pager = Pager::new(false);
+        pager.follow = true;
+        assert_eq!(pager.read_more(&mut Cursor::new("a\r\nb")).unwrap(), 1);
+        assert_eq!(pager.lines, ["a"]);
+
+        // 追記された残りで行が完成する
+        assert_eq!(pager.read_more(&mut Cursor::new("c\n")).unwrap(), 1);
+        assert_eq!(pager.lines, ["a", "bc"]);
+    }
+
+    #[test]
+    fn test_format_line() {
+        let re = Regex::new("o").unwrap();
+        assert_eq!(
+            format_line("foo\tbar", None, 80, None),
+            vec![("foo    bar".to_string(), false)]
+        );
+        assert_eq!(
+            format_line("foo", Some(12), 80, Some(&re)),
+            vec![
+                ("    12 ".to_string(), false),
+                ("f".to_string(), false),
+                ("o".to_string(), true),
+                ("o".to_string(), true),
+            ]
+        );
+        assert_eq!(
+            format_line("hello world", None, 5, Some(&re)),
+            vec![("hell".to_string(), false), ("o".to_string(), true)]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "pagr";
+const NUMBERS: &str = "tests/inputs/numbers.txt";
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("does-not-exist")
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(
+            "does-not-exist: .* [(]os error 2[)]",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_pattern() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-p", "(", NUMBERS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid pattern \"(\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn passes_through_when_not_a_terminal() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg(NUMBERS)
+        .assert()
+        .success()
+        .stdout("one\ntwo\nthree\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn line_numbers() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("-N")
+        .write_stdin(fs::read_to_string(NUMBERS)?)
+        .assert()
+        .success()
+        .stdout("     1 one\n     2 two\n     3 three\n");
+    Ok(())
+}
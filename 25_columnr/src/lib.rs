@@ -0,0 +1,286 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+
+use clap::ArgAction::{Append, SetTrue};
+use clap::{value_parser, Arg, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{open, warn_to, CommandExt, ToolError, EXIT_FAILURE};
+use thiserror::Error;
+use unicode_width::UnicodeWidthStr;
+
+const DEFAULT_WIDTH: usize = 80;
+
+#[derive(Debug)]
+pub struct Config {
+    files: Vec<String>,
+    table: bool,
+    separators: Option<String>,
+    output_separator: String,
+    width: usize,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("columnr")
+        .version("0.1.0")
+        .about("Rust column")
+        .arg(
+            Arg::new("files")
+                .value_name("FILE")
+                .help(tr_str("Input file(s)", "入力ファイル"))
+                .action(Append)
+                .default_value("-"),
+        )
+        .arg(
+            Arg::new("table")
+                .short('t')
+                .long("table")
+                .help(tr_str(
+                    "Align delimited input into a table",
+                    "区切られた入力を表に揃える",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("separators")
+                .value_name("SEPARATORS")
+                .short('s')
+                .long("separator")
+                .help(tr_str(
+                    "Characters that delimit input columns [default: whitespace]",
+                    "入力の列を区切る文字 [既定: 空白]",
+                )),
+        )
+        .arg(
+            Arg::new("output_separator")
+                .value_name("STRING")
+                .short('o')
+                .long("output-separator")
+                .help(tr_str(
+                    "String placed between table columns",
+                    "表の列のあいだに置く文字列",
+                ))
+                .default_value("  "),
+        )
+        .arg(
+            Arg::new("width")
+                .value_name("WIDTH")
+                .short('c')
+                .long("columns")
+                .help(tr_str(
+                    "Output width when filling columns",
+                    "列を埋めるときの出力幅",
+                ))
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .get_tool_matches();
+
+    let width = match matches.get_one::<u64>("width") {
+        Some(width) => *width as usize,
+        None => env::var("COLUMNS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|w| *w > 0)
+            .unwrap_or(DEFAULT_WIDTH),
+    };
+
+    Ok(Config {
+        files: matches
+            .get_many::<String>("files")
+            .unwrap()
+            .map(|v| v.to_string())
+            .collect(),
+        table: matches.get_flag("table"),
+        separators: matches.get_one::<String>("separators").cloned(),
+        output_separator: matches
+            .get_one::<String>("output_separator")
+            .unwrap()
+            .to_string(),
+        width,
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    let mut lines = vec![];
+    for filename in &config.files {
+        match open(filename) {
+            Err(e) => {
+                warn_to(err, "columnr", &format_args!("{}: {}", filename, tr_io(&e)));
+                status = EXIT_FAILURE;
+            }
+            Ok(file) => {
+                for line in file.lines() {
+                    let line = line?;
+                    if !line.trim().is_empty() {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+    }
+
+    let output = if config.table {
+        let rows: Vec<Vec<&str>> = lines
+            .iter()
+            .map(|line| split_row(line, config.separators.as_deref()))
+            .collect();
+        format_table(&rows, &config.output_separator)
+    } else {
+        fill_columns(&lines, config.width)
+    };
+    for line in output {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(status)
+}
+
+fn split_row<'a>(line: &'a str, separators: Option<&str>) -> Vec<&'a str> {
+    match separators {
+        None => line.split_whitespace().collect(),
+        Some(seps) => line.split(|c| seps.contains(c)).collect(),
+    }
+}
+
+fn is_number(cell: &str) -> bool {
+    cell.parse::<f64>().is_ok() && cell.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Decides which columns hold numbers and should be right-aligned. A
+/// non-numeric first row is treated as a header and does not count.
+fn numeric_columns(rows: &[Vec<&str>], num_columns: usize) -> Vec<bool> {
+    (0..num_columns)
+        .map(|col| {
+            let mut cells = rows
+                .iter()
+                .enumerate()
+                .filter_map(|(i, row)| row.get(col).map(|cell| (i, *cell)))
+                .filter(|(_, cell)| !cell.is_empty())
+                .peekable();
+            if matches!(cells.peek(), Some((0, cell)) if !is_number(cell)) {
+                cells.next();
+            }
+            let mut any = false;
+            for (_, cell) in cells {
+                if !is_number(cell) {
+                    return false;
+                }
+                any = true;
+            }
+            any
+        })
+        .collect()
+}
+
+fn format_table(rows: &[Vec<&str>], separator: &str) -> Vec<String> {
+    let num_columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut widths = vec![0; num_columns];
+    for row in rows {
+        for (col, cell) in row.iter().enumerate() {
+            widths[col] = widths[col].max(cell.width());
+        }
+    }
+    let numeric = numeric_columns(rows, num_columns);
+
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(col, cell)| {
+                    let padding = " ".repeat(widths[col] - cell.width());
+                    if numeric[col] {
+                        format!("{}{}", padding, cell)
+                    } else if col + 1 == row.len() {
+                        // 行末に空白を残さない
+                        cell.to_string()
+                    } else {
+                        format!("{}{}", cell, padding)
+                    }
+                })
+                .collect();
+            cells.join(separator)
+        })
+        .collect()
+}
+
+/// Lays entries out down the columns, fitting as many columns of the
+/// widest entry (plus two spaces) as the width allows.
+fn fill_columns(entries: &[String], width: usize) -> Vec<String> {
+    if entries.is_empty() {
+        return vec![];
+    }
+    let column_width = entries.iter().map(|e| e.width()).max().unwrap_or(0) + 2;
+    let num_columns = (width / column_width).max(1);
+    let num_rows = entries.len().div_ceil(num_columns);
+
+    (0..num_rows)
+        .map(|row| {
+            let cells: Vec<&String> = entries.iter().skip(row).step_by(num_rows).collect();
+            let mut line = String::new();
+            for (i, cell) in cells.iter().enumerate() {
+                line.push_str(cell);
+                if i + 1 < cells.len() {
+                    line.push_str(&" ".repeat(column_width - cell.width()));
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fill_columns, format_table, numeric_columns, split_row};
+
+    #[test]
+    fn test_split_row() {
+        assert_eq!(split_row("  a  b\tc ", None), vec!["a", "b", "c"]);
+        assert_eq!(split_row("a,,b;c", Some(",;")), vec!["a", "", "b", "c"]);
+    }
+
+    #[test]
+    fn test_numeric_columns() {
+        let rows = vec![
+            vec!["name", "size", "id"],
+            vec!["foo", "10", "x1"],
+            vec!["barbaz", "-2.5", ""],
+        ];
+        assert_eq!(numeric_columns(&rows, 3), vec![false, true, false]);
+
+        // 見出しだけの列は数値とみなさない
+        assert_eq!(numeric_columns(&[vec!["a"]], 1), vec![false]);
+        assert_eq!(numeric_columns(&[vec!["1"], vec!["nan"]], 1), vec![false]);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![
+            vec!["name", "size"],
+            vec!["foo", "10"],
+            vec!["日本語", "1234", "extra"],
+        ];
+        assert_eq!(
+            format_table(&rows, " | "),
+            vec!["name   | size", "foo    |   10", "日本語 | 1234 | extra",]
+        );
+    }
+
+    #[test]
+    fn test_fill_columns() {
+        let entries: Vec<String> = ["a", "bb", "c", "d", "e"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(fill_columns(&entries, 12), vec!["a   c   e", "bb  d"]);
+        assert_eq!(fill_columns(&entries, 1).len(), 5);
+        assert!(fill_columns(&[], 80).is_empty());
+    }
+}
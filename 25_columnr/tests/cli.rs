@@ -0,0 +1,85 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const PRG: &str = "columnr";
+const MOVIES: &str = "tests/inputs/movies1.tsv";
+const PS: &str = "tests/inputs/ps.txt";
+const FRUITS: &str = "tests/inputs/fruits.txt";
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_width() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "0", FRUITS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '0'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-t", "does-not-exist", PS])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::is_match(
+            "does-not-exist: .* [(]os error 2[)]",
+        )?)
+        .stdout(fs::read_to_string("tests/expected/ps.txt.t.out")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+fn run(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read_to_string(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn movies_tab_separated() -> TestResult {
+    run(
+        &["-t", "-s", "\t", MOVIES],
+        "tests/expected/movies1.tsv.t.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ps_table() -> TestResult {
+    run(&["--table", PS], "tests/expected/ps.txt.t.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn ps_output_separator() -> TestResult {
+    run(&["-t", "-o", " | ", PS], "tests/expected/ps.txt.t.o.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn fruits_fill() -> TestResult {
+    run(&["-c", "30", FRUITS], "tests/expected/fruits.txt.c30.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-t", "-s", ","])
+        .write_stdin("a,1\nbbb,22\n")
+        .assert()
+        .success()
+        .stdout("a     1\nbbb  22\n");
+    Ok(())
+}
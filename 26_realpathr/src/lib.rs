@@ -0,0 +1,319 @@
+use std::collections::VecDeque;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::io::{ErrorKind, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+
+use clap::ArgAction::{Append, SetTrue};
+use clap::{Arg, ArgGroup, Command};
+use clir_common::i18n::{tr_io, tr_str};
+use clir_common::{warn_to, CommandExt, ToolError, EXIT_FAILURE};
+use thiserror::Error;
+
+const MAX_SYMLINKS: usize = 40;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Missing {
+    Forbidden,
+    LastOnly,
+    Allowed,
+}
+
+#[derive(Debug)]
+pub struct Config {
+    paths: Vec<String>,
+    missing: Missing,
+    physical: bool,
+    relative_to: Option<String>,
+    zero: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{path}: {}", tr_io(.source))]
+    Resolve { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Root,
+    Parent,
+    Name(OsString),
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("realpathr")
+        .version("0.1.0")
+        .about("Rust realpath")
+        .arg(
+            Arg::new("paths")
+                .value_name("PATH")
+                .help(tr_str("Path(s) to resolve", "解決するパス"))
+                .required(true)
+                .action(Append),
+        )
+        .arg(
+            Arg::new("canonicalize_existing")
+                .short('e')
+                .long("canonicalize-existing")
+                .help(tr_str(
+                    "All components of the path must exist",
+                    "パスの要素がすべて存在しなければならない",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("canonicalize_missing")
+                .short('m')
+                .long("canonicalize-missing")
+                .help(tr_str(
+                    "No path components need exist",
+                    "パスの要素が存在しなくてもよい",
+                ))
+                .action(SetTrue),
+        )
+        .group(ArgGroup::new("missing").args(["canonicalize_existing", "canonicalize_missing"]))
+        .arg(
+            Arg::new("no_symlinks")
+                .short('s')
+                .long("no-symlinks")
+                .visible_alias("strip")
+                .help(tr_str(
+                    "Don't expand symlinks",
+                    "シンボリックリンクを展開しない",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("relative_to")
+                .value_name("DIR")
+                .long("relative-to")
+                .help(tr_str(
+                    "Print the resolved path relative to DIR",
+                    "解決したパスをDIRからの相対パスで表示する",
+                )),
+        )
+        .arg(
+            Arg::new("zero")
+                .short('z')
+                .long("zero")
+                .help(tr_str(
+                    "End each output line with NUL, not newline",
+                    "出力の各行を改行ではなくNULで終える",
+                ))
+                .action(SetTrue),
+        )
+        .get_tool_matches();
+
+    let missing = if matches.get_flag("canonicalize_existing") {
+        Missing::Forbidden
+    } else if matches.get_flag("canonicalize_missing") {
+        Missing::Allowed
+    } else {
+        Missing::LastOnly
+    };
+
+    Ok(Config {
+        paths: matches
+            .get_many::<String>("paths")
+            .expect("paths required")
+            .map(|v| v.to_string())
+            .collect(),
+        missing,
+        physical: !matches.get_flag("no_symlinks"),
+        relative_to: matches.get_one::<String>("relative_to").cloned(),
+        zero: matches.get_flag("zero"),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut status = 0;
+    let base = config
+        .relative_to
+        .as_ref()
+        .map(|dir| {
+            resolve(Path::new(dir), config.missing, config.physical).map_err(|source| {
+                Error::Resolve {
+                    path: dir.to_string(),
+                    source,
+                }
+            })
+        })
+        .transpose()?;
+    let terminator = if config.zero { b'\0' } else { b'\n' };
+
+    for path in &config.paths {
+        match resolve(Path::new(path), config.missing, config.physical) {
+            Err(e) => {
+                warn_to(err, "realpathr", &format_args!("{}: {}", path, tr_io(&e)));
+                status = EXIT_FAILURE;
+            }
+            Ok(resolved) => {
+                let resolved = match &base {
+                    Some(base) => relative_to(&resolved, base),
+                    None => resolved,
+                };
+                out.write_all(resolved.as_os_str().as_bytes())?;
+                out.write_all(&[terminator])?;
+            }
+        }
+    }
+    Ok(status)
+}
+
+fn parts(path: &Path) -> Vec<Part> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::RootDir => Some(Part::Root),
+            Component::ParentDir => Some(Part::Parent),
+            Component::Normal(name) => Some(Part::Name(name.to_os_string())),
+            Component::CurDir | Component::Prefix(_) => None,
+        })
+        .collect()
+}
+
+/// Makes `path` absolute, removing `.` and `..` and, when `physical` is
+/// set, replacing symlinks with their targets one component at a time.
+fn resolve(path: &Path, missing: Missing, physical: bool) -> io::Result<PathBuf> {
+    if path.as_os_str().is_empty() {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            "No such file or directory",
+        ));
+    }
+    let mut result = if path.is_absolute() {
+        PathBuf::from("/")
+    } else {
+        env::current_dir()?
+    };
+    let mut queue: VecDeque<Part> = parts(path).into();
+    let mut links = 0;
+
+    while let Some(part) = queue.pop_front() {
+        let name = match part {
+            Part::Root => {
+                result = PathBuf::from("/");
+                continue;
+            }
+            Part::Parent => {
+                result.pop();
+                continue;
+            }
+            Part::Name(name) => name,
+        };
+        result.push(name);
+
+        let is_last = !queue.iter().any(|p| matches!(p, Part::Name(_)));
+        match fs::symlink_metadata(&result) {
+            Ok(metadata) if physical && metadata.file_type().is_symlink() => {
+                links += 1;
+                if links > MAX_SYMLINKS {
+                    return Err(io::Error::other("Too many levels of symbolic links"));
+                }
+                let target = fs::read_link(&result)?;
+                result.pop();
+                for part in parts(&target).into_iter().rev() {
+                    queue.push_front(part);
+                }
+            }
+            Ok(_) if is_last || missing == Missing::Allowed => {}
+            Ok(_) => {
+                // -sでは解決しないリンクもディレクトリを指していればよい
+                if !fs::metadata(&result).is_ok_and(|m| m.is_dir()) {
+                    return Err(io::Error::other("Not a directory"));
+                }
+            }
+            Err(e) => match missing {
+                Missing::Allowed => {}
+                Missing::LastOnly if is_last && e.kind() == ErrorKind::NotFound => {}
+                _ => return Err(e),
+            },
+        }
+    }
+    Ok(result)
+}
+
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::path::{Path, PathBuf};
+
+    use tempfile::TempDir;
+
+    use super::{relative_to, resolve, Missing};
+
+    #[test]
+    fn test_relative_to() {
+        let rel = |a: &str, b: &str| relative_to(Path::new(a), Path::new(b));
+        assert_eq!(rel("/a/b/c", "/a/b"), PathBuf::from("c"));
+        assert_eq!(rel("/a/b", "/a/b"), PathBuf::from("."));
+        assert_eq!(rel("/a/x/y", "/a/b/c"), PathBuf::from("../../x/y"));
+        assert_eq!(rel("/", "/a"), PathBuf::from(".."));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = TempDir::new().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join("real")).unwrap();
+        fs::write(root.join("real/file"), "").unwrap();
+        symlink("real", root.join("link")).unwrap();
+        symlink("loop", root.join("loop")).unwrap();
+
+        let link = root.join("link/./file");
+        assert_eq!(
+            resolve(&link, Missing::Forbidden, true).unwrap(),
+            root.join("real/file")
+        );
+        assert_eq!(
+            resolve(&link, Missing::Forbidden, false).unwrap(),
+            root.join("link/file")
+        );
+
+        // シンボリックリンクを解決してから「..」をたどる
+        let parent = root.join("link/../real");
+        assert_eq!(
+            resolve(&parent, Missing::Forbidden, true).unwrap(),
+            root.join("real")
+        );
+
+        let missing = root.join("real/missing");
+        assert!(resolve(&missing, Missing::Forbidden, true).is_err());
+        assert_eq!(resolve(&missing, Missing::LastOnly, true).unwrap(), missing);
+        let deep = root.join("missing/../x/y");
+        assert!(resolve(&deep, Missing::LastOnly, true).is_err());
+        assert_eq!(
+            resolve(&deep, Missing::Allowed, true).unwrap(),
+            root.join("x/y")
+        );
+
+        assert!(resolve(&root.join("real/file/x"), Missing::LastOnly, true).is_err());
+        assert!(resolve(&root.join("loop"), Missing::Allowed, true).is_err());
+    }
+}
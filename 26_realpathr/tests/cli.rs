@@ -0,0 +1,109 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, TestResult};
+use predicates::prelude::*;
+use std::fs;
+use std::io;
+
+const PRG: &str = "realpathr";
+
+// --------------------------------------------------
+fn setup() -> io::Result<(Fixture, String)> {
+    let fixture = Fixture::new()?;
+    fixture.dir("real/sub")?;
+    fixture.file("real/file.txt", "")?;
+    fixture.symlink("link", "real")?;
+    let root = fs::canonicalize(fixture.root())?.display().to_string();
+    Ok((fixture, root))
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn resolves_symlinks() -> TestResult {
+    let (_dir, root) = setup()?;
+    Command::cargo_bin(PRG)?
+        .current_dir(&root)
+        .args(["link/file.txt", "./link/sub/..", "real/new.txt"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{0}/real/file.txt\n{0}/real\n{0}/real/new.txt\n",
+            root
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn existing_and_missing() -> TestResult {
+    let (_dir, root) = setup()?;
+    let missing = format!("{}/nope/file.txt", root);
+
+    Command::cargo_bin(PRG)?
+        .arg(&missing)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("(os error 2)"))
+        .stdout("");
+
+    Command::cargo_bin(PRG)?
+        .args(["-e", &format!("{}/real/new.txt", root)])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("(os error 2)"))
+        .stdout("");
+
+    Command::cargo_bin(PRG)?
+        .args(["-m", &missing])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", missing));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_symlinks() -> TestResult {
+    let (_dir, root) = setup()?;
+    Command::cargo_bin(PRG)?
+        .args(["-s", &format!("{}/link/sub/../file.txt", root)])
+        .assert()
+        .success()
+        .stdout(format!("{}/link/file.txt\n", root));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn relative_to() -> TestResult {
+    let (_dir, root) = setup()?;
+    Command::cargo_bin(PRG)?
+        .current_dir(&root)
+        .args(["--relative-to", "real/sub", "link/file.txt", "real/sub"])
+        .assert()
+        .success()
+        .stdout("../file.txt\n.\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zero() -> TestResult {
+    let (_dir, root) = setup()?;
+    Command::cargo_bin(PRG)?
+        .current_dir(&root)
+        .args(["-z", "--relative-to", ".", "link", "real/sub"])
+        .assert()
+        .success()
+        .stdout("real\0real/sub\0");
+    Ok(())
+}
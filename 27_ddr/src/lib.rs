@@ -0,0 +1,568 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
+
+use clap::ArgAction::Append;
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{parse_size, CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use thiserror::Error;
+
+const DEFAULT_BLOCK_SIZE: usize = 512;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Status {
+    Default,
+    NoXfer,
+    None,
+    Progress,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    input: Option<String>,
+    output: Option<String>,
+    block_size: usize,
+    count: Option<u64>,
+    skip: u64,
+    seek: u64,
+    notrunc: bool,
+    status: Status,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_text("unrecognized operand '{0}'", "認識できないオペランドです '{0}'", &[.0]))]
+    UnrecognizedOperand(String),
+    #[error("{}", tr_text("invalid number: '{0}'", "数値が不正です: '{0}'", &[.0]))]
+    InvalidNumber(String),
+    #[error("{}", tr_text("invalid conversion: '{0}'", "変換が不正です: '{0}'", &[.0]))]
+    InvalidConversion(String),
+    #[error("{}", tr_text("invalid status level: '{0}'", "statusのレベルが不正です: '{0}'", &[.0]))]
+    InvalidStatus(String),
+    #[error(
+        "{}",
+        tr_str("skip offset too large", "skipのオフセットが大きすぎます")
+    )]
+    SkipTooLarge,
+    #[error(
+        "{}",
+        tr_str("seek offset too large", "seekのオフセットが大きすぎます")
+    )]
+    SeekTooLarge,
+    #[error("{}", tr_text(
+        "failed to open '{0}': {1}",
+        "'{0}' を開けません: {1}",
+        &[.path, &tr_io(.source)],
+    ))]
+    Open { path: String, source: io::Error },
+    #[error("{}", tr_text(
+        "failed to truncate '{0}': {1}",
+        "'{0}' を切り詰められません: {1}",
+        &[.path, &tr_io(.source)],
+    ))]
+    Truncate { path: String, source: io::Error },
+    #[error("{}", tr_text(
+        "'{0}': cannot skip: {1}",
+        "'{0}': skipできません: {1}",
+        &[.path, &tr_io(.source)],
+    ))]
+    Skip { path: String, source: io::Error },
+    #[error("{}", tr_text(
+        "'{0}': cannot seek: {1}",
+        "'{0}': seekできません: {1}",
+        &[.path, &tr_io(.source)],
+    ))]
+    Seek { path: String, source: io::Error },
+    #[error("{}", tr_text("'{0}': cannot seek", "'{0}': seekできません", &[.0]))]
+    Unseekable(String),
+    #[error("{}", tr_text(
+        "error reading '{0}': {1}",
+        "'{0}' の読み込みエラー: {1}",
+        &[.path, &tr_io(.source)],
+    ))]
+    Read { path: String, source: io::Error },
+    #[error("{}", tr_text(
+        "error writing '{0}': {1}",
+        "'{0}' の書き込みエラー: {1}",
+        &[.path, &tr_io(.source)],
+    ))]
+    Write { path: String, source: io::Error },
+    #[error("{}", tr_text(
+        "memory exhausted by input buffer of size {0} bytes",
+        "{0}バイトの入力バッファでメモリを使い果たしました",
+        &[.0],
+    ))]
+    MemoryExhausted(usize),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::UnrecognizedOperand(_)
+            | Error::InvalidNumber(_)
+            | Error::InvalidConversion(_)
+            | Error::InvalidStatus(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    full_in: u64,
+    partial_in: u64,
+    full_out: u64,
+    partial_out: u64,
+    bytes: u64,
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("ddr")
+        .version("0.1.0")
+        .about("Rust dd")
+        .arg(
+            Arg::new("operands")
+                .value_name("OPERAND")
+                .help(tr_str(
+                    "if=FILE of=FILE bs=BYTES count=N skip=N seek=N \
+                     conv=notrunc status=none|noxfer|progress",
+                    "if=ファイル of=ファイル bs=バイト数 count=N skip=N seek=N \
+                     conv=notrunc status=none|noxfer|progress",
+                ))
+                .action(Append),
+        )
+        .get_tool_matches();
+
+    let operands: Vec<&str> = matches
+        .get_many::<String>("operands")
+        .unwrap_or_default()
+        .map(|s| s.as_str())
+        .collect();
+    parse_operands(&operands)
+}
+
+fn parse_operands(operands: &[&str]) -> Result<Config, Error> {
+    let mut config = Config {
+        input: None,
+        output: None,
+        block_size: DEFAULT_BLOCK_SIZE,
+        count: None,
+        skip: 0,
+        seek: 0,
+        notrunc: false,
+        status: Status::Default,
+    };
+
+    for operand in operands {
+        let (key, value) = operand
+            .split_once('=')
+            .ok_or_else(|| Error::UnrecognizedOperand(operand.to_string()))?;
+        match key {
+            "if" => config.input = Some(value.to_string()),
+            "of" => config.output = Some(value.to_string()),
+            "bs" => {
+                config.block_size = match usize::try_from(parse_number(value)?) {
+                    Ok(0) | Err(_) => return Err(Error::InvalidNumber(value.to_string())),
+                    Ok(n) => n,
+                }
+            }
+            "count" => config.count = Some(parse_number(value)?),
+            "skip" => config.skip = parse_number(value)?,
+            "seek" => config.seek = parse_number(value)?,
+            "conv" => {
+                for conv in value.split(',') {
+                    match conv {
+                        "notrunc" => config.notrunc = true,
+                        _ => return Err(Error::InvalidConversion(conv.to_string())),
+                    }
+                }
+            }
+            "status" => {
+                config.status = match value {
+                    "none" => Status::None,
+                    "noxfer" => Status::NoXfer,
+                    "progress" => Status::Progress,
+                    _ => return Err(Error::InvalidStatus(value.to_string())),
+                }
+            }
+            _ => return Err(Error::UnrecognizedOperand(operand.to_string())),
+        }
+    }
+    Ok(config)
+}
+
+/// Parses a dd number: a size as read by [`parse_size`], or with dd's own
+/// `c` (bytes) and `w` (2-byte words) units, optionally multiplied by
+/// further numbers with `x`.
+fn parse_number(val: &str) -> Result<u64, Error> {
+    val.split('x').try_fold(1u64, |acc, part| {
+        let (size, multiplier) = match (part.strip_suffix('c'), part.strip_suffix('w')) {
+            (Some(bytes), _) => (bytes, 1),
+            (_, Some(words)) => (words, 2),
+            _ => (part, 1),
+        };
+        parse_size(size)
+            .ok()
+            .and_then(|n| n.checked_mul(multiplier))
+            .and_then(|n| acc.checked_mul(n))
+            .ok_or_else(|| Error::InvalidNumber(val.to_string()))
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<(), Error> {
+    let input_name = config.input.as_deref().unwrap_or("standard input");
+    let output_name = config.output.as_deref().unwrap_or("standard output");
+    let skip_bytes = config
+        .skip
+        .checked_mul(config.block_size as u64)
+        .ok_or(Error::SkipTooLarge)?;
+    let seek_bytes = config
+        .seek
+        .checked_mul(config.block_size as u64)
+        .ok_or(Error::SeekTooLarge)?;
+
+    let mut input: Box<dyn Read> = match &config.input {
+        Some(path) => {
+            let mut file = File::open(path).map_err(|source| Error::Open {
+                path: path.to_string(),
+                source,
+            })?;
+            file.seek(SeekFrom::Start(skip_bytes))
+                .map_err(|source| Error::Skip {
+                    path: path.to_string(),
+                    source,
+                })?;
+            Box::new(file)
+        }
+        None => {
+            let mut stdin = io::stdin();
+            // 標準入力はシークできないので読み捨てる
+            io::copy(&mut (&mut stdin).take(skip_bytes), &mut io::sink()).map_err(|source| {
+                Error::Skip {
+                    path: input_name.to_string(),
+                    source,
+                }
+            })?;
+            Box::new(stdin)
+        }
+    };
+
+    let mut output: Box<dyn Write + '_> = match &config.output {
+        Some(path) => {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)
+                .map_err(|source| Error::Open {
+                    path: path.to_string(),
+                    source,
+                })?;
+            // デバイスなど通常ファイル以外は切り詰めない
+            if !config.notrunc && file.metadata().is_ok_and(|m| m.is_file()) {
+                file.set_len(seek_bytes).map_err(|source| Error::Truncate {
+                    path: path.to_string(),
+                    source,
+                })?;
+            }
+            file.seek(SeekFrom::Start(seek_bytes))
+                .map_err(|source| Error::Seek {
+                    path: path.to_string(),
+                    source,
+                })?;
+            Box::new(file)
+        }
+        None if seek_bytes > 0 => {
+            return Err(Error::Unseekable(output_name.to_string()));
+        }
+        None => Box::new(out),
+    };
+
+    let mut buf = alloc_buffer(config.block_size)?;
+    let start = Instant::now();
+    let mut stats = Stats::default();
+    let result = copy(
+        &mut input,
+        &mut output,
+        err,
+        &mut buf,
+        &config,
+        &mut stats,
+        start,
+    );
+    let flushed = output.flush();
+    let elapsed = start.elapsed();
+
+    if config.status != Status::None {
+        if config.status == Status::Progress && elapsed >= PROGRESS_INTERVAL {
+            writeln!(err)?;
+        }
+        write!(err, "{}", format_summary(&stats, elapsed, config.status))?;
+    }
+    match result {
+        Err(Failure::Read(source)) => Err(Error::Read {
+            path: input_name.to_string(),
+            source,
+        }),
+        Err(Failure::Write(source)) => Err(Error::Write {
+            path: output_name.to_string(),
+            source,
+        }),
+        Ok(()) => flushed.map_err(|source| Error::Write {
+            path: output_name.to_string(),
+            source,
+        }),
+    }
+}
+
+/// Allocates the block buffer, failing with an error rather than
+/// aborting when `bs=` asks for more memory than there is.
+fn alloc_buffer(block_size: usize) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+    buf.try_reserve_exact(block_size)
+        .map_err(|_| Error::MemoryExhausted(block_size))?;
+    buf.resize(block_size, 0);
+    Ok(buf)
+}
+
+#[derive(Debug)]
+enum Failure {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+fn copy(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    progress: &mut dyn Write,
+    buf: &mut [u8],
+    config: &Config,
+    stats: &mut Stats,
+    start: Instant,
+) -> Result<(), Failure> {
+    let mut last_progress = start;
+
+    while config
+        .count
+        .is_none_or(|count| stats.full_in + stats.partial_in < count)
+    {
+        let n = match input.read(buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Failure::Read(e)),
+        };
+        if n == buf.len() {
+            stats.full_in += 1;
+        } else {
+            stats.partial_in += 1;
+        }
+
+        output.write_all(&buf[..n]).map_err(Failure::Write)?;
+        if n == buf.len() {
+            stats.full_out += 1;
+        } else {
+            stats.partial_out += 1;
+        }
+        stats.bytes += n as u64;
+
+        if config.status == Status::Progress && last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let _ = write!(
+                progress,
+                "\r{}",
+                format_transfer(stats.bytes, start.elapsed())
+            );
+        }
+    }
+    Ok(())
+}
+
+fn format_summary(stats: &Stats, elapsed: Duration, status: Status) -> String {
+    let mut summary = format!(
+        "{}+{} records in\n{}+{} records out\n",
+        stats.full_in, stats.partial_in, stats.full_out, stats.partial_out
+    );
+    if status != Status::NoXfer {
+        summary.push_str(&format_transfer(stats.bytes, elapsed));
+        summary.push('\n');
+    }
+    summary
+}
+
+fn format_transfer(bytes: u64, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 {
+        format!("{}/s", human_size(bytes as f64 / secs))
+    } else {
+        "Infinity B/s".to_string()
+    };
+    if bytes < 1000 {
+        format!("{} bytes copied, {:.4} s, {}", bytes, secs, rate)
+    } else {
+        format!(
+            "{} bytes ({}) copied, {:.4} s, {}",
+            bytes,
+            human_size(bytes as f64),
+            secs,
+            rate
+        )
+    }
+}
+
+fn human_size(bytes: f64) -> String {
+    let units = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1000.0 && unit + 1 < units.len() {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, units[unit])
+    } else {
+        format!("{:.1} {}", value, units[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor};
+    use std::time::{Duration, Instant};
+
+    use super::{
+        alloc_buffer, copy, format_summary, human_size, parse_number, parse_operands, Stats, Status,
+    };
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("0").unwrap(), 0);
+        assert_eq!(parse_number("10").unwrap(), 10);
+        assert_eq!(parse_number("10c").unwrap(), 10);
+        assert_eq!(parse_number("2w").unwrap(), 4);
+        assert_eq!(parse_number("2b").unwrap(), 1024);
+        assert_eq!(parse_number("1K").unwrap(), 1024);
+        assert_eq!(parse_number("1k").unwrap(), 1024);
+        assert_eq!(parse_number("1KiB").unwrap(), 1024);
+        assert_eq!(parse_number("1kB").unwrap(), 1000);
+        assert_eq!(parse_number("4M").unwrap(), 4 * 1024 * 1024);
+        assert!(parse_number("2x3xK").is_err());
+        assert_eq!(parse_number("2x3x1K").unwrap(), 6 * 1024);
+
+        assert!(parse_number("").is_err());
+        assert!(parse_number("foo").is_err());
+        assert!(parse_number("1X").is_err());
+        assert!(parse_number("1KX").is_err());
+        assert!(parse_number("20E").is_err());
+    }
+
+    #[test]
+    fn test_parse_operands() {
+        let config = parse_operands(&[
+            "if=in",
+            "of=out",
+            "bs=1K",
+            "count=3",
+            "skip=1",
+            "seek=2",
+            "conv=notrunc",
+            "status=progress",
+        ])
+        .unwrap();
+        assert_eq!(config.input.as_deref(), Some("in"));
+        assert_eq!(config.output.as_deref(), Some("out"));
+        assert_eq!(config.block_size, 1024);
+        assert_eq!(config.count, Some(3));
+        assert_eq!(config.skip, 1);
+        assert_eq!(config.seek, 2);
+        assert!(config.notrunc);
+        assert_eq!(config.status, Status::Progress);
+
+        assert_eq!(parse_operands(&[]).unwrap().block_size, 512);
+
+        let err = |ops: &[&str]| parse_operands(ops).unwrap_err().to_string();
+        assert_eq!(err(&["foo"]), "unrecognized operand 'foo'");
+        assert_eq!(err(&["foo=1"]), "unrecognized operand 'foo=1'");
+        assert_eq!(err(&["bs=0"]), "invalid number: '0'");
+        assert_eq!(err(&["conv=sync"]), "invalid conversion: 'sync'");
+        assert_eq!(err(&["status=loud"]), "invalid status level: 'loud'");
+    }
+
+    #[test]
+    fn test_copy() {
+        let config = parse_operands(&["bs=4", "count=3"]).unwrap();
+        let mut input = Cursor::new(b"0123456789".to_vec());
+        let mut output = vec![];
+        let mut stats = Stats::default();
+        copy(
+            &mut input,
+            &mut output,
+            &mut io::sink(),
+            &mut alloc_buffer(config.block_size).unwrap(),
+            &config,
+            &mut stats,
+            Instant::now(),
+        )
+        .unwrap();
+        assert_eq!(output, b"0123456789");
+        assert_eq!((stats.full_in, stats.partial_in), (2, 1));
+        assert_eq!((stats.full_out, stats.partial_out), (2, 1));
+        assert_eq!(stats.bytes, 10);
+
+        // countに達したら読み込みをやめる
+        let config = parse_operands(&["bs=3", "count=2"]).unwrap();
+        let mut input = Cursor::new(b"0123456789".to_vec());
+        let mut output = vec![];
+        let mut stats = Stats::default();
+        copy(
+            &mut input,
+            &mut output,
+            &mut io::sink(),
+            &mut alloc_buffer(config.block_size).unwrap(),
+            &config,
+            &mut stats,
+            Instant::now(),
+        )
+        .unwrap();
+        assert_eq!(output, b"012345");
+    }
+
+    #[test]
+    fn test_alloc_buffer() {
+        assert_eq!(alloc_buffer(4).unwrap(), vec![0; 4]);
+        assert_eq!(
+            alloc_buffer(usize::MAX).unwrap_err().to_string(),
+            format!(
+                "memory exhausted by input buffer of size {} bytes",
+                usize::MAX
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_summary() {
+        let stats = Stats {
+            full_in: 2,
+            partial_in: 1,
+            full_out: 2,
+            partial_out: 1,
+            bytes: 2400,
+        };
+        assert_eq!(
+            format_summary(&stats, Duration::from_secs(2), Status::Default),
+            "2+1 records in\n2+1 records out\n\
+             2400 bytes (2.4 kB) copied, 2.0000 s, 1.2 kB/s\n"
+        );
+        assert_eq!(
+            format_summary(&stats, Duration::from_secs(2), Status::NoXfer),
+            "2+1 records in\n2+1 records out\n"
+        );
+        assert_eq!(human_size(999.0), "999 B");
+        assert_eq!(human_size(1_500_000.0), "1.5 MB");
+    }
+}
@@ -0,0 +1,117 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, TestResult};
+use predicates::prelude::*;
+use std::fs;
+
+const PRG: &str = "ddr";
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_operand() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("foo=bar")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unrecognized operand 'foo=bar'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_number() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("bs=1X")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid number: '1X'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_huge_block_size() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("bs=18446744073709551615")
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "memory exhausted by input buffer of size 18446744073709551615 bytes",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_input() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("if=does-not-exist")
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(
+            "failed to open 'does-not-exist': .* [(]os error 2[)]",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_to_stdout() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["bs=4", "skip=1", "count=2"])
+        .write_stdin("0123456789abcdef")
+        .assert()
+        .success()
+        .stdout("456789ab")
+        .stderr(predicate::str::starts_with(
+            "2+0 records in\n2+0 records out\n8 bytes copied, ",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn partial_records() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["bs=4", "status=noxfer"])
+        .write_stdin("0123456789")
+        .assert()
+        .success()
+        .stdout("0123456789")
+        .stderr("2+1 records in\n2+1 records out\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files_with_seek() -> TestResult {
+    let fixture = Fixture::new()?;
+    let input = fixture.file("in", "0123456789")?;
+    let output = fixture.file("out", "abcdefghijklmnop")?;
+    let input = format!("if={}", input);
+    let of = format!("of={}", output);
+
+    Command::cargo_bin(PRG)?
+        .args([
+            &input,
+            &of,
+            "bs=2",
+            "skip=1",
+            "seek=2",
+            "count=2",
+            "conv=notrunc",
+        ])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(fs::read_to_string(&output)?, "abcd2345ijklmnop");
+
+    // notruncがなければ書き込み位置で切り詰める
+    Command::cargo_bin(PRG)?
+        .args([&input, &of, "bs=2", "seek=1", "count=1", "status=none"])
+        .assert()
+        .success()
+        .stderr("");
+    assert_eq!(fs::read_to_string(&output)?, "ab01");
+    Ok(())
+}
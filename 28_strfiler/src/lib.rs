@@ -0,0 +1,263 @@
+use std::fs;
+use std::io::{self, Write};
+
+use clap::ArgAction::SetTrue;
+use clap::{value_parser, Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{RngCore, SeedableRng};
+use thiserror::Error;
+
+const VERSION: u32 = 2;
+const STR_RANDOM: u32 = 0x1;
+
+#[derive(Debug)]
+pub struct Config {
+    source: String,
+    output: String,
+    delimiter: u8,
+    randomize: bool,
+    seed: Option<u64>,
+    silent: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_text(
+        "Delimiter must be a single byte, not \"{0}\"",
+        "区切りは1バイトにしてください (\"{0}\" ではなく)",
+        &[.0],
+    ))]
+    BadDelimiter(String),
+    #[error("{path}: {}", tr_io(.source))]
+    Open { path: String, source: io::Error },
+    #[error("{path}: {}", tr_io(.source))]
+    Create { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::BadDelimiter(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+/// The fixed-size header at the start of a strfile `.dat` file.
+#[derive(Debug, PartialEq)]
+struct Header {
+    numstr: u32,
+    longlen: u32,
+    shortlen: u32,
+    flags: u32,
+    delimiter: u8,
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("strfiler")
+        .version("0.1.0")
+        .about("Rust strfile")
+        .arg(
+            Arg::new("source")
+                .value_name("SOURCE")
+                .help(tr_str("Fortune file to index", "索引を作るfortuneファイル"))
+                .required(true),
+        )
+        .arg(Arg::new("output").value_name("OUTPUT").help(tr_str(
+            "Index file to write [default: SOURCE.dat]",
+            "書き出す索引ファイル [既定: SOURCE.dat]",
+        )))
+        .arg(
+            Arg::new("delimiter")
+                .value_name("CHAR")
+                .short('c')
+                .help(tr_str("Delimiter character", "区切り文字"))
+                .default_value("%"),
+        )
+        .arg(
+            Arg::new("randomize")
+                .short('o')
+                .long("randomize")
+                .help(tr_str(
+                    "Randomize the order of the offset table",
+                    "オフセット表の順序をランダムにする",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("seed")
+                .value_name("SEED")
+                .long("seed")
+                .help(tr_str("Random seed", "乱数のシード"))
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("silent")
+                .short('s')
+                .long("silent")
+                .help(tr_str("Don't print a summary", "概要を表示しない"))
+                .action(SetTrue),
+        )
+        .get_tool_matches();
+
+    let delimiter = matches.get_one::<String>("delimiter").unwrap();
+    if delimiter.len() != 1 {
+        return Err(Error::BadDelimiter(delimiter.to_string()));
+    }
+    let source = matches.get_one::<String>("source").unwrap().to_string();
+
+    Ok(Config {
+        output: matches
+            .get_one::<String>("output")
+            .cloned()
+            .unwrap_or_else(|| format!("{}.dat", source)),
+        source,
+        delimiter: delimiter.as_bytes()[0],
+        randomize: matches.get_flag("randomize"),
+        seed: matches.get_one::<u64>("seed").copied(),
+        silent: matches.get_flag("silent"),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    let data = fs::read(&config.source).map_err(|source| Error::Open {
+        path: config.source.clone(),
+        source,
+    })?;
+    let (mut header, mut offsets) = scan(&data, config.delimiter);
+    if config.randomize {
+        let mut rng: Box<dyn RngCore> = match config.seed {
+            Some(val) => Box::new(StdRng::seed_from_u64(val)),
+            None => Box::new(rand::thread_rng()),
+        };
+        // 末尾のオフセットはファイル終端なので並べ替えない
+        let last = offsets.len() - 1;
+        offsets[..last].shuffle(&mut rng);
+        header.flags |= STR_RANDOM;
+    }
+
+    fs::write(&config.output, encode(&header, &offsets)).map_err(|source| Error::Create {
+        path: config.output.clone(),
+        source,
+    })?;
+
+    if !config.silent {
+        writeln!(out, "\"{}\" created", config.output)?;
+        writeln!(out, "There were {} strings", header.numstr)?;
+        writeln!(out, "Longest string: {} bytes", header.longlen)?;
+        writeln!(out, "Shortest string: {} bytes", header.shortlen)?;
+    }
+    Ok(())
+}
+
+/// Finds the start of every non-empty string in `data`. Strings are
+/// separated by lines holding only the delimiter. The returned table ends
+/// with the offset just past the last string, as strfile expects.
+fn scan(data: &[u8], delimiter: u8) -> (Header, Vec<u32>) {
+    let mut offsets = vec![];
+    let mut lengths = vec![];
+    let mut start = 0;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let end = data[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(data.len(), |i| pos + i + 1);
+        let line = &data[pos..end];
+        if line.strip_suffix(b"\n").unwrap_or(line) == [delimiter] {
+            if pos > start {
+                offsets.push(start as u32);
+                lengths.push((pos - start) as u32);
+            }
+            start = end;
+        }
+        pos = end;
+    }
+    if data.len() > start {
+        offsets.push(start as u32);
+        lengths.push((data.len() - start) as u32);
+    }
+    offsets.push(data.len() as u32);
+
+    let header = Header {
+        numstr: lengths.len() as u32,
+        longlen: lengths.iter().copied().max().unwrap_or(0),
+        shortlen: lengths.iter().copied().min().unwrap_or(0),
+        flags: 0,
+        delimiter,
+    };
+    (header, offsets)
+}
+
+/// Serializes the header and offset table in big-endian byte order.
+fn encode(header: &Header, offsets: &[u32]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for val in [
+        VERSION,
+        header.numstr,
+        header.longlen,
+        header.shortlen,
+        header.flags,
+    ] {
+        bytes.extend_from_slice(&val.to_be_bytes());
+    }
+    bytes.extend_from_slice(&[header.delimiter, 0, 0, 0]);
+    for offset in offsets {
+        bytes.extend_from_slice(&offset.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, scan, Header};
+
+    #[test]
+    fn test_scan() {
+        let (header, offsets) = scan(b"ab\n%\n\n%\n%\nc\n%\nlast", b'%');
+        assert_eq!(
+            header,
+            Header {
+                numstr: 4,
+                longlen: 4,
+                shortlen: 1,
+                flags: 0,
+                delimiter: b'%',
+            }
+        );
+        assert_eq!(offsets, vec![0, 5, 10, 14, 18]);
+
+        // 空文字列は数えない
+        let (header, offsets) = scan(b"%\n%\n", b'%');
+        assert_eq!(header.numstr, 0);
+        assert_eq!(offsets, vec![4]);
+
+        let (header, offsets) = scan(b"a\n@\nbb\n@\n", b'@');
+        assert_eq!(header.numstr, 2);
+        assert_eq!(offsets, vec![0, 4, 9]);
+    }
+
+    #[test]
+    fn test_encode() {
+        let header = Header {
+            numstr: 1,
+            longlen: 3,
+            shortlen: 3,
+            flags: 1,
+            delimiter: b'%',
+        };
+        assert_eq!(
+            encode(&header, &[0, 5]),
+            vec![
+                0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, 3, 0, 0, 0, 1, b'%', 0, 0, 0, 0, 0, 0,
+                0, 0, 0, 0, 5,
+            ]
+        );
+    }
+}
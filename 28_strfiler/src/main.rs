@@ -0,0 +1,10 @@
+use std::io;
+
+fn main() {
+    let result = strfiler::get_args().and_then(|config| {
+        strfiler::run(config, &mut io::stdout().lock(), &mut io::stderr().lock())
+    });
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("strfiler", &e));
+    }
+}
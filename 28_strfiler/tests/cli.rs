@@ -0,0 +1,97 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, TestResult};
+use predicates::prelude::*;
+use std::fs;
+
+const PRG: &str = "strfiler";
+const JOKES: &str = "tests/inputs/jokes";
+
+// --------------------------------------------------
+fn read_u32s(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("tests/inputs/missing")
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(
+            "tests/inputs/missing: .* [(]os error 2[)]",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_delimiter() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-c", "%%", JOKES])
+        .assert()
+        .failure()
+        .stderr("strfiler: Delimiter must be a single byte, not \"%%\"\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn jokes() -> TestResult {
+    let fixture = Fixture::new()?;
+    let source = fixture.file("jokes", fs::read(JOKES)?)?;
+    let dat = format!("{}.dat", source);
+
+    Command::cargo_bin(PRG)?
+        .arg(&source)
+        .assert()
+        .success()
+        .stdout(format!(
+            "\"{}\" created\nThere were 6 strings\nLongest string: 100 bytes\nShortest string: 61 bytes\n",
+            dat
+        ));
+
+    let bytes = fs::read(&dat)?;
+    assert_eq!(&bytes[20..24], b"%\0\0\0");
+    let header = read_u32s(&bytes[..20]);
+    assert_eq!(header, vec![2, 6, 100, 61, 0]);
+    let offsets = read_u32s(&bytes[24..]);
+    assert_eq!(offsets, vec![0, 80, 154, 256, 345, 408, 491]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn randomize_silent() -> TestResult {
+    let fixture = Fixture::new()?;
+    let dat = fixture.path("jokes.idx");
+
+    Command::cargo_bin(PRG)?
+        .args(["-s", "-o", "--seed", "1", JOKES])
+        .arg(&dat)
+        .assert()
+        .success()
+        .stdout("");
+
+    let bytes = fs::read(&dat)?;
+    assert_eq!(read_u32s(&bytes[..20]), vec![2, 6, 100, 61, 1]);
+    let mut offsets = read_u32s(&bytes[24..]);
+    // 末尾のファイル終端は動かさず、それ以外を並べ替える
+    assert_eq!(offsets.pop(), Some(491));
+    offsets.sort();
+    assert_eq!(offsets, vec![0, 80, 154, 256, 345, 408]);
+    Ok(())
+}
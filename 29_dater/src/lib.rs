@@ -0,0 +1,418 @@
+#[cfg(unix)]
+use std::ffi::CStr;
+use std::fs;
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::mem::MaybeUninit;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{
+    DateTime, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc,
+};
+use clap::ArgAction::SetTrue;
+use clap::{Arg, Command};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{CommandExt, ToolError, EXIT_FAILURE, EXIT_USAGE};
+use thiserror::Error;
+
+const DEFAULT_FORMAT: &str = "%a %b %e %H:%M:%S %Z %Y";
+
+#[derive(Debug)]
+pub struct Config {
+    time: DateTime<Utc>,
+    format: String,
+    utc: bool,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_text("invalid date format \"{0}\"", "日付の書式が不正です \"{0}\"", &[.0]))]
+    InvalidFormat(String),
+    #[error("{}", tr_text("invalid date \"{0}\"", "日付が不正です \"{0}\"", &[.0]))]
+    InvalidDate(String),
+    #[error("{path}: {}", tr_io(.source))]
+    Stat { path: String, source: io::Error },
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {
+    fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidFormat(_) | Error::InvalidDate(_) => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("dater")
+        .version("0.1.0")
+        .about("Rust date")
+        .arg(Arg::new("format").value_name("+FORMAT").help(tr_str(
+            "strftime format, e.g. +%Y-%m-%d",
+            "strftimeの書式(例: +%Y-%m-%d)",
+        )))
+        .arg(
+            Arg::new("utc")
+                .short('u')
+                .long("utc")
+                .visible_alias("universal")
+                .help(tr_str(
+                    "Print and parse times in UTC",
+                    "時刻をUTCで表示・解釈する",
+                ))
+                .action(SetTrue),
+        )
+        .arg(
+            Arg::new("date")
+                .value_name("STRING")
+                .short('d')
+                .long("date")
+                .help(tr_str(
+                    "Display the time described by STRING, not now",
+                    "現在ではなくSTRINGが表す時刻を表示する",
+                )),
+        )
+        .arg(
+            Arg::new("reference")
+                .value_name("FILE")
+                .short('r')
+                .long("reference")
+                .help(tr_str(
+                    "Display the last modification time of FILE",
+                    "FILEの最終更新時刻を表示する",
+                ))
+                .conflicts_with("date"),
+        )
+        .get_tool_matches();
+
+    let format = match matches.get_one::<String>("format") {
+        None => DEFAULT_FORMAT.to_string(),
+        Some(val) => match val.strip_prefix('+') {
+            Some(format) => check_format(format)?,
+            None => return Err(Error::InvalidFormat(val.to_string())),
+        },
+    };
+    let utc = matches.get_flag("utc");
+
+    let time = if let Some(date) = matches.get_one::<String>("date") {
+        parse_date(date, Utc::now(), utc)?
+    } else if let Some(filename) = matches.get_one::<String>("reference") {
+        let modified = fs::metadata(filename)
+            .and_then(|m| m.modified())
+            .map_err(|source| Error::Stat {
+                path: filename.to_string(),
+                source,
+            })?;
+        DateTime::from(modified)
+    } else {
+        Utc::now()
+    };
+
+    Ok(Config { time, format, utc })
+}
+
+pub fn run(config: Config, out: &mut impl Write, _err: &mut impl Write) -> Result<(), Error> {
+    if config.utc {
+        let format = to_chrono(&config.format, "UTC");
+        writeln!(out, "{}", config.time.format(&format))?;
+    } else {
+        let time = config.time.with_timezone(&Local);
+        let zone = zone_name(&time).unwrap_or_else(|| time.format("%:z").to_string());
+        writeln!(out, "{}", time.format(&to_chrono(&config.format, &zone)))?;
+    }
+    Ok(())
+}
+
+/// Rejects formats chrono cannot render, which would otherwise panic
+/// while printing.
+fn check_format(format: &str) -> Result<String, Error> {
+    if StrftimeItems::new(&to_chrono(format, "UTC")).any(|item| item == Item::Error) {
+        return Err(Error::InvalidFormat(format!("+{}", format)));
+    }
+    Ok(format.to_string())
+}
+
+/// Rewrites the directives where date and chrono differ: `%N` (and
+/// `%3N`, `%6N`, `%9N`) is chrono's `%f`, and `%Z` becomes `zone`, since
+/// chrono only knows the offset of a local time.
+fn to_chrono(format: &str, zone: &str) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let rest = chars.as_str();
+        let (width, directive) = match rest.as_bytes() {
+            [w @ (b'3' | b'6' | b'9'), b'N', ..] => (Some(*w as char), 'N'),
+            _ => (None, rest.chars().next().unwrap_or('%')),
+        };
+        match directive {
+            'N' => {
+                out.push('%');
+                out.extend(width);
+                out.push('f');
+                chars.nth(usize::from(width.is_some()));
+            }
+            'Z' => {
+                out.push_str(&zone.replace('%', "%%"));
+                chars.next();
+            }
+            _ => {
+                out.push('%');
+                out.extend(chars.next());
+            }
+        }
+    }
+    out
+}
+
+/// The abbreviation of the local time zone at `time`, such as `JST`,
+/// as the C library reads it from `TZ`.
+#[cfg(unix)]
+fn zone_name(time: &DateTime<Local>) -> Option<String> {
+    let secs = libc::time_t::try_from(time.timestamp()).ok()?;
+    let mut tm = MaybeUninit::<libc::tm>::zeroed();
+    // SAFETY: localtime_r only writes into the provided struct, and
+    // tm_zone then points into the C library's static zone data.
+    unsafe {
+        if libc::localtime_r(&secs, tm.as_mut_ptr()).is_null() {
+            return None;
+        }
+        let zone = tm.assume_init().tm_zone;
+        if zone.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(zone).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(not(unix))]
+fn zone_name(_time: &DateTime<Local>) -> Option<String> {
+    None
+}
+
+/// Parses `val` as an absolute time (`@SECONDS[.FRACTION]`, RFC 3339, RFC 2822 or
+/// `YYYY-MM-DD [HH:MM[:SS]]`) optionally followed by relative items such as
+/// `2 days ago`, `+3 hours`, `tomorrow` or `next week`. Relative items
+/// alone count from `now`. Times without a zone are local unless `utc`.
+fn parse_date(val: &str, now: DateTime<Utc>, utc: bool) -> Result<DateTime<Utc>, Error> {
+    let error = || Error::InvalidDate(val.to_string());
+    let words: Vec<&str> = val.split_whitespace().collect();
+
+    let (mut time, rest) = (1..=words.len())
+        .rev()
+        .find_map(|n| parse_absolute(&words[..n].join(" "), utc).map(|t| (t, &words[n..])))
+        .unwrap_or((now, &words[..]));
+
+    for (amount, unit) in parse_relative(rest).ok_or_else(error)? {
+        time = add(time, amount, unit).ok_or_else(error)?;
+    }
+    Ok(time)
+}
+
+fn parse_absolute(val: &str, utc: bool) -> Option<DateTime<Utc>> {
+    if let Some(secs) = val.strip_prefix('@') {
+        let (secs, frac) = secs.split_once('.').unwrap_or((secs, ""));
+        if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let nanos = format!("{:0<9}", frac).parse().ok()?;
+        return DateTime::from_timestamp(secs.parse().ok()?, nanos);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(val) {
+        return Some(time.into());
+    }
+    if let Ok(time) = DateTime::parse_from_rfc2822(val) {
+        return Some(time.into());
+    }
+
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(val, f).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(val, "%Y-%m-%d")
+                .ok()
+                .map(|d| d.and_time(NaiveTime::MIN))
+        })?;
+    if utc {
+        Some(Utc.from_utc_datetime(&naive))
+    } else {
+        // 夏時間の切り替えで重複する時刻は早い方をとる
+        Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.into())
+    }
+}
+
+fn parse_unit(word: &str) -> Option<Unit> {
+    let word = word.to_lowercase();
+    let unit = match word.strip_suffix('s').unwrap_or(&word) {
+        "sec" | "second" => Unit::Second,
+        "min" | "minute" => Unit::Minute,
+        "hour" => Unit::Hour,
+        "day" => Unit::Day,
+        "week" => Unit::Week,
+        "month" => Unit::Month,
+        "year" => Unit::Year,
+        _ => return None,
+    };
+    Some(unit)
+}
+
+/// Reads a sequence of `[+-]N UNIT [ago]` items. The number may be
+/// attached to the unit (`3days`), `next`/`last` stand for 1 and -1, and
+/// `today`, `yesterday` and `tomorrow` are whole items on their own.
+fn parse_relative(words: &[&str]) -> Option<Vec<(i64, Unit)>> {
+    let mut items = vec![];
+    let mut words = words.iter().map(|w| w.to_lowercase()).peekable();
+
+    while let Some(word) = words.next() {
+        let (amount, unit) = match word.as_str() {
+            "now" | "today" => (0, Unit::Day),
+            "yesterday" => (-1, Unit::Day),
+            "tomorrow" => (1, Unit::Day),
+            "next" | "last" => {
+                let unit = parse_unit(&words.next()?)?;
+                (if word == "next" { 1 } else { -1 }, unit)
+            }
+            _ => {
+                let digits = word
+                    .find(|c: char| !c.is_ascii_digit() && c != '+' && c != '-')
+                    .unwrap_or(word.len());
+                let (num, unit) = word.split_at(digits);
+                let amount = match num {
+                    "" => 1,
+                    _ => num.parse().ok()?,
+                };
+                let unit = match unit {
+                    "" => parse_unit(&words.next()?)?,
+                    _ => parse_unit(unit)?,
+                };
+                (amount, unit)
+            }
+        };
+        if words.peek().is_some_and(|w| w == "ago") {
+            words.next();
+            items.push((-amount, unit));
+        } else {
+            items.push((amount, unit));
+        }
+    }
+    Some(items)
+}
+
+fn add(time: DateTime<Utc>, amount: i64, unit: Unit) -> Option<DateTime<Utc>> {
+    let seconds = match unit {
+        Unit::Second => 1,
+        Unit::Minute => 60,
+        Unit::Hour => 3600,
+        Unit::Day | Unit::Week => {
+            let days = Days::new(amount.unsigned_abs() * if unit == Unit::Week { 7 } else { 1 });
+            return if amount < 0 {
+                time.checked_sub_days(days)
+            } else {
+                time.checked_add_days(days)
+            };
+        }
+        Unit::Month | Unit::Year => {
+            let months = amount.unsigned_abs() * if unit == Unit::Year { 12 } else { 1 };
+            let months = Months::new(u32::try_from(months).ok()?);
+            return if amount < 0 {
+                time.checked_sub_months(months)
+            } else {
+                time.checked_add_months(months)
+            };
+        }
+    };
+    time.checked_add_signed(TimeDelta::try_seconds(amount.checked_mul(seconds)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+
+    use super::{check_format, parse_date, parse_relative, to_chrono, Unit};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_relative() {
+        assert_eq!(
+            parse_relative(&["2", "days", "ago", "+3hours", "next", "Week"]),
+            Some(vec![(-2, Unit::Day), (3, Unit::Hour), (1, Unit::Week)])
+        );
+        assert_eq!(
+            parse_relative(&["yesterday", "min"]),
+            Some(vec![(-1, Unit::Day), (1, Unit::Minute)])
+        );
+        assert_eq!(parse_relative(&[]), Some(vec![]));
+        assert_eq!(parse_relative(&["2"]), None);
+        assert_eq!(parse_relative(&["3", "fortnights"]), None);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let now = utc(2024, 1, 31, 12, 0, 0);
+        let parse = |val| parse_date(val, now, true).unwrap();
+
+        assert_eq!(parse("@0"), utc(1970, 1, 1, 0, 0, 0));
+        assert_eq!(
+            parse("@1.25"),
+            utc(1970, 1, 1, 0, 0, 1) + TimeDelta::milliseconds(250)
+        );
+        assert_eq!(parse("2020-02-29"), utc(2020, 2, 29, 0, 0, 0));
+        assert_eq!(parse("2020-02-29 13:45"), utc(2020, 2, 29, 13, 45, 0));
+        assert_eq!(parse("2020-02-29T13:45:10"), utc(2020, 2, 29, 13, 45, 10));
+        assert_eq!(
+            parse("2020-02-29T13:45:10+09:00"),
+            utc(2020, 2, 29, 4, 45, 10)
+        );
+        assert_eq!(
+            parse("Sat, 29 Feb 2020 13:45:10 +0000"),
+            utc(2020, 2, 29, 13, 45, 10)
+        );
+        assert_eq!(parse("tomorrow"), utc(2024, 2, 1, 12, 0, 0));
+        assert_eq!(parse("1 month"), utc(2024, 2, 29, 12, 0, 0));
+        assert_eq!(parse("2020-02-29 1 year ago"), utc(2019, 2, 28, 0, 0, 0));
+        assert_eq!(
+            parse("2020-02-29 13:45 -90 minutes"),
+            utc(2020, 2, 29, 12, 15, 0)
+        );
+        assert!(parse_date("2020-02-30", now, true).is_err());
+        assert!(parse_date("someday", now, true).is_err());
+    }
+
+    #[test]
+    fn test_check_format() {
+        assert!(check_format("%Y-%m-%d %H:%M").is_ok());
+        assert!(check_format("100%%").is_ok());
+        assert!(check_format("%Q").is_err());
+        assert!(check_format("%s.%N %3N %Z").is_ok());
+    }
+
+    #[test]
+    fn test_to_chrono() {
+        assert_eq!(to_chrono("%T.%N %Z", "JST"), "%T.%f JST");
+        assert_eq!(to_chrono("%3N|%6N|%9N|%4N", "UTC"), "%3f|%6f|%9f|%4N");
+        assert_eq!(to_chrono("%%N %%Z 100%", "A%B"), "%%N %%Z 100%");
+        assert_eq!(to_chrono("%Z", "A%B"), "A%%B");
+    }
+}
@@ -0,0 +1,9 @@
+use std::io;
+
+fn main() {
+    let result = dater::get_args()
+        .and_then(|config| dater::run(config, &mut io::stdout().lock(), &mut io::stderr().lock()));
+    if let Err(e) = result {
+        std::process::exit(clir_common::report("dater", &e));
+    }
+}
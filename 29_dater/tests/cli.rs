@@ -0,0 +1,175 @@
+use assert_cmd::Command;
+use clir_testkit::{Fixture, TestResult};
+use predicates::prelude::*;
+use std::fs::File;
+use std::time::{Duration, UNIX_EPOCH};
+
+const PRG: &str = "dater";
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("%Y")
+        .assert()
+        .failure()
+        .stderr("dater: invalid date format \"%Y\"\n");
+
+    Command::cargo_bin(PRG)?
+        .arg("+%Q")
+        .assert()
+        .failure()
+        .stderr("dater: invalid date format \"+%Q\"\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_date() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", "next blue moon"])
+        .assert()
+        .failure()
+        .stderr("dater: invalid date \"next blue moon\"\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_reference() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-r", "tests/missing"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(
+            "tests/missing: .* [(]os error 2[)]",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_date_with_reference() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", "@0", "-r", "Cargo.toml"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn now() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-u", "+%Y"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match("^[0-9]{4}\n$")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn default_format_utc() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-u", "-d", "@0"])
+        .assert()
+        .success()
+        .stdout("Thu Jan  1 00:00:00 UTC 1970\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn local_time_zone() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("TZ", "JST-9")
+        .args(["-d", "2024-03-01 09:30", "+%F %T %z"])
+        .assert()
+        .success()
+        .stdout("2024-03-01 09:30:00 +0900\n");
+
+    Command::cargo_bin(PRG)?
+        .env("TZ", "JST-9")
+        .args(["-u", "-d", "2024-03-01 09:30", "+%F %T %z"])
+        .assert()
+        .success()
+        .stdout("2024-03-01 09:30:00 +0000\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zone_abbreviation() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .env("TZ", "JST-9")
+        .args(["-d", "@0"])
+        .assert()
+        .success()
+        .stdout("Thu Jan  1 09:00:00 JST 1970\n");
+
+    // 夏時間かどうかで略称が変わる
+    for (date, expected) in [
+        ("@1704067200", "EST -0500\n"),
+        ("@1720000000", "EDT -0400\n"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .env("TZ", "EST5EDT,M3.2.0,M11.1.0")
+            .args(["-d", date, "+%Z %z"])
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+
+    Command::cargo_bin(PRG)?
+        .env("TZ", "JST-9")
+        .args(["-u", "-d", "@0", "+%Z"])
+        .assert()
+        .success()
+        .stdout("UTC\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn nanoseconds() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-u", "-d", "@1.5", "+%s.%N|%3N|%6N|%%N"])
+        .assert()
+        .success()
+        .stdout("1.500000000|500|500000|%N\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn relative_date() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-u", "-d", "2024-02-29 12:00 1 year ago 2 hours", "+%F %R"])
+        .assert()
+        .success()
+        .stdout("2023-02-28 14:00\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reference() -> TestResult {
+    let fixture = Fixture::new()?;
+    let file = fixture.file("reference", "")?;
+    File::options()
+        .write(true)
+        .open(&file)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000_000))?;
+
+    Command::cargo_bin(PRG)?
+        .arg("-u")
+        .arg("-r")
+        .arg(&file)
+        .arg("+%s %F %T")
+        .assert()
+        .success()
+        .stdout("1000000000 2001-09-09 01:46:40\n");
+    Ok(())
+}
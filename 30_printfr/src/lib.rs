@@ -0,0 +1,812 @@
+use std::io;
+use std::io::Write;
+
+use clap::{Arg, ArgAction, Command};
+use clir_common::escape::{escape, parse_digits, unescape, EscapeError, Escapes};
+use clir_common::i18n::{tr_io, tr_str, tr_text};
+use clir_common::{warn_to, CommandExt, ToolError, EXIT_FAILURE};
+use thiserror::Error;
+
+#[derive(Debug)]
+pub struct Config {
+    format: String,
+    args: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{}", tr_text(
+        "{0}: invalid conversion specification",
+        "{0}: 変換指定が不正です",
+        &[.0],
+    ))]
+    InvalidConversion(String),
+    #[error(transparent)]
+    Escape(#[from] EscapeError),
+    #[error("{}", tr_text("invalid field width: '{0}'", "フィールド幅が不正です: '{0}'", &[.0]))]
+    InvalidWidth(String),
+    #[error("{}", tr_text("invalid precision: '{0}'", "精度が不正です: '{0}'", &[.0]))]
+    InvalidPrecision(String),
+    #[error("{}", tr_io(.0))]
+    Io(#[from] io::Error),
+}
+
+impl ToolError for Error {}
+
+/// A `%` directive: flags, field width, precision and conversion.
+#[derive(Debug, Default)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    conv: u8,
+}
+
+/// The arguments the format consumes, recycled by [`run`].
+struct Args<'a> {
+    args: &'a [String],
+    used: usize,
+    status: i32,
+    warnings: Vec<String>,
+}
+
+pub fn get_args() -> Result<Config, Error> {
+    let matches = Command::new("printfr")
+        .version("0.1.0")
+        .about("Rust printf")
+        .arg(
+            Arg::new("format")
+                .value_name("FORMAT")
+                .help(tr_str(
+                    "Format with %-directives and backslash escapes",
+                    "%指示子とバックスラッシュのエスケープを含む書式",
+                ))
+                .required(true)
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::new("args")
+                .value_name("ARGUMENT")
+                .help(tr_str(
+                    "Values for the directives; the format is reused until all are consumed",
+                    "指示子に渡す値(すべて使い切るまで書式を繰り返す)",
+                ))
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true),
+        )
+        .get_tool_matches();
+
+    Ok(Config {
+        format: matches.get_one::<String>("format").unwrap().to_string(),
+        args: matches
+            .get_many::<String>("args")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+    })
+}
+
+pub fn run(config: Config, out: &mut impl Write, err: &mut impl Write) -> Result<i32, Error> {
+    let mut args = Args {
+        args: &config.args,
+        used: 0,
+        status: 0,
+        warnings: vec![],
+    };
+    loop {
+        let mut buf = vec![];
+        let used = args.used;
+        let result = format_once(config.format.as_bytes(), &mut args, &mut buf);
+        // エラーで止まるときも、それまでの出力は書き出す
+        out.write_all(&buf)?;
+        for warning in args.warnings.drain(..) {
+            warn_to(err, "printfr", &warning);
+        }
+        let stop = result?;
+        // 引数が残っていれば、1つでも消費する限り書式を繰り返す
+        if stop || args.used == used || args.used >= args.args.len() {
+            break;
+        }
+    }
+    Ok(args.status)
+}
+
+/// Expands `format` once into `out`. Returns whether `\c` asked to stop
+/// all output.
+fn format_once(format: &[u8], args: &mut Args, out: &mut Vec<u8>) -> Result<bool, Error> {
+    let mut i = 0;
+    while i < format.len() {
+        match format[i] {
+            b'\\' => match escape(&format[i + 1..], Escapes::Printf, out)? {
+                Some(len) => i += 1 + len,
+                None => return Ok(true),
+            },
+            b'%' if format.get(i + 1) == Some(&b'%') => {
+                out.push(b'%');
+                i += 2;
+            }
+            b'%' => {
+                let (spec, len) = parse_spec(&format[i..], args)?;
+                if convert(&spec, args, out)? {
+                    return Ok(true);
+                }
+                i += len;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// The largest field width or precision, C's `INT_MAX`.
+const MAX_FIELD: u64 = i32::MAX as u64;
+
+/// Parses the directive at the start of `format`, taking `*` widths and
+/// precisions from `args`. Returns it with its length.
+fn parse_spec(format: &[u8], args: &mut Args) -> Result<(Spec, usize), Error> {
+    let mut spec = Spec::default();
+    let mut i = 1;
+    while let Some(&flag) = format.get(i) {
+        match flag {
+            b'-' => spec.left = true,
+            b'+' => spec.plus = true,
+            b' ' => spec.space = true,
+            b'#' => spec.alt = true,
+            b'0' => spec.zero = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    if format.get(i) == Some(&b'*') {
+        let width = clamp_i64(args.int());
+        if width.unsigned_abs() > MAX_FIELD {
+            return Err(Error::InvalidWidth(args.last().to_string()));
+        }
+        // C の printf と同じく、負の幅は左寄せを意味する
+        spec.left |= width < 0;
+        spec.width = width.unsigned_abs() as usize;
+        i += 1;
+    } else {
+        let (width, len) = parse_digits(&format[i..], 10, usize::MAX);
+        if width > MAX_FIELD {
+            return Err(Error::InvalidWidth(digits_text(&format[i..i + len])));
+        }
+        spec.width = width as usize;
+        i += len;
+    }
+    if format.get(i) == Some(&b'.') {
+        i += 1;
+        if format.get(i) == Some(&b'*') {
+            let precision = clamp_i64(args.int());
+            if precision > MAX_FIELD as i64 {
+                return Err(Error::InvalidPrecision(args.last().to_string()));
+            }
+            // 負の精度は指定なしと同じ
+            spec.precision = usize::try_from(precision).ok();
+            i += 1;
+        } else {
+            let (precision, len) = parse_digits(&format[i..], 10, usize::MAX);
+            if precision > MAX_FIELD {
+                return Err(Error::InvalidPrecision(digits_text(&format[i..i + len])));
+            }
+            spec.precision = Some(precision as usize);
+            i += len;
+        }
+    }
+    // 長さ修飾子は受け付けるが意味はない
+    while matches!(
+        format.get(i),
+        Some(b'h' | b'l' | b'L' | b'q' | b'j' | b'z' | b't')
+    ) {
+        i += 1;
+    }
+    let invalid = |end: usize| {
+        Error::InvalidConversion(
+            String::from_utf8_lossy(&format[..end.min(format.len())]).to_string(),
+        )
+    };
+    match format.get(i) {
+        Some(b'b') if i > 1 => Err(invalid(i + 1)),
+        Some(&conv) if b"diouxXfFeEgGcsb".contains(&conv) => {
+            spec.conv = conv;
+            Ok((spec, i + 1))
+        }
+        _ => Err(invalid(i + 1)),
+    }
+}
+
+/// Writes one argument converted by `spec`. Returns whether a `\c` in
+/// a `%b` argument asked to stop all output.
+fn convert(spec: &Spec, args: &mut Args, out: &mut Vec<u8>) -> Result<bool, Error> {
+    match spec.conv {
+        b'd' | b'i' => {
+            let val = clamp_i64(args.int());
+            let digits = int_digits(val.unsigned_abs().to_string(), spec.precision);
+            pad(
+                spec,
+                sign(spec, val < 0),
+                &digits,
+                spec.precision.is_none(),
+                out,
+            );
+        }
+        b'o' | b'u' | b'x' | b'X' => {
+            // 負の値は strtoumax と同じく 2^64 で折り返す
+            let val = args.int() as u64;
+            let (digits, prefix) = match spec.conv {
+                b'o' => (format!("{:o}", val), ""),
+                b'u' => (val.to_string(), ""),
+                b'x' => (format!("{:x}", val), "0x"),
+                _ => (format!("{:X}", val), "0X"),
+            };
+            let mut digits = int_digits(digits, spec.precision);
+            let prefix = match spec.conv {
+                b'o' if spec.alt && !digits.starts_with('0') => {
+                    digits.insert(0, '0');
+                    ""
+                }
+                b'x' | b'X' if spec.alt && val != 0 => prefix,
+                _ => "",
+            };
+            pad(spec, prefix, &digits, spec.precision.is_none(), out);
+        }
+        b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => {
+            let val = args.float();
+            let body = format_float(val, spec);
+            let body = if spec.conv.is_ascii_uppercase() {
+                body.to_uppercase()
+            } else {
+                body
+            };
+            let negative = val.is_sign_negative();
+            pad(spec, sign(spec, negative), &body, val.is_finite(), out);
+        }
+        b'c' => {
+            let byte = args.str().as_bytes().first().copied().unwrap_or(0);
+            pad_bytes(spec, &[byte], out);
+        }
+        b's' => {
+            let arg = args.str().as_bytes();
+            let len = spec.precision.map_or(arg.len(), |p| p.min(arg.len()));
+            pad_bytes(spec, &arg[..len], out);
+        }
+        _ => return Ok(unescape(args.str().as_bytes(), Escapes::PrintfArg, out)?),
+    }
+    Ok(false)
+}
+
+impl<'a> Args<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.used)?;
+        self.used += 1;
+        Some(arg)
+    }
+
+    /// The argument consumed last, or `""` if there was none.
+    fn last(&self) -> &'a str {
+        self.used
+            .checked_sub(1)
+            .and_then(|i| self.args.get(i))
+            .map_or("", String::as_str)
+    }
+
+    fn str(&mut self) -> &'a str {
+        self.next().unwrap_or("")
+    }
+
+    fn int(&mut self) -> i128 {
+        let arg = self.str();
+        parse_int(arg).unwrap_or_else(|(val, msg)| self.warn(arg, msg, val))
+    }
+
+    fn float(&mut self) -> f64 {
+        let arg = self.str();
+        parse_float(arg).unwrap_or_else(|(val, msg)| self.warn(arg, msg, val))
+    }
+
+    /// Records a conversion problem and returns the value to use anyway.
+    fn warn<T>(&mut self, arg: &str, msg: &str, val: T) -> T {
+        self.warnings.push(format!("'{}': {}", arg, msg));
+        self.status = EXIT_FAILURE;
+        val
+    }
+}
+
+fn not_numeric() -> &'static str {
+    tr_str("expected a numeric value", "数値ではありません")
+}
+
+fn not_converted() -> &'static str {
+    tr_str(
+        "value not completely converted",
+        "値を最後まで変換できません",
+    )
+}
+
+fn out_of_range() -> &'static str {
+    tr_str("Numerical result out of range", "数値が範囲外です")
+}
+
+/// Parses an integer like strtoimax: decimal, `0x` hex or `0` octal, or
+/// the code of a character after a quote, as in `"'a"`. On failure the
+/// error still carries the value to print.
+fn parse_int(arg: &str) -> Result<i128, (i128, &'static str)> {
+    if let Some(ch) = char_constant(arg) {
+        return Ok(ch as i128);
+    }
+    let s = arg.trim_start();
+    if s.is_empty() {
+        return Ok(0);
+    }
+    let (negative, s) = match s.as_bytes()[0] {
+        b'-' => (true, &s[1..]),
+        b'+' => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (radix, digits) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => (16, hex),
+        None if s.starts_with('0') => (8, s),
+        None => (10, s),
+    };
+    let len = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(digits.len());
+    let val = digits[..len].chars().try_fold(0u64, |val, c| {
+        val.checked_mul(radix as u64)?
+            .checked_add(c.to_digit(radix).unwrap() as u64)
+    });
+    let signed = |val: u64| {
+        if negative {
+            -(val as i128)
+        } else {
+            val as i128
+        }
+    };
+    match val {
+        // 「0x」だけなら strtol は「0」まで読む
+        _ if len == 0 && radix == 16 => Err((0, not_converted())),
+        _ if len == 0 => Err((0, not_numeric())),
+        None => Err((signed(u64::MAX), out_of_range())),
+        Some(val) if len < digits.len() => Err((signed(val), not_converted())),
+        Some(val) => Ok(signed(val)),
+    }
+}
+
+/// Like [`parse_int`], for floating-point numbers as strtod reads them.
+fn parse_float(arg: &str) -> Result<f64, (f64, &'static str)> {
+    if let Some(ch) = char_constant(arg) {
+        return Ok(ch as u32 as f64);
+    }
+    let s = arg.trim_start();
+    if s.is_empty() {
+        return Ok(0.0);
+    }
+    match float_prefix(s) {
+        None => Err((0.0, not_numeric())),
+        Some((val, len)) if len < s.len() => Err((val, not_converted())),
+        Some((val, _)) => Ok(val),
+    }
+}
+
+/// The longest prefix of `s` that is a number, with its length.
+fn float_prefix(s: &str) -> Option<(f64, usize)> {
+    let bytes = s.as_bytes();
+    let negative = bytes[0] == b'-';
+    let start = usize::from(matches!(bytes[0], b'+' | b'-'));
+    let signed = |val: f64| if negative { -val } else { val };
+    let rest = &s[start..];
+    for word in ["infinity", "inf", "nan"] {
+        if rest.len() >= word.len() && rest[..word.len()].eq_ignore_ascii_case(word) {
+            let val = if word == "nan" {
+                f64::NAN
+            } else {
+                f64::INFINITY
+            };
+            return Some((signed(val), start + word.len()));
+        }
+    }
+    if let Some(hex) = rest.strip_prefix("0x").or_else(|| rest.strip_prefix("0X")) {
+        let len = hex
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(hex.len());
+        if len > 0 {
+            let val = hex[..len]
+                .chars()
+                .fold(0.0, |val, c| val * 16.0 + c.to_digit(16).unwrap() as f64);
+            return Some((signed(val), start + 2 + len));
+        }
+    }
+    let digits = |i: &mut usize| {
+        let from = *i;
+        while bytes.get(*i).is_some_and(u8::is_ascii_digit) {
+            *i += 1;
+        }
+        *i - from
+    };
+    let mut i = start;
+    let mut count = digits(&mut i);
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        count += digits(&mut i);
+    }
+    if count == 0 {
+        return None;
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(bytes.get(j), Some(b'+' | b'-')) {
+            j += 1;
+        }
+        if digits(&mut j) > 0 {
+            i = j;
+        }
+    }
+    s[..i].parse().ok().map(|val| (val, i))
+}
+
+/// The character after a leading `'` or `"`, as the shell printf takes it.
+fn char_constant(arg: &str) -> Option<char> {
+    let mut chars = arg.chars();
+    match chars.next() {
+        Some('\'' | '"') => Some(chars.next().unwrap_or('\0')),
+        _ => None,
+    }
+}
+
+fn digits_text(digits: &[u8]) -> String {
+    String::from_utf8_lossy(digits).to_string()
+}
+
+fn clamp_i64(val: i128) -> i64 {
+    val.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Pads integer digits to the precision, the minimum number of digits.
+/// A zero precision prints nothing for zero, as C does.
+fn int_digits(digits: String, precision: Option<usize>) -> String {
+    match precision {
+        Some(0) if digits == "0" => String::new(),
+        Some(p) if p > digits.len() => "0".repeat(p - digits.len()) + &digits,
+        _ => digits,
+    }
+}
+
+fn sign(spec: &Spec, negative: bool) -> &'static str {
+    if negative {
+        "-"
+    } else if spec.plus {
+        "+"
+    } else if spec.space {
+        " "
+    } else {
+        ""
+    }
+}
+
+/// The magnitude of `val` in `%f`, `%e` or `%g` notation; the sign is
+/// added by [`pad`].
+fn format_float(val: f64, spec: &Spec) -> String {
+    let val = val.abs();
+    if val.is_nan() {
+        return "nan".to_string();
+    }
+    if val.is_infinite() {
+        return "inf".to_string();
+    }
+    let precision = spec.precision.unwrap_or(6);
+    match spec.conv.to_ascii_lowercase() {
+        b'f' if spec.alt && precision == 0 => format!("{:.0}.", val),
+        b'f' => fixed(val, precision),
+        b'e' => {
+            let body = format_exp(val, precision);
+            match body.split_once('e') {
+                Some((mantissa, exp)) if spec.alt && precision == 0 => {
+                    format!("{}.e{}", mantissa, exp)
+                }
+                _ => body,
+            }
+        }
+        _ => {
+            let precision = precision.max(1);
+            // 有効数字に丸めた後の指数で %e と %f を選ぶ
+            let exp = if val == 0.0 {
+                0
+            } else {
+                let body = format!("{:.*e}", (precision - 1).min(EXACT_DIGITS), val);
+                body.split_once('e').unwrap().1.parse::<i32>().unwrap()
+            };
+            let body = if exp < -4 || exp >= precision as i32 {
+                format_exp(val, precision - 1)
+            } else {
+                fixed(val, (precision as i64 - 1 - exp as i64) as usize)
+            };
+            if spec.alt {
+                return body;
+            }
+            let (mantissa, exp) = match body.split_once('e') {
+                Some((mantissa, exp)) => (mantissa, format!("e{}", exp)),
+                None => (&body[..], String::new()),
+            };
+            let mantissa = if mantissa.contains('.') {
+                mantissa.trim_end_matches('0').trim_end_matches('.')
+            } else {
+                mantissa
+            };
+            format!("{}{}", mantissa, exp)
+        }
+    }
+}
+
+/// More digits than any `f64` needs: its exact decimal expansion ends
+/// within 1074 places, so further digits are all zero. std's `{:.*}`
+/// also rejects precisions above `u16::MAX`.
+const EXACT_DIGITS: usize = 1100;
+
+/// `val` with `precision` digits after the point.
+fn fixed(val: f64, precision: usize) -> String {
+    let shown = precision.min(EXACT_DIGITS);
+    let mut body = format!("{:.*}", shown, val);
+    body.extend(std::iter::repeat_n('0', precision - shown));
+    body
+}
+
+/// `%e` notation with C's signed, at least two-digit exponent.
+fn format_exp(val: f64, precision: usize) -> String {
+    let shown = precision.min(EXACT_DIGITS);
+    let body = format!("{:.*e}", shown, val);
+    let (mantissa, exp) = body.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    format!(
+        "{}{}e{}{:02}",
+        mantissa,
+        "0".repeat(precision - shown),
+        if exp < 0 { '-' } else { '+' },
+        exp.unsigned_abs()
+    )
+}
+
+/// Pads a number to the field width. Zeros go between the sign or prefix
+/// and the digits when the `0` flag applies.
+fn pad(spec: &Spec, prefix: &str, digits: &str, zero_ok: bool, out: &mut Vec<u8>) {
+    let len = prefix.len() + digits.len();
+    let fill = spec.width.saturating_sub(len);
+    if spec.left {
+        out.extend_from_slice(prefix.as_bytes());
+        out.extend_from_slice(digits.as_bytes());
+        out.resize(out.len() + fill, b' ');
+    } else if spec.zero && zero_ok {
+        out.extend_from_slice(prefix.as_bytes());
+        out.resize(out.len() + fill, b'0');
+        out.extend_from_slice(digits.as_bytes());
+    } else {
+        out.resize(out.len() + fill, b' ');
+        out.extend_from_slice(prefix.as_bytes());
+        out.extend_from_slice(digits.as_bytes());
+    }
+}
+
+/// Pads `%s` and `%c` output, which the `0` flag does not affect. Widths
+/// count bytes, as in coreutils.
+fn pad_bytes(spec: &Spec, text: &[u8], out: &mut Vec<u8>) {
+    let fill = spec.width.saturating_sub(text.len());
+    if !spec.left {
+        out.resize(out.len() + fill, b' ');
+    }
+    out.extend_from_slice(text);
+    if spec.left {
+        out.resize(out.len() + fill, b' ');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_float, parse_int, run, Config};
+
+    fn printf(format: &str, args: &[&str]) -> (String, String, i32) {
+        let config = Config {
+            format: format.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        };
+        let mut out = vec![];
+        let mut err = vec![];
+        let status = run(config, &mut out, &mut err).unwrap();
+        (
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+            status,
+        )
+    }
+
+    fn out(format: &str, args: &[&str]) -> String {
+        printf(format, args).0
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(out("%d %i %d", &["42", "-3", "'a"]), "42 -3 97");
+        assert_eq!(out("%d %d %d", &["0x1F", "017", " +5"]), "31 15 5");
+        assert_eq!(
+            out(
+                "%.3d|%5.2d|%-5d|%05d|%+d|% d",
+                &["5", "3", "7", "-42", "5", "5"]
+            ),
+            "005|   03|7    |-0042|+5| 5"
+        );
+        assert_eq!(
+            out(
+                "%u %x %X %#x %#o %o %#x",
+                &["-1", "255", "255", "255", "8", "0", "0"]
+            ),
+            "18446744073709551615 ff FF 0xff 010 0 0"
+        );
+        assert_eq!(
+            out("[%.0d][%*d|%-*d]", &["0", "4", "1", "3", "2"]),
+            "[][   1|2  ]"
+        );
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(
+            out(
+                "%f %.0f %#.0f %08.3f %+.2f",
+                &["3.14159", "2.5", "2", "-3.14159", "1"]
+            ),
+            "3.141590 2 2. -003.142 +1.00"
+        );
+        assert_eq!(
+            out("%e %E %.0e %#.0e", &["1234.5", "0.000123", "15", "15"]),
+            "1.234500e+03 1.230000E-04 2e+01 2.e+01"
+        );
+        assert_eq!(
+            out(
+                "%g %g %G %g %g %g %g %#g %.3g %g",
+                &[
+                    "1234567", "123456", "0.0001", "100000", "1e-5", "0", "-0", "1.5", "3.14159",
+                    "1e100"
+                ]
+            ),
+            "1.23457e+06 123456 0.0001 100000 1e-05 0 -0 1.50000 3.14 1e+100"
+        );
+        assert_eq!(
+            out("%f %f %F %5f", &["inf", "-inf", "nan", "0x10"]),
+            "inf -inf NAN 16.000000"
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(
+            out("[%5s][%-5s][%.2s][%5.1s]", &["ab", "ab", "abc", "xyz"]),
+            "[   ab][ab   ][ab][    x]"
+        );
+        assert_eq!(out("[%c][%3c][%-3c]", &["xyz", "a", "b"]), "[x][  a][b  ]");
+        assert_eq!(out("%c", &[""]), "\0");
+        assert_eq!(out("%b|", &[r"a\tb\0101\101"]), "a\tbAA|");
+        assert_eq!(out("%s|%b|%s", &["1", r"x\cy", "3"]), "1|x");
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(out(r#"a\tb\n\\\"\q"#, &[]), "a\tb\n\\\"\\q");
+        assert_eq!(out(r"\101\0101\x41\x4a2", &[]), "A\u{8}1AJ2");
+        assert_eq!(out(r"é\U0001F600", &[]), "é😀");
+        assert_eq!(out(r"a\cb", &[]), "a");
+        assert_eq!(out(r"100%% %s", &["sure"]), "100% sure");
+    }
+
+    #[test]
+    fn test_recycling() {
+        assert_eq!(out("%s-%s\n", &["a", "b", "c"]), "a-b\nc-\n");
+        assert_eq!(out("x\n", &["unused"]), "x\n");
+        assert_eq!(out("%s %d\n", &[]), " 0\n");
+        assert_eq!(out("[%s]", &["a", "b"]), "[a][b]");
+    }
+
+    #[test]
+    fn test_bad_numbers() {
+        assert_eq!(
+            printf("%d\n", &["12abc"]),
+            (
+                "12\n".to_string(),
+                "printfr: '12abc': value not completely converted\n".to_string(),
+                1
+            )
+        );
+        let (out, err, status) = printf("%d %f", &["abc", "1e"]);
+        assert_eq!(out, "0 1.000000");
+        assert!(err.contains("'abc': expected a numeric value"));
+        assert!(err.contains("'1e': value not completely converted"));
+        assert_eq!(status, 1);
+        assert_eq!(
+            printf("%d", &["99999999999999999999"]).0,
+            "9223372036854775807"
+        );
+        assert_eq!(printf("%d", &[""]), ("0".to_string(), String::new(), 0));
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(parse_int("-0x10"), Ok(-16));
+        assert_eq!(parse_int("0x"), Err((0, "value not completely converted")));
+        assert_eq!(parse_int("+"), Err((0, "expected a numeric value")));
+        assert_eq!(parse_int("\"é"), Ok(233));
+        assert_eq!(parse_float("  1.5e3"), Ok(1500.0));
+        assert_eq!(parse_float(".5"), Ok(0.5));
+        assert_eq!(
+            parse_float("1.5e+"),
+            Err((1.5, "value not completely converted"))
+        );
+        assert_eq!(parse_float("."), Err((0.0, "expected a numeric value")));
+        assert!(parse_float("-NaN").unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_invalid_conversions() {
+        for (format, spec) in [("a%z", "%z"), ("%", "%"), ("%5%", "%5%"), ("[%5b]", "%5b")] {
+            let config = Config {
+                format: format.to_string(),
+                args: vec![],
+            };
+            let mut out = vec![];
+            let err = run(config, &mut out, &mut vec![]).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("{}: invalid conversion specification", spec)
+            );
+        }
+        let config = Config {
+            format: r"a\x".to_string(),
+            args: vec![],
+        };
+        let mut out = vec![];
+        let err = run(config, &mut out, &mut vec![]).unwrap_err();
+        assert_eq!(err.to_string(), "missing hexadecimal number in escape");
+        assert_eq!(out, b"a");
+    }
+
+    #[test]
+    fn test_field_limits() {
+        for (format, args, msg) in [
+            (
+                "%*d",
+                &["100000000000", "1"][..],
+                "invalid field width: '100000000000'",
+            ),
+            (
+                "%-*d",
+                &["-3000000000", "1"],
+                "invalid field width: '-3000000000'",
+            ),
+            ("%3000000000d", &["1"], "invalid field width: '3000000000'"),
+            (
+                "%.*f",
+                &["3000000000", "1"],
+                "invalid precision: '3000000000'",
+            ),
+            ("%.3000000000s", &["a"], "invalid precision: '3000000000'"),
+        ] {
+            let config = Config {
+                format: format.to_string(),
+                args: args.iter().map(|a| a.to_string()).collect(),
+            };
+            let err = run(config, &mut vec![], &mut vec![]).unwrap_err();
+            assert_eq!(err.to_string(), msg);
+        }
+        let long = out("%.70000f", &["1"]);
+        assert_eq!(long.len(), 70002);
+        assert!(long.starts_with("1.000") && long.ends_with("000"));
+        let long = out("%.2000e", &["0.1"]);
+        assert!(long.starts_with("1.000000000000000055511151231257827"));
+        assert!(long.ends_with("0000e-01"));
+        assert_eq!(long.len(), 2006);
+        assert_eq!(out("%.70000g", &["0.5"]), "0.5");
+    }
+}
@@ -0,0 +1,11 @@
+use std::io;
+
+fn main() {
+    let result = printfr::get_args().and_then(|config| {
+        printfr::run(config, &mut io::stdout().lock(), &mut io::stderr().lock())
+    });
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => std::process::exit(clir_common::report("printfr", &e)),
+    }
+}
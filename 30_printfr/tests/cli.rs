@@ -0,0 +1,163 @@
+use assert_cmd::Command;
+use clir_testkit::{GnuCompare, TestResult};
+use predicates::prelude::*;
+
+const PRG: &str = "printfr";
+
+// --------------------------------------------------
+#[test]
+fn usage() -> TestResult {
+    for flag in &["-h", "--help"] {
+        Command::cargo_bin(PRG)?
+            .arg(flag)
+            .assert()
+            .stdout(predicate::str::contains("Usage"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_no_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("<FORMAT>"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn formats() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([r"%s has %d items at %05.2f\n", "cart", "-3", "1.5"])
+        .assert()
+        .success()
+        .stdout("cart has -3 items at 01.50\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recycles_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([r"%s=%x\n", "a", "10", "b", "255"])
+        .assert()
+        .success()
+        .stdout("a=a\nb=ff\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bad_number() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([r"%d\n", "12abc", "7"])
+        .assert()
+        .code(1)
+        .stdout("12\n7\n")
+        .stderr("printfr: '12abc': value not completely converted\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bad_number_ja() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([r"%d %x\n", "abc", r"\x"])
+        .env("LC_ALL", "ja_JP.UTF-8")
+        .assert()
+        .code(1)
+        .stdout("0 0\n")
+        .stderr("printfr: 'abc': 数値ではありません\nprintfr: '\\x': 数値ではありません\n");
+    Command::cargo_bin(PRG)?
+        .arg(r"a\x")
+        .env("LC_ALL", "ja_JP.UTF-8")
+        .assert()
+        .code(1)
+        .stderr("printfr: エスケープに16進数がありません\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_invalid_conversion() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["a%yb", "1"])
+        .assert()
+        .code(1)
+        .stdout("a")
+        .stderr("printfr: %y: invalid conversion specification\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_huge_field() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["a%*d", "100000000000", "1"])
+        .assert()
+        .code(1)
+        .stdout("a")
+        .stderr("printfr: invalid field width: '100000000000'\n");
+    Command::cargo_bin(PRG)?
+        .args(["%.70000f", "1"])
+        .assert()
+        .success()
+        .stdout(format!("1.{}", "0".repeat(70000)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn same_as_gnu() -> TestResult {
+    let cases: &[&[&str]] = &[
+        &[r"%s|%5s|%-5s|%.2s\n", "abc", "de", "f", "ghi"],
+        &[
+            r"%d %i %5d %-5d| %05d %+d % d %.3d\n",
+            "1",
+            "-2",
+            "3",
+            "4",
+            "-5",
+            "6",
+            "7",
+            "8",
+        ],
+        &[r"%o %#o %u %x %#X\n", "8", "8", "-1", "255", "255"],
+        &[r"%d %d %d %d\n", "'A", "0x1f", "017", " 9"],
+        &[
+            r"%f %.2f %e %E %g %G %#g\n",
+            "3.14159",
+            "2.675",
+            "1234.5",
+            "0.00012",
+            "1e-5",
+            "1e20",
+            "2",
+        ],
+        &[r"%08.3f|%-8.2e|%+g\n", "-3.14159", "42", "0.5"],
+        &[r"%*d|%-*d|%.*f\n", "5", "1", "4", "2", "2", "3.14159"],
+        &[r"%b|%b\n", r"a\tb\0101", r"\101\\"],
+        &[r"%c%c%c\n", "hello", "w", "!"],
+        &[r"%s-%s\n", "a", "b", "c"],
+        &[r"%d %s\n", "-5", "-x"],
+        &[r#"\101\x42\t\\\a\e\"\q\n"#],
+        &[r"100%% %s\n", "done"],
+        &[r"%d\n", "12abc", "abc", "99999999999999999999", ""],
+        &[r"%f\n", "1e", "x", "0x10"],
+        &[r"a%s\cb\n", "x"],
+        &[r"[%b]\n", r"one\ctwo", "three"],
+        &["%z", "1"],
+        &["x%", "1"],
+        &[r"\x"],
+        &["%*d", "100000000000", "1"],
+        &["%.*f", "3000000000", "1"],
+        &["%.70000f", "1"],
+        &["%.1500e|%.1200g", "0.5", "0.25"],
+    ];
+    for args in cases {
+        GnuCompare::new(PRG, "printf").args(*args).assert()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Cursor, LineWriter};
+
+use clir_common::{parse_pos, Terminator};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use csv::{ReaderBuilder, StringRecord};
+use regex::Regex;
+
+const SIZE: usize = 8 * 1024 * 1024;
+
+fn catr_number(c: &mut Criterion) {
+    let text = benches::text(SIZE);
+    let path = env::temp_dir().join("clir-bench-catr.txt");
+    fs::write(&path, &text).unwrap();
+    let args = ["catr", "-n", path.to_str().unwrap()];
+
+    // 端末向けの行バッファと、パイプやファイル向けのバッファを比べる
+    let mut group = c.benchmark_group("catr");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("number_line_buffered", |b| {
+        let mut out = LineWriter::new(File::create("/dev/null").unwrap());
+        b.iter(|| catr::run_with(args, &mut out, &mut io::sink()).unwrap())
+    });
+    group.bench_function("number_buffered", |b| {
+        let mut out = BufWriter::new(File::create("/dev/null").unwrap());
+        b.iter(|| catr::run_with(args, &mut out, &mut io::sink()).unwrap())
+    });
+    group.finish();
+    fs::remove_file(path).unwrap();
+}
+
+fn wcr_count(c: &mut Criterion) {
+    let text = benches::text(SIZE);
+    let mut group = c.benchmark_group("wcr");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("count", |b| {
+        b.iter(|| wcr::count(Cursor::new(&text)).unwrap())
+    });
+    group.bench_function("count_lines_bytes", |b| {
+        b.iter(|| wcr::count_lines_bytes(Cursor::new(&text)).unwrap())
+    });
+    group.finish();
+}
+
+fn wcr_parallel(c: &mut Criterion) {
+    // 大きめのログが並んだディレクトリを、1スレッドと複数スレッドで数える
+    let dir = env::temp_dir().join("clir-bench-wcr");
+    fs::create_dir_all(&dir).unwrap();
+    let text = benches::text(SIZE / 4);
+    let files: Vec<String> = (0..16)
+        .map(|i| {
+            let path = dir.join(format!("{}.log", i));
+            fs::write(&path, &text).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("wcr_files");
+    group.throughput(Throughput::Bytes((text.len() * files.len()) as u64));
+    for jobs in ["1", "4"] {
+        let args: Vec<&str> = ["wcr", "-j", jobs]
+            .into_iter()
+            .chain(files.iter().map(String::as_str))
+            .collect();
+        group.bench_function(format!("jobs_{}", jobs), |b| {
+            b.iter(|| wcr::run_with(&args, &mut io::sink(), &mut io::sink()).unwrap())
+        });
+    }
+    group.finish();
+    fs::remove_dir_all(dir).unwrap();
+}
+
+fn grepr_find_lines(c: &mut Criterion) {
+    let text = benches::text(SIZE);
+    let mut group = c.benchmark_group("grepr");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for (name, pattern) in [("literal", "fox"), ("regex", r"^\w+ (lazy|dog)$")] {
+        let pattern = Regex::new(pattern).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                grepr::find_lines(Cursor::new(&text), &pattern, false, Terminator::Newline).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn cutr_extract(c: &mut Criterion) {
+    let text = benches::text(SIZE / 8);
+    let lines: Vec<String> = Cursor::new(&text).lines().map(Result::unwrap).collect();
+    let records: Vec<StringRecord> = ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(&benches::csv(SIZE / 8, 12)[..])
+        .records()
+        .map(Result::unwrap)
+        .collect();
+    let pos = parse_pos("1,3-5,8-12").unwrap();
+
+    let mut group = c.benchmark_group("cutr");
+    group.throughput(Throughput::Bytes((SIZE / 8) as u64));
+    group.bench_function("extract_chars", |b| {
+        b.iter(|| {
+            for line in &lines {
+                cutr::extract_chars(line, &pos);
+            }
+        })
+    });
+    group.bench_function("extract_bytes", |b| {
+        b.iter(|| {
+            for line in &lines {
+                cutr::extract_bytes(line, &pos);
+            }
+        })
+    });
+    group.bench_function("extract_fields", |b| {
+        b.iter(|| {
+            for record in &records {
+                cutr::extract_fields(record, &pos);
+            }
+        })
+    });
+    group.finish();
+}
+
+fn tailr_seek(c: &mut Criterion) {
+    let text = benches::text(SIZE);
+    let total_lines = text.iter().filter(|&&b| b == b'\n').count() as i64;
+    let total_bytes = text.len() as i64;
+    let last = tailr::parse_num("10").unwrap();
+
+    let mut group = c.benchmark_group("tailr");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("last_lines", |b| {
+        b.iter_batched(
+            || Cursor::new(&text),
+            |file| tailr::print_lines(&mut io::sink(), file, &last, total_lines).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("last_bytes", |b| {
+        b.iter_batched(
+            || Cursor::new(&text),
+            |file| tailr::print_bytes(&mut io::sink(), file, &last, total_bytes).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    hot_paths,
+    catr_number,
+    wcr_count,
+    wcr_parallel,
+    grepr_find_lines,
+    cutr_extract,
+    tailr_seek
+);
+criterion_main!(hot_paths);
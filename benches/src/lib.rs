@@ -0,0 +1,38 @@
+//! Generated inputs for the criterion benchmarks in `benches/`. They are
+//! built in memory with a fixed seed so runs can be compared.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+const WORDS: [&str; 12] = [
+    "the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog", "zürich", "東京", "42", "",
+];
+
+/// About `size` bytes of text lines made of space-separated words,
+/// including some non-ASCII and empty ones.
+pub fn text(size: usize) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut text = Vec::with_capacity(size + 128);
+    while text.len() < size {
+        let len = rng.gen_range(0..16);
+        let line: Vec<&str> = (0..len).map(|_| *WORDS.choose(&mut rng).unwrap()).collect();
+        text.extend_from_slice(line.join(" ").as_bytes());
+        text.push(b'\n');
+    }
+    text
+}
+
+/// About `size` bytes of CSV rows with `columns` fields each.
+pub fn csv(size: usize, columns: usize) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut csv = Vec::with_capacity(size + 128);
+    while csv.len() < size {
+        let row: Vec<&str> = (0..columns)
+            .map(|_| *WORDS.choose(&mut rng).unwrap())
+            .collect();
+        csv.extend_from_slice(row.join(",").as_bytes());
+        csv.push(b'\n');
+    }
+    csv
+}
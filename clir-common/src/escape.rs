@@ -0,0 +1,172 @@
+//! Backslash escapes as `echo -e` and `printf` expand them.
+
+use thiserror::Error;
+
+use crate::i18n::{tr, Msg};
+
+/// Which escapes are recognized, as the dialects differ in octal and
+/// Unicode escapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escapes {
+    /// bash's `echo -e`: octal is `\0NNN`, `\E` is escape too, and a
+    /// `\x` without digits is kept.
+    Echo,
+    /// A `printf` format: octal is `\NNN`, with `\"`, `\u` and `\U`.
+    Printf,
+    /// A `printf` `%b` argument, which also takes octal as `\0NNN`.
+    PrintfArg,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EscapeError {
+    #[error("{}", tr(Msg::MissingHex, &[]))]
+    MissingHex,
+    #[error("{}", tr(Msg::InvalidUniversal, &[.0]))]
+    InvalidUniversal(String),
+}
+
+/// Expands the backslash escapes of `text` into `out`. Returns whether
+/// `\c` asked to stop the output there. Unknown escapes are kept as they
+/// are; only the `printf` dialects can fail.
+pub fn unescape(text: &[u8], escapes: Escapes, out: &mut Vec<u8>) -> Result<bool, EscapeError> {
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] != b'\\' {
+            out.push(text[i]);
+            i += 1;
+            continue;
+        }
+        match escape(&text[i + 1..], escapes, out)? {
+            Some(len) => i += 1 + len,
+            None => return Ok(true),
+        }
+    }
+    Ok(false)
+}
+
+/// Expands the escape after a backslash at the start of `rest` into
+/// `out`, returning how many bytes it used, or `None` for `\c`.
+pub fn escape(
+    rest: &[u8],
+    escapes: Escapes,
+    out: &mut Vec<u8>,
+) -> Result<Option<usize>, EscapeError> {
+    let Some(&c) = rest.first() else {
+        out.push(b'\\');
+        return Ok(Some(0));
+    };
+    let printf = escapes != Escapes::Echo;
+    let simple = match c {
+        b'"' if printf => b'"',
+        b'\\' => b'\\',
+        b'a' => 0x07,
+        b'b' => 0x08,
+        b'e' => 0x1b,
+        b'E' if !printf => 0x1b,
+        b'f' => 0x0c,
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'v' => 0x0b,
+        b'c' => return Ok(None),
+        b'x' => {
+            let (val, len) = parse_digits(&rest[1..], 16, 2);
+            match len {
+                0 if printf => return Err(EscapeError::MissingHex),
+                0 => out.extend_from_slice(b"\\x"),
+                _ => out.push(val as u8),
+            }
+            return Ok(Some(1 + len));
+        }
+        b'0'..=b'7' if printf || c == b'0' => {
+            let skip = usize::from(escapes != Escapes::Printf && c == b'0');
+            let (val, len) = parse_digits(&rest[skip..], 8, 3);
+            // bash と同じく 0o377 を超える値は下位8ビットにする
+            out.push(val as u8);
+            return Ok(Some(skip + len));
+        }
+        b'u' | b'U' if printf => {
+            let want = if c == b'u' { 4 } else { 8 };
+            let (val, len) = parse_digits(&rest[1..], 16, want);
+            if len < want {
+                return Err(EscapeError::MissingHex);
+            }
+            let ch = char::from_u32(val as u32).ok_or_else(|| {
+                EscapeError::InvalidUniversal(String::from_utf8_lossy(&rest[..1 + len]).to_string())
+            })?;
+            out.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            return Ok(Some(1 + len));
+        }
+        _ => {
+            out.extend_from_slice(&[b'\\', c]);
+            return Ok(Some(1));
+        }
+    };
+    out.push(simple);
+    Ok(Some(1))
+}
+
+/// Reads up to `max` leading digits in `radix`, returning their value and
+/// how many there were. The value saturates instead of overflowing.
+pub fn parse_digits(bytes: &[u8], radix: u32, max: usize) -> (u64, usize) {
+    bytes
+        .iter()
+        .take(max)
+        .map_while(|&b| char::from(b).to_digit(radix))
+        .fold((0u64, 0), |(val, len), digit| {
+            (
+                val.saturating_mul(radix as u64)
+                    .saturating_add(digit as u64),
+                len + 1,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_digits, unescape, EscapeError, Escapes};
+
+    fn expand(text: &str, escapes: Escapes) -> Result<(Vec<u8>, bool), EscapeError> {
+        let mut out = vec![];
+        let stop = unescape(text.as_bytes(), escapes, &mut out)?;
+        Ok((out, stop))
+    }
+
+    #[test]
+    fn test_unescape() {
+        let echo = |text| expand(text, Escapes::Echo).unwrap().0;
+        assert_eq!(echo(r"a\tb\nc\\d"), b"a\tb\nc\\d");
+        assert_eq!(echo(r"\a\b\e\E\f\r\v"), b"\x07\x08\x1b\x1b\x0c\r\x0b");
+        assert_eq!(echo(r"\x41\x4a2\x7"), b"AJ2\x07");
+        assert_eq!(echo(r"\0101\01012\0\07"), b"AA2\0\x07");
+        assert_eq!(echo(r"\0777\xff"), [0xff, 0xff]);
+        assert_eq!(echo(r#"\q\xg\8\101\""#), br#"\q\xg\8\101\""#);
+        assert_eq!(echo(r"tail\"), br"tail\");
+        assert_eq!(expand("a\\cb", Escapes::Echo), Ok((b"a".to_vec(), true)));
+
+        let printf = |text| expand(text, Escapes::Printf).unwrap().0;
+        assert_eq!(printf(r#"\101\0101\"\E"#), b"A\x081\"\\E");
+        assert_eq!(printf(r"é\U0001F600"), "é😀".as_bytes());
+        assert_eq!(expand(r"\101\0101", Escapes::PrintfArg).unwrap().0, b"AA");
+
+        assert_eq!(expand(r"\x", Escapes::Printf), Err(EscapeError::MissingHex));
+        assert_eq!(
+            expand(r"\u12", Escapes::PrintfArg),
+            Err(EscapeError::MissingHex)
+        );
+        assert_eq!(
+            expand(r"\ud800", Escapes::Printf),
+            Err(EscapeError::InvalidUniversal("ud800".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_digits() {
+        assert_eq!(parse_digits(b"17z", 8, 3), (15, 2));
+        assert_eq!(parse_digits(b"ffff", 16, 2), (255, 2));
+        assert_eq!(
+            parse_digits(b"99999999999999999999999", 10, usize::MAX),
+            (u64::MAX, 23)
+        );
+    }
+}
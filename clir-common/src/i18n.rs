@@ -0,0 +1,304 @@
+//! A small message catalog for the strings the tools show to users.
+//! The language is picked from the locale like gettext does; everything
+//! that is not Japanese falls back to English.
+
+use std::env;
+use std::fmt::Display;
+use std::io;
+use std::sync::OnceLock;
+
+use clap::{Arg, ArgAction, Command};
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Ja,
+}
+
+impl Lang {
+    /// Reads the language from a locale name such as `ja_JP.UTF-8`.
+    pub fn from_locale(locale: &str) -> Lang {
+        if locale == "ja" || locale.starts_with("ja_") || locale.starts_with("ja.") {
+            Lang::Ja
+        } else {
+            Lang::En
+        }
+    }
+
+    /// The language of the process, from the first of `LC_ALL`,
+    /// `LC_MESSAGES` and `LANG` that is set and not empty.
+    pub fn current() -> Lang {
+        *LANG.get_or_init(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|name| env::var(name).ok())
+                .find(|val| !val.is_empty())
+                .map_or(Lang::En, |val| Lang::from_locale(&val))
+        })
+    }
+}
+
+/// A catalog entry. Texts may refer to their arguments as `{0}`, `{1}`...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    InvalidInteger,
+    InvalidInterval,
+    InvalidSize,
+    IllegalListValue,
+    BadRange,
+    AppletNotFound,
+    MultiCallUsage,
+    AvailableTools,
+    UsageHeading,
+    ArgumentsHeading,
+    OptionsHeading,
+    HelpFlag,
+    VersionFlag,
+    ProgressFlag,
+    UnbalancedQuotes,
+    MissingHex,
+    InvalidUniversal,
+}
+
+impl Msg {
+    fn text(self, lang: Lang) -> &'static str {
+        let (en, ja) = match self {
+            Msg::InvalidInteger => ("Invalid integer \"{0}\"", "整数ではありません \"{0}\""),
+            Msg::InvalidInterval => (
+                "invalid time interval \"{0}\"",
+                "時間間隔が不正です \"{0}\"",
+            ),
+            Msg::InvalidSize => ("invalid size \"{0}\"", "サイズが不正です \"{0}\""),
+            Msg::IllegalListValue => (
+                "illegal list value: \"{0}\"",
+                "リストの値が不正です: \"{0}\"",
+            ),
+            Msg::BadRange => (
+                "First number in range ({0}) must be lower than second number ({1})",
+                "範囲の最初の数 ({0}) は2番目の数 ({1}) より小さくしてください",
+            ),
+            Msg::AppletNotFound => ("{0}: applet not found", "{0}: そのツールはありません"),
+            Msg::MultiCallUsage => ("Usage: {0} TOOL [ARGS]...", "使い方: {0} TOOL [ARGS]..."),
+            Msg::AvailableTools => ("Available tools:", "使えるツール:"),
+            Msg::UsageHeading => ("Usage:", "使い方:"),
+            Msg::ArgumentsHeading => ("Arguments", "引数"),
+            Msg::OptionsHeading => ("Options", "オプション"),
+            Msg::HelpFlag => ("Print help", "ヘルプを表示する"),
+            Msg::VersionFlag => ("Print version", "バージョンを表示する"),
+            Msg::ProgressFlag => ("Show progress on STDERR", "進捗を標準エラー出力に表示する"),
+            Msg::UnbalancedQuotes => ("${0}: unbalanced quotes", "${0}: 引用符が対応していません"),
+            Msg::MissingHex => (
+                "missing hexadecimal number in escape",
+                "エスケープに16進数がありません",
+            ),
+            Msg::InvalidUniversal => (
+                "invalid universal character name \\{0}",
+                "国際文字名 \\{0} が不正です",
+            ),
+        };
+        match lang {
+            Lang::En => en,
+            Lang::Ja => ja,
+        }
+    }
+}
+
+/// Looks up `msg` in the catalog of the current language and fills in
+/// `args`.
+pub fn tr(msg: Msg, args: &[&dyn Display]) -> String {
+    tr_in(Lang::current(), msg, args)
+}
+
+/// Like [`tr`], for an explicit language.
+pub fn tr_in(lang: Lang, msg: Msg, args: &[&dyn Display]) -> String {
+    fill(msg.text(lang), args)
+}
+
+/// Like [`tr`], for a message only one tool shows, given as its English
+/// and Japanese texts instead of a catalog entry.
+pub fn tr_text(en: &str, ja: &str, args: &[&dyn Display]) -> String {
+    fill(tr_str(en, ja), args)
+}
+
+/// Picks the text of the current language, for fixed strings such as
+/// the help of an argument.
+pub fn tr_str<'a>(en: &'a str, ja: &'a str) -> &'a str {
+    match Lang::current() {
+        Lang::En => en,
+        Lang::Ja => ja,
+    }
+}
+
+/// Describes an I/O error in the current language.
+pub fn tr_io(err: &io::Error) -> String {
+    tr_io_in(Lang::current(), err)
+}
+
+/// Like [`tr_io`], for an explicit language. Only errors from the OS are
+/// translated, keeping the `(os error N)` that Rust appends; messages a
+/// tool wrote itself are returned as they are.
+pub fn tr_io_in(lang: Lang, err: &io::Error) -> String {
+    let (Lang::Ja, Some(code)) = (lang, err.raw_os_error()) else {
+        return err.to_string();
+    };
+    let text = match err.kind() {
+        io::ErrorKind::NotFound => "そのようなファイルやディレクトリはありません",
+        io::ErrorKind::PermissionDenied => "許可がありません",
+        io::ErrorKind::AlreadyExists => "ファイルが存在します",
+        io::ErrorKind::IsADirectory => "ディレクトリです",
+        io::ErrorKind::NotADirectory => "ディレクトリではありません",
+        io::ErrorKind::DirectoryNotEmpty => "ディレクトリが空ではありません",
+        io::ErrorKind::ReadOnlyFilesystem => "読み込み専用のファイルシステムです",
+        io::ErrorKind::StorageFull => "デバイスに空き領域がありません",
+        io::ErrorKind::BrokenPipe => "パイプが切断されました",
+        _ => return err.to_string(),
+    };
+    format!("{} (os error {})", text, code)
+}
+
+// 引数の中の「{1}」などを置き換えないよう、テキストは一度だけ走査する
+fn fill(text: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let arg = rest
+            .find('}')
+            .and_then(|end| Some((end, args.get(rest[1..end].parse::<usize>().ok()?)?)));
+        match arg {
+            Some((end, arg)) => {
+                out.push_str(&arg.to_string());
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Translates the parts of the help that clap generates itself: the
+/// usage and section headings and the `--help`/`--version` flags.
+/// English commands are returned unchanged.
+pub fn localize(cmd: Command, lang: Lang) -> Command {
+    if lang == Lang::En {
+        return cmd;
+    }
+    let has_version = cmd.get_version().is_some();
+    // 見出しはclapに固定されているので、テンプレートと各引数の見出しで置き換える
+    let cmd = cmd
+        .help_template(format!(
+            "{{before-help}}{{about-with-newline}}\n{} {{usage}}\n\n{{all-args}}{{after-help}}",
+            Msg::UsageHeading.text(lang)
+        ))
+        .mut_args(|arg| {
+            let heading = if arg.is_positional() {
+                Msg::ArgumentsHeading
+            } else {
+                Msg::OptionsHeading
+            };
+            arg.help_heading(heading.text(lang))
+        })
+        .disable_help_flag(true)
+        .arg(
+            Arg::new("help")
+                .short('h')
+                .long("help")
+                .help(Msg::HelpFlag.text(lang))
+                .help_heading(Msg::OptionsHeading.text(lang))
+                .action(ArgAction::Help),
+        );
+    if !has_version {
+        return cmd;
+    }
+    cmd.disable_version_flag(true).arg(
+        Arg::new("version")
+            .short('V')
+            .long("version")
+            .help(Msg::VersionFlag.text(lang))
+            .help_heading(Msg::OptionsHeading.text(lang))
+            .action(ArgAction::Version),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{Arg, Command};
+
+    use std::io;
+
+    use super::{localize, tr_in, tr_io_in, Lang, Msg};
+
+    #[test]
+    fn test_from_locale() {
+        assert_eq!(Lang::from_locale("ja_JP.UTF-8"), Lang::Ja);
+        assert_eq!(Lang::from_locale("ja"), Lang::Ja);
+        assert_eq!(Lang::from_locale("en_US.UTF-8"), Lang::En);
+        assert_eq!(Lang::from_locale("C"), Lang::En);
+        assert_eq!(Lang::from_locale("jam"), Lang::En);
+    }
+
+    #[test]
+    fn test_tr_in() {
+        assert_eq!(
+            tr_in(Lang::En, Msg::BadRange, &[&3, &2]),
+            "First number in range (3) must be lower than second number (2)"
+        );
+        assert_eq!(
+            tr_in(Lang::Ja, Msg::InvalidInteger, &[&"foo"]),
+            "整数ではありません \"foo\""
+        );
+        assert_eq!(
+            tr_in(Lang::En, Msg::BadRange, &[&"{1}", &2]),
+            "First number in range ({1}) must be lower than second number (2)"
+        );
+        assert_eq!(
+            tr_in(Lang::En, Msg::AppletNotFound, &[]),
+            "{0}: applet not found"
+        );
+    }
+
+    #[test]
+    fn test_tr_io_in() {
+        let err = io::Error::from_raw_os_error(libc::ENOENT);
+        assert_eq!(
+            tr_io_in(Lang::En, &err),
+            "No such file or directory (os error 2)"
+        );
+        assert_eq!(
+            tr_io_in(Lang::Ja, &err),
+            "そのようなファイルやディレクトリはありません (os error 2)"
+        );
+
+        // OS以外のエラーはツール自身の文言なので訳さない
+        let err = io::Error::new(io::ErrorKind::NotFound, "gone");
+        assert_eq!(tr_io_in(Lang::Ja, &err), "gone");
+    }
+
+    #[test]
+    fn test_localize() {
+        let cmd = || {
+            Command::new("tool")
+                .version("0.1.0")
+                .arg(Arg::new("file").help("Input file"))
+                .arg(Arg::new("quiet").short('q').help("Be quiet"))
+        };
+        let help = localize(cmd(), Lang::En).render_help().to_string();
+        assert!(help.contains("Usage: tool"));
+        assert!(help.contains("Print help"));
+
+        let help = localize(cmd(), Lang::Ja).render_help().to_string();
+        assert!(help.contains("使い方: tool"));
+        assert!(help.contains("引数:"));
+        assert!(help.contains("オプション:"));
+        assert!(help.contains("ヘルプを表示する"));
+        assert!(help.contains("バージョンを表示する"));
+        assert!(!help.contains("Options:"));
+    }
+}
@@ -0,0 +1,1152 @@
+//! Helpers shared by the command-line tools in this workspace.
+
+pub mod escape;
+pub mod i18n;
+#[cfg(unix)]
+pub mod meta;
+pub mod posix;
+pub mod progress;
+
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fmt::Display;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::num::NonZeroUsize;
+use std::ops::Range;
+#[cfg(unix)]
+use std::os::fd::{FromRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use bzip2::bufread::MultiBzDecoder;
+use clap::builder::TypedValueParser;
+use clap::ArgAction::SetTrue;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use clap_complete::Shell;
+use flate2::bufread::MultiGzDecoder;
+use regex::Regex;
+use serde::Deserialize;
+use thiserror::Error;
+use xz2::bufread::XzDecoder;
+
+use crate::i18n::{localize, tr, tr_io, Lang, Msg};
+
+pub type MyResult<T> = Result<T, Box<dyn Error>>;
+pub type PositionList = Vec<Range<usize>>;
+
+static ARGS: OnceLock<Vec<OsString>> = OnceLock::new();
+
+/// Exit status for runtime failures such as unreadable files.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit status for invalid arguments, matching what clap uses.
+pub const EXIT_USAGE: i32 = 2;
+
+/// An invalid number or position list given on the command line.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct ParseError(String);
+
+/// An unreadable or malformed config file.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("{path}: {}", tr_io(.source))]
+    Read { path: String, source: io::Error },
+    #[error("{path}: {source}")]
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[error("{}", tr(Msg::UnbalancedQuotes, &[.0]))]
+    Env(String),
+}
+
+impl ToolError for ConfigError {
+    fn exit_code(&self) -> i32 {
+        EXIT_USAGE
+    }
+}
+
+/// One `[tool]` table of the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolConfig {
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+/// An error a tool can report before exiting.
+pub trait ToolError: Display {
+    fn exit_code(&self) -> i32 {
+        EXIT_FAILURE
+    }
+}
+
+impl ToolError for Box<dyn Error> {}
+
+/// Prints `tool: message` to STDERR without stopping.
+pub fn warn(tool: &str, err: &dyn Display) {
+    warn_to(&mut io::stderr(), tool, err);
+}
+
+/// Like [`warn`], but writes to `out`. Write errors are ignored since
+/// there is nowhere left to report them.
+pub fn warn_to(out: &mut dyn Write, tool: &str, err: &dyn Display) {
+    let _ = writeln!(out, "{}: {}", tool, err);
+}
+
+/// Prints a fatal error like [`warn`] and returns the status to exit with.
+pub fn report(tool: &str, err: &impl ToolError) -> i32 {
+    warn(tool, err);
+    err.exit_code()
+}
+
+/// Makes every tool parse `args` instead of the process arguments. The
+/// multi-call binary uses this to hand a tool its own argv.
+pub fn set_args(args: Vec<OsString>) {
+    ARGS.set(args).expect("arguments already set");
+}
+
+/// The arguments the current tool should parse, program name included.
+pub fn args() -> Vec<OsString> {
+    ARGS.get()
+        .cloned()
+        .unwrap_or_else(|| env::args_os().collect())
+}
+
+pub trait CommandExt {
+    /// Like [`Command::get_matches`], but reads [`args`] after the tool's
+    /// defaults from the config file (see [`config_args`]) and the
+    /// environment (see [`env_args`]), and handles the hidden
+    /// `--completions SHELL`, `--man` and `--no-env` flags every tool
+    /// shares.
+    fn get_tool_matches(self) -> ArgMatches;
+
+    /// Like [`CommandExt::get_tool_matches`], for tools that rewrite their
+    /// arguments before parsing.
+    fn get_tool_matches_from(self, args: Vec<OsString>) -> ArgMatches;
+}
+
+impl CommandExt for Command {
+    fn get_tool_matches(self) -> ArgMatches {
+        self.get_tool_matches_from(args())
+    }
+
+    fn get_tool_matches_from(mut self, args: Vec<OsString>) -> ArgMatches {
+        reset_sigpipe();
+        self = localize(self, Lang::current());
+
+        // 必須の引数がなくても補完スクリプトを出せるよう、clapより先に見る
+        if let Some(val) = find_option(&args, "--completions") {
+            let shell = value_parser!(Shell)
+                .parse_ref(&self, None, val)
+                .unwrap_or_else(|e| e.exit());
+            // マルチコールバイナリのシンボリックリンク名でも補完が効くように
+            let bin_name = args
+                .first()
+                .and_then(|a| Path::new(a).file_name())
+                .and_then(|a| a.to_str())
+                .unwrap_or(self.get_name())
+                .to_string();
+            clap_complete::generate(shell, &mut self, bin_name, &mut io::stdout());
+            process::exit(0);
+        }
+        if has_flag(&args, "--man") {
+            let name = self.get_name().to_string();
+            if let Err(e) = write_man(self, &mut io::stdout()) {
+                warn(&name, &e);
+                process::exit(EXIT_FAILURE);
+            }
+            process::exit(0);
+        }
+
+        // 設定ファイルの既定値はコマンドラインの引数より前に置き、後から上書きできるようにする
+        let defaults = config_args(self.get_name()).unwrap_or_else(|e| {
+            process::exit(report(self.get_name(), &e));
+        });
+        // 環境変数は設定ファイルより後に置き、その場限りの指定を優先する
+        let env_defaults = if has_flag(&args, "--no-env") {
+            vec![]
+        } else {
+            env_args(self.get_name()).unwrap_or_else(|e| {
+                process::exit(report(self.get_name(), &e));
+            })
+        };
+        let args: Vec<OsString> = args
+            .iter()
+            .take(1)
+            .cloned()
+            .chain(defaults.into_iter().map(OsString::from))
+            .chain(env_defaults.into_iter().map(OsString::from))
+            .chain(args.iter().skip(1).cloned())
+            .collect();
+
+        // 値を付け忘れたときにclapのエラーを出すためだけの引数
+        self.arg(
+            Arg::new("completions")
+                .long("completions")
+                .value_name("SHELL")
+                .value_parser(value_parser!(Shell))
+                .hide(true),
+        )
+        .arg(Arg::new("man").long("man").action(SetTrue).hide(true))
+        .arg(Arg::new("no-env").long("no-env").action(SetTrue).hide(true))
+        .get_matches_from(args)
+    }
+}
+
+/// Restores the default SIGPIPE action, which Rust sets to ignore, so a
+/// tool writing into a closed pipe (`catr big | head`) ends quietly the
+/// way other Unix tools do instead of failing with "Broken pipe".
+#[cfg(unix)]
+pub fn reset_sigpipe() {
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+}
+
+/// Other systems have no SIGPIPE, so there is nothing to restore.
+#[cfg(not(unix))]
+pub fn reset_sigpipe() {}
+
+/// Writes a roff man page generated from the clap definition of `cmd`.
+pub fn write_man(cmd: Command, out: &mut dyn Write) -> io::Result<()> {
+    clap_mangen::Man::new(cmd).render(out)
+}
+
+/// The config file: `$XDG_CONFIG_HOME/clir/config.toml`, or
+/// `~/.config/clir/config.toml` when that variable is unset.
+pub fn config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("clir").join("config.toml"))
+}
+
+/// Default arguments for `tool` from the config file, e.g.
+///
+/// ```toml
+/// [grepr]
+/// args = ["-i"]
+/// ```
+///
+/// A missing file or table means no defaults.
+pub fn config_args(tool: &str) -> Result<Vec<String>, ConfigError> {
+    let Some(path) = config_path() else {
+        return Ok(vec![]);
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(source) => {
+            return Err(ConfigError::Read {
+                path: path.display().to_string(),
+                source,
+            })
+        }
+    };
+    parse_config(&text, tool).map_err(|source| ConfigError::Parse {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+fn parse_config(text: &str, tool: &str) -> Result<Vec<String>, toml::de::Error> {
+    let mut tools: HashMap<String, ToolConfig> = toml::from_str(text)?;
+    Ok(tools.remove(tool).unwrap_or_default().args)
+}
+
+/// The environment variable holding default arguments for `tool`, e.g.
+/// `GREPR_OPTIONS` for `grepr`.
+pub fn env_var(tool: &str) -> String {
+    format!("{}_OPTIONS", tool.to_uppercase().replace('-', "_"))
+}
+
+/// Default arguments for `tool` from [`env_var`], split like a shell
+/// would, e.g. `GREPR_OPTIONS="-i -e 'a b'"`. Unset means no defaults.
+pub fn env_args(tool: &str) -> Result<Vec<String>, ConfigError> {
+    let name = env_var(tool);
+    let val = env::var(&name).ok();
+    split_env_args(name, val.as_deref())
+}
+
+/// Splits the value `val` of the variable `name`, if it is set.
+fn split_env_args(name: String, val: Option<&str>) -> Result<Vec<String>, ConfigError> {
+    match val {
+        Some(val) => shlex::split(val).ok_or(ConfigError::Env(name)),
+        None => Ok(vec![]),
+    }
+}
+
+/// Finds the value given to the long option `name`, as either
+/// `NAME VALUE` or `NAME=VALUE`, before any `--`.
+fn find_option<'a>(args: &'a [OsString], name: &str) -> Option<&'a OsStr> {
+    let mut iter = args.iter().skip(1).take_while(|a| *a != "--");
+    while let Some(arg) = iter.next() {
+        if arg == name {
+            return iter.next().map(|a| a.as_os_str());
+        }
+        if let Some(val) = arg
+            .to_str()
+            .and_then(|a| a.strip_prefix(name))
+            .and_then(|a| a.strip_prefix('='))
+        {
+            return Some(OsStr::new(val));
+        }
+    }
+    None
+}
+
+/// Whether the long flag `name` appears before any `--`.
+fn has_flag(args: &[OsString], name: &str) -> bool {
+    args.iter()
+        .skip(1)
+        .take_while(|a| *a != "--")
+        .any(|a| a == name)
+}
+
+/// What ends a record: a newline, or a NUL byte as with `-z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    Newline,
+    Nul,
+}
+
+impl Terminator {
+    pub fn byte(self) -> u8 {
+        match self {
+            Terminator::Newline => b'\n',
+            Terminator::Nul => b'\0',
+        }
+    }
+}
+
+/// Reads newline, CRLF or NUL terminated records into one reused buffer.
+pub struct RecordReader<R> {
+    reader: R,
+    terminator: Terminator,
+    buf: Vec<u8>,
+}
+
+/// A record borrowed from a [`RecordReader`], valid until the next read.
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a> {
+    raw: &'a [u8],
+    terminator: Terminator,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(reader: R, terminator: Terminator) -> Self {
+        RecordReader {
+            reader,
+            terminator,
+            buf: vec![],
+        }
+    }
+
+    /// Reads the next record, or `None` at end of input. The last record
+    /// may lack its terminator.
+    pub fn next_record(&mut self) -> io::Result<Option<Record<'_>>> {
+        self.buf.clear();
+        if self
+            .reader
+            .read_until(self.terminator.byte(), &mut self.buf)?
+            == 0
+        {
+            return Ok(None);
+        }
+        Ok(Some(Record {
+            raw: &self.buf,
+            terminator: self.terminator,
+        }))
+    }
+}
+
+impl<'a> Record<'a> {
+    /// The record as read, terminator included.
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// The record without its terminator. A carriage return before a
+    /// newline counts as part of the terminator.
+    pub fn body(&self) -> &'a [u8] {
+        match self.raw.strip_suffix(&[self.terminator.byte()]) {
+            Some(body) if self.terminator == Terminator::Newline => {
+                body.strip_suffix(b"\r").unwrap_or(body)
+            }
+            Some(body) => body,
+            None => self.raw,
+        }
+    }
+}
+
+/// An input file named on the command line. `-`, `/dev/stdin` and
+/// `/dev/fd/0` read standard input and other `/dev/fd/N` read the open
+/// descriptor N, so both work where `/dev/fd` is not mounted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileArg {
+    name: String,
+    source: Source,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Source {
+    Stdin,
+    #[cfg(unix)]
+    Fd(RawFd),
+    Path(PathBuf),
+}
+
+impl FileArg {
+    pub fn new(name: impl Into<String>) -> FileArg {
+        let name = name.into();
+        let source = match name.as_str() {
+            "-" | "/dev/stdin" | "/dev/fd/0" => Source::Stdin,
+            _ => fd_source(&name).unwrap_or_else(|| Source::Path(PathBuf::from(&name))),
+        };
+        FileArg { name, source }
+    }
+
+    /// The argument as given, for messages and headers.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_stdin(&self) -> bool {
+        self.source == Source::Stdin
+    }
+
+    /// The path to open, or `None` for standard input and descriptors.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.source {
+            Source::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Opens the input for buffered reading. Directories are refused
+    /// up front, as reading one would fail on the first read anyway.
+    pub fn open(&self) -> io::Result<Box<dyn BufRead>> {
+        let file = match &self.source {
+            Source::Stdin => return Ok(Box::new(BufReader::new(io::stdin()))),
+            // 元の記述子を閉じないよう複製してから File にする
+            #[cfg(unix)]
+            Source::Fd(fd) => match unsafe { libc::dup(*fd) } {
+                -1 => return Err(io::Error::last_os_error()),
+                fd => unsafe { File::from_raw_fd(fd) },
+            },
+            Source::Path(path) => File::open(path)?,
+        };
+        if file.metadata()?.is_dir() {
+            return Err(is_a_directory());
+        }
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    /// Like [`FileArg::open`], but transparently decompresses gzip, bzip2,
+    /// xz and zstd input.
+    pub fn open_decompressed(&self) -> io::Result<Box<dyn BufRead>> {
+        decompress(self.open()?, &self.name)
+    }
+}
+
+/// The open descriptor a `/dev/fd/N` name refers to.
+#[cfg(unix)]
+fn fd_source(name: &str) -> Option<Source> {
+    name.strip_prefix("/dev/fd/")?.parse().ok().map(Source::Fd)
+}
+
+/// Other systems have no numbered descriptors, so these are plain paths.
+#[cfg(not(unix))]
+fn fd_source(_name: &str) -> Option<Source> {
+    None
+}
+
+/// The error of reading a directory, with the OS's own message where
+/// there is one.
+#[cfg(unix)]
+fn is_a_directory() -> io::Error {
+    io::Error::from_raw_os_error(libc::EISDIR)
+}
+
+#[cfg(not(unix))]
+fn is_a_directory() -> io::Error {
+    io::Error::from(io::ErrorKind::IsADirectory)
+}
+
+impl Display for FileArg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl From<&str> for FileArg {
+    fn from(name: &str) -> FileArg {
+        FileArg::new(name)
+    }
+}
+
+impl From<&String> for FileArg {
+    fn from(name: &String) -> FileArg {
+        FileArg::new(name.as_str())
+    }
+}
+
+/// Opens `filename` for buffered reading, treating `-` as standard input.
+/// See [`FileArg`].
+pub fn open(filename: &str) -> io::Result<Box<dyn BufRead>> {
+    FileArg::new(filename).open()
+}
+
+/// Reads the NUL-separated file names given to `--files0-from`, one at a
+/// time as they are needed. The list may come from standard input (`-`),
+/// and its last name may or may not be followed by a NUL.
+pub fn read_files0(list: &FileArg) -> io::Result<impl Iterator<Item = io::Result<FileArg>>> {
+    Ok(list.open()?.split(b'\0').map(|name| {
+        let name = name?;
+        if name.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid zero-length file name",
+            ));
+        }
+        String::from_utf8(name)
+            .map(FileArg::new)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+/// Each format with its magic bytes and file extension.
+const COMPRESSIONS: [(Compression, &[u8], &str); 4] = [
+    (Compression::Gzip, b"\x1f\x8b", ".gz"),
+    (Compression::Bzip2, b"BZh", ".bz2"),
+    (Compression::Xz, b"\xfd7zXZ\x00", ".xz"),
+    (Compression::Zstd, b"\x28\xb5\x2f\xfd", ".zst"),
+];
+
+/// Like [`open`], but transparently decompresses gzip, bzip2, xz and zstd
+/// input.
+pub fn open_decompressed(filename: &str) -> io::Result<Box<dyn BufRead>> {
+    FileArg::new(filename).open_decompressed()
+}
+
+/// Wraps `reader` in a streaming decompressor when it starts with the
+/// magic bytes of gzip, bzip2, xz or zstd, or failing that when `filename`
+/// has a `.gz`, `.bz2`, `.xz` or `.zst` extension. Other input is returned as is.
+pub fn decompress(mut reader: Box<dyn BufRead>, filename: &str) -> io::Result<Box<dyn BufRead>> {
+    let head = reader.fill_buf()?;
+    let format = COMPRESSIONS
+        .iter()
+        .find(|(_, magic, _)| head.starts_with(magic))
+        .or_else(|| {
+            COMPRESSIONS
+                .iter()
+                .find(|(_, _, ext)| filename.ends_with(ext))
+        })
+        .map(|(format, _, _)| *format);
+
+    Ok(match format {
+        Some(Compression::Gzip) => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Some(Compression::Bzip2) => Box::new(BufReader::new(MultiBzDecoder::new(reader))),
+        Some(Compression::Xz) => Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader))),
+        Some(Compression::Zstd) => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        None => reader,
+    })
+}
+
+pub fn parse_int<T: FromStr>(val: &str) -> Result<T, ParseError> {
+    val.parse()
+        .map_err(|_| ParseError(tr(Msg::InvalidInteger, &[&val])))
+}
+
+/// Parses a time interval such as `1.5s`, `250ms`, `2m` or `1h30m`. A
+/// bare number means seconds when it is the whole interval. Intervals
+/// too long for a [`Duration`] saturate to [`Duration::MAX`].
+pub fn parse_duration(val: &str) -> Result<Duration, ParseError> {
+    let error = || ParseError(tr(Msg::InvalidInterval, &[&val]));
+    let mut total = Duration::ZERO;
+    let mut rest = val;
+
+    if rest.is_empty() {
+        return Err(error());
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (num, tail) = rest.split_at(digits);
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+
+        let num: f64 = num.parse().map_err(|_| error())?;
+        let seconds = match unit {
+            // 単位のない数値は単独で指定されたときだけ秒とみなす
+            "" if total.is_zero() && tail.is_empty() => 1.0,
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            _ => return Err(error()),
+        };
+        // 表せないほど長い時間は、事実上無限として最大値に丸める
+        let duration = Duration::try_from_secs_f64(num * seconds).unwrap_or(Duration::MAX);
+        total = total.saturating_add(duration);
+        rest = tail;
+    }
+    Ok(total)
+}
+
+/// Parses a size such as `100`, `4K`, `2MiB` or `1GB`. As in GNU
+/// coreutils, `K`, `M`, `G`... and their `KiB` forms are powers of 1024,
+/// the `KB` forms powers of 1000 and `b` is 512.
+pub fn parse_size(val: &str) -> Result<u64, ParseError> {
+    let error = || ParseError(tr(Msg::InvalidSize, &[&val]));
+    let digits = val.find(|c: char| !c.is_ascii_digit()).unwrap_or(val.len());
+    let (num, unit) = val.split_at(digits);
+    let num: u64 = num.parse().map_err(|_| error())?;
+
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "b" => 512,
+        _ => {
+            let mut chars = unit.chars();
+            let power = match chars.next() {
+                Some('K' | 'k') => 1,
+                Some('M') => 2,
+                Some('G') => 3,
+                Some('T') => 4,
+                Some('P') => 5,
+                Some('E') => 6,
+                _ => return Err(error()),
+            };
+            let base: u64 = match chars.as_str() {
+                "" | "iB" => 1024,
+                "B" => 1000,
+                _ => return Err(error()),
+            };
+            base.pow(power)
+        }
+    };
+    num.checked_mul(multiplier).ok_or_else(error)
+}
+
+/// Parses a comma-separated list of 1-based positions and ranges such as
+/// `1,3-5` into 0-based half-open ranges. As in GNU cut, `-5` starts at
+/// the first position and `3-` runs to the end, which is `usize::MAX`;
+/// callers clamp the ranges to the length of each record.
+pub fn parse_pos(range: &str) -> Result<PositionList, ParseError> {
+    let range_re = Regex::new(r"^(\d*)-(\d*)$").unwrap();
+    range
+        .split(',')
+        .map(|val| {
+            parse_index(val).map(|n| n..n + 1).or_else(|e| {
+                range_re.captures(val).ok_or(e).and_then(|captures| {
+                    // 両端を省いた「-」だけは範囲にならない
+                    let (n1, n2) = match (&captures[1], &captures[2]) {
+                        ("", "") => return Err(tr(Msg::IllegalListValue, &[&val])),
+                        ("", end) => return Ok(0..parse_index(end)? + 1),
+                        (start, "") => return Ok(parse_index(start)?..usize::MAX),
+                        (start, end) => (parse_index(start)?, parse_index(end)?),
+                    };
+                    if n1 >= n2 {
+                        return Err(tr(Msg::BadRange, &[&(n1 + 1), &(n2 + 1)]));
+                    }
+                    Ok(n1..n2 + 1)
+                })
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map_err(ParseError)
+}
+
+fn parse_index(input: &str) -> Result<usize, String> {
+    let value_error = || tr(Msg::IllegalListValue, &[&input]);
+    if input.starts_with('+') {
+        Err(value_error())
+    } else {
+        input
+            .parse::<NonZeroUsize>()
+            .map(|n| usize::from(n) - 1)
+            .map_err(|_| value_error())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use std::error::Error;
+    use std::ffi::{OsStr, OsString};
+    use std::fs;
+    #[cfg(unix)]
+    use std::fs::File;
+    #[cfg(unix)]
+    use std::io::BufRead;
+    use std::io::{self, Cursor, Read, Write};
+    #[cfg(unix)]
+    use std::os::fd::AsRawFd;
+    use std::time::Duration;
+
+    use bzip2::write::BzEncoder;
+    use flate2::write::GzEncoder;
+    use proptest::prelude::*;
+    use xz2::write::XzEncoder;
+
+    use super::{
+        decompress, env_args, env_var, find_option, has_flag, parse_config, parse_duration,
+        parse_int, parse_pos, parse_size, read_files0, split_env_args, FileArg, RecordReader,
+        Terminator, ToolError, EXIT_FAILURE,
+    };
+
+    #[test]
+    fn test_exit_code() {
+        // 型付けされていないエラーは一般的な失敗として扱う
+        let err: Box<dyn Error> = From::from("oops");
+        assert_eq!(err.exit_code(), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn test_file_arg() {
+        for name in ["-", "/dev/stdin", "/dev/fd/0"] {
+            assert!(FileArg::new(name).is_stdin());
+            assert_eq!(FileArg::new(name).name(), name);
+        }
+        assert!(!FileArg::new("/dev/fd/3").is_stdin());
+        assert!(!FileArg::new("/dev/fd/x").is_stdin());
+
+        let err = FileArg::new("src").open().err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::IsADirectory);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_file_arg_fd() {
+        // 記述子は複製して読むので、元のファイルは開いたまま
+        let file = File::open("Cargo.toml").unwrap();
+        let arg = FileArg::new(format!("/dev/fd/{}", file.as_raw_fd()));
+        let mut line = String::new();
+        arg.open().unwrap().read_line(&mut line).unwrap();
+        assert_eq!(line, "[package]\n");
+        assert!(file.metadata().is_ok());
+    }
+
+    #[test]
+    fn test_read_files0() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = dir.path().join("list");
+        let names = |arg: &FileArg| -> io::Result<Vec<String>> {
+            read_files0(arg)
+                .and_then(|files| files.map(|f| f.map(|f| f.name().to_string())).collect())
+        };
+
+        fs::write(&list, "a\0b c\0-").unwrap();
+        let arg = FileArg::new(list.to_str().unwrap());
+        assert_eq!(names(&arg).unwrap(), ["a", "b c", "-"]);
+        fs::write(&list, "a\0").unwrap();
+        assert_eq!(names(&arg).unwrap(), ["a"]);
+        fs::write(&list, "").unwrap();
+        assert!(names(&arg).unwrap().is_empty());
+
+        fs::write(&list, "a\0\0b").unwrap();
+        let err = names(&arg).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "invalid zero-length file name");
+    }
+
+    #[test]
+    fn test_find_option() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        let find = |a: &[&str]| find_option(&args(a), "--completions").map(OsStr::to_owned);
+
+        assert_eq!(
+            find(&["catr", "--completions", "bash"]),
+            Some("bash".into())
+        );
+        assert_eq!(
+            find(&["catr", "-n", "--completions=zsh"]),
+            Some("zsh".into())
+        );
+        assert_eq!(find(&["catr", "--completions"]), None);
+        assert_eq!(find(&["catr", "--completionsx"]), None);
+        // 「--」以降はファイル名
+        assert_eq!(find(&["catr", "--", "--completions", "bash"]), None);
+        // プログラム名は見ない
+        assert_eq!(find(&["--completions=fish"]), None);
+    }
+
+    #[test]
+    fn test_has_flag() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert!(has_flag(&args(&["catr", "-n", "--man"]), "--man"));
+        assert!(!has_flag(&args(&["catr", "--manual"]), "--man"));
+        assert!(!has_flag(&args(&["catr", "--", "--man"]), "--man"));
+    }
+
+    #[test]
+    fn test_record_reader() {
+        let records = |text: &[u8], terminator| {
+            let mut reader = RecordReader::new(text, terminator);
+            let mut records = vec![];
+            while let Some(record) = reader.next_record().unwrap() {
+                records.push((record.raw().to_vec(), record.body().to_vec()));
+            }
+            records
+        };
+
+        assert_eq!(
+            records(b"a\nb\r\n\nc", Terminator::Newline),
+            vec![
+                (b"a\n".to_vec(), b"a".to_vec()),
+                (b"b\r\n".to_vec(), b"b".to_vec()),
+                (b"\n".to_vec(), b"".to_vec()),
+                (b"c".to_vec(), b"c".to_vec()),
+            ]
+        );
+
+        // 改行で終わらない行末の「\r」はデータとして残す
+        assert_eq!(
+            records(b"a\r", Terminator::Newline),
+            vec![(b"a\r".to_vec(), b"a\r".to_vec())]
+        );
+
+        assert_eq!(
+            records(b"a\nb\0c\r\n\0", Terminator::Nul),
+            vec![
+                (b"a\nb\0".to_vec(), b"a\nb".to_vec()),
+                (b"c\r\n\0".to_vec(), b"c\r\n".to_vec()),
+            ]
+        );
+        assert!(records(b"", Terminator::Nul).is_empty());
+    }
+
+    #[test]
+    fn test_decompress() {
+        let read = |data: Vec<u8>, filename: &str| {
+            let mut text = String::new();
+            decompress(Box::new(Cursor::new(data)), filename)?.read_to_string(&mut text)?;
+            Ok::<_, std::io::Error>(text)
+        };
+
+        let mut gz = GzEncoder::new(vec![], Default::default());
+        gz.write_all(b"gzip\n").unwrap();
+        let gz = gz.finish().unwrap();
+        assert_eq!(read(gz.clone(), "-").unwrap(), "gzip\n");
+
+        // 連結したgzipメンバーはすべて展開する
+        assert_eq!(
+            read([gz.clone(), gz].concat(), "-").unwrap(),
+            "gzip\ngzip\n"
+        );
+
+        let mut bz = BzEncoder::new(vec![], Default::default());
+        bz.write_all(b"bzip2\n").unwrap();
+        let bz = bz.finish().unwrap();
+        assert_eq!(read(bz.clone(), "-").unwrap(), "bzip2\n");
+        assert_eq!(
+            read([bz.clone(), bz].concat(), "a.bz2").unwrap(),
+            "bzip2\nbzip2\n"
+        );
+
+        let mut xz = XzEncoder::new(vec![], 6);
+        xz.write_all(b"xz\n").unwrap();
+        assert_eq!(read(xz.finish().unwrap(), "a.txt").unwrap(), "xz\n");
+
+        let zst = zstd::encode_all(&b"zstd\n"[..], 0).unwrap();
+        assert_eq!(read(zst, "-").unwrap(), "zstd\n");
+
+        assert_eq!(read(b"plain\n".to_vec(), "plain.txt").unwrap(), "plain\n");
+        assert_eq!(read(vec![], "-").unwrap(), "");
+
+        // 拡張子だけが圧縮形式を示す場合はその形式として読む
+        assert!(read(b"plain\n".to_vec(), "plain.gz").is_err());
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let text = r#"
+            [grepr]
+            args = ["-i", "--count"]
+
+            [findr]
+        "#;
+        assert_eq!(parse_config(text, "grepr").unwrap(), vec!["-i", "--count"]);
+        assert!(parse_config(text, "findr").unwrap().is_empty());
+        assert!(parse_config(text, "catr").unwrap().is_empty());
+        assert!(parse_config("", "catr").unwrap().is_empty());
+
+        // 表でないものや未知のキーはエラー
+        assert!(parse_config("grepr = 1", "grepr").is_err());
+        assert!(parse_config("[grepr]\nflags = []", "catr").is_err());
+        assert!(parse_config("[grepr]\nargs = \"-i\"", "grepr").is_err());
+    }
+
+    #[test]
+    fn test_env_args() {
+        assert_eq!(env_var("grepr"), "GREPR_OPTIONS");
+        assert_eq!(env_var("clir-test"), "CLIR_TEST_OPTIONS");
+
+        // 環境変数は他のテストと共有されるので、書き換えずに値を渡す
+        assert!(env_args("clir-test-unset").unwrap().is_empty());
+        let name = || "CLIR_TEST_OPTIONS".to_string();
+        assert!(split_env_args(name(), None).unwrap().is_empty());
+        assert_eq!(
+            split_env_args(name(), Some("-i  -e 'a b'")).unwrap(),
+            vec!["-i", "-e", "a b"]
+        );
+        assert_eq!(
+            split_env_args(name(), Some("-e 'a"))
+                .unwrap_err()
+                .to_string(),
+            "$CLIR_TEST_OPTIONS: unbalanced quotes"
+        );
+    }
+
+    #[test]
+    fn test_parse_int() {
+        // 正の整数をusizeとして解析する
+        let res = parse_int::<usize>("1");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), 1usize);
+
+        // 負の数をi32として解析する
+        let res = parse_int::<i32>("-1");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), -1i32);
+
+        // 数字以外の文字列を解析すると失敗する
+        let res = parse_int::<i64>("foo");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "Invalid integer \"foo\"");
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("100").unwrap(), 100);
+        assert_eq!(parse_size("2b").unwrap(), 1024);
+        assert_eq!(parse_size("4K").unwrap(), 4096);
+        assert_eq!(parse_size("4k").unwrap(), 4096);
+        assert_eq!(parse_size("4KiB").unwrap(), 4096);
+        assert_eq!(parse_size("4KB").unwrap(), 4000);
+        assert_eq!(parse_size("2M").unwrap(), 2 << 20);
+        assert_eq!(parse_size("1GB").unwrap(), 1_000_000_000);
+        assert_eq!(parse_size("1E").unwrap(), 1 << 60);
+
+        assert!(parse_size("").is_err());
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("-1").is_err());
+        assert!(parse_size("4X").is_err());
+        assert!(parse_size("4mB").is_err());
+        assert!(parse_size("16E").is_err());
+        assert_eq!(
+            parse_size("4Q").unwrap_err().to_string(),
+            "invalid size \"4Q\""
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("5").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("1d1s").unwrap(), Duration::from_secs(86401));
+        assert_eq!(parse_duration(".5m").unwrap(), Duration::from_secs(30));
+
+        assert_eq!(
+            parse_duration("18000000000000000000s18000000000000000000s").unwrap(),
+            Duration::MAX
+        );
+        assert_eq!(parse_duration(&"9".repeat(400)).unwrap(), Duration::MAX);
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("foo").is_err());
+        assert!(parse_duration("1x").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("1..5s").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+
+    #[test]
+    fn test_parse_pos() {
+        // 空文字はエラー
+        assert!(parse_pos("").is_err());
+
+        // ゼロはエラー
+        let res = parse_pos("0");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"0\"",);
+
+        let res = parse_pos("0-1");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"0\"",);
+
+        // 数字の前に「+」が付く場合はエラー
+        let res = parse_pos("+1");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"+1\"",);
+
+        let res = parse_pos("+1-2");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"+1-2\"",);
+
+        let res = parse_pos("1-+2");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"1-+2\"",);
+
+        // 数字はエラー
+        let res = parse_pos("a");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a\"",);
+
+        let res = parse_pos("1,a");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a\"",);
+
+        let res = parse_pos("1-a");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"1-a\"",);
+
+        let res = parse_pos("a-1");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a-1\"",);
+
+        // エラーになる範囲
+        let res = parse_pos("-");
+        assert!(res.is_err());
+
+        let res = parse_pos(",");
+        assert!(res.is_err());
+
+        let res = parse_pos("1,");
+        assert!(res.is_err());
+
+        let res = parse_pos("1-1-1");
+        assert!(res.is_err());
+
+        let res = parse_pos("1-1-a");
+        assert!(res.is_err());
+
+        // 最初の数字は2番目より小さい必要がある
+        let res = parse_pos("1-1");
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "First number in range (1) must be lower than second number (1)"
+        );
+
+        let res = parse_pos("2-1");
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "First number in range (2) must be lower than second number (1)"
+        );
+
+        // 以下のケースは受け入れられる
+        let res = parse_pos("1");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1]);
+
+        let res = parse_pos("01");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1]);
+
+        let res = parse_pos("1,3");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1, 2..3]);
+
+        let res = parse_pos("001,0003");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1, 2..3]);
+
+        let res = parse_pos("1-3");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..3]);
+
+        let res = parse_pos("0001-03");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..3]);
+
+        let res = parse_pos("1,7,3-5");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1, 6..7, 2..5]);
+
+        let res = parse_pos("15,19-20");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![14..15, 18..20]);
+
+        // 片側を省いた範囲は先頭から、または末尾まで
+        assert_eq!(parse_pos("3-").unwrap(), vec![2..usize::MAX]);
+        assert_eq!(parse_pos("-5").unwrap(), vec![0..5]);
+        assert_eq!(parse_pos("-1,4-").unwrap(), vec![0..1, 3..usize::MAX]);
+        assert_eq!(
+            parse_pos("-0").unwrap_err().to_string(),
+            "illegal list value: \"0\""
+        );
+    }
+
+    /// A 1-based position or closed range as a user would write it.
+    fn position() -> impl Strategy<Value = (usize, Option<usize>)> {
+        (1..usize::MAX / 2).prop_flat_map(|start| {
+            prop_oneof![
+                Just((start, None)),
+                (start + 1..=usize::MAX / 2).prop_map(move |end| (start, Some(end))),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_pos_round_trip(positions in prop::collection::vec(position(), 1..8)) {
+            let list = positions
+                .iter()
+                .map(|(start, end)| match end {
+                    Some(end) => format!("{}-{}", start, end),
+                    None => start.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            let expected: Vec<_> = positions
+                .iter()
+                .map(|&(start, end)| start - 1..end.unwrap_or(start))
+                .collect();
+            prop_assert_eq!(parse_pos(&list).unwrap(), expected);
+        }
+
+        #[test]
+        fn prop_parse_pos_ranges_not_empty(list in "[0-9,+-]{0,20}") {
+            // 受け付けたリストの範囲は必ず1つ以上の位置を含む
+            if let Ok(ranges) = parse_pos(&list) {
+                prop_assert!(!ranges.is_empty());
+                prop_assert!(ranges.iter().all(|r| r.start < r.end));
+            }
+        }
+
+        #[test]
+        fn prop_parse_pos_rejects_backward_ranges(start in 1..usize::MAX, back in 0..1000usize) {
+            let end = start.saturating_sub(back).max(1);
+            let list = format!("{}-{}", start, end);
+            prop_assert!(parse_pos(&list).is_err());
+        }
+    }
+}
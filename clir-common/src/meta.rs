@@ -0,0 +1,153 @@
+//! Formatting of file metadata the way `ls -l` shows it, shared by the
+//! tools that list files (`statr`, `findr --ls`).
+
+use std::fs;
+use std::fs::Metadata;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, TimeDelta};
+
+/// Files modified longer ago than this show the year instead of the
+/// time of day, as in `ls -l`. It is half of an average Gregorian year.
+const RECENT_SECS: i64 = 31_556_952 / 2;
+
+/// The mode as `ls -l` shows it, e.g. `drwxr-xr-x`.
+pub fn mode_string(metadata: &Metadata) -> String {
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_dir() {
+        'd'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else if file_type.is_block_device() {
+        'b'
+    } else if file_type.is_char_device() {
+        'c'
+    } else {
+        '-'
+    };
+    format!(
+        "{}{}",
+        kind,
+        permission_string(metadata.permissions().mode())
+    )
+}
+
+/// The nine permission characters of `mode`, with the setuid, setgid
+/// and sticky bits folded into the execute columns.
+pub fn permission_string(mode: u32) -> String {
+    let special = [(0o4000, 's', 'S'), (0o2000, 's', 'S'), (0o1000, 't', 'T')];
+    [(0o400, 0o200, 0o100), (0o40, 0o20, 0o10), (0o4, 0o2, 0o1)]
+        .iter()
+        .zip(special)
+        .map(|(&(read, write, exec), (bit, set_exec, set_no_exec))| {
+            let exec = match (mode & bit != 0, mode & exec != 0) {
+                (true, true) => set_exec,
+                (true, false) => set_no_exec,
+                (false, true) => 'x',
+                (false, false) => '-',
+            };
+            format!(
+                "{}{}{}",
+                if mode & read != 0 { 'r' } else { '-' },
+                if mode & write != 0 { 'w' } else { '-' },
+                exec
+            )
+        })
+        .collect()
+}
+
+/// The name of the user `uid`, if it has one.
+pub fn user_name(uid: u32) -> Option<String> {
+    uzers::get_user_by_uid(uid).map(|user| user.name().to_string_lossy().into_owned())
+}
+
+/// The name of the group `gid`, if it has one.
+pub fn group_name(gid: u32) -> Option<String> {
+    uzers::get_group_by_gid(gid).map(|group| group.name().to_string_lossy().into_owned())
+}
+
+/// The modification time as `ls -l` shows it: `Oct 18 12:34` for recent
+/// files and `Jan  2  2020` for old or future ones.
+pub fn ls_time(metadata: &Metadata, now: SystemTime) -> String {
+    let Some(time) = DateTime::from_timestamp(metadata.mtime(), metadata.mtime_nsec() as u32)
+    else {
+        return "-".to_string();
+    };
+    let time = time.with_timezone(&Local);
+    let now = DateTime::<Local>::from(now);
+    let recent = time > now - TimeDelta::seconds(RECENT_SECS) && time <= now;
+    let format = if recent { "%b %e %H:%M" } else { "%b %e  %Y" };
+    time.format(format).to_string()
+}
+
+/// One line of `find -ls` for `path`: inode, size in 1K blocks, mode,
+/// links, owner, group, size (or device numbers), modification time and
+/// the path, followed by the target of a symlink.
+pub fn long_line(path: &Path, metadata: &Metadata, now: SystemTime) -> String {
+    let file_type = metadata.file_type();
+    let size = if file_type.is_block_device() || file_type.is_char_device() {
+        let dev = metadata.rdev();
+        format!("{:>3}, {:>3}", libc::major(dev), libc::minor(dev))
+    } else {
+        metadata.len().to_string()
+    };
+    let mut line = format!(
+        "{:>9} {:>6} {} {:>3} {:<8} {:<8} {:>8} {} {}",
+        metadata.ino(),
+        metadata.blocks().div_ceil(2),
+        mode_string(metadata),
+        metadata.nlink(),
+        user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string()),
+        group_name(metadata.gid()).unwrap_or_else(|| metadata.gid().to_string()),
+        size,
+        ls_time(metadata, now),
+        path.display()
+    );
+    if file_type.is_symlink() {
+        if let Ok(target) = fs::read_link(path) {
+            line.push_str(&format!(" -> {}", target.display()));
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::{ls_time, permission_string};
+
+    #[test]
+    fn test_permission_string() {
+        assert_eq!(permission_string(0o644), "rw-r--r--");
+        assert_eq!(permission_string(0o755), "rwxr-xr-x");
+        assert_eq!(permission_string(0o000), "---------");
+
+        // setuid/setgid/stickyビット
+        assert_eq!(permission_string(0o4755), "rwsr-xr-x");
+        assert_eq!(permission_string(0o2644), "rw-r-Sr--");
+        assert_eq!(permission_string(0o1777), "rwxrwxrwt");
+    }
+
+    #[test]
+    fn test_ls_time() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mtime = Local.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+        file.as_file().set_modified(mtime.into()).unwrap();
+        let metadata = file.as_file().metadata().unwrap();
+
+        // 半年以内なら時刻を、それより古いか未来なら年を出す
+        let soon = Local.with_ymd_and_hms(2020, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(ls_time(&metadata, soon.into()), "Jan  2 03:04");
+        let later = Local.with_ymd_and_hms(2021, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(ls_time(&metadata, later.into()), "Jan  2  2020");
+        let before = Local.with_ymd_and_hms(2019, 12, 1, 0, 0, 0).unwrap();
+        assert_eq!(ls_time(&metadata, before.into()), "Jan  2  2020");
+    }
+}
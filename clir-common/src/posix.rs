@@ -0,0 +1,104 @@
+//! POSIX regular expressions in the syntax of the regex crate.
+
+/// Turns a POSIX basic regular expression into the syntax of the regex
+/// crate by swapping which of `(){}|+?` need a backslash.
+pub fn basic_to_extended(pattern: &str) -> String {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut re = String::new();
+    // * は式やグループの先頭では繰り返すものがないので文字通り
+    let mut at_start = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let was_start = at_start;
+        at_start = false;
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                i += 1;
+                match chars[i] {
+                    c @ ('(' | ')' | '{' | '}' | '|' | '+' | '?') => {
+                        re.push(c);
+                        at_start = matches!(c, '(' | '|');
+                    }
+                    c => {
+                        re.push('\\');
+                        re.push(c);
+                    }
+                }
+            }
+            c @ ('(' | ')' | '{' | '}' | '|' | '+' | '?') => {
+                re.push('\\');
+                re.push(c);
+            }
+            '^' if was_start => {
+                re.push('^');
+                at_start = true;
+            }
+            '*' if was_start => re.push_str("\\*"),
+            '[' => {
+                if let Some((class, len)) = bracket_to_regex(&chars[i..], &['^']) {
+                    re.push_str(&class);
+                    i += len;
+                    continue;
+                }
+                re.push_str("\\[");
+            }
+            c => re.push(c),
+        }
+        i += 1;
+    }
+    re
+}
+
+/// Turns the POSIX bracket expression at the start of `chars` into a
+/// regex class, returning it with the number of chars it used. A `]`
+/// right after the opening (and negation) is literal, `[:alpha:]` style
+/// classes are kept, and everything else is escaped since the regex
+/// crate gives meaning to `\`, `[`, `&&`, `~~` and `--` in classes.
+pub fn bracket_to_regex(chars: &[char], negations: &[char]) -> Option<(String, usize)> {
+    let mut class = String::from("[");
+    let mut i = 1;
+    if chars.get(i).is_some_and(|c| negations.contains(c)) {
+        class.push('^');
+        i += 1;
+    }
+    let start = i;
+    loop {
+        let c = *chars.get(i)?;
+        if c == ']' && i > start {
+            class.push(']');
+            return Some((class, i + 1));
+        }
+        if c == '[' && chars.get(i + 1) == Some(&':') {
+            let len = chars[i..].windows(2).position(|pair| pair == [':', ']'])? + 2;
+            class.extend(&chars[i..i + len]);
+            i += len;
+            continue;
+        }
+        // 前後に文字がある - だけを範囲として残す
+        if c == '-' && i > start && chars.get(i + 1).is_some_and(|&c| c != ']') {
+            class.push('-');
+        } else {
+            class.push_str(&regex::escape(&c.to_string()));
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::basic_to_extended;
+
+    #[test]
+    fn test_basic_to_extended() {
+        assert_eq!(basic_to_extended(r"\(1\)\(.\)"), "(1)(.)");
+        assert_eq!(basic_to_extended(r"a+b?(c)|d{2}"), r"a\+b\?\(c\)\|d\{2\}");
+        assert_eq!(basic_to_extended(r"a\+b\?c\{2,3\}\|d"), "a+b?c{2,3}|d");
+        assert_eq!(basic_to_extended(r"*a\(*b\)^*c*"), r"\*a(\*b)^*c*");
+        assert_eq!(basic_to_extended(r"^*\.x"), r"^\*\.x");
+        assert_eq!(
+            basic_to_extended("[]a-z[:digit:]^-]x["),
+            r"[\]a-z[:digit:]\^\-]x\["
+        );
+        assert_eq!(basic_to_extended("[^]]"), r"[^\]]");
+    }
+}
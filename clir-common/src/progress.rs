@@ -0,0 +1,179 @@
+//! Opt-in progress display for long operations, enabled per tool with
+//! `--progress`. It draws on STDERR only when that is a terminal, so
+//! redirected output and logs stay clean.
+
+use std::io::BufRead;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use clap::Arg;
+use clap::ArgAction::SetTrue;
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::i18n::{tr, Msg};
+
+/// The `--progress` flag every tool that reports progress shares.
+pub fn arg() -> Arg {
+    Arg::new("progress")
+        .long("progress")
+        .help(tr(Msg::ProgressFlag, &[]))
+        .action(SetTrue)
+}
+
+/// Counts items (files, entries...) and bytes processed so far. A
+/// disabled `Progress` does nothing, so callers need not check.
+#[derive(Debug)]
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    unit: &'static str,
+    bytes: AtomicU64,
+}
+
+impl Progress {
+    /// Starts a spinner labelled with `tool`. `unit` names the items
+    /// counted, e.g. `entries`.
+    pub fn new(enabled: bool, tool: &str, unit: &'static str) -> Progress {
+        Progress::with_target(enabled, tool, unit, ProgressDrawTarget::stderr())
+    }
+
+    fn with_target(
+        enabled: bool,
+        tool: &str,
+        unit: &'static str,
+        target: ProgressDrawTarget,
+    ) -> Progress {
+        let bar = enabled.then(|| {
+            let bar = ProgressBar::with_draw_target(None, target).with_style(
+                ProgressStyle::with_template("{spinner} {prefix}: {pos} {msg} [{elapsed}]")
+                    .unwrap(),
+            );
+            bar.set_prefix(tool.to_string());
+            bar.set_message(unit);
+            bar.enable_steady_tick(Duration::from_millis(100));
+            bar
+        });
+        Progress {
+            bar,
+            unit,
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn inc(&self, items: u64) {
+        if let Some(bar) = &self.bar {
+            bar.inc(items);
+        }
+    }
+
+    pub fn inc_bytes(&self, bytes: u64) {
+        if let Some(bar) = &self.bar {
+            let total = self.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+            bar.set_message(format!("{}, {}", self.unit, HumanBytes(total)));
+        }
+    }
+
+    pub fn items(&self) -> u64 {
+        self.bar.as_ref().map_or(0, |bar| bar.position())
+    }
+
+    /// Removes the spinner so it does not mix with later output.
+    pub fn finish(&self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Bars showing how much of each large input has been read, one line per
+/// input being read at the time. Disabled bars do nothing.
+#[derive(Debug)]
+pub struct ByteBars {
+    multi: Option<MultiProgress>,
+    tool: String,
+}
+
+impl ByteBars {
+    pub fn new(enabled: bool, tool: &str) -> ByteBars {
+        ByteBars::with_target(enabled, tool, ProgressDrawTarget::stderr())
+    }
+
+    fn with_target(enabled: bool, tool: &str, target: ProgressDrawTarget) -> ByteBars {
+        ByteBars {
+            multi: enabled.then(|| MultiProgress::with_draw_target(target)),
+            tool: tool.to_string(),
+        }
+    }
+
+    /// Wraps `reader` for `name`, which is `len` bytes long, so reading
+    /// it moves a bar. The bar goes away when the reader is dropped.
+    pub fn wrap<'a>(
+        &self,
+        name: &str,
+        len: u64,
+        reader: Box<dyn BufRead + 'a>,
+    ) -> Box<dyn BufRead + 'a> {
+        let Some(multi) = &self.multi else {
+            return reader;
+        };
+        let bar = multi.add(ProgressBar::new(len)).with_style(
+            ProgressStyle::with_template(
+                "{prefix}: {wide_msg} {bar:30} {bytes}/{total_bytes} [{eta}]",
+            )
+            .unwrap(),
+        );
+        bar.set_prefix(self.tool.clone());
+        bar.set_message(name.to_string());
+        Box::new(bar.wrap_read(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use indicatif::ProgressDrawTarget;
+
+    use super::{ByteBars, Progress};
+
+    #[test]
+    fn test_progress() {
+        let progress =
+            Progress::with_target(true, "findr", "entries", ProgressDrawTarget::hidden());
+        progress.inc(2);
+        progress.inc(3);
+        progress.inc_bytes(10);
+        assert_eq!(progress.items(), 5);
+        assert_eq!(progress.bar.as_ref().unwrap().message(), "entries, 10 B");
+
+        // 無効なときは何も数えない
+        let progress = Progress::new(false, "findr", "entries");
+        progress.inc(2);
+        assert_eq!(progress.items(), 0);
+    }
+
+    #[test]
+    fn test_byte_bars() {
+        let bars = ByteBars::with_target(true, "wcr", ProgressDrawTarget::hidden());
+        let mut text = String::new();
+        bars.wrap("a.txt", 5, Box::new(&b"hello"[..]))
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "hello");
+        assert!(bars.multi.is_some());
+
+        // 無効なときは包まずにそのまま返す
+        let bars = ByteBars::new(false, "wcr");
+        let mut text = String::new();
+        bars.wrap("a.txt", 5, Box::new(&b"hello"[..]))
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "hello");
+        assert!(bars.multi.is_none());
+    }
+}
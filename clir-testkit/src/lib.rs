@@ -0,0 +1,261 @@
+//! Helpers shared by the integration tests of the tools in this workspace:
+//! temporary fixture directories, generated inputs and comparison against
+//! the GNU tool a program reimplements.
+
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use assert_cmd::Command;
+use rand::distributions::Alphanumeric;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tempfile::TempDir;
+
+pub type TestResult = Result<(), Box<dyn Error>>;
+
+/// A random alphanumeric string of 7 characters.
+pub fn random_string() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(7)
+        .map(char::from)
+        .collect()
+}
+
+/// A file name that does not exist in the current directory.
+pub fn gen_bad_file() -> String {
+    loop {
+        let filename = random_string();
+        if fs::metadata(&filename).is_err() {
+            return filename;
+        }
+    }
+}
+
+/// `lines` lines of random words, the same for the same `seed`. Some
+/// lines are empty or repeated so tools like `uniq` have work to do.
+pub fn gen_text(lines: usize, seed: u64) -> String {
+    const WORDS: &[&str] = &[
+        "the",
+        "quick",
+        "brown",
+        "fox",
+        "jumps",
+        "over",
+        "lazy",
+        "dog",
+        "Foo",
+        "bar",
+        "a\tb",
+        "日本語",
+    ];
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut text = String::new();
+    let mut prev = String::new();
+    for _ in 0..lines {
+        let line = match rng.gen_range(0..10) {
+            0 => String::new(),
+            1 => prev.clone(),
+            _ => (0..rng.gen_range(1..8))
+                .map(|_| WORDS[rng.gen_range(0..WORDS.len())])
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        text.push_str(&line);
+        text.push('\n');
+        prev = line;
+    }
+    text
+}
+
+/// A temporary directory to build input files in. It is removed when
+/// dropped.
+pub struct Fixture {
+    dir: TempDir,
+}
+
+impl Fixture {
+    pub fn new() -> io::Result<Fixture> {
+        Ok(Fixture {
+            dir: tempfile::tempdir()?,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The path of `name` in the fixture, as a string for `args`.
+    pub fn path(&self, name: &str) -> String {
+        self.root().join(name).display().to_string()
+    }
+
+    /// Writes the file `name`, creating its parent directories.
+    pub fn file(&self, name: &str, contents: impl AsRef<[u8]>) -> io::Result<String> {
+        let path = self.root().join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        Ok(path.display().to_string())
+    }
+
+    pub fn dir(&self, name: &str) -> io::Result<String> {
+        let path = self.root().join(name);
+        fs::create_dir_all(&path)?;
+        Ok(path.display().to_string())
+    }
+
+    /// Makes `name` a symbolic link to `target`, which is used as given.
+    pub fn symlink(&self, name: &str, target: &str) -> io::Result<String> {
+        let path = self.root().join(name);
+        symlink(target, &path)?;
+        Ok(path.display().to_string())
+    }
+}
+
+/// The GNU version of `tool` on the `PATH`, if there is one. Other
+/// implementations (BSD, busybox) are ignored since their output differs.
+pub fn gnu(tool: &str) -> Option<PathBuf> {
+    let out = process::Command::new(tool).arg("--version").output().ok()?;
+    String::from_utf8_lossy(&out.stdout)
+        .contains("GNU")
+        .then(|| PathBuf::from(tool))
+}
+
+/// What a run printed and how it exited.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub stdout: String,
+    pub stderr: String,
+    pub code: Option<i32>,
+}
+
+/// Runs a tool of this workspace and its GNU counterpart the same way
+/// and checks that they behave alike.
+pub struct GnuCompare {
+    prg: String,
+    tool: String,
+    args: Vec<String>,
+    stdin: Vec<u8>,
+    dir: Option<PathBuf>,
+    stderr: bool,
+}
+
+impl GnuCompare {
+    /// Compares the binary `prg` of the current package with `tool`.
+    pub fn new(prg: &str, tool: &str) -> GnuCompare {
+        GnuCompare {
+            prg: prg.to_string(),
+            tool: tool.to_string(),
+            args: vec![],
+            stdin: vec![],
+            dir: None,
+            stderr: true,
+        }
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> GnuCompare
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.args
+            .extend(args.into_iter().map(|a| a.as_ref().to_string()));
+        self
+    }
+
+    pub fn stdin(mut self, stdin: impl Into<Vec<u8>>) -> GnuCompare {
+        self.stdin = stdin.into();
+        self
+    }
+
+    /// Runs both in `dir` so relative paths resolve the same.
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> GnuCompare {
+        self.dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Skips comparing STDERR, for tools whose messages are worded
+    /// differently.
+    pub fn ignore_stderr(mut self) -> GnuCompare {
+        self.stderr = false;
+        self
+    }
+
+    /// Runs both and panics with both outcomes when they differ. Passes
+    /// without running anything when the GNU tool is not installed.
+    pub fn assert(self) -> TestResult {
+        let Some(gnu) = gnu(&self.tool) else {
+            eprintln!("{} is not installed, skipping", self.tool);
+            return Ok(());
+        };
+        let ours = self.run(Command::cargo_bin(&self.prg)?)?;
+        let theirs = self.run(Command::new(gnu))?;
+        assert_eq!(
+            ours, theirs,
+            "{} and {} differ for {:?}",
+            self.prg, self.tool, self.args
+        );
+        Ok(())
+    }
+
+    fn run(&self, mut cmd: Command) -> Result<Outcome, Box<dyn Error>> {
+        if let Some(dir) = &self.dir {
+            cmd.current_dir(dir);
+        }
+        let out = cmd
+            .args(&self.args)
+            .write_stdin(self.stdin.clone())
+            .output()?;
+        // メッセージの先頭のプログラム名だけはどうしても違うので揃える
+        let stderr = if self.stderr {
+            String::from_utf8_lossy(&out.stderr)
+                .lines()
+                .map(|line| match line.strip_prefix(&format!("{}: ", self.prg)) {
+                    Some(rest) => format!("{}: {}\n", self.tool, rest),
+                    None => format!("{}\n", line),
+                })
+                .collect()
+        } else {
+            String::new()
+        };
+        Ok(Outcome {
+            stdout: String::from_utf8_lossy(&out.stdout).to_string(),
+            stderr,
+            code: out.status.code(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{gen_text, Fixture};
+
+    #[test]
+    fn test_gen_text() {
+        let text = gen_text(100, 1);
+        assert_eq!(text.lines().count(), 100);
+        assert_eq!(text, gen_text(100, 1));
+        assert_ne!(text, gen_text(100, 2));
+    }
+
+    #[test]
+    fn test_fixture() {
+        let fixture = Fixture::new().unwrap();
+        let file = fixture.file("a/b.txt", "hello\n").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "hello\n");
+        assert_eq!(file, fixture.path("a/b.txt"));
+        let link = fixture.symlink("c", "a/b.txt").unwrap();
+        assert_eq!(fs::read_link(link).unwrap().to_str(), Some("a/b.txt"));
+        let root = fixture.root().to_path_buf();
+        drop(fixture);
+        assert!(!root.exists());
+    }
+}
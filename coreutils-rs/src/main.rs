@@ -0,0 +1,168 @@
+//! Every tool in one binary, busybox style. The tool is picked from the
+//! name the binary was invoked as (`ln -s coreutils-rs cat`) or from the
+//! first argument (`coreutils-rs cat -n FILE`).
+//!
+//! A small static binary for container images can be built with
+//! `cargo build -p coreutils-rs --profile release-small --target x86_64-unknown-linux-musl`.
+
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::process;
+
+use clir_common::i18n::{tr, Msg};
+use clir_common::{report, set_args, warn, EXIT_USAGE};
+
+/// Parses the arguments of a tool and runs it on the standard streams.
+/// STDERR is not kept locked, as progress bars may write to it from
+/// other threads. Tools marked `buffered` get the same STDOUT writer as
+/// their own `main.rs`: buffered unless it is a terminal.
+macro_rules! run {
+    ($tool:ident) => {
+        $tool::get_args()
+            .and_then(|config| $tool::run(config, &mut io::stdout().lock(), &mut io::stderr()))
+    };
+    ($tool:ident, buffered) => {{
+        let stdout = io::stdout();
+        let mut out: Box<dyn Write> = if stdout.is_terminal() {
+            Box::new(stdout.lock())
+        } else {
+            Box::new(BufWriter::new(stdout.lock()))
+        };
+        $tool::get_args().and_then(|config| $tool::run(config, &mut out, &mut io::stderr()))
+    }};
+}
+
+/// Runs a tool and converts the result into an exit status. Tools whose
+/// `run` returns the status themselves are marked with `status`.
+macro_rules! tool {
+    ($name:expr, $tool:ident) => {
+        match run!($tool) {
+            Ok(()) => 0,
+            Err(e) => report($name, &e),
+        }
+    };
+    ($name:expr, $tool:ident, status $(, $writer:ident)?) => {
+        match run!($tool $(, $writer)?) {
+            Ok(code) => code,
+            Err(e) => report($name, &e),
+        }
+    };
+}
+
+/// Canonical names, each also reachable by its crate name (`cat`/`catr`).
+const TOOLS: [(&str, &str); 29] = [
+    ("cal", "calr"),
+    ("cat", "catr"),
+    ("column", "columnr"),
+    ("comm", "commr"),
+    ("cut", "cutr"),
+    ("date", "dater"),
+    ("dd", "ddr"),
+    ("diff", "diffr"),
+    ("echo", "echor"),
+    ("find", "findr"),
+    ("fold", "foldr"),
+    ("fortune", "fortuner"),
+    ("grep", "grepr"),
+    ("head", "headr"),
+    ("pager", "pagr"),
+    ("printf", "printfr"),
+    ("realpath", "realpathr"),
+    ("sed", "sedr"),
+    ("shuf", "shufr"),
+    ("sleep", "sleepr"),
+    ("stat", "statr"),
+    ("strfile", "strfiler"),
+    ("tail", "tailr"),
+    ("time", "timerr"),
+    ("tr", "trr"),
+    ("truncate", "truncater"),
+    ("uniq", "uniqr"),
+    ("wc", "wcr"),
+    ("yes", "yesr"),
+];
+
+fn main() {
+    let mut args: Vec<OsString> = env::args_os().collect();
+
+    // 自分自身の名前で呼ばれたときは最初の引数をサブコマンドとみなす
+    let invoked = args
+        .first()
+        .and_then(|a| Path::new(a).file_stem())
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    let name = match lookup(&invoked) {
+        Some(name) => name,
+        None => {
+            args.remove(0);
+            let Some(cmd) = args.first().and_then(|a| a.to_str()) else {
+                usage();
+                process::exit(EXIT_USAGE);
+            };
+            match lookup(cmd) {
+                Some(name) => name,
+                None => {
+                    warn("coreutils-rs", &tr(Msg::AppletNotFound, &[&cmd]));
+                    usage();
+                    process::exit(EXIT_USAGE);
+                }
+            }
+        }
+    };
+    set_args(args);
+    process::exit(dispatch(name));
+}
+
+fn lookup(name: &str) -> Option<&'static str> {
+    TOOLS
+        .iter()
+        .find(|(tool, krate)| *tool == name || *krate == name)
+        .map(|(tool, _)| *tool)
+}
+
+fn usage() {
+    eprintln!("{}", tr(Msg::MultiCallUsage, &[&"coreutils-rs"]));
+    eprintln!();
+    eprintln!("{}", tr(Msg::AvailableTools, &[]));
+    let names: Vec<&str> = TOOLS.iter().map(|(tool, _)| *tool).collect();
+    eprintln!("    {}", names.join(" "));
+}
+
+fn dispatch(name: &str) -> i32 {
+    match name {
+        "cal" => tool!(name, calr),
+        "cat" => tool!(name, catr, status, buffered),
+        "column" => tool!(name, columnr, status),
+        "comm" => tool!(name, commr),
+        "cut" => tool!(name, cutr, status),
+        "date" => tool!(name, dater),
+        "dd" => tool!(name, ddr),
+        "diff" => tool!(name, diffr, status),
+        "echo" => tool!(name, echor),
+        "find" => tool!(name, findr, status),
+        "fold" => tool!(name, foldr, status),
+        "fortune" => tool!(name, fortuner),
+        "grep" => tool!(name, grepr, status),
+        "head" => tool!(name, headr, status),
+        "pager" => tool!(name, pagr),
+        "printf" => tool!(name, printfr, status),
+        "realpath" => tool!(name, realpathr, status),
+        "sed" => tool!(name, sedr, status),
+        "shuf" => tool!(name, shufr),
+        "sleep" => tool!(name, sleepr),
+        "stat" => tool!(name, statr, status),
+        "strfile" => tool!(name, strfiler),
+        "tail" => tool!(name, tailr, status),
+        "time" => tool!(name, timerr, status),
+        "tr" => tool!(name, trr),
+        "truncate" => tool!(name, truncater, status),
+        "uniq" => tool!(name, uniqr),
+        "wc" => tool!(name, wcr, status),
+        "yes" => tool!(name, yesr),
+        _ => unreachable!("unknown tool {}", name),
+    }
+}
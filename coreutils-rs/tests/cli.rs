@@ -0,0 +1,110 @@
+use assert_cmd::cargo::cargo_bin;
+use assert_cmd::Command;
+use clir_testkit::{Fixture, TestResult};
+use predicates::prelude::*;
+
+const PRG: &str = "coreutils-rs";
+
+#[test]
+fn dies_no_args() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Available tools:"));
+    Ok(())
+}
+
+#[test]
+fn dies_unknown_tool() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("frobnicate")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "coreutils-rs: frobnicate: applet not found",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dies_unknown_tool_ja() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("frobnicate")
+        .env_remove("LC_ALL")
+        .env_remove("LC_MESSAGES")
+        .env("LANG", "ja_JP.UTF-8")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "coreutils-rs: frobnicate: そのツールはありません",
+        ))
+        .stderr(predicate::str::contains("使えるツール:"));
+    Ok(())
+}
+
+#[test]
+fn subcommand() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["echo", "-n", "hello", "there"])
+        .assert()
+        .success()
+        .stdout("hello there");
+    Ok(())
+}
+
+#[test]
+fn subcommand_crate_name() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["wcr", "-l"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2"));
+    Ok(())
+}
+
+#[test]
+fn subcommand_error() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["cal", "0"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::starts_with("cal: year \"0\""));
+    Ok(())
+}
+
+#[test]
+fn subcommand_status() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["printf", r"%d\n", "1x"])
+        .assert()
+        .code(1)
+        .stdout("1\n")
+        .stderr(predicate::str::contains("value not completely converted"));
+    Ok(())
+}
+
+#[test]
+fn subcommand_completions() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["grep", "--completions", "fish"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("complete -c grep"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn argv0() -> TestResult {
+    let fixture = Fixture::new()?;
+    let link = fixture.symlink("head", &cargo_bin(PRG).display().to_string())?;
+
+    Command::new(&link)
+        .args(["-n", "1"])
+        .write_stdin("one\ntwo\n")
+        .assert()
+        .success()
+        .stdout("one\n");
+    Ok(())
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+use std::sync::OnceLock;
+
+use libfuzzer_sys::fuzz_target;
+use regex::Regex;
+
+static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&flags, data)) = data.split_first() else {
+        return;
+    };
+    let patterns = PATTERNS.get_or_init(|| {
+        ["", "^", "$", "fox", "(?i)THE", "^$", r"\w+\s\w+", "."]
+            .iter()
+            .map(|p| Regex::new(p).unwrap())
+            .collect()
+    });
+    let pattern = &patterns[flags as usize % patterns.len()];
+    grepr::fuzz::find_lines_in(data, pattern, flags & 0x10 != 0, flags & 0x20 != 0);
+});
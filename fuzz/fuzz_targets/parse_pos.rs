@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let list = String::from_utf8_lossy(data);
+    if let Ok(ranges) = clir_common::parse_pos(&list) {
+        assert!(!ranges.is_empty());
+        assert!(ranges.iter().all(|r| r.start < r.end));
+    }
+});